@@ -54,24 +54,56 @@ fn generate_bindings(include_dirs: &[PathBuf]) {
         .allowlist_function("rte_mp_disable")
         .allowlist_function("rte_eal_process_type")
         .allowlist_function("rte_pktmbuf_free_bulk")
-        .allowlist_function("rte_mempool_avail_count") // this can be removed
+        .allowlist_function("rte_mempool_avail_count")
+        .allowlist_function("rte_mempool_in_use_count")
+        .allowlist_function("rte_mempool_obj_iter")
+        .allowlist_function("rte_ring_create")
+        .allowlist_function("rte_ring_free")
+        .allowlist_function("rte_dev_probe")
+        .allowlist_function("rte_dev_remove")
+        .allowlist_function("rte_eth_dev_get_port_by_name")
+        .allowlist_function("rte_eth_dev_get_name_by_port")
         .allowlist_function("rte_eth_dev_info_get")
         .allowlist_function("rte_eth_dev_count_avail")
         .allowlist_function("rte_eth_macaddr_get")
         .allowlist_function("rte_eth_stats_get")
+        .allowlist_function("rte_eth_stats_reset")
+        .allowlist_function("rte_eth_xstats_get")
+        .allowlist_function("rte_eth_xstats_get_names")
+        .allowlist_function("rte_eth_xstats_get_by_id")
+        .allowlist_function("rte_eth_xstats_get_names_by_id")
+        .allowlist_function("rte_eth_xstats_get_id_by_name")
+        .allowlist_function("rte_eth_xstats_reset")
         .allowlist_function("rte_eth_dev_socket_id")
         .allowlist_function("rte_eth_dev_configure")
+        .allowlist_function("rte_eth_dev_get_mtu")
+        .allowlist_function("rte_eth_dev_set_mtu")
+        .allowlist_function("rte_eth_dev_conf_get")
         .allowlist_function("rte_eth_dev_start")
         .allowlist_function("rte_eth_dev_stop")
         .allowlist_function("rte_eth_dev_close")
         .allowlist_function("rte_eth_rx_queue_setup")
         .allowlist_function("rte_eth_tx_queue_setup")
+        .allowlist_function("rte_eth_dev_rx_queue_start")
+        .allowlist_function("rte_eth_dev_rx_queue_stop")
+        .allowlist_function("rte_eth_dev_tx_queue_start")
+        .allowlist_function("rte_eth_dev_tx_queue_stop")
         .allowlist_function("rte_eth_promiscuous_enable")
         .allowlist_function("rte_eth_promiscuous_disable")
+        .allowlist_function("rte_eth_dev_set_link_up")
+        .allowlist_function("rte_eth_dev_set_link_down")
+        .allowlist_function("rte_eth_dev_callback_register")
+        .allowlist_function("rte_eth_dev_callback_unregister")
         .allowlist_function("rte_eth_dev_rss_reta_update")
         .allowlist_function("rte_eth_dev_rss_reta_query")
         .allowlist_function("rte_eth_dev_rss_hash_update")
         .allowlist_function("rte_eth_dev_rss_hash_conf_get")
+        .allowlist_function("rte_eth_dev_vlan_filter")
+        .allowlist_function("rte_flow_validate")
+        .allowlist_function("rte_flow_create")
+        .allowlist_function("rte_flow_destroy")
+        .allowlist_function("rte_flow_query")
+        .allowlist_function("rte_flow_flush")
         .allowlist_function("rte_eal_init")
         .allowlist_function("rte_eal_cleanup")
         // generate useful dpdk types
@@ -81,7 +113,19 @@ fn generate_bindings(include_dirs: &[PathBuf]) {
         .allowlist_type("rte_mempool")
         .allowlist_type("rte_mbuf")
         .allowlist_type("rte_eth_stats")
+        .allowlist_type("rte_eth_xstat")
+        .allowlist_type("rte_eth_xstat_name")
         .allowlist_type("rte_proc_type_t")
+        .allowlist_type("rte_flow_attr")
+        .allowlist_type("rte_flow_item")
+        .allowlist_type("rte_flow_item_type")
+        .allowlist_type("rte_flow_item_(eth|ipv4|ipv6|tcp|udp)")
+        .allowlist_type("rte_flow_action")
+        .allowlist_type("rte_flow_action_type")
+        .allowlist_type("rte_flow_action_(queue|rss|mark|count)")
+        .allowlist_type("rte_flow_query_count")
+        .allowlist_type("rte_flow_error")
+        .allowlist_type("rte_flow_error_type")
         // generate useful dpdk macros defined in rte_build_config.h.
         .allowlist_var("RTE_MAX_LCORE")
         .allowlist_var("RTE_MAX_NUMA_NODES")
@@ -89,8 +133,18 @@ fn generate_bindings(include_dirs: &[PathBuf]) {
         .allowlist_var("RTE_MBUF_DEFAULT_DATAROOM")
         .allowlist_var("RTE_PKTMBUF_HEADROOM")
         .allowlist_var("RTE_ETHDEV_QUEUE_STAT_CNTRS")
+        .allowlist_var("RTE_ETH_XSTATS_NAME_SIZE")
+        .allowlist_var("RTE_MAX_ETHPORTS")
+        .allowlist_var("RTE_DEV_NAME_MAX_LEN")
+        // Ring flags (from wrapper.h static consts)
+        .allowlist_var("RUST_RING_F_.*")
         // RSS hash type constants (from wrapper.h static consts)
         .allowlist_var("RUST_RTE_ETH_RSS_.*")
+        // Device capability flags (from wrapper.h static consts)
+        .allowlist_var("RUST_RTE_ETH_DEV_.*")
+        // Offload capability and mbuf offload flags (from wrapper.h static consts)
+        .allowlist_var("RUST_RTE_ETH_(RX|TX)_OFFLOAD_.*")
+        .allowlist_var("RUST_RTE_MBUF_F_.*")
         .header("include/wrapper.h");
 
     let bindings = bgbuilder
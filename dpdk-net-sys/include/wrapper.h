@@ -5,7 +5,11 @@
 #include <rte_config.h>
 #include <rte_eal.h>
 #include <rte_ethdev.h>
+#include <rte_flow.h>
+#include <rte_gso.h>
 #include <rte_mbuf.h>
+#include <rte_net.h>
+#include <rte_ring.h>
 
 // Wrapper functions for accessing rte_errno (per-lcore macro)
 int rust_get_rte_errno(void);
@@ -27,12 +31,76 @@ char* rust_pktmbuf_adj(struct rte_mbuf *m, uint16_t len);
 int rust_pktmbuf_trim(struct rte_mbuf *m, uint16_t len);
 void rust_pktmbuf_reset(struct rte_mbuf *m);
 uint16_t rust_pktmbuf_data_room_size(struct rte_mempool *mp);
+void rust_pktmbuf_set_tx_offload(struct rte_mbuf *m, uint64_t ol_flags, uint16_t l2_len,
+                                 uint16_t l3_len, uint16_t l4_len);
+int rust_pktmbuf_tx_cksum_prepare(struct rte_mbuf *m);
+void rust_pktmbuf_set_tso_segsz(struct rte_mbuf *m, uint16_t tso_segsz);
+int rust_pktmbuf_chain(struct rte_mbuf *head, struct rte_mbuf *tail);
+struct rte_mbuf* rust_pktmbuf_next(const struct rte_mbuf *m);
+uint16_t rust_pktmbuf_nb_segs(const struct rte_mbuf *m);
+struct rte_mbuf* rust_pktmbuf_lastseg(struct rte_mbuf *m);
+int rust_pktmbuf_linearize(struct rte_mbuf *m);
+void rust_pktmbuf_sw_cksum(struct rte_mbuf *m);
+uint16_t rust_pktmbuf_l2_len(const struct rte_mbuf *m);
+void rust_pktmbuf_set_l2_len(struct rte_mbuf *m, uint16_t l2_len);
+uint32_t rust_pktmbuf_rss_hash(const struct rte_mbuf *m);
+
+// Mempool diagnostics (struct fields and inline cache/get/put functions)
+unsigned int rust_mempool_size(const struct rte_mempool *mp);
+unsigned int rust_mempool_cache_size(const struct rte_mempool *mp);
+unsigned int rust_mempool_cache_count(const struct rte_mempool *mp, unsigned int lcore_id);
+void rust_mempool_flush_caches(struct rte_mempool *mp);
+int rust_mempool_get(struct rte_mempool *mp, void **obj);
+void rust_mempool_put(struct rte_mempool *mp, void *obj);
+
+// Generic segmentation offload (builds the rte_gso_ctx for TCP/IPv4)
+int rust_gso_segment(struct rte_mbuf *pkt, struct rte_mempool *direct_pool,
+                     struct rte_mempool *indirect_pool, uint16_t gso_size,
+                     struct rte_mbuf **pkts_out, uint16_t nb_pkts_out);
 
 // Ethernet RX/TX burst wrappers (static inline functions)
 uint16_t rust_eth_rx_burst(uint16_t port_id, uint16_t queue_id,
                            struct rte_mbuf **rx_pkts, uint16_t nb_pkts);
 uint16_t rust_eth_tx_burst(uint16_t port_id, uint16_t queue_id,
                            struct rte_mbuf **tx_pkts, uint16_t nb_pkts);
+int rust_eth_rx_queue_count(uint16_t port_id, uint16_t queue_id);
+
+// Ring wrappers (static inline functions)
+unsigned int rust_ring_enqueue_burst(struct rte_ring *r, void *const *objs, unsigned int n);
+unsigned int rust_ring_dequeue_burst(struct rte_ring *r, void **objs, unsigned int n);
+unsigned int rust_ring_count(const struct rte_ring *r);
+unsigned int rust_ring_capacity(const struct rte_ring *r);
+
+// Link status wrapper (rte_eth_link uses bitfields inside a union)
+struct rust_eth_link {
+    uint32_t speed;
+    uint8_t duplex;
+    uint8_t autoneg;
+    uint8_t status;
+};
+int rust_eth_link_get(uint16_t port_id, struct rust_eth_link *link, int wait);
+
+// rte_flow item setters (item structs wrap protocol headers in anonymous
+// unions, so fields are filled in C). Values are host byte order.
+void rust_flow_item_eth_set(struct rte_flow_item_eth *item, const uint8_t *dst,
+                            const uint8_t *src, uint16_t ether_type);
+void rust_flow_item_ipv4_set(struct rte_flow_item_ipv4 *item, uint32_t src,
+                             uint32_t dst, uint8_t proto);
+void rust_flow_item_ipv6_set(struct rte_flow_item_ipv6 *item, const uint8_t *src,
+                             const uint8_t *dst, uint8_t proto);
+void rust_flow_item_tcp_set(struct rte_flow_item_tcp *item, uint16_t src_port,
+                            uint16_t dst_port);
+void rust_flow_item_udp_set(struct rte_flow_item_udp *item, uint16_t src_port,
+                            uint16_t dst_port);
+
+// Device capability flags (expanded from RTE_BIT32 macros for bindgen)
+static const uint32_t RUST_RTE_ETH_DEV_INTR_LSC = RTE_ETH_DEV_INTR_LSC;
+static const uint32_t RUST_RTE_ETH_DEV_INTR_RMV = RTE_ETH_DEV_INTR_RMV;
+
+// Ring creation flags
+static const unsigned int RUST_RING_F_SP_ENQ = RING_F_SP_ENQ;
+static const unsigned int RUST_RING_F_SC_DEQ = RING_F_SC_DEQ;
+static const unsigned int RUST_RING_F_EXACT_SZ = RING_F_EXACT_SZ;
 
 // RSS hash type constants (expanded from RTE_BIT64 macros for bindgen)
 static const uint64_t RUST_RTE_ETH_RSS_IPV4 = RTE_ETH_RSS_IPV4;
@@ -55,4 +123,40 @@ static const uint64_t RUST_RTE_ETH_RSS_IP = RTE_ETH_RSS_IP;
 static const uint64_t RUST_RTE_ETH_RSS_TCP = RTE_ETH_RSS_TCP;
 static const uint64_t RUST_RTE_ETH_RSS_UDP = RTE_ETH_RSS_UDP;
 
+// Checksum offload capability flags (expanded from RTE_BIT64 macros for bindgen)
+static const uint64_t RUST_RTE_ETH_RX_OFFLOAD_IPV4_CKSUM = RTE_ETH_RX_OFFLOAD_IPV4_CKSUM;
+static const uint64_t RUST_RTE_ETH_RX_OFFLOAD_UDP_CKSUM = RTE_ETH_RX_OFFLOAD_UDP_CKSUM;
+static const uint64_t RUST_RTE_ETH_RX_OFFLOAD_TCP_CKSUM = RTE_ETH_RX_OFFLOAD_TCP_CKSUM;
+static const uint64_t RUST_RTE_ETH_RX_OFFLOAD_SCATTER = RTE_ETH_RX_OFFLOAD_SCATTER;
+static const uint64_t RUST_RTE_ETH_TX_OFFLOAD_IPV4_CKSUM = RTE_ETH_TX_OFFLOAD_IPV4_CKSUM;
+static const uint64_t RUST_RTE_ETH_TX_OFFLOAD_UDP_CKSUM = RTE_ETH_TX_OFFLOAD_UDP_CKSUM;
+static const uint64_t RUST_RTE_ETH_TX_OFFLOAD_TCP_CKSUM = RTE_ETH_TX_OFFLOAD_TCP_CKSUM;
+static const uint64_t RUST_RTE_ETH_TX_OFFLOAD_TCP_TSO = RTE_ETH_TX_OFFLOAD_TCP_TSO;
+static const uint64_t RUST_RTE_ETH_TX_OFFLOAD_MULTI_SEGS = RTE_ETH_TX_OFFLOAD_MULTI_SEGS;
+
+// VLAN offload capability flags
+static const uint64_t RUST_RTE_ETH_RX_OFFLOAD_VLAN_STRIP = RTE_ETH_RX_OFFLOAD_VLAN_STRIP;
+static const uint64_t RUST_RTE_ETH_RX_OFFLOAD_VLAN_FILTER = RTE_ETH_RX_OFFLOAD_VLAN_FILTER;
+static const uint64_t RUST_RTE_ETH_TX_OFFLOAD_VLAN_INSERT = RTE_ETH_TX_OFFLOAD_VLAN_INSERT;
+
+// Mbuf offload flags (expanded from RTE_BIT64 macros for bindgen)
+static const uint64_t RUST_RTE_MBUF_F_RX_IP_CKSUM_MASK = RTE_MBUF_F_RX_IP_CKSUM_MASK;
+static const uint64_t RUST_RTE_MBUF_F_RX_IP_CKSUM_GOOD = RTE_MBUF_F_RX_IP_CKSUM_GOOD;
+static const uint64_t RUST_RTE_MBUF_F_RX_IP_CKSUM_BAD = RTE_MBUF_F_RX_IP_CKSUM_BAD;
+static const uint64_t RUST_RTE_MBUF_F_RX_IP_CKSUM_NONE = RTE_MBUF_F_RX_IP_CKSUM_NONE;
+static const uint64_t RUST_RTE_MBUF_F_RX_L4_CKSUM_MASK = RTE_MBUF_F_RX_L4_CKSUM_MASK;
+static const uint64_t RUST_RTE_MBUF_F_RX_L4_CKSUM_GOOD = RTE_MBUF_F_RX_L4_CKSUM_GOOD;
+static const uint64_t RUST_RTE_MBUF_F_RX_L4_CKSUM_BAD = RTE_MBUF_F_RX_L4_CKSUM_BAD;
+static const uint64_t RUST_RTE_MBUF_F_RX_L4_CKSUM_NONE = RTE_MBUF_F_RX_L4_CKSUM_NONE;
+static const uint64_t RUST_RTE_MBUF_F_TX_IPV4 = RTE_MBUF_F_TX_IPV4;
+static const uint64_t RUST_RTE_MBUF_F_TX_IPV6 = RTE_MBUF_F_TX_IPV6;
+static const uint64_t RUST_RTE_MBUF_F_TX_IP_CKSUM = RTE_MBUF_F_TX_IP_CKSUM;
+static const uint64_t RUST_RTE_MBUF_F_TX_TCP_CKSUM = RTE_MBUF_F_TX_TCP_CKSUM;
+static const uint64_t RUST_RTE_MBUF_F_TX_UDP_CKSUM = RTE_MBUF_F_TX_UDP_CKSUM;
+static const uint64_t RUST_RTE_MBUF_F_TX_TCP_SEG = RTE_MBUF_F_TX_TCP_SEG;
+static const uint64_t RUST_RTE_MBUF_F_RX_VLAN = RTE_MBUF_F_RX_VLAN;
+static const uint64_t RUST_RTE_MBUF_F_RX_VLAN_STRIPPED = RTE_MBUF_F_RX_VLAN_STRIPPED;
+static const uint64_t RUST_RTE_MBUF_F_TX_VLAN = RTE_MBUF_F_TX_VLAN;
+static const uint64_t RUST_RTE_MBUF_F_RX_RSS_HASH = RTE_MBUF_F_RX_RSS_HASH;
+
 #endif // DPDK_WRAPPER_H
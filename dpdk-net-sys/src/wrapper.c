@@ -1,5 +1,9 @@
 #include "wrapper.h"
 #include <rte_errno.h>
+#include <rte_ip.h>
+#include <rte_tcp.h>
+#include <rte_udp.h>
+#include <string.h>
 
 int rust_get_rte_errno(void) {
     return rte_errno;
@@ -70,6 +74,140 @@ uint16_t rust_pktmbuf_data_room_size(struct rte_mempool *mp) {
     return rte_pktmbuf_data_room_size(mp);
 }
 
+void rust_pktmbuf_set_tx_offload(struct rte_mbuf *m, uint64_t ol_flags, uint16_t l2_len,
+                                 uint16_t l3_len, uint16_t l4_len) {
+    m->ol_flags |= ol_flags;
+    m->l2_len = l2_len;
+    m->l3_len = l3_len;
+    m->l4_len = l4_len;
+}
+
+int rust_pktmbuf_tx_cksum_prepare(struct rte_mbuf *m) {
+    // Zeroes the IPv4 header checksum and writes the L4 pseudo-header
+    // checksum, as most NICs expect for checksum offload
+    return rte_net_intel_cksum_prepare(m);
+}
+
+void rust_pktmbuf_set_tso_segsz(struct rte_mbuf *m, uint16_t tso_segsz) {
+    m->tso_segsz = tso_segsz;
+}
+
+uint16_t rust_pktmbuf_l2_len(const struct rte_mbuf *m) {
+    return m->l2_len;
+}
+
+void rust_pktmbuf_set_l2_len(struct rte_mbuf *m, uint16_t l2_len) {
+    m->l2_len = l2_len;
+}
+
+uint32_t rust_pktmbuf_rss_hash(const struct rte_mbuf *m) {
+    return m->hash.rss;
+}
+
+int rust_pktmbuf_chain(struct rte_mbuf *head, struct rte_mbuf *tail) {
+    return rte_pktmbuf_chain(head, tail);
+}
+
+struct rte_mbuf* rust_pktmbuf_next(const struct rte_mbuf *m) {
+    return m->next;
+}
+
+uint16_t rust_pktmbuf_nb_segs(const struct rte_mbuf *m) {
+    return m->nb_segs;
+}
+
+struct rte_mbuf* rust_pktmbuf_lastseg(struct rte_mbuf *m) {
+    return rte_pktmbuf_lastseg(m);
+}
+
+int rust_pktmbuf_linearize(struct rte_mbuf *m) {
+    return rte_pktmbuf_linearize(m);
+}
+
+void rust_pktmbuf_sw_cksum(struct rte_mbuf *m) {
+    // IPv4 header and TCP/UDP checksums in software, for packets whose
+    // headers (l2_len/l3_len) are in the first segment. Handles chains.
+    struct rte_ipv4_hdr *ip = rte_pktmbuf_mtod_offset(m, struct rte_ipv4_hdr *, m->l2_len);
+    uint16_t l4_off = m->l2_len + m->l3_len;
+
+    ip->hdr_checksum = 0;
+    ip->hdr_checksum = rte_ipv4_cksum(ip);
+    if (ip->next_proto_id == IPPROTO_TCP) {
+        struct rte_tcp_hdr *tcp = rte_pktmbuf_mtod_offset(m, struct rte_tcp_hdr *, l4_off);
+        tcp->cksum = 0;
+        tcp->cksum = rte_ipv4_udptcp_cksum_mbuf(m, ip, l4_off);
+    } else if (ip->next_proto_id == IPPROTO_UDP) {
+        struct rte_udp_hdr *udp = rte_pktmbuf_mtod_offset(m, struct rte_udp_hdr *, l4_off);
+        udp->dgram_cksum = 0;
+        udp->dgram_cksum = rte_ipv4_udptcp_cksum_mbuf(m, ip, l4_off);
+    }
+    m->ol_flags &= ~(RTE_MBUF_F_TX_IP_CKSUM | RTE_MBUF_F_TX_L4_MASK | RTE_MBUF_F_TX_TCP_SEG);
+}
+
+unsigned int rust_mempool_size(const struct rte_mempool *mp) {
+    return mp->size;
+}
+
+unsigned int rust_mempool_cache_size(const struct rte_mempool *mp) {
+    return mp->cache_size;
+}
+
+unsigned int rust_mempool_cache_count(const struct rte_mempool *mp, unsigned int lcore_id) {
+    if (mp->cache_size == 0 || lcore_id >= RTE_MAX_LCORE)
+        return 0;
+    return mp->local_cache[lcore_id].len;
+}
+
+// Move every lcore's cached objects back to the common pool.
+// Only safe while no other thread uses the pool.
+void rust_mempool_flush_caches(struct rte_mempool *mp) {
+    if (mp->cache_size == 0)
+        return;
+    for (unsigned int i = 0; i < RTE_MAX_LCORE; i++)
+        rte_mempool_cache_flush(&mp->local_cache[i], mp);
+}
+
+int rust_mempool_get(struct rte_mempool *mp, void **obj) {
+    return rte_mempool_get(mp, obj);
+}
+
+void rust_mempool_put(struct rte_mempool *mp, void *obj) {
+    rte_mempool_put(mp, obj);
+}
+
+unsigned int rust_ring_enqueue_burst(struct rte_ring *r, void *const *objs, unsigned int n) {
+    return rte_ring_enqueue_burst(r, objs, n, NULL);
+}
+
+unsigned int rust_ring_dequeue_burst(struct rte_ring *r, void **objs, unsigned int n) {
+    return rte_ring_dequeue_burst(r, objs, n, NULL);
+}
+
+unsigned int rust_ring_count(const struct rte_ring *r) {
+    return rte_ring_count(r);
+}
+
+unsigned int rust_ring_capacity(const struct rte_ring *r) {
+    return rte_ring_get_capacity(r);
+}
+
+int rust_gso_segment(struct rte_mbuf *pkt, struct rte_mempool *direct_pool,
+                     struct rte_mempool *indirect_pool, uint16_t gso_size,
+                     struct rte_mbuf **pkts_out, uint16_t nb_pkts_out) {
+    struct rte_gso_ctx ctx = {
+        .direct_pool = direct_pool,
+        .indirect_pool = indirect_pool,
+        .flag = 0,
+        .gso_types = RTE_ETH_TX_OFFLOAD_TCP_TSO,
+        .gso_size = gso_size,
+    };
+    int ret = rte_gso_segment(pkt, &ctx, pkts_out, nb_pkts_out);
+    if (ret < 0) {
+        rte_errno = -ret;
+    }
+    return ret;
+}
+
 uint16_t rust_eth_rx_burst(uint16_t port_id, uint16_t queue_id,
                            struct rte_mbuf **rx_pkts, uint16_t nb_pkts) {
     return rte_eth_rx_burst(port_id, queue_id, rx_pkts, nb_pkts);
@@ -79,3 +217,55 @@ uint16_t rust_eth_tx_burst(uint16_t port_id, uint16_t queue_id,
                            struct rte_mbuf **tx_pkts, uint16_t nb_pkts) {
     return rte_eth_tx_burst(port_id, queue_id, tx_pkts, nb_pkts);
 }
+
+int rust_eth_rx_queue_count(uint16_t port_id, uint16_t queue_id) {
+    return rte_eth_rx_queue_count(port_id, queue_id);
+}
+
+int rust_eth_link_get(uint16_t port_id, struct rust_eth_link *link, int wait) {
+    struct rte_eth_link l;
+    int ret = wait ? rte_eth_link_get(port_id, &l) : rte_eth_link_get_nowait(port_id, &l);
+    if (ret < 0) {
+        // ethdev returns -errno; mirror it into rte_errno for check_rte_success
+        rte_errno = -ret;
+        return ret;
+    }
+    link->speed = l.link_speed;
+    link->duplex = l.link_duplex;
+    link->autoneg = l.link_autoneg;
+    link->status = l.link_status;
+    return 0;
+}
+
+void rust_flow_item_eth_set(struct rte_flow_item_eth *item, const uint8_t *dst,
+                            const uint8_t *src, uint16_t ether_type) {
+    memcpy(&item->hdr.dst_addr, dst, RTE_ETHER_ADDR_LEN);
+    memcpy(&item->hdr.src_addr, src, RTE_ETHER_ADDR_LEN);
+    item->hdr.ether_type = rte_cpu_to_be_16(ether_type);
+}
+
+void rust_flow_item_ipv4_set(struct rte_flow_item_ipv4 *item, uint32_t src,
+                             uint32_t dst, uint8_t proto) {
+    item->hdr.src_addr = rte_cpu_to_be_32(src);
+    item->hdr.dst_addr = rte_cpu_to_be_32(dst);
+    item->hdr.next_proto_id = proto;
+}
+
+void rust_flow_item_ipv6_set(struct rte_flow_item_ipv6 *item, const uint8_t *src,
+                             const uint8_t *dst, uint8_t proto) {
+    memcpy(&item->hdr.src_addr, src, 16);
+    memcpy(&item->hdr.dst_addr, dst, 16);
+    item->hdr.proto = proto;
+}
+
+void rust_flow_item_tcp_set(struct rte_flow_item_tcp *item, uint16_t src_port,
+                            uint16_t dst_port) {
+    item->hdr.src_port = rte_cpu_to_be_16(src_port);
+    item->hdr.dst_port = rte_cpu_to_be_16(dst_port);
+}
+
+void rust_flow_item_udp_set(struct rte_flow_item_udp *item, uint16_t src_port,
+                            uint16_t dst_port) {
+    item->hdr.src_port = rte_cpu_to_be_16(src_port);
+    item->hdr.dst_port = rte_cpu_to_be_16(dst_port);
+}
@@ -4,6 +4,9 @@
 //! for setting up a multi-queue DPDK server:
 //! - Ethernet device configuration
 //! - Per-queue worker threads with tokio runtimes
+//! - Link status monitoring (LSC events when the device supports them)
+//! - Software RSS dispatcher for multi-queue on NICs without hardware RSS
+//! - Cross-queue redirection of TCP segments that RSS sends to the wrong queue
 //! - Graceful shutdown with CancellationToken
 //!
 //! You provide a factory function that creates your server given a `TcpListener`.
@@ -50,13 +53,19 @@
 //! ```
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 
 use dpdk_net::api::rte::eth::{EthConf, EthDev, rss_hf};
+use dpdk_net::api::rte::event::{EthEventStream, EthEventType};
+use dpdk_net::api::rte::queue::RxQueue;
 use dpdk_net::api::rte::thread::{ThreadRegistration, set_cpu_affinity};
-use dpdk_net::device::SharedArpCache;
-use dpdk_net::runtime::Reactor;
-use dpdk_net::socket::TcpListener;
+use dpdk_net::device::{
+    ArpAnnounceConfig, DEFAULT_FLOW_REDIRECT_RING_SIZE, DEFAULT_SOFT_RSS_RING_SIZE, FlowRedirect,
+    SharedArpCache, SoftRss, SoftRssQueue,
+};
+use dpdk_net::runtime::{Reactor, ReactorHandle};
+use dpdk_net::socket::{RssPortAllocator, TcpListener};
 
 use smoltcp::iface::{Config, Interface};
 use smoltcp::time::Instant;
@@ -74,12 +83,18 @@ use crate::eth_dev_config::EthDevConfig;
 pub struct ServerContext {
     /// The TCP listener bound to the server port
     pub listener: TcpListener,
+    /// This queue's reactor, for outbound connections
+    /// (`TcpStream::connect_ephemeral` picks ports that RSS maps back here)
+    pub reactor: ReactorHandle,
     /// Cancellation token for graceful shutdown
     pub cancel: CancellationToken,
     /// Queue ID (0-based)
     pub queue_id: usize,
     /// Server port number
     pub port: u16,
+    /// Current link state of the port, updated on link status change events.
+    /// Servers can use this to fail readiness checks while the link is down.
+    pub link_up: Arc<AtomicBool>,
 }
 
 /// Builder for configuring and running a multi-queue DPDK server.
@@ -288,20 +303,32 @@ impl DpdkServerRunner {
         let total_mbufs = self.mbufs_per_queue * num_queues as u32;
 
         // Only enable RSS if the device supports it (reta_size > 0)
-        let eth_conf = if reta_size > 0 {
+        let mut eth_conf = if reta_size > 0 {
             EthConf::new().rss_with_hash(rss_hf::NONFRAG_IPV4_TCP | rss_hf::NONFRAG_IPV6_TCP)
+        } else if num_queues > 1 {
+            info!("Device does not support RSS (reta_size=0), using software RSS dispatcher");
+            EthConf::new()
         } else {
             info!("Device does not support RSS (reta_size=0), using simple queue mode");
             EthConf::new()
         };
 
+        // Enable link status change interrupts if the device can raise them
+        let lsc_supported = EthDev::new(0).supports_lsc_interrupt().unwrap_or(false);
+        if lsc_supported {
+            eth_conf = eth_conf.lsc_interrupt();
+        } else {
+            info!("Device does not support LSC interrupts, link changes will not be reported");
+        }
+
         let eth_dev_config = EthDevConfig::new()
             .mempool_name("server_pool")
             .num_mbufs(total_mbufs)
             .nb_queues(num_queues as u16)
             .rx_desc(self.rx_desc)
             .tx_desc(self.tx_desc)
-            .eth_conf(eth_conf);
+            .eth_conf(eth_conf)
+            .checksum_offload(true);
 
         let (mempool, eth_dev) = eth_dev_config
             .clone()
@@ -339,12 +366,67 @@ impl DpdkServerRunner {
             }
         }
 
+        // Without hardware RSS, one thread reads RX queue 0 and spreads flows
+        // over per-queue rings that the workers read instead
+        let soft_rss = (reta_size == 0 && num_queues > 1).then(|| {
+            SoftRss::new(
+                RxQueue::new(0, 0),
+                num_queues as u16,
+                DEFAULT_SOFT_RSS_RING_SIZE,
+            )
+            .expect("Failed to create software RSS rings")
+        });
+        let soft_rss_queues: Option<Vec<SoftRssQueue>> = soft_rss.as_ref().map(|soft_rss| {
+            (0..soft_rss.nb_queues())
+                .filter_map(|q| soft_rss.queue(q))
+                .collect()
+        });
+
+        // Segments hashed to a queue other than their connection's (asymmetric
+        // hashing, client connections) are handed to the owning queue
+        let flow_redirect = (num_queues > 1).then(|| {
+            FlowRedirect::new(0, num_queues as u16, DEFAULT_FLOW_REDIRECT_RING_SIZE)
+                .expect("Failed to create flow redirect rings")
+        });
+        let flow_redirect_for_stats = flow_redirect.clone();
+
+        // Local ports for outbound connections whose replies RSS steers back
+        // to the connecting queue
+        let port_allocators: Vec<RssPortAllocator> = (0..num_queues as u16)
+            .map(|queue_id| match &soft_rss {
+                Some(soft_rss) => {
+                    RssPortAllocator::new(*soft_rss.key(), soft_rss.reta().to_vec(), queue_id)
+                        .expect("Software RSS key and RETA are always valid")
+                }
+                None if num_queues > 1 => RssPortAllocator::from_eth_dev(&eth_dev, queue_id)
+                    .unwrap_or_else(|e| {
+                        warn!(queue_id, error = %e, "No RSS-aware port selection, relying on flow redirect");
+                        RssPortAllocator::any_queue()
+                    }),
+                None => RssPortAllocator::any_queue(),
+            })
+            .collect();
+
         // Get MAC address
         let mac = eth_dev.mac_addr().expect("Failed to get MAC address");
         let mac_addr = EthernetAddress(mac.addr_bytes);
 
         self.print_interface_info(ip_addr, mac_addr, gateway);
 
+        let link_up = match eth_dev.link() {
+            Ok(link) => {
+                info!(%link, "Link status");
+                if !link.up {
+                    warn!("Link is down");
+                }
+                Arc::new(AtomicBool::new(link.up))
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to query link status, assuming up");
+                Arc::new(AtomicBool::new(true))
+            }
+        };
+
         // Setup Ctrl+C handler
         let cancel = CancellationToken::new();
         let cancel_clone = cancel.clone();
@@ -354,6 +436,10 @@ impl DpdkServerRunner {
         })
         .expect("Failed to set Ctrl+C handler");
 
+        let link_monitor =
+            lsc_supported.then(|| Self::spawn_link_monitor(cancel.clone(), link_up.clone()));
+        let dispatcher = soft_rss.map(|soft_rss| Self::spawn_soft_rss(soft_rss, cancel.clone()));
+
         let start_time = std::time::Instant::now();
 
         // Wrap factory in Arc for sharing across threads
@@ -383,7 +469,11 @@ impl DpdkServerRunner {
             ip_addr,
             gateway,
             shared_arp_cache.clone(),
+            soft_rss_queues.as_deref(),
+            flow_redirect.clone(),
+            &port_allocators,
             factory.clone(),
+            link_up.clone(),
         );
 
         // Run queue 0 on the current thread
@@ -396,7 +486,11 @@ impl DpdkServerRunner {
             ip_addr,
             gateway,
             shared_arp_cache,
+            soft_rss_queues.and_then(|queues| queues.into_iter().next()),
+            flow_redirect,
+            port_allocators[0].clone(),
             factory,
+            link_up,
             self.port,
             self.tcp_rx_buffer,
             self.tcp_tx_buffer,
@@ -407,6 +501,12 @@ impl DpdkServerRunner {
         for handle in handles {
             let _ = handle.join();
         }
+        if let Some(handle) = link_monitor {
+            let _ = handle.join();
+        }
+        if let Some(handle) = dispatcher {
+            let _ = handle.join();
+        }
 
         let runtime_secs = start_time.elapsed().as_secs();
 
@@ -415,11 +515,22 @@ impl DpdkServerRunner {
             info!(
                 runtime_secs,
                 arp_cache_version = cache.version(),
+                arp_cache_entries = cache.len(),
                 "Server stopped"
             );
         } else {
             info!(runtime_secs, "Server stopped");
         }
+        if let Some(redirect) = flow_redirect_for_stats {
+            for (queue_id, stats) in redirect.stats().iter().enumerate() {
+                info!(
+                    queue_id,
+                    redirected = stats.redirected,
+                    dropped = stats.dropped,
+                    "Flow redirect queue stats"
+                );
+            }
+        }
 
         self.cleanup(eth_dev, num_queues);
         drop(mempool);
@@ -436,7 +547,11 @@ impl DpdkServerRunner {
         ip_addr: Ipv4Address,
         gateway: Ipv4Address,
         shared_arp_cache: Option<SharedArpCache>,
+        rx_ring: Option<SoftRssQueue>,
+        flow_redirect: Option<FlowRedirect>,
+        port_allocator: RssPortAllocator,
         factory: Arc<F>,
+        link_up: Arc<AtomicBool>,
         port: u16,
         tcp_rx: usize,
         tcp_tx: usize,
@@ -461,6 +576,12 @@ impl DpdkServerRunner {
 
         // Create DPDK device using shared config
         let mut device = eth_dev_config.create_device(mempool, queue_id as u16);
+        if let Some(rx_ring) = rx_ring {
+            device = device.with_soft_rss(rx_ring);
+        }
+        if let Some(redirect) = flow_redirect {
+            device = device.with_flow_redirect(redirect, queue_id as u16);
+        }
 
         // Enable shared ARP cache for multi-queue setups
         if let Some(cache) = shared_arp_cache {
@@ -478,6 +599,13 @@ impl DpdkServerRunner {
             }
         }
 
+        // Resolve the gateway and announce ourselves before the first connection
+        device = device.with_arp_announce(
+            ArpAnnounceConfig::new().gateway(gateway),
+            mac_addr.0,
+            ip_addr,
+        );
+
         // Configure smoltcp interface
         let config = Config::new(mac_addr.into());
         let mut iface = Interface::new(config, &mut device, Instant::now());
@@ -497,7 +625,7 @@ impl DpdkServerRunner {
 
         local.block_on(&rt, async {
             // Create reactor
-            let reactor = Reactor::new(device, iface);
+            let reactor = Reactor::new(device, iface).with_port_allocator(port_allocator);
             let handle = reactor.handle();
 
             // Create cancel flag for reactor - set when factory finishes
@@ -516,9 +644,11 @@ impl DpdkServerRunner {
             // Create and run server
             let ctx = ServerContext {
                 listener,
+                reactor: handle,
                 cancel,
                 queue_id,
                 port,
+                link_up,
             };
             factory(ctx).await;
 
@@ -541,7 +671,11 @@ impl DpdkServerRunner {
         ip_addr: Ipv4Address,
         gateway: Ipv4Address,
         shared_arp_cache: Option<SharedArpCache>,
+        soft_rss_queues: Option<&[SoftRssQueue]>,
+        flow_redirect: Option<FlowRedirect>,
+        port_allocators: &[RssPortAllocator],
         factory: Arc<F>,
+        link_up: Arc<AtomicBool>,
     ) -> Vec<thread::JoinHandle<()>>
     where
         F: Fn(ServerContext) -> Fut + Send + Sync + 'static,
@@ -558,6 +692,10 @@ impl DpdkServerRunner {
             let eth_dev_config = eth_dev_config.clone();
             let factory = factory.clone();
             let shared_arp_cache = shared_arp_cache.clone();
+            let rx_ring = soft_rss_queues.and_then(|queues| queues.get(queue_id).cloned());
+            let flow_redirect = flow_redirect.clone();
+            let port_allocator = port_allocators.get(queue_id).cloned().unwrap_or_default();
+            let link_up = link_up.clone();
             let port = self.port;
             let tcp_rx = self.tcp_rx_buffer;
             let tcp_tx = self.tcp_tx_buffer;
@@ -575,7 +713,11 @@ impl DpdkServerRunner {
                         ip_addr,
                         gateway,
                         shared_arp_cache,
+                        rx_ring,
+                        flow_redirect,
+                        port_allocator,
                         factory,
+                        link_up,
                         port,
                         tcp_rx,
                         tcp_tx,
@@ -590,6 +732,85 @@ impl DpdkServerRunner {
         handles
     }
 
+    /// Spawn the software RSS dispatcher thread, which reads RX queue 0 until
+    /// cancelled.
+    fn spawn_soft_rss(mut soft_rss: SoftRss, cancel: CancellationToken) -> thread::JoinHandle<()> {
+        thread::Builder::new()
+            .name("soft-rss".to_string())
+            .spawn(move || {
+                let _dpdk_registration = ThreadRegistration::new()
+                    .expect("Failed to register dispatcher thread with DPDK");
+                while !cancel.is_cancelled() {
+                    // Amortize the cancellation check over many bursts
+                    for _ in 0..1024 {
+                        if soft_rss.poll() == 0 {
+                            std::hint::spin_loop();
+                        }
+                    }
+                }
+                for (queue_id, stats) in soft_rss.stats().iter().enumerate() {
+                    info!(
+                        queue_id,
+                        dispatched = stats.dispatched,
+                        dropped = stats.dropped,
+                        "Software RSS queue stats"
+                    );
+                }
+            })
+            .expect("Failed to spawn software RSS thread")
+    }
+
+    /// Spawn a thread that logs link status changes and updates `link_up`.
+    fn spawn_link_monitor(
+        cancel: CancellationToken,
+        link_up: Arc<AtomicBool>,
+    ) -> thread::JoinHandle<()> {
+        thread::Builder::new()
+            .name("link-monitor".to_string())
+            .spawn(move || {
+                let mut events = match EthEventStream::new(
+                    0,
+                    &[
+                        EthEventType::LinkStatusChange,
+                        EthEventType::Removal,
+                        EthEventType::Destroy,
+                    ],
+                ) {
+                    Ok(events) => events,
+                    Err(e) => {
+                        warn!(error = %e, "Failed to register link event callback");
+                        return;
+                    }
+                };
+                let rt = Builder::new_current_thread().build().unwrap();
+                rt.block_on(async {
+                    loop {
+                        tokio::select! {
+                            _ = cancel.cancelled() => break,
+                            event = events.recv() => match event.event_type {
+                                EthEventType::LinkStatusChange => {
+                                    let Some(link) = event.link else { continue };
+                                    if link.up {
+                                        info!(%link, "Link up");
+                                    } else {
+                                        warn!("Link down");
+                                    }
+                                    link_up.store(link.up, Ordering::Relaxed);
+                                }
+                                EthEventType::Removal | EthEventType::Destroy => {
+                                    warn!("Device removed, shutting down");
+                                    link_up.store(false, Ordering::Relaxed);
+                                    cancel.cancel();
+                                }
+                                EthEventType::New => {}
+                            },
+                        }
+                    }
+                });
+            })
+            .expect("Failed to spawn link monitor thread")
+    }
+
     fn print_banner(
         &self,
         ip_addr: Ipv4Address,
@@ -625,12 +846,26 @@ impl DpdkServerRunner {
 
     fn cleanup(&self, eth_dev: EthDev, _num_queues: usize) {
         // Print device stats before cleanup
-        // Note: Per-queue stats (q_ipackets, q_opackets) were removed in DPDK 25.11.0
-        if let Ok(stats) = eth_dev.stats() {
+        // Per-queue counters come from xstats (removed from rte_eth_stats in DPDK 25.11.0)
+        if let Ok(stats) = eth_dev.eth_stats() {
             info!(
-                "Device stats: ipackets={}, opackets={}, ibytes={}, obytes={}",
-                stats.ipackets, stats.opackets, stats.ibytes, stats.obytes
+                "Device stats: ipackets={}, opackets={}, ibytes={}, obytes={}, imissed={}, rx_nombuf={}",
+                stats.ipackets,
+                stats.opackets,
+                stats.ibytes,
+                stats.obytes,
+                stats.imissed,
+                stats.rx_nombuf
             );
+            for (queue_id, q) in stats.rx_queues.iter().enumerate() {
+                debug!(
+                    queue_id,
+                    packets = q.packets,
+                    bytes = q.bytes,
+                    errors = q.errors,
+                    "RX queue stats"
+                );
+            }
         }
 
         debug!("Cleaning up");
@@ -8,6 +8,7 @@
 
 use dpdk_net::api::rte::eal::{Eal, EalBuilder};
 use dpdk_net::api::rte::eth::EthDev;
+use dpdk_net::device::{FaultConfig, FaultyDevice};
 
 use crate::eth_dev_config::EthDevConfig;
 
@@ -143,6 +144,12 @@ impl DpdkTestContextBuilder {
         self
     }
 
+    /// Enable TCP segmentation offload (hardware or software GSO).
+    pub fn tso(mut self, enabled: bool) -> Self {
+        self.eth_dev_config = self.eth_dev_config.tso(enabled);
+        self
+    }
+
     /// Build the test context and DpdkDevice.
     ///
     /// Returns the context (which must be kept alive) and the device for smoltcp/Reactor.
@@ -167,6 +174,18 @@ impl DpdkTestContextBuilder {
         Ok((context, device))
     }
 
+    /// Build the test context and a DpdkDevice wrapped in a [`FaultyDevice`].
+    ///
+    /// Use [`FaultyDevice::handle`] before handing the device to a reactor
+    /// to change the faults or read the counters during the test.
+    pub fn build_with_faults(
+        self,
+        faults: FaultConfig,
+    ) -> Result<(DpdkTestContext, FaultyDevice<DpdkDevice>), dpdk_net::api::Errno> {
+        let (context, device) = self.build()?;
+        Ok((context, FaultyDevice::new(device, faults)))
+    }
+
     /// Build only the EthDev and DpdkDevice, assuming EAL is already initialized.
     ///
     /// Use this when you have a global EAL and want to recreate devices per test.
@@ -8,9 +8,10 @@ use std::sync::Arc;
 
 use dpdk_net::api::Errno;
 use dpdk_net::api::rte::eth::{EthConf, EthDev, EthDevBuilder, RxQueueConf, TxQueueConf};
+use dpdk_net::api::rte::gso::GsoContext;
 use dpdk_net::api::rte::pktmbuf::{MemPool, MemPoolConfig};
 use dpdk_net::api::rte::queue::{RxQueue, TxQueue};
-use dpdk_net::device::DpdkDevice;
+use dpdk_net::device::{DpdkDevice, TsoConfig};
 
 /// Default headroom reserved at the front of each mbuf (matches RTE_PKTMBUF_HEADROOM)
 pub const DEFAULT_MBUF_HEADROOM: usize = 128;
@@ -56,6 +57,8 @@ pub struct EthDevConfig {
     pub(crate) mtu: usize,
     pub(crate) port_id: u16,
     pub(crate) eth_conf: Option<EthConf>,
+    pub(crate) checksum_offload: bool,
+    pub(crate) tso: bool,
 }
 
 impl Default for EthDevConfig {
@@ -78,6 +81,8 @@ impl EthDevConfig {
             mtu: DEFAULT_MTU,
             port_id: 0,
             eth_conf: None,
+            checksum_offload: false,
+            tso: false,
         }
     }
 
@@ -138,6 +143,9 @@ impl EthDevConfig {
     }
 
     /// Set the MTU.
+    ///
+    /// Also used as the port MTU unless a custom `eth_conf` is set. MTUs
+    /// larger than one mbuf (jumbo frames) use scatter RX and multi-segment TX.
     pub fn mtu(mut self, mtu: usize) -> Self {
         self.mtu = mtu;
         self
@@ -151,12 +159,29 @@ impl EthDevConfig {
 
     /// Set custom ethernet configuration.
     ///
-    /// If not set, uses `EthConf::new()` (default configuration).
+    /// If not set, uses `EthConf::new()` with the configured MTU.
     pub fn eth_conf(mut self, conf: EthConf) -> Self {
         self.eth_conf = Some(conf);
         self
     }
 
+    /// Enable checksum offload (default: disabled).
+    ///
+    /// Devices created with [`create_device`](Self::create_device) leave the
+    /// checksums the NIC supports to the NIC.
+    pub fn checksum_offload(mut self, enabled: bool) -> Self {
+        self.checksum_offload = enabled;
+        self
+    }
+
+    /// Enable TCP segmentation offload (default: disabled).
+    ///
+    /// Uses the NIC's TSO when available, software GSO otherwise.
+    pub fn tso(mut self, enabled: bool) -> Self {
+        self.tso = enabled;
+        self
+    }
+
     /// Get the mbuf capacity (data room size minus headroom).
     pub fn mbuf_capacity(&self) -> usize {
         self.data_room_size as usize - DEFAULT_MBUF_HEADROOM
@@ -174,7 +199,9 @@ impl EthDevConfig {
         let mempool = Arc::new(MemPool::create(self.mempool_name.clone(), &mempool_config)?);
 
         // Configure and start ethernet device
-        let eth_conf = self.eth_conf.unwrap_or_default();
+        let eth_conf = self
+            .eth_conf
+            .unwrap_or_else(|| EthConf::new().mtu(self.mtu as u32));
 
         let eth_dev = EthDevBuilder::new(self.port_id)
             .eth_conf(eth_conf)
@@ -182,6 +209,8 @@ impl EthDevConfig {
             .nb_tx_queues(self.nb_tx_queues)
             .rx_queue_conf(RxQueueConf::new().nb_desc(self.rx_desc))
             .tx_queue_conf(TxQueueConf::new().nb_desc(self.tx_desc))
+            .checksum_offload(self.checksum_offload)
+            .tso(self.tso)
             .build(&mempool)?;
 
         Ok((mempool, eth_dev))
@@ -190,9 +219,32 @@ impl EthDevConfig {
     /// Create a DpdkDevice for the specified queue.
     ///
     /// The mempool should be the one returned from `build()`.
+    /// Checksum offloads negotiated by `build()` are applied to the device.
+    /// With TSO enabled, ports without hardware TSO get a per-queue pool of
+    /// indirect mbufs for software GSO.
     pub fn create_device(&self, mempool: Arc<MemPool>, queue_id: u16) -> DpdkDevice {
         let rxq = RxQueue::new(self.port_id, queue_id);
         let txq = TxQueue::new(self.port_id, queue_id);
-        DpdkDevice::new(rxq, txq, mempool, self.mtu, self.mbuf_capacity())
+        let eth_dev = EthDev::new(self.port_id);
+        let checksum = eth_dev.checksum_offload().unwrap_or_default();
+        let device = DpdkDevice::new(rxq, txq, mempool.clone(), self.mtu, self.mbuf_capacity())
+            .with_checksum_offload(checksum);
+        if !self.tso {
+            return device;
+        }
+        if eth_dev.tso_enabled().unwrap_or(false) {
+            return device.with_tso(TsoConfig::hardware());
+        }
+        let pool_name = format!("{}_gso{}", self.mempool_name, queue_id);
+        match GsoContext::indirect_pool(pool_name, self.num_mbufs) {
+            Ok(indirect_pool) => {
+                let gso = GsoContext::new(mempool, Arc::new(indirect_pool));
+                device.with_tso(TsoConfig::software(gso))
+            }
+            Err(e) => {
+                tracing::warn!(queue_id, error = %e, "Failed to create GSO pool, TSO disabled");
+                device
+            }
+        }
     }
 }
@@ -1,6 +1,7 @@
 pub mod app;
 pub mod eth_dev_config;
 pub mod manual;
+pub mod net_ring;
 pub mod udp;
 
 pub mod dpdk_test;
@@ -318,6 +318,7 @@ pub fn tcp_echo_test(use_hardware: bool) {
             .nb_tx_queues(1)
             .rx_queue_conf(RxQueueConf::new().nb_desc(1024))
             .tx_queue_conf(TxQueueConf::new().nb_desc(1024))
+            .checksum_offload(true)
             .build(&mempool)
             .expect("Failed to configure eth device");
 
@@ -330,7 +331,8 @@ pub fn tcp_echo_test(use_hardware: bool) {
             std::sync::Arc::new(mempool),
             DEFAULT_MTU,
             mbuf_capacity,
-        );
+        )
+        .with_checksum_offload(eth_dev.checksum_offload().unwrap_or_default());
 
         let ctx = DpdkTestContext::from_parts(eal, eth_dev);
         (ctx, device)
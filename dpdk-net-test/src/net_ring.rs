@@ -0,0 +1,102 @@
+//! net_ring0 Loopback Helpers
+//!
+//! Every TX queue of the net_ring0 vdev loops back to the RX queue with the
+//! same index, so device tests send frames on a `DpdkDevice` and read them
+//! back on it, or on another device sharing that RX queue.
+//!
+//! - `NetRing` - EAL and port set up for net_ring0, creating devices per queue
+//! - `send` / `receive` - push a frame through a device and collect the
+//!   frames smoltcp would see
+
+use std::sync::Arc;
+
+use dpdk_net::api::Errno;
+use dpdk_net::api::rte::eal::EalBuilder;
+use dpdk_net::api::rte::eth::EthDev;
+use dpdk_net::api::rte::pktmbuf::MemPool;
+use dpdk_net::device::DpdkDevice;
+use smoltcp::phy::{Device, RxToken, TxToken};
+use smoltcp::time::Instant;
+
+use crate::dpdk_test::DpdkTestContext;
+use crate::eth_dev_config::EthDevConfig;
+
+/// net_ring0 port with `nb_queues` queue pairs and its mempool.
+///
+/// Unlike `DpdkTestContextBuilder`, no device is created up front: tests
+/// call [`NetRing::device`] for each queue they need, as often as they like.
+///
+/// # Example
+/// ```no_run
+/// use dpdk_net_test::net_ring::{self, NetRing};
+///
+/// let ring = NetRing::new("my_test_pool", 1).expect("Failed to set up net_ring0");
+/// let mut device = ring.device(0);
+/// net_ring::send(&mut device, &net_ring::frame(1));
+/// assert_eq!(net_ring::receive(&mut device, 1), [net_ring::frame(1)]);
+/// ```
+pub struct NetRing {
+    config: EthDevConfig,
+    mempool: Arc<MemPool>,
+    /// Dropped last: closes the port, then cleans up EAL
+    context: DpdkTestContext,
+}
+
+impl NetRing {
+    /// Initialize EAL with net_ring0 and start its port.
+    pub fn new(mempool_name: &str, nb_queues: u16) -> Result<Self, Errno> {
+        let eal = EalBuilder::new()
+            .no_huge()
+            .no_pci()
+            .vdev("net_ring0")
+            .init()?;
+        let config = EthDevConfig::new()
+            .mempool_name(mempool_name)
+            .nb_queues(nb_queues);
+        let (mempool, eth_dev) = config.clone().build()?;
+        Ok(Self {
+            config,
+            mempool,
+            context: DpdkTestContext::from_parts(eal, eth_dev),
+        })
+    }
+
+    /// Create a device for `queue_id`.
+    pub fn device(&self, queue_id: u16) -> DpdkDevice {
+        self.config.create_device(self.mempool.clone(), queue_id)
+    }
+
+    /// Get a reference to the ethernet device.
+    pub fn eth_dev(&self) -> &EthDev {
+        self.context.eth_dev()
+    }
+}
+
+/// 60-byte broadcast Ethernet/IPv4 frame whose source MAC and last byte are `id`
+pub fn frame(id: u8) -> Vec<u8> {
+    let mut frame = vec![0u8; 60];
+    frame[..6].copy_from_slice(&[0xff; 6]);
+    frame[6..12].copy_from_slice(&[0x02, 0, 0, 0, 0, id]);
+    frame[12..14].copy_from_slice(&[0x08, 0x00]);
+    frame[59] = id;
+    frame
+}
+
+/// Queue `frame` on the device's TX batch.
+pub fn send(device: &mut DpdkDevice, frame: &[u8]) {
+    let token = device.transmit(Instant::now()).expect("No TX token");
+    token.consume(frame.len(), |buf| buf.copy_from_slice(frame));
+}
+
+/// Frames smoltcp would see, polling until `count` arrive or the device idles.
+pub fn receive(device: &mut DpdkDevice, count: usize) -> Vec<Vec<u8>> {
+    let mut frames = Vec::new();
+    for _ in 0..1000 {
+        if let Some((rx, _tx)) = device.receive(Instant::now()) {
+            frames.push(rx.consume(|frame| frame.to_vec()));
+        } else if frames.len() >= count {
+            break;
+        }
+    }
+    frames
+}
@@ -0,0 +1,63 @@
+//! Proactive ARP Test
+//!
+//! Checks that a device with an `ArpAnnounceConfig` sends gratuitous ARP and a
+//! gateway ARP request on its first poll, and announces again when the
+//! address changes. TX frames are observed with a capture tap.
+
+use dpdk_net::device::{
+    ArpAnnounceConfig, Capture, CaptureConfig, Direction, build_arp_request, build_gratuitous_arp,
+};
+use dpdk_net_test::dpdk_test::DpdkTestContextBuilder;
+use smoltcp::phy::Device;
+use smoltcp::time::{Duration, Instant};
+use std::net::Ipv4Addr;
+
+const MAC: [u8; 6] = [0x02, 0, 0, 0, 0, 0x01];
+const OUR_IP: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 5);
+const NEW_IP: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 6);
+const GATEWAY: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+
+#[test]
+fn test_arp_announce() {
+    let (_ctx, device) = DpdkTestContextBuilder::new()
+        .vdev("net_ring0")
+        .mempool_name("arp_announce_test_pool")
+        .build()
+        .expect("Failed to create DPDK test context");
+
+    let capture = Capture::ring(64, CaptureConfig::new().rx(false));
+    capture.start();
+    let config = ArpAnnounceConfig::new()
+        .gateway(GATEWAY)
+        .gratuitous_count(1)
+        .request_count(1);
+    let mut device = device
+        .with_capture(capture.clone())
+        .with_arp_announce(config, MAC, OUR_IP);
+
+    // First poll sends the announcement; the next one flushes it
+    let t0 = Instant::from_secs(1);
+    while device.receive(t0).is_some() {}
+    while device.receive(t0 + Duration::from_millis(1)).is_some() {}
+
+    let sent: Vec<Vec<u8>> = capture.drain().into_iter().map(|p| p.data).collect();
+    assert_eq!(
+        sent,
+        [
+            build_gratuitous_arp(MAC, OUR_IP),
+            build_arp_request(MAC, OUR_IP, GATEWAY)
+        ]
+    );
+
+    // Nothing more once the rounds are exhausted
+    while device.receive(t0 + Duration::from_secs(5)).is_some() {}
+    assert!(capture.drain().is_empty());
+
+    // Address change: announce the new binding
+    device.set_ipv4_addr(NEW_IP);
+    while device.receive(t0 + Duration::from_secs(6)).is_some() {}
+    while device.receive(t0 + Duration::from_secs(6)).is_some() {}
+    let sent = capture.drain();
+    assert!(sent.iter().all(|p| p.direction == Direction::Tx));
+    assert_eq!(sent[0].data, build_gratuitous_arp(MAC, NEW_IP));
+}
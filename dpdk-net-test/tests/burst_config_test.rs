@@ -0,0 +1,55 @@
+//! Burst Config Test
+//!
+//! Frames sent on a net_ring0 device loop back to it. With an RX burst of 8,
+//! 20 waiting frames must arrive in bursts of 8, 8 and 4. A TX flush
+//! threshold of 1 must put each frame on the ring as soon as it is written.
+//! An adaptive burst starting at 4 must grow while its bursts come back full.
+
+use dpdk_net::device::{BurstConfig, rx_burst_bucket};
+use dpdk_net_test::net_ring::{NetRing, frame, receive, send};
+
+#[test]
+fn test_burst_config() {
+    let ring = NetRing::new("burst_config_test_pool", 1).expect("Failed to set up net_ring0");
+
+    // Fixed burst of 8
+    let mut device = ring.device(0).with_burst(BurstConfig::new().rx_burst(8));
+    for id in 0..20 {
+        send(&mut device, &frame(id));
+    }
+    assert_eq!(receive(&mut device, 20).len(), 20);
+    let stats = device.stats();
+    assert_eq!(stats.rx_bursts[rx_burst_bucket(8)], 2);
+    assert_eq!(stats.rx_bursts[rx_burst_bucket(4)], 1);
+    assert_eq!(device.rx_burst_size(), 8);
+    drop(device);
+
+    // Every frame flushed on its own
+    let mut device = ring
+        .device(0)
+        .with_burst(BurstConfig::new().tx_flush_threshold(1));
+    send(&mut device, &frame(1));
+    assert_eq!(device.stats().tx_packets, 1);
+    send(&mut device, &frame(2));
+    assert_eq!(device.stats().tx_packets, 2);
+    assert_eq!(receive(&mut device, 2).len(), 2);
+    drop(device);
+
+    // Adaptive: full bursts double the size
+    let burst = BurstConfig::new()
+        .rx_burst(4)
+        .adaptive(4, 64)
+        .sample_interval(1000);
+    let mut device = ring.device(0).with_burst(burst);
+    for id in 0..64 {
+        send(&mut device, &frame(id));
+    }
+    assert_eq!(receive(&mut device, 64).len(), 64);
+    // 4, 8, 16, 32, then the last 4 (which shrinks it back to 32)
+    let stats = device.stats();
+    for size in [8, 16, 32] {
+        assert_eq!(stats.rx_bursts[rx_burst_bucket(size)], 1, "{stats:?}");
+    }
+    assert_eq!(stats.rx_bursts[rx_burst_bucket(4)], 2, "{stats:?}");
+    assert_eq!(device.rx_burst_size(), 32);
+}
@@ -0,0 +1,68 @@
+//! Classifier Test
+//!
+//! A classifier on a net_ring0 device diverts LLDP frames, drops IPv4
+//! packets to port 23 and accepts the rest. Frames sent on TX queue 0 loop
+//! back to RX queue 0: smoltcp must only see the accepted frame, and the
+//! LLDP frame must come out of the divert queue intact.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dpdk_net::device::{DivertQueue, Packet, Verdict};
+use dpdk_net_test::net_ring::{NetRing, receive, send};
+
+const ETHERTYPE_LLDP: u16 = 0x88cc;
+
+/// Ethernet frame with an IPv4/TCP header to `dst_port`, or an LLDP frame
+fn frame(ethertype: u16, dst_port: u16) -> Vec<u8> {
+    let mut frame = vec![0u8; 64];
+    frame[..6].copy_from_slice(&[0xff; 6]);
+    frame[6..12].copy_from_slice(&[0x02, 0, 0, 0, 0, 1]);
+    frame[12..14].copy_from_slice(&ethertype.to_be_bytes());
+    if ethertype == 0x0800 {
+        frame[14] = 0x45;
+        frame[23] = 6;
+        frame[26..30].copy_from_slice(&[10, 0, 0, 1]);
+        frame[30..34].copy_from_slice(&[10, 0, 0, 2]);
+        frame[34..36].copy_from_slice(&40000u16.to_be_bytes());
+        frame[36..38].copy_from_slice(&dst_port.to_be_bytes());
+    }
+    frame
+}
+
+#[test]
+fn test_classifier() {
+    let ring = NetRing::new("classifier_test_pool", 1).expect("Failed to set up net_ring0");
+
+    let seen = Arc::new(AtomicU64::new(0));
+    let seen_by_classifier = seen.clone();
+    let divert = DivertQueue::default();
+    let mut device = ring
+        .device(0)
+        .with_classifier(move |packet: &Packet<'_>| {
+            seen_by_classifier.fetch_add(1, Ordering::Relaxed);
+            if packet.ethertype() == Some(ETHERTYPE_LLDP) {
+                return Verdict::Divert;
+            }
+            match packet.five_tuple() {
+                Some(tuple) if tuple.dst_port == 23 => Verdict::Drop,
+                _ => Verdict::Accept,
+            }
+        })
+        .with_divert_queue(divert.clone());
+
+    let lldp = frame(ETHERTYPE_LLDP, 0);
+    let telnet = frame(0x0800, 23);
+    let http = frame(0x0800, 80);
+    send(&mut device, &lldp);
+    send(&mut device, &telnet);
+    send(&mut device, &http);
+
+    assert_eq!(receive(&mut device, 1), [http]);
+    assert_eq!(seen.load(Ordering::Relaxed), 3);
+
+    let diverted = divert.try_recv().expect("LLDP frame not diverted");
+    assert_eq!(diverted.data(), &lldp[..]);
+    assert!(divert.is_empty());
+    assert_eq!(divert.dropped(), 0);
+}
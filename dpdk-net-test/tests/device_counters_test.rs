@@ -0,0 +1,48 @@
+//! Device Counters Test
+//!
+//! Frames sent on a net_ring0 device loop back to it: the TX and RX packet
+//! and byte counters must match what was sent, the burst histogram must
+//! account for every poll, and a snapshot taken from another thread through
+//! the shared counters must see the same values.
+
+use dpdk_net::device::{DeviceStats, RX_BURST_BUCKETS, rx_burst_bucket};
+use dpdk_net_test::dpdk_test::DpdkTestContextBuilder;
+use dpdk_net_test::net_ring::{frame, receive, send};
+
+const FRAMES: usize = 10;
+const FRAME_LEN: usize = 60;
+
+#[test]
+fn test_device_counters() {
+    let (_ctx, mut device) = DpdkTestContextBuilder::new()
+        .vdev("net_ring0")
+        .mempool_name("device_counters_test_pool")
+        .build()
+        .expect("Failed to create DPDK test context");
+    let counters = device.counters();
+    assert_eq!(counters.snapshot(), DeviceStats::default());
+
+    for id in 0..FRAMES as u8 {
+        send(&mut device, &frame(id));
+    }
+    // Batched until the next poll
+    assert_eq!(device.stats().tx_packets, 0);
+    assert_eq!(receive(&mut device, FRAMES).len(), FRAMES);
+
+    let stats = std::thread::spawn(move || counters.snapshot())
+        .join()
+        .unwrap();
+    assert_eq!(stats, device.stats());
+    assert_eq!(stats.tx_packets, FRAMES as u64);
+    assert_eq!(stats.tx_bytes, (FRAMES * FRAME_LEN) as u64);
+    assert_eq!(stats.rx_packets, FRAMES as u64);
+    assert_eq!(stats.rx_bytes, (FRAMES * FRAME_LEN) as u64);
+    assert_eq!(stats.tx_dropped + stats.tx_alloc_failures, 0);
+
+    // All frames came in one burst, and the idle polls after it were empty
+    assert_eq!(stats.rx_bursts[rx_burst_bucket(FRAMES)], 1);
+    assert!(stats.rx_bursts[0] > 0);
+    let bursts: u64 = stats.rx_bursts.iter().sum();
+    assert_eq!(bursts, stats.rx_bursts[0] + 1);
+    assert_eq!(stats.rx_bursts.len(), RX_BURST_BUCKETS);
+}
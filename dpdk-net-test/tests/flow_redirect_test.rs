@@ -0,0 +1,115 @@
+//! Flow Redirect Test
+//!
+//! Two devices on net_ring0 queues 0 and 1 share a `FlowRedirect`. Queue 1
+//! opens a connection (sends a SYN), and the peer's reply is made to arrive on
+//! queue 0 by sending it on TX queue 0, which loops back to RX queue 0. Queue 0
+//! must hand the reply to queue 1 instead of delivering it, keep segments of
+//! unknown flows, and stop redirecting once queue 1 resets the connection.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use dpdk_net::device::{
+    BurstConfig, DEFAULT_FLOW_REDIRECT_RING_SIZE, DpdkDevice, FlowKey, FlowRedirect, MAX_RX_BURST,
+};
+use dpdk_net_test::net_ring::{NetRing, receive, send};
+
+const LOCAL: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 2);
+const PEER: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+const LOCAL_PORT: u16 = 40000;
+const PEER_PORT: u16 = 80;
+
+const SYN: u8 = 0x02;
+const RST: u8 = 0x04;
+const ACK: u8 = 0x10;
+
+/// Ethernet/IPv4/TCP frame
+fn tcp_frame(src: Ipv4Addr, src_port: u16, dst: Ipv4Addr, dst_port: u16, flags: u8) -> Vec<u8> {
+    let mut frame = vec![0u8; 14 + 20 + 20];
+    frame[12..14].copy_from_slice(&[0x08, 0x00]);
+    frame[14] = 0x45;
+    frame[16..18].copy_from_slice(&40u16.to_be_bytes());
+    frame[22] = 64;
+    frame[23] = 6;
+    frame[26..30].copy_from_slice(&src.octets());
+    frame[30..34].copy_from_slice(&dst.octets());
+    frame[34..36].copy_from_slice(&src_port.to_be_bytes());
+    frame[36..38].copy_from_slice(&dst_port.to_be_bytes());
+    frame[46] = 0x50;
+    frame[47] = flags;
+    frame
+}
+
+#[test]
+fn test_flow_redirect() {
+    let ring = NetRing::new("flow_redirect_test_pool", 2).expect("Failed to set up net_ring0");
+
+    let redirect = FlowRedirect::new(0, 2, DEFAULT_FLOW_REDIRECT_RING_SIZE)
+        .expect("Failed to create flow redirect rings");
+    let mut queue0 = ring.device(0).with_flow_redirect(redirect.clone(), 0);
+    let mut queue1 = ring.device(1).with_flow_redirect(redirect.clone(), 1);
+
+    // Queue 1 connects: sending the SYN makes it the owner
+    send(
+        &mut queue1,
+        &tcp_frame(LOCAL, LOCAL_PORT, PEER, PEER_PORT, SYN),
+    );
+    let key = FlowKey {
+        local_addr: IpAddr::V4(LOCAL),
+        local_port: LOCAL_PORT,
+        remote_addr: IpAddr::V4(PEER),
+        remote_port: PEER_PORT,
+    };
+    assert_eq!(redirect.owner(&key), Some(1));
+    // The SYN loops back to queue 1 itself
+    assert_eq!(receive(&mut queue1, 1).len(), 1);
+
+    // The reply arrives on queue 0, which passes it on
+    let reply = tcp_frame(PEER, PEER_PORT, LOCAL, LOCAL_PORT, SYN | ACK);
+    let unknown = tcp_frame(PEER, PEER_PORT, LOCAL, LOCAL_PORT + 1, ACK);
+    send(&mut queue0, &reply);
+    send(&mut queue0, &unknown);
+    assert_eq!(receive(&mut queue0, 1), [unknown]);
+    assert_eq!(receive(&mut queue1, 1), std::slice::from_ref(&reply));
+    assert_eq!(redirect.stats()[1].redirected, 1);
+    assert_eq!(redirect.stats()[1].dropped, 0);
+
+    // Resetting the connection releases it
+    send(
+        &mut queue1,
+        &tcp_frame(LOCAL, LOCAL_PORT, PEER, PEER_PORT, RST | ACK),
+    );
+    assert_eq!(redirect.owner(&key), None);
+    receive(&mut queue1, 1);
+    send(&mut queue0, &reply);
+    assert_eq!(receive(&mut queue0, 1), [reply]);
+
+    check_full_burst(queue0, queue1, &redirect);
+}
+
+/// Redirected segments are not starved by RX bursts that fill the batch.
+fn check_full_burst(mut queue0: DpdkDevice, queue1: DpdkDevice, redirect: &FlowRedirect) {
+    let mut queue1 = queue1.with_burst(BurstConfig::new().rx_burst(MAX_RX_BURST));
+    let port = LOCAL_PORT + 2;
+    send(&mut queue1, &tcp_frame(LOCAL, port, PEER, PEER_PORT, SYN));
+    receive(&mut queue1, 1);
+
+    // One segment waits in queue 1's ring...
+    let reply = tcp_frame(PEER, PEER_PORT, LOCAL, port, SYN | ACK);
+    send(&mut queue0, &reply);
+    assert!(receive(&mut queue0, 0).is_empty());
+    assert_eq!(redirect.stats()[1].redirected, 2);
+
+    // ...while more than a full burst waits on its RX queue
+    let flood = MAX_RX_BURST + 64;
+    let other = tcp_frame(PEER, PEER_PORT, LOCAL, LOCAL_PORT + 3, ACK);
+    for _ in 0..flood {
+        send(&mut queue1, &other);
+    }
+    let frames = receive(&mut queue1, flood + 1);
+    assert_eq!(frames.len(), flood + 1);
+    let position = frames.iter().position(|frame| *frame == reply);
+    assert!(
+        position.is_some_and(|position| position < MAX_RX_BURST),
+        "Redirected segment not in the first burst: {position:?}"
+    );
+}
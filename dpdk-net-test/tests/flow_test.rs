@@ -0,0 +1,67 @@
+// Test: rte_flow rule validation on a vdev
+//
+// net_ring has no flow offload support, so rules must be rejected with a
+// structured FlowError rather than crashing or silently succeeding.
+
+use dpdk_net::api::Errno;
+use dpdk_net::api::rte::eal::EalBuilder;
+use dpdk_net::api::rte::eth::{EthConf, EthDevBuilder, RxQueueConf, TxQueueConf};
+use dpdk_net::api::rte::flow::{self, EthPattern, FlowRule, Ipv4Pattern, L4Pattern};
+use dpdk_net::api::rte::pktmbuf::{MemPool, MemPoolConfig};
+use dpdk_net_test::dpdk_test::DEFAULT_MBUF_DATA_ROOM_SIZE;
+use std::net::Ipv4Addr;
+
+#[test]
+fn test_flow_unsupported_on_ring() {
+    let _eal = EalBuilder::new()
+        .no_huge()
+        .no_pci()
+        .vdev("net_ring0")
+        .init()
+        .expect("Failed to initialize EAL");
+
+    let mempool_config = MemPoolConfig::new()
+        .num_mbufs(1024)
+        .data_room_size(DEFAULT_MBUF_DATA_ROOM_SIZE as u16);
+    let mempool = MemPool::create("flow_pool", &mempool_config).expect("Failed to create mempool");
+
+    let eth_dev = EthDevBuilder::new(0)
+        .eth_conf(EthConf::new())
+        .nb_rx_queues(2)
+        .nb_tx_queues(2)
+        .rx_queue_conf(RxQueueConf::new().nb_desc(512))
+        .tx_queue_conf(TxQueueConf::new().nb_desc(512))
+        .build(&mempool)
+        .expect("Failed to configure eth device");
+
+    // Pin a listening port to queue 1
+    let rule = FlowRule::new()
+        .eth_any()
+        .ipv4(Ipv4Pattern::new().dst(Ipv4Addr::new(192, 168, 1, 1), 32))
+        .tcp(L4Pattern::new().dst_port(8080))
+        .queue(1)
+        .count();
+    assert_eq!(rule.pattern().len(), 3);
+    assert_eq!(rule.actions().len(), 2);
+
+    let err = rule
+        .validate(0)
+        .expect_err("net_ring should not support rte_flow");
+    println!("validate: {}", err);
+    assert!(
+        matches!(err.errno, Errno::ENOTSUP | Errno::ENOSYS),
+        "unexpected errno {}",
+        err.errno
+    );
+
+    // ARP to queue 0
+    let arp_rule = FlowRule::new()
+        .eth(EthPattern::new().ether_type(0x0806))
+        .queue(0);
+    assert!(arp_rule.create(0).is_err());
+
+    assert!(flow::flush(0).is_err());
+
+    eth_dev.stop().expect("Failed to stop device");
+    eth_dev.close().expect("Failed to close device");
+}
@@ -0,0 +1,70 @@
+// Test: runtime port attach/detach via devargs
+//
+// net_ring vdevs can be probed and removed after EAL init, which exercises
+// the hotplug path without hardware.
+
+use dpdk_net::api::rte::eal::EalBuilder;
+use dpdk_net::api::rte::eth::{EthConf, EthDev, EthDevBuilder, RxQueueConf, TxQueueConf};
+use dpdk_net::api::rte::event::{EthEventStream, EthEventType};
+use dpdk_net::api::rte::pktmbuf::{MemPool, MemPoolConfig};
+use dpdk_net_test::dpdk_test::DEFAULT_MBUF_DATA_ROOM_SIZE;
+
+#[test]
+fn test_attach_detach() {
+    let _eal = EalBuilder::new()
+        .no_huge()
+        .no_pci()
+        .vdev("net_ring0")
+        .init()
+        .expect("Failed to initialize EAL");
+    assert_eq!(EthDev::count_avail(), 1);
+
+    let mempool_config = MemPoolConfig::new()
+        .num_mbufs(1024)
+        .data_room_size(DEFAULT_MBUF_DATA_ROOM_SIZE as u16);
+    let mempool =
+        MemPool::create("hotplug_pool", &mempool_config).expect("Failed to create mempool");
+
+    let mut events = EthEventStream::all_ports(&[EthEventType::New, EthEventType::Destroy])
+        .expect("Failed to register event callbacks");
+
+    // Unknown driver
+    assert!(EthDev::attach("net_does_not_exist0").is_err());
+
+    let dev = EthDev::attach("net_ring1").expect("Failed to attach net_ring1");
+    let port_id = dev.port_id();
+    assert_eq!(EthDev::count_avail(), 2);
+    assert_eq!(dev.name().expect("Failed to get port name"), "net_ring1");
+    assert_eq!(EthDev::port_by_name("net_ring1"), Ok(port_id));
+    assert_eq!(EthDev::port_by_name("net_ring0"), Ok(0));
+
+    let event = events.try_recv().expect("Expected a New event");
+    assert_eq!(event.event_type, EthEventType::New);
+    assert_eq!(event.port_id, port_id);
+
+    // The attached port is usable like any EAL-probed port
+    let eth_dev = EthDevBuilder::new(port_id)
+        .eth_conf(EthConf::new())
+        .nb_rx_queues(1)
+        .nb_tx_queues(1)
+        .rx_queue_conf(RxQueueConf::new().nb_desc(512))
+        .tx_queue_conf(TxQueueConf::new().nb_desc(512))
+        .build(&mempool)
+        .expect("Failed to configure attached device");
+    assert!(eth_dev.link().expect("Failed to get link status").up);
+
+    eth_dev.detach().expect("Failed to detach net_ring1");
+    assert_eq!(EthDev::count_avail(), 1);
+    assert!(EthDev::port_by_name("net_ring1").is_err());
+
+    let event = events.try_recv().expect("Expected a Destroy event");
+    assert_eq!(event.event_type, EthEventType::Destroy);
+    assert_eq!(event.port_id, port_id);
+    assert!(event.link.is_none());
+
+    // The name can be reused once the device is gone
+    let dev = EthDev::attach("net_ring1").expect("Failed to re-attach net_ring1");
+    dev.detach().expect("Failed to detach net_ring1 again");
+
+    drop(events);
+}
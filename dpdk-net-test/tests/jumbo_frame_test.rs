@@ -0,0 +1,143 @@
+//! Jumbo Frame Test
+//!
+//! Runs a 9000-byte MTU on the standard 2KB mempool. Frames larger than one
+//! mbuf are sent as segment chains; net_ring loops them back unchanged, so
+//! the receive path sees multi-segment packets too.
+
+use dpdk_net::api::rte::mbuf::Mbuf;
+use dpdk_net::api::rte::pktmbuf::{MemPool, MemPoolConfig};
+use dpdk_net::runtime::Reactor;
+use dpdk_net::socket::{TcpListener, TcpStream};
+use dpdk_net_test::dpdk_test::{
+    DEFAULT_MBUF_DATA_ROOM_SIZE, DEFAULT_MBUF_HEADROOM, DpdkTestContextBuilder,
+};
+use smoltcp::iface::{Config, Interface};
+use smoltcp::phy::Device;
+use smoltcp::time::Instant;
+use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, Ipv4Address};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::runtime::Builder;
+
+const JUMBO_MTU: usize = 9000;
+const SERVER_PORT: u16 = 8080;
+const CLIENT_PORT: u16 = 49152;
+const SERVER_IP: Ipv4Address = Ipv4Address::new(192, 168, 1, 1);
+const PAYLOAD_LEN: usize = 64 * 1024;
+
+#[test]
+fn test_jumbo_frames() {
+    let (ctx, mut device) = DpdkTestContextBuilder::new()
+        .vdev("net_ring0")
+        .mempool_name("jumbo_test_pool")
+        .mtu(JUMBO_MTU)
+        .build()
+        .expect("Failed to create DPDK test context");
+
+    assert_eq!(
+        ctx.eth_dev().mtu().expect("Failed to get MTU"),
+        JUMBO_MTU as u16
+    );
+    assert_eq!(device.capabilities().max_transmission_unit, JUMBO_MTU);
+
+    test_mbuf_chains();
+
+    let mac_addr = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    let config = Config::new(mac_addr.into());
+    let mut iface = Interface::new(config, &mut device, Instant::now());
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::new(IpAddress::Ipv4(SERVER_IP), 24))
+            .unwrap();
+    });
+
+    let rt = Builder::new_current_thread().enable_all().build().unwrap();
+    let local = tokio::task::LocalSet::new();
+
+    local.block_on(&rt, async {
+        let reactor = Reactor::new(device, iface);
+        let handle = reactor.handle();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let reactor_cancel = cancel.clone();
+        let reactor_task = tokio::task::spawn_local(async move {
+            reactor.run(reactor_cancel).await;
+        });
+
+        let mut listener = TcpListener::bind(&handle, SERVER_PORT, PAYLOAD_LEN, PAYLOAD_LEN)
+            .expect("Failed to bind listener");
+        let server = tokio::task::spawn_local(async move {
+            let stream = listener.accept().await.expect("accept failed");
+            let mut received = Vec::with_capacity(PAYLOAD_LEN);
+            let mut buf = vec![0u8; PAYLOAD_LEN];
+            while received.len() < PAYLOAD_LEN {
+                let len = stream.recv(&mut buf).await.expect("server recv failed");
+                received.extend_from_slice(&buf[..len]);
+            }
+            stream.close().await;
+            received
+        });
+
+        let client = TcpStream::connect(
+            &handle,
+            IpAddress::Ipv4(SERVER_IP),
+            SERVER_PORT,
+            CLIENT_PORT,
+            PAYLOAD_LEN,
+            PAYLOAD_LEN,
+        )
+        .expect("connect failed");
+        client.wait_connected().await.expect("connection failed");
+
+        let payload: Vec<u8> = (0..PAYLOAD_LEN).map(|i| (i % 251) as u8).collect();
+        let mut sent = 0;
+        while sent < payload.len() {
+            sent += client
+                .send(&payload[sent..])
+                .await
+                .expect("client send failed");
+        }
+
+        let received = server.await.expect("server task panicked");
+        assert_eq!(received, payload);
+        client.close().await;
+
+        cancel.store(true, Ordering::Relaxed);
+        let _ = reactor_task.await;
+    });
+}
+
+/// Build and inspect chained mbufs directly.
+fn test_mbuf_chains() {
+    let config = MemPoolConfig::new()
+        .num_mbufs(255)
+        .data_room_size(DEFAULT_MBUF_DATA_ROOM_SIZE as u16);
+    let pool = MemPool::create("jumbo_chain_pool", &config).expect("Failed to create mempool");
+    let frame: Vec<u8> = (0..JUMBO_MTU).map(|i| i as u8).collect();
+
+    let mut mbuf = Mbuf::alloc_from_slice(&pool, &frame[..100]).expect("alloc failed");
+    assert!(mbuf.is_contiguous());
+    let capacity = mbuf.capacity();
+
+    assert!(mbuf.extend_from_slice(&pool, &frame[100..]));
+    assert_eq!(mbuf.pkt_len(), JUMBO_MTU);
+    assert_eq!(mbuf.data_len(), capacity);
+    assert_eq!(mbuf.nb_segs() as usize, JUMBO_MTU.div_ceil(capacity));
+    assert_eq!(mbuf.segments().map(<[u8]>::len).sum::<usize>(), JUMBO_MTU);
+    assert_eq!(mbuf.to_vec(), frame);
+
+    // Does not fit in the first segment
+    assert!(!mbuf.linearize());
+
+    let chained = Mbuf::alloc_from_slice(&pool, &frame).expect("chain alloc failed");
+    assert_eq!(chained.nb_segs(), mbuf.nb_segs());
+    assert_eq!(chained.to_vec(), frame);
+
+    // No data room: only an empty packet fits
+    let config = MemPoolConfig::new()
+        .num_mbufs(63)
+        .data_room_size(DEFAULT_MBUF_HEADROOM as u16);
+    let pool = MemPool::create("jumbo_empty_pool", &config).expect("Failed to create mempool");
+    let empty = Mbuf::alloc_from_slice(&pool, &[]).expect("alloc failed");
+    assert_eq!(empty.pkt_len(), 0);
+    assert!(Mbuf::alloc_from_slice(&pool, &frame[..1]).is_none());
+}
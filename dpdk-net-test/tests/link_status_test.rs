@@ -0,0 +1,75 @@
+// Test: link status query and link event subscription
+//
+// net_ring supports administrative link up/down, which lets us toggle the
+// link state without hardware. It does not raise LSC or RMV interrupts, so
+// delivery of those events needs a NIC with link interrupts and is not
+// covered here; the event stream is checked with the Destroy event that
+// closing the port raises.
+
+use dpdk_net::api::rte::eal::EalBuilder;
+use dpdk_net::api::rte::eth::{EthConf, EthDevBuilder, RxQueueConf, TxQueueConf};
+use dpdk_net::api::rte::event::{EthEventStream, EthEventType};
+use dpdk_net::api::rte::pktmbuf::{MemPool, MemPoolConfig};
+use dpdk_net_test::dpdk_test::DEFAULT_MBUF_DATA_ROOM_SIZE;
+
+#[test]
+fn test_link_status_toggle() {
+    let _eal = EalBuilder::new()
+        .no_huge()
+        .no_pci()
+        .vdev("net_ring0")
+        .init()
+        .expect("Failed to initialize EAL");
+
+    let mempool_config = MemPoolConfig::new()
+        .num_mbufs(1024)
+        .data_room_size(DEFAULT_MBUF_DATA_ROOM_SIZE as u16);
+    let mempool = MemPool::create("link_pool", &mempool_config).expect("Failed to create mempool");
+
+    // Subscribe before starting so no event is missed
+    let mut events = EthEventStream::new(
+        0,
+        &[
+            EthEventType::LinkStatusChange,
+            EthEventType::Removal,
+            EthEventType::Destroy,
+        ],
+    )
+    .expect("Failed to register event callbacks");
+
+    let eth_dev = EthDevBuilder::new(0)
+        .eth_conf(EthConf::new())
+        .nb_rx_queues(1)
+        .nb_tx_queues(1)
+        .rx_queue_conf(RxQueueConf::new().nb_desc(512))
+        .tx_queue_conf(TxQueueConf::new().nb_desc(512))
+        .build(&mempool)
+        .expect("Failed to configure eth device");
+
+    let link = eth_dev.link().expect("Failed to get link status");
+    println!("Link after start: {}", link);
+    assert!(link.up, "Link should be up after start");
+
+    eth_dev.set_link_down().expect("Failed to set link down");
+    let link = eth_dev.link_nowait().expect("Failed to get link status");
+    assert!(!link.up, "Link should be down after set_link_down");
+    assert_eq!(link.to_string(), "down");
+
+    eth_dev.set_link_up().expect("Failed to set link up");
+    let link = eth_dev.link_nowait().expect("Failed to get link status");
+    assert!(link.up, "Link should be up after set_link_up");
+
+    // Administrative link changes on net_ring raise no event
+    assert!(events.try_recv().is_none());
+
+    eth_dev.stop().expect("Failed to stop device");
+    eth_dev.close().expect("Failed to close device");
+
+    // Closing releases the port
+    let event = events.try_recv().expect("Expected a Destroy event");
+    assert_eq!(event.event_type, EthEventType::Destroy);
+    assert_eq!(event.port_id, 0);
+    assert!(event.link.is_none());
+    assert!(events.try_recv().is_none());
+    assert_eq!(events.dropped(), 0);
+}
@@ -0,0 +1,84 @@
+// Test: mempool usage counters, allocation failures and leak detection
+
+use dpdk_net::api::rte::eal::EalBuilder;
+use dpdk_net::api::rte::pktmbuf::{MemPool, MemPoolConfig};
+use dpdk_net_test::dpdk_test::DEFAULT_MBUF_DATA_ROOM_SIZE;
+
+#[test]
+fn test_mempool_stats() {
+    let _eal = EalBuilder::new()
+        .no_huge()
+        .no_pci()
+        .init()
+        .expect("Failed to initialize EAL");
+
+    // No cache: every free mbuf is visible to every lcore
+    let config = MemPoolConfig::new()
+        .num_mbufs(63)
+        .cache_size(0)
+        .data_room_size(DEFAULT_MBUF_DATA_ROOM_SIZE as u16)
+        .track_leaks(true);
+    let pool = MemPool::create("stats_pool", &config).expect("Failed to create mempool");
+    assert_eq!(pool.name(), "stats_pool");
+
+    let stats = pool.stats();
+    assert_eq!(stats.size, 63);
+    assert_eq!(stats.avail, 63);
+    assert_eq!(stats.in_use, 0);
+    assert_eq!(stats.cache_size, 0);
+    assert_eq!(stats.alloc_failures, 0);
+    // Safety: no port or other thread uses the pool
+    assert!(unsafe { pool.outstanding() }.is_empty());
+
+    // Exhaust the pool
+    let mut held = Vec::new();
+    while let Some(mbuf) = pool.try_alloc() {
+        held.push(mbuf);
+    }
+    assert_eq!(held.len(), 63);
+    assert_eq!(pool.in_use_count(), 63);
+    assert_eq!(pool.alloc_failures(), 1);
+
+    // Failures through a looked-up handle are counted on the same pool
+    let pool_ref = MemPool::lookup("stats_pool").expect("Failed to lookup mempool");
+    assert!(pool_ref.try_alloc().is_none());
+    assert_eq!(pool.alloc_failures(), 2);
+    assert_eq!(pool_ref.stats().alloc_failures, 2);
+
+    // Outstanding mbufs are the ones we hold
+    held.truncate(3);
+    let outstanding = unsafe { pool.outstanding() };
+    assert_eq!(outstanding.len(), 3);
+    let mut held_addrs: Vec<usize> = held.iter().map(|m| m.as_ptr() as usize).collect();
+    let mut found: Vec<usize> = outstanding.iter().map(|m| m.addr).collect();
+    held_addrs.sort_unstable();
+    found.sort_unstable();
+    assert_eq!(held_addrs, found);
+    // The check returns the free objects it borrowed
+    assert_eq!(pool.avail_count(), 60);
+
+    drop(held);
+    assert_eq!(pool.in_use_count(), 0);
+    assert!(unsafe { pool.outstanding() }.is_empty());
+
+    check_cache_occupancy();
+}
+
+fn check_cache_occupancy() {
+    let config = MemPoolConfig::new()
+        .num_mbufs(1023)
+        .cache_size(32)
+        .data_room_size(DEFAULT_MBUF_DATA_ROOM_SIZE as u16);
+    let pool = MemPool::create("cache_pool", &config).expect("Failed to create mempool");
+
+    // The EAL main thread has an lcore cache: freed mbufs land there
+    let batch: Vec<_> = (0..8).map(|_| pool.try_alloc().unwrap()).collect();
+    drop(batch);
+
+    let stats = pool.stats();
+    assert_eq!(stats.cache_size, 32);
+    assert!(stats.cached > 0, "Expected cached mbufs: {:?}", stats);
+    let per_lcore: u32 = pool.cache_counts().iter().map(|&(_, count)| count).sum();
+    assert_eq!(per_lcore, stats.cached);
+    assert_eq!(stats.avail + stats.in_use, stats.size);
+}
@@ -0,0 +1,85 @@
+// Test: per-queue start/stop, deferred start and reconfiguring a stopped port
+//
+// net_ring has no per-queue start/stop ops, so those calls may report ENOTSUP;
+// reconfiguration goes through the generic ethdev path and must work.
+
+use dpdk_net::api::Errno;
+use dpdk_net::api::rte::eal::EalBuilder;
+use dpdk_net::api::rte::eth::{EthConf, EthDevBuilder, RxQueueConf, TxQueueConf};
+use dpdk_net::api::rte::pktmbuf::{MemPool, MemPoolConfig};
+use dpdk_net_test::dpdk_test::DEFAULT_MBUF_DATA_ROOM_SIZE;
+
+fn ok_or_unsupported(result: Result<(), Errno>, what: &str) {
+    match result {
+        Ok(()) | Err(Errno::ENOTSUP) => {}
+        Err(e) => panic!("{} failed: {}", what, e),
+    }
+}
+
+#[test]
+fn test_queue_control_and_reconfigure() {
+    let _eal = EalBuilder::new()
+        .no_huge()
+        .no_pci()
+        .vdev("net_ring0")
+        .init()
+        .expect("Failed to initialize EAL");
+
+    let mempool_config = MemPoolConfig::new()
+        .num_mbufs(4095)
+        .data_room_size(DEFAULT_MBUF_DATA_ROOM_SIZE as u16);
+    let mempool =
+        MemPool::create("queue_ctl_pool", &mempool_config).expect("Failed to create mempool");
+
+    // Two queue pairs; the second RX queue is started on demand
+    let eth_dev = EthDevBuilder::new(0)
+        .eth_conf(EthConf::new())
+        .nb_rx_queues(2)
+        .nb_tx_queues(2)
+        .rx_queue_conf(RxQueueConf::new().nb_desc(512).deferred_start(true))
+        .tx_queue_conf(TxQueueConf::new().nb_desc(512))
+        .build(&mempool)
+        .expect("Failed to configure eth device");
+    assert_eq!(eth_dev.nb_queues(), Ok((2, 2)));
+
+    ok_or_unsupported(eth_dev.rx_queue_start(0), "rx_queue_start(0)");
+    ok_or_unsupported(eth_dev.rx_queue_start(1), "rx_queue_start(1)");
+    ok_or_unsupported(eth_dev.rx_queue_stop(1), "rx_queue_stop(1)");
+    ok_or_unsupported(eth_dev.tx_queue_stop(1), "tx_queue_stop(1)");
+    ok_or_unsupported(eth_dev.tx_queue_start(1), "tx_queue_start(1)");
+
+    // Out of range queue
+    assert!(eth_dev.rx_queue_start(8).is_err());
+
+    // Empty RETA is rejected without touching the device
+    assert_eq!(eth_dev.configure_rss_reta_queues(&[]), Err(Errno::EINVAL));
+
+    // Scale up: stop, reconfigure with more queues and descriptors, restart
+    eth_dev.stop().expect("Failed to stop device");
+    let eth_dev = EthDevBuilder::new(0)
+        .eth_conf(EthConf::new())
+        .nb_rx_queues(4)
+        .nb_tx_queues(4)
+        .rx_queue_conf(RxQueueConf::new().nb_desc(1024))
+        .tx_queue_conf(TxQueueConf::new().nb_desc(1024))
+        .configure(&mempool)
+        .expect("Failed to reconfigure stopped device");
+    eth_dev.start().expect("Failed to restart device");
+    assert_eq!(eth_dev.nb_queues(), Ok((4, 4)));
+
+    // Scale down
+    eth_dev.stop().expect("Failed to stop device");
+    let eth_dev = EthDevBuilder::new(0)
+        .eth_conf(EthConf::new())
+        .nb_rx_queues(1)
+        .nb_tx_queues(1)
+        .rx_queue_conf(RxQueueConf::new().nb_desc(256))
+        .tx_queue_conf(TxQueueConf::new().nb_desc(256))
+        .build(&mempool)
+        .expect("Failed to reconfigure stopped device");
+    assert_eq!(eth_dev.nb_queues(), Ok((1, 1)));
+    assert!(eth_dev.link().expect("Failed to get link status").up);
+
+    eth_dev.stop().expect("Failed to stop device");
+    eth_dev.close().expect("Failed to close device");
+}
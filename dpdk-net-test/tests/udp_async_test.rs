@@ -8,7 +8,7 @@
 //! EAL and EthDev are initialized once globally; each test recreates the DpdkDevice.
 
 use dpdk_net::api::rte::eal::{Eal, EalBuilder};
-use dpdk_net::device::DpdkDevice;
+use dpdk_net::device::{DEFAULT_UDP_ZERO_COPY_QUEUE_LEN, DpdkDevice};
 use dpdk_net::runtime::Reactor;
 use dpdk_net::socket::UdpSocket;
 use dpdk_net_test::eth_dev_config::EthDevConfig;
@@ -17,6 +17,7 @@ use smoltcp::time::Instant;
 use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, IpEndpoint, Ipv4Address};
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 use tokio::runtime::Builder;
 
 const SERVER_PORT: u16 = 9999;
@@ -244,3 +245,148 @@ fn test_udp_multiple_sockets() {
         reactor_task.await.unwrap();
     });
 }
+
+/// Test: zero-copy receive, datagrams handed over in their mbufs
+#[test]
+#[serial_test::serial]
+fn test_udp_zero_copy_loopback() {
+    let mut device = create_test_device().with_udp_zero_copy(DEFAULT_UDP_ZERO_COPY_QUEUE_LEN);
+
+    let mac = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x04]);
+    let config = Config::new(mac.into());
+    let mut iface = Interface::new(config, &mut device, Instant::now());
+    let client_ip = Ipv4Address::new(192, 168, 1, 2);
+    iface.update_ip_addrs(|addrs| {
+        addrs
+            .push(IpCidr::new(IpAddress::Ipv4(SERVER_IP), 24))
+            .unwrap();
+        addrs
+            .push(IpCidr::new(IpAddress::Ipv4(client_ip), 24))
+            .unwrap();
+    });
+
+    let rt = Builder::new_current_thread().enable_time().build().unwrap();
+    let local = tokio::task::LocalSet::new();
+
+    local.block_on(&rt, async {
+        let reactor = Reactor::new(device, iface);
+        let handle = reactor.handle();
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_clone = cancel.clone();
+
+        let reactor_task = tokio::task::spawn_local(async move {
+            reactor.run(cancel_clone).await;
+        });
+
+        let server = UdpSocket::bind_zero_copy(&handle, SERVER_PORT, 16, 16, 1500)
+            .expect("Failed to bind zero-copy socket");
+        assert!(UdpSocket::bind_zero_copy(&handle, SERVER_PORT, 16, 16, 1500).is_err());
+        let client = UdpSocket::bind(&handle, CLIENT_PORT, 16, 16, 1500)
+            .expect("Failed to bind client socket");
+
+        let server_endpoint = IpEndpoint::new(IpAddress::Ipv4(SERVER_IP), SERVER_PORT);
+        for i in 0..3u8 {
+            let message = [b'z', b'c', b'0' + i];
+            client
+                .send_to(&message, server_endpoint)
+                .await
+                .expect("Failed to send");
+
+            let (payload, metadata) =
+                tokio::time::timeout(Duration::from_secs(5), server.recv_zero_copy())
+                    .await
+                    .expect("Timed out waiting for datagram")
+                    .expect("Failed to receive");
+            assert_eq!(&*payload, &message);
+            assert_eq!(metadata.endpoint.port, CLIENT_PORT);
+            assert_eq!(metadata.local_address, Some(IpAddress::Ipv4(SERVER_IP)));
+            // The first datagram may have raced the reactor learning our addresses
+            if i > 0 {
+                assert!(payload.is_zero_copy());
+            }
+        }
+        assert_eq!(server.zero_copy_dropped(), 0);
+
+        drop(client);
+        drop(server);
+        cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+        reactor_task.await.unwrap();
+    });
+}
+
+/// Test: zero-copy send, payloads written straight into device mbufs
+#[test]
+#[serial_test::serial]
+fn test_udp_zero_copy_send_loopback() {
+    let mut device = create_test_device().with_udp_zero_copy(DEFAULT_UDP_ZERO_COPY_QUEUE_LEN);
+
+    let mac = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x05]);
+    let config = Config::new(mac.into());
+    let mut iface = Interface::new(config, &mut device, Instant::now());
+    let client_ip = Ipv4Address::new(192, 168, 1, 2);
+    iface.update_ip_addrs(|addrs| {
+        addrs
+            .push(IpCidr::new(IpAddress::Ipv4(SERVER_IP), 24))
+            .unwrap();
+        addrs
+            .push(IpCidr::new(IpAddress::Ipv4(client_ip), 24))
+            .unwrap();
+    });
+
+    let rt = Builder::new_current_thread().enable_time().build().unwrap();
+    let local = tokio::task::LocalSet::new();
+
+    local.block_on(&rt, async {
+        let reactor = Reactor::new(device, iface);
+        let handle = reactor.handle();
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let cancel_clone = cancel.clone();
+
+        let reactor_task = tokio::task::spawn_local(async move {
+            reactor.run(cancel_clone).await;
+        });
+
+        let server = UdpSocket::bind_zero_copy(&handle, SERVER_PORT, 16, 16, 1500)
+            .expect("Failed to bind server socket");
+        let client = UdpSocket::bind_zero_copy(&handle, CLIENT_PORT, 16, 16, 1500)
+            .expect("Failed to bind client socket");
+        let plain = UdpSocket::bind(&handle, CLIENT_PORT + 1, 16, 16, 1500)
+            .expect("Failed to bind plain socket");
+        assert!(plain.alloc_tx_mbuf().is_none());
+
+        // The first datagram goes through smoltcp, which resolves the
+        // server's MAC; the rest are sent from their mbufs
+        let server_endpoint = IpEndpoint::new(IpAddress::Ipv4(SERVER_IP), SERVER_PORT);
+        for i in 0..8u8 {
+            let message = [b'm', b'b', b'0' + i];
+            let mut mbuf = client.alloc_tx_mbuf().expect("Mempool exhausted");
+            mbuf.append(message.len())
+                .expect("No tailroom")
+                .copy_from_slice(&message);
+            let sent = client
+                .send_mbuf_to(mbuf, server_endpoint)
+                .await
+                .expect("Failed to send");
+            assert_eq!(sent, message.len());
+
+            let (payload, metadata) =
+                tokio::time::timeout(Duration::from_secs(5), server.recv_zero_copy())
+                    .await
+                    .expect("Timed out waiting for datagram")
+                    .expect("Failed to receive");
+            assert_eq!(&*payload, &message);
+            assert_eq!(metadata.endpoint.port, CLIENT_PORT);
+        }
+        // Only the first one was copied through smoltcp
+        assert_eq!(client.zero_copy_sent(), 7);
+        assert_eq!(plain.zero_copy_sent(), 0);
+
+        drop(plain);
+        drop(client);
+        drop(server);
+        cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+        reactor_task.await.unwrap();
+    });
+}
@@ -0,0 +1,44 @@
+//! VLAN Test
+//!
+//! Without VLAN offload on net_ring0, tags are pushed and popped in
+//! software. A device on VLAN 300 must get its own frames back untagged
+//! through the loopback (untagged frames would be dropped). Then two
+//! sub-interfaces, VLANs 100 and 200, share RX queue 0 through a
+//! `VlanDemux`: each sends on TX queue 0, which loops back to RX queue 0,
+//! and each must receive only the frame of its own VLAN.
+
+use dpdk_net::api::rte::eth::VlanOffload;
+use dpdk_net::api::rte::queue::RxQueue;
+use dpdk_net::device::{VlanDemux, VlanDemuxStats};
+use dpdk_net_test::net_ring::{NetRing, frame, receive, send};
+
+#[test]
+fn test_vlan() {
+    let ring = NetRing::new("vlan_test_pool", 1).expect("Failed to set up net_ring0");
+    let offload = VlanOffload::default();
+    assert_eq!(ring.eth_dev().vlan_offload().unwrap_or_default(), offload);
+
+    // Software tag on TX, software strip on RX
+    let mut vlan300 = ring.device(0).with_vlan(300, offload);
+    send(&mut vlan300, &frame(3));
+    assert_eq!(receive(&mut vlan300, 1), [frame(3)]);
+    drop(vlan300);
+
+    // Two sub-interfaces on one RX queue
+    let demux = VlanDemux::new(RxQueue::new(0, 0), [100, 200]);
+    assert!(demux.queue(300).is_none());
+    let mut vlan100 = ring
+        .device(0)
+        .with_vlan_queue(demux.queue(100).unwrap(), offload);
+    let mut vlan200 = ring
+        .device(0)
+        .with_vlan_queue(demux.queue(200).unwrap(), offload);
+
+    send(&mut vlan100, &frame(1));
+    send(&mut vlan200, &frame(2));
+    // Polling flushes each device's TX batch before reading its share
+    assert_eq!(receive(&mut vlan100, 1), [frame(1)]);
+    assert_eq!(receive(&mut vlan200, 1), [frame(2)]);
+    assert!(receive(&mut vlan100, 0).is_empty());
+    assert_eq!(demux.stats(), VlanDemuxStats::default());
+}
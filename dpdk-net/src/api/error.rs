@@ -12,6 +12,16 @@ pub fn check_rte_success(ret: i32) -> Result<()> {
     if ret < 0 { Err(rte_errno()) } else { Ok(()) }
 }
 
+/// Check the return value of APIs that return a negative errno
+/// instead of setting `rte_errno`.
+pub fn check_errno(ret: i32) -> Result<()> {
+    if ret < 0 {
+        Err(Errno::from_raw(-ret))
+    } else {
+        Ok(())
+    }
+}
+
 // Currently dpdk has only few error codes defined in rte_errno.h
 // So we ignore them for now.
 // See: /usr/local/include/rte_errno.h
@@ -1,4 +1,4 @@
 mod error;
-pub use error::{Errno, Result, check_rte_success, rte_errno};
+pub use error::{Errno, Result, check_errno, check_rte_success, rte_errno};
 
 pub mod rte;
@@ -7,7 +7,7 @@ use dpdk_net_sys::ffi;
 use tracing::{debug, warn};
 
 use super::pktmbuf::MemPool;
-use crate::api::{Result, check_rte_success};
+use crate::api::{Result, check_errno, check_rte_success};
 
 /// Ethernet device port ID
 pub type PortId = u16;
@@ -147,6 +147,144 @@ pub mod rss_hf {
     pub const UDP: u64 = ffi::RUST_RTE_ETH_RSS_UDP;
 }
 
+/// RX offload flags (RTE_ETH_RX_OFFLOAD_*)
+/// Re-exported from generated bindings (from wrapper.h static consts)
+pub mod rx_offload {
+    use dpdk_net_sys::ffi;
+
+    /// IPv4 header checksum verification
+    pub const IPV4_CKSUM: u64 = ffi::RUST_RTE_ETH_RX_OFFLOAD_IPV4_CKSUM;
+    /// UDP checksum verification
+    pub const UDP_CKSUM: u64 = ffi::RUST_RTE_ETH_RX_OFFLOAD_UDP_CKSUM;
+    /// TCP checksum verification
+    pub const TCP_CKSUM: u64 = ffi::RUST_RTE_ETH_RX_OFFLOAD_TCP_CKSUM;
+    /// Receive frames larger than one mbuf as segment chains
+    pub const SCATTER: u64 = ffi::RUST_RTE_ETH_RX_OFFLOAD_SCATTER;
+    /// Strip the 802.1Q tag into the mbuf's `vlan_tci`
+    pub const VLAN_STRIP: u64 = ffi::RUST_RTE_ETH_RX_OFFLOAD_VLAN_STRIP;
+    /// Drop frames for VLANs not added with [`EthDev::vlan_filter`](super::EthDev::vlan_filter)
+    pub const VLAN_FILTER: u64 = ffi::RUST_RTE_ETH_RX_OFFLOAD_VLAN_FILTER;
+}
+
+/// TX offload flags (RTE_ETH_TX_OFFLOAD_*)
+/// Re-exported from generated bindings (from wrapper.h static consts)
+pub mod tx_offload {
+    use dpdk_net_sys::ffi;
+
+    /// IPv4 header checksum computation
+    pub const IPV4_CKSUM: u64 = ffi::RUST_RTE_ETH_TX_OFFLOAD_IPV4_CKSUM;
+    /// UDP checksum computation
+    pub const UDP_CKSUM: u64 = ffi::RUST_RTE_ETH_TX_OFFLOAD_UDP_CKSUM;
+    /// TCP checksum computation
+    pub const TCP_CKSUM: u64 = ffi::RUST_RTE_ETH_TX_OFFLOAD_TCP_CKSUM;
+    /// TCP segmentation offload
+    pub const TCP_TSO: u64 = ffi::RUST_RTE_ETH_TX_OFFLOAD_TCP_TSO;
+    /// Transmit of multi-segment (chained) mbufs
+    pub const MULTI_SEGS: u64 = ffi::RUST_RTE_ETH_TX_OFFLOAD_MULTI_SEGS;
+    /// Insert the mbuf's `vlan_tci` as an 802.1Q tag
+    pub const VLAN_INSERT: u64 = ffi::RUST_RTE_ETH_TX_OFFLOAD_VLAN_INSERT;
+}
+
+/// IPv4/TCP/UDP checksum offloads enabled on a port.
+///
+/// Negotiated by [`EthDevBuilder::build`] from the device capabilities when
+/// requested with [`EthDevBuilder::checksum_offload`], and read back with
+/// [`EthDev::checksum_offload`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChecksumOffload {
+    /// NIC verifies IPv4 header checksums on receive
+    pub rx_ipv4: bool,
+    /// NIC verifies TCP checksums on receive
+    pub rx_tcp: bool,
+    /// NIC verifies UDP checksums on receive
+    pub rx_udp: bool,
+    /// NIC computes IPv4 header checksums on transmit
+    pub tx_ipv4: bool,
+    /// NIC computes TCP checksums on transmit
+    pub tx_tcp: bool,
+    /// NIC computes UDP checksums on transmit
+    pub tx_udp: bool,
+}
+
+impl ChecksumOffload {
+    /// Build from RX/TX offload bitmaps (capabilities or configured offloads)
+    pub fn from_offloads(rx: u64, tx: u64) -> Self {
+        Self {
+            rx_ipv4: rx & rx_offload::IPV4_CKSUM != 0,
+            rx_tcp: rx & rx_offload::TCP_CKSUM != 0,
+            rx_udp: rx & rx_offload::UDP_CKSUM != 0,
+            tx_ipv4: tx & tx_offload::IPV4_CKSUM != 0,
+            tx_tcp: tx & tx_offload::TCP_CKSUM != 0,
+            tx_udp: tx & tx_offload::UDP_CKSUM != 0,
+        }
+    }
+
+    /// RX offload bits for `rte_eth_conf.rxmode.offloads`
+    pub fn rx_offloads(&self) -> u64 {
+        let mut offloads = 0;
+        if self.rx_ipv4 {
+            offloads |= rx_offload::IPV4_CKSUM;
+        }
+        if self.rx_tcp {
+            offloads |= rx_offload::TCP_CKSUM;
+        }
+        if self.rx_udp {
+            offloads |= rx_offload::UDP_CKSUM;
+        }
+        offloads
+    }
+
+    /// TX offload bits for `rte_eth_conf.txmode.offloads`
+    pub fn tx_offloads(&self) -> u64 {
+        let mut offloads = 0;
+        if self.tx_ipv4 {
+            offloads |= tx_offload::IPV4_CKSUM;
+        }
+        if self.tx_tcp {
+            offloads |= tx_offload::TCP_CKSUM;
+        }
+        if self.tx_udp {
+            offloads |= tx_offload::UDP_CKSUM;
+        }
+        offloads
+    }
+
+    /// Any RX checksum verification is offloaded
+    pub fn any_rx(&self) -> bool {
+        self.rx_ipv4 || self.rx_tcp || self.rx_udp
+    }
+
+    /// Any TX checksum computation is offloaded
+    pub fn any_tx(&self) -> bool {
+        self.tx_ipv4 || self.tx_tcp || self.tx_udp
+    }
+}
+
+/// 802.1Q VLAN offloads enabled on a port.
+///
+/// Negotiated by [`EthDevBuilder::build`] when requested with
+/// [`EthDevBuilder::vlan_offload`], read back with [`EthDev::vlan_offload`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VlanOffload {
+    /// NIC strips the tag into the mbuf's `vlan_tci` on receive
+    pub rx_strip: bool,
+    /// NIC inserts the mbuf's `vlan_tci` as a tag on transmit
+    pub tx_insert: bool,
+}
+
+impl VlanOffload {
+    /// Build from RX/TX offload bitmaps (capabilities or configured offloads)
+    pub fn from_offloads(rx: u64, tx: u64) -> Self {
+        Self {
+            rx_strip: rx & rx_offload::VLAN_STRIP != 0,
+            tx_insert: tx & tx_offload::VLAN_INSERT != 0,
+        }
+    }
+}
+
+/// Ethernet header + CRC, added to the MTU to get the largest frame
+const ETHER_OVERHEAD: usize = 14 + 4;
+
 /// Standard Microsoft RSS key (40 bytes) for Toeplitz hash
 /// This key provides good distribution for TCP/IP traffic
 pub const RSS_KEY_40: [u8; 40] = [
@@ -170,6 +308,10 @@ pub struct EthConf {
     pub rss_hf: u64,
     /// RSS key (None = use driver default, Some = use this key)
     pub rss_key: Option<Vec<u8>>,
+    /// Enable link status change interrupts (required for LSC events on most PMDs)
+    pub lsc_interrupt: bool,
+    /// Enable device removal interrupts (required for RMV events)
+    pub rmv_interrupt: bool,
 }
 
 impl EthConf {
@@ -234,6 +376,23 @@ impl EthConf {
         self
     }
 
+    /// Enable link status change interrupts.
+    ///
+    /// Only set this if the device reports [`EthDev::supports_lsc_interrupt`],
+    /// otherwise `rte_eth_dev_configure` fails with EINVAL.
+    pub fn lsc_interrupt(mut self) -> Self {
+        self.lsc_interrupt = true;
+        self
+    }
+
+    /// Enable device removal interrupts.
+    ///
+    /// Only set this if the device reports [`EthDev::supports_rmv_interrupt`].
+    pub fn rmv_interrupt(mut self) -> Self {
+        self.rmv_interrupt = true;
+        self
+    }
+
     /// Convert to raw rte_eth_conf
     /// Returns the config and an optional key buffer that must be kept alive
     fn to_raw(&self) -> (ffi::rte_eth_conf, Option<Vec<u8>>) {
@@ -246,6 +405,8 @@ impl EthConf {
         conf.txmode.mq_mode = self.tx_mode.mq_mode as u32;
         conf.txmode.offloads = self.tx_mode.offloads;
         conf.lpbk_mode = self.loopback_mode;
+        conf.intr_conf.set_lsc(self.lsc_interrupt as u32);
+        conf.intr_conf.set_rmv(self.rmv_interrupt as u32);
 
         let mut key_buffer: Option<Vec<u8>> = None;
 
@@ -280,6 +441,8 @@ pub struct RxQueueConf {
     pub socket_id: i32,
     /// Optional RX conf (None uses device defaults)
     pub conf: Option<ffi::rte_eth_rxconf>,
+    /// Leave the queue stopped when the port starts
+    pub deferred_start: bool,
 }
 
 impl Default for RxQueueConf {
@@ -288,6 +451,7 @@ impl Default for RxQueueConf {
             nb_desc: 1024,
             socket_id: -1,
             conf: None,
+            deferred_start: false,
         }
     }
 }
@@ -319,6 +483,15 @@ impl RxQueueConf {
         self.conf = Some(conf);
         self
     }
+
+    /// Leave the queue stopped when the port starts.
+    ///
+    /// Start it later with [`EthDev::rx_queue_start`]. Overrides
+    /// `rx_deferred_start` in the raw configuration.
+    pub fn deferred_start(mut self, deferred: bool) -> Self {
+        self.deferred_start = deferred;
+        self
+    }
 }
 
 /// TX queue configuration
@@ -330,6 +503,8 @@ pub struct TxQueueConf {
     pub socket_id: i32,
     /// Optional TX conf (None uses device defaults)
     pub conf: Option<ffi::rte_eth_txconf>,
+    /// Leave the queue stopped when the port starts
+    pub deferred_start: bool,
 }
 
 impl Default for TxQueueConf {
@@ -338,6 +513,7 @@ impl Default for TxQueueConf {
             nb_desc: 1024,
             socket_id: -1,
             conf: None,
+            deferred_start: false,
         }
     }
 }
@@ -369,6 +545,187 @@ impl TxQueueConf {
         self.conf = Some(conf);
         self
     }
+
+    /// Leave the queue stopped when the port starts.
+    ///
+    /// Start it later with [`EthDev::tx_queue_start`]. Overrides
+    /// `tx_deferred_start` in the raw configuration.
+    pub fn deferred_start(mut self, deferred: bool) -> Self {
+        self.deferred_start = deferred;
+        self
+    }
+}
+
+/// Link status of an Ethernet device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkStatus {
+    /// Link speed in Mbps (`None` if the driver reports an unknown speed)
+    pub speed_mbps: Option<u32>,
+    /// Full duplex (false = half duplex)
+    pub full_duplex: bool,
+    /// Autonegotiation enabled
+    pub autoneg: bool,
+    /// Link is up
+    pub up: bool,
+}
+
+impl From<&ffi::rust_eth_link> for LinkStatus {
+    fn from(link: &ffi::rust_eth_link) -> Self {
+        Self {
+            // RTE_ETH_SPEED_NUM_UNKNOWN is UINT32_MAX
+            speed_mbps: (link.speed != u32::MAX).then_some(link.speed),
+            full_duplex: link.duplex != 0,
+            autoneg: link.autoneg != 0,
+            up: link.status != 0,
+        }
+    }
+}
+
+impl std::fmt::Display for LinkStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.up {
+            return write!(f, "down");
+        }
+        match self.speed_mbps {
+            Some(speed) => write!(f, "up {} Mbps", speed)?,
+            None => write!(f, "up (unknown speed)")?,
+        }
+        write!(
+            f,
+            " {}{}",
+            if self.full_duplex {
+                "full-duplex"
+            } else {
+                "half-duplex"
+            },
+            if self.autoneg { " autoneg" } else { "" }
+        )
+    }
+}
+
+/// A single extended statistic (xstat) reported by the driver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XStat {
+    /// Xstat ID (stable for the lifetime of the port configuration)
+    pub id: u64,
+    /// Xstat name (e.g. "rx_missed_errors", "rx_q0_packets")
+    pub name: String,
+    /// Counter value
+    pub value: u64,
+}
+
+/// Per-queue packet counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    /// Packets received/transmitted on this queue
+    pub packets: u64,
+    /// Bytes received/transmitted on this queue
+    pub bytes: u64,
+    /// Errors on this queue (only reported for RX by most drivers)
+    pub errors: u64,
+}
+
+/// Typed device statistics with per-queue counters.
+///
+/// Port-level counters come from `rte_eth_stats_get`. Per-queue counters are
+/// no longer part of `rte_eth_stats` (removed in DPDK 25.11.0), so they are
+/// collected from the generic `rx_qN_*` / `tx_qN_*` xstats instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EthStats {
+    /// Successfully received packets
+    pub ipackets: u64,
+    /// Successfully transmitted packets
+    pub opackets: u64,
+    /// Successfully received bytes
+    pub ibytes: u64,
+    /// Successfully transmitted bytes
+    pub obytes: u64,
+    /// Packets dropped by the NIC because no RX descriptor was available
+    pub imissed: u64,
+    /// Erroneous received packets
+    pub ierrors: u64,
+    /// Failed transmitted packets
+    pub oerrors: u64,
+    /// RX mbuf allocation failures
+    pub rx_nombuf: u64,
+    /// Per-RX-queue counters, indexed by queue ID
+    pub rx_queues: Vec<QueueStats>,
+    /// Per-TX-queue counters, indexed by queue ID
+    pub tx_queues: Vec<QueueStats>,
+}
+
+impl From<&ffi::rte_eth_stats> for EthStats {
+    fn from(stats: &ffi::rte_eth_stats) -> Self {
+        Self {
+            ipackets: stats.ipackets,
+            opackets: stats.opackets,
+            ibytes: stats.ibytes,
+            obytes: stats.obytes,
+            imissed: stats.imissed,
+            ierrors: stats.ierrors,
+            oerrors: stats.oerrors,
+            rx_nombuf: stats.rx_nombuf,
+            rx_queues: Vec::new(),
+            tx_queues: Vec::new(),
+        }
+    }
+}
+
+impl EthStats {
+    /// Fill in per-queue counters from a list of xstats.
+    ///
+    /// Recognizes the generic ethdev names `rx_q<N>_packets`, `rx_q<N>_bytes`,
+    /// `rx_q<N>_errors`, `tx_q<N>_packets` and `tx_q<N>_bytes`.
+    /// Other xstats are ignored.
+    pub fn apply_queue_xstats(&mut self, xstats: &[XStat]) {
+        for xstat in xstats {
+            let Some((is_rx, queue, counter)) = parse_queue_xstat_name(&xstat.name) else {
+                continue;
+            };
+            let queues = if is_rx {
+                &mut self.rx_queues
+            } else {
+                &mut self.tx_queues
+            };
+            let queue = queue as usize;
+            if queues.len() <= queue {
+                queues.resize(queue + 1, QueueStats::default());
+            }
+            match counter {
+                "packets" => queues[queue].packets = xstat.value,
+                "bytes" => queues[queue].bytes = xstat.value,
+                "errors" => queues[queue].errors = xstat.value,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Parse a generic per-queue xstat name like `rx_q3_bytes`.
+///
+/// Returns `(is_rx, queue_id, counter)` where counter is the suffix after the queue.
+fn parse_queue_xstat_name(name: &str) -> Option<(bool, QueueId, &str)> {
+    let (is_rx, rest) = if let Some(rest) = name.strip_prefix("rx_q") {
+        (true, rest)
+    } else if let Some(rest) = name.strip_prefix("tx_q") {
+        (false, rest)
+    } else {
+        return None;
+    };
+    let (queue, counter) = rest.split_once('_')?;
+    let queue = queue.parse::<QueueId>().ok()?;
+    match counter {
+        "packets" | "bytes" | "errors" => Some((is_rx, queue, counter)),
+        _ => None,
+    }
+}
+
+/// Convert a raw xstat name buffer into an owned String.
+fn xstat_name_to_string(name: &ffi::rte_eth_xstat_name) -> String {
+    // Safety: DPDK guarantees the name is NUL-terminated within RTE_ETH_XSTATS_NAME_SIZE
+    unsafe { std::ffi::CStr::from_ptr(name.name.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
 }
 
 /// Ethernet device wrapper
@@ -395,6 +752,71 @@ impl EthDev {
         unsafe { ffi::rte_eth_dev_count_avail() }
     }
 
+    /// Probe a device at runtime and return a handle for its port.
+    ///
+    /// `devargs` uses the same syntax as the EAL `--vdev`/`-a` options, e.g.
+    /// `"net_ring1"`, `"net_tap0,iface=tap0"` or `"net_af_packet0,iface=eth1"`.
+    /// The port is named after the device (the part before the first `,`).
+    /// If the device creates several ports, the first one is returned.
+    ///
+    /// The returned port is not configured; use [`EthDevBuilder`] on its
+    /// port ID. Subscribe to [`EthEventType::New`](super::event::EthEventType::New)
+    /// to observe ports created by other means.
+    pub fn attach(devargs: &str) -> Result<Self> {
+        let c_devargs = std::ffi::CString::new(devargs).map_err(|_| crate::api::Errno::EINVAL)?;
+        let ret = unsafe { ffi::rte_dev_probe(c_devargs.as_ptr()) };
+        check_errno(ret)?;
+
+        let name = devargs.split(',').next().unwrap_or(devargs);
+        // Devargs may carry a bus prefix ("vdev:net_ring1"); port names do not
+        let port_id = Self::port_by_name(name).or_else(|e| match name.split_once(':') {
+            Some((_, rest)) => Self::port_by_name(rest),
+            None => Err(e),
+        })?;
+        debug!(port_id, devargs, "Attached device");
+        Ok(Self::new(port_id))
+    }
+
+    /// Look up a port ID by device name (e.g. `"net_ring0"` or a PCI address).
+    pub fn port_by_name(name: &str) -> Result<PortId> {
+        let c_name = std::ffi::CString::new(name).map_err(|_| crate::api::Errno::EINVAL)?;
+        let mut port_id: PortId = 0;
+        let ret = unsafe { ffi::rte_eth_dev_get_port_by_name(c_name.as_ptr(), &mut port_id) };
+        check_errno(ret)?;
+        Ok(port_id)
+    }
+
+    /// Get the device name of the port.
+    pub fn name(&self) -> Result<String> {
+        let mut buf = [0 as std::ffi::c_char; ffi::RTE_DEV_NAME_MAX_LEN as usize];
+        let ret = unsafe { ffi::rte_eth_dev_get_name_by_port(self.port_id, buf.as_mut_ptr()) };
+        check_errno(ret)?;
+        // Safety: DPDK NUL-terminates the name within RTE_ETH_NAME_MAX_LEN
+        Ok(unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) }
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    /// Stop the port and remove its underlying device.
+    ///
+    /// The driver closes and releases every port of the device, raising
+    /// [`EthEventType::Destroy`](super::event::EthEventType::Destroy) for
+    /// each. All queues must no longer be polled, and mbufs received from
+    /// the port may still be in use only if their mempool outlives it.
+    pub fn detach(self) -> Result<()> {
+        let info = self.info()?;
+        if info.device.is_null() {
+            return Err(crate::api::Errno::ENODEV);
+        }
+        if let Err(e) = self.stop() {
+            debug!(port_id = self.port_id, "Stop before detach failed: {}", e);
+        }
+        let ret = unsafe { ffi::rte_dev_remove(info.device) };
+        check_errno(ret)?;
+        debug!(port_id = self.port_id, "Detached device");
+        Ok(())
+    }
+
     /// Get device info.
     ///
     /// **Note**: The `max_rx_queues` and `max_tx_queues` fields in the returned
@@ -426,6 +848,57 @@ impl EthDev {
         Ok(unsafe { addr.assume_init() })
     }
 
+    /// Check whether the device can raise link status change interrupts.
+    pub fn supports_lsc_interrupt(&self) -> Result<bool> {
+        Ok(self.dev_flags()? & ffi::RUST_RTE_ETH_DEV_INTR_LSC != 0)
+    }
+
+    /// Check whether the device can raise removal interrupts.
+    pub fn supports_rmv_interrupt(&self) -> Result<bool> {
+        Ok(self.dev_flags()? & ffi::RUST_RTE_ETH_DEV_INTR_RMV != 0)
+    }
+
+    fn dev_flags(&self) -> Result<u32> {
+        let info = self.info()?;
+        if info.dev_flags.is_null() {
+            return Ok(0);
+        }
+        Ok(unsafe { *info.dev_flags })
+    }
+
+    /// Get the link status, waiting for link negotiation to complete.
+    ///
+    /// This may block for several seconds on hardware NICs.
+    /// Use [`link_nowait`](Self::link_nowait) from polling loops.
+    pub fn link(&self) -> Result<LinkStatus> {
+        self.link_get(true)
+    }
+
+    /// Get the current link status without waiting.
+    pub fn link_nowait(&self) -> Result<LinkStatus> {
+        self.link_get(false)
+    }
+
+    fn link_get(&self, wait: bool) -> Result<LinkStatus> {
+        let mut link = MaybeUninit::<ffi::rust_eth_link>::uninit();
+        let ret = unsafe { ffi::rust_eth_link_get(self.port_id, link.as_mut_ptr(), wait as i32) };
+        check_rte_success(ret)?;
+        let link = unsafe { link.assume_init() };
+        Ok(LinkStatus::from(&link))
+    }
+
+    /// Set the link administratively up.
+    pub fn set_link_up(&self) -> Result<()> {
+        let ret = unsafe { ffi::rte_eth_dev_set_link_up(self.port_id) };
+        check_errno(ret)
+    }
+
+    /// Set the link administratively down.
+    pub fn set_link_down(&self) -> Result<()> {
+        let ret = unsafe { ffi::rte_eth_dev_set_link_down(self.port_id) };
+        check_errno(ret)
+    }
+
     /// Get device statistics
     pub fn stats(&self) -> Result<ffi::rte_eth_stats> {
         let mut stats = MaybeUninit::<ffi::rte_eth_stats>::uninit();
@@ -434,6 +907,113 @@ impl EthDev {
         Ok(unsafe { stats.assume_init() })
     }
 
+    /// Get typed device statistics, including per-queue counters.
+    ///
+    /// Per-queue counters are read from xstats. If the driver does not
+    /// report xstats, `rx_queues`/`tx_queues` are left empty.
+    pub fn eth_stats(&self) -> Result<EthStats> {
+        let mut stats = EthStats::from(&self.stats()?);
+        if let Ok(xstats) = self.xstats() {
+            stats.apply_queue_xstats(&xstats);
+        }
+        Ok(stats)
+    }
+
+    /// Reset the basic device statistics.
+    pub fn stats_reset(&self) -> Result<()> {
+        let ret = unsafe { ffi::rte_eth_stats_reset(self.port_id) };
+        check_errno(ret)
+    }
+
+    /// Get the names of all extended statistics.
+    ///
+    /// The index in the returned Vec is the xstat ID. Fails with `EAGAIN` if
+    /// the set of xstats grows while it is read.
+    pub fn xstats_names(&self) -> Result<Vec<String>> {
+        let count = unsafe { ffi::rte_eth_xstats_get_names(self.port_id, std::ptr::null_mut(), 0) };
+        check_errno(count)?;
+
+        let mut names: Vec<ffi::rte_eth_xstat_name> =
+            vec![unsafe { std::mem::zeroed() }; count as usize];
+        let ret = unsafe {
+            ffi::rte_eth_xstats_get_names(self.port_id, names.as_mut_ptr(), names.len() as u32)
+        };
+        check_errno(ret)?;
+        if ret as usize > names.len() {
+            // Too small: nothing was filled in
+            return Err(crate::api::Errno::EAGAIN);
+        }
+        names.truncate(ret as usize);
+
+        Ok(names.iter().map(xstat_name_to_string).collect())
+    }
+
+    /// Get all extended statistics with their names and values.
+    pub fn xstats(&self) -> Result<Vec<XStat>> {
+        let names = self.xstats_names()?;
+
+        let mut values: Vec<ffi::rte_eth_xstat> = vec![unsafe { std::mem::zeroed() }; names.len()];
+        let ret = unsafe {
+            ffi::rte_eth_xstats_get(self.port_id, values.as_mut_ptr(), values.len() as u32)
+        };
+        check_errno(ret)?;
+        if ret as usize > values.len() {
+            // The set of xstats grew between the two calls (e.g. queues reconfigured)
+            return Err(crate::api::Errno::EAGAIN);
+        }
+        values.truncate(ret as usize);
+
+        Ok(values
+            .iter()
+            .filter_map(|v| {
+                names.get(v.id as usize).map(|name| XStat {
+                    id: v.id,
+                    name: name.clone(),
+                    value: v.value,
+                })
+            })
+            .collect())
+    }
+
+    /// Look up the ID of an extended statistic by name.
+    ///
+    /// Resolve IDs once and use [`xstats_by_id`](Self::xstats_by_id) for cheap
+    /// periodic scraping of a few counters.
+    pub fn xstat_id_by_name(&self, name: &str) -> Result<u64> {
+        let c_name = std::ffi::CString::new(name).map_err(|_| crate::api::Errno::EINVAL)?;
+        let mut id = 0u64;
+        let ret =
+            unsafe { ffi::rte_eth_xstats_get_id_by_name(self.port_id, c_name.as_ptr(), &mut id) };
+        check_errno(ret)?;
+        Ok(id)
+    }
+
+    /// Get the values of the given extended statistics.
+    ///
+    /// Values are returned in the same order as `ids`.
+    pub fn xstats_by_id(&self, ids: &[u64]) -> Result<Vec<u64>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut values = vec![0u64; ids.len()];
+        let ret = unsafe {
+            ffi::rte_eth_xstats_get_by_id(
+                self.port_id,
+                ids.as_ptr(),
+                values.as_mut_ptr(),
+                ids.len() as u32,
+            )
+        };
+        check_errno(ret)?;
+        Ok(values)
+    }
+
+    /// Reset all extended statistics (this also resets the basic statistics).
+    pub fn xstats_reset(&self) -> Result<()> {
+        let ret = unsafe { ffi::rte_eth_xstats_reset(self.port_id) };
+        check_errno(ret)
+    }
+
     /// Configure the device
     pub fn configure(&self, nb_rx_queues: u16, nb_tx_queues: u16, conf: &EthConf) -> Result<()> {
         let (raw_conf, _key_buffer) = conf.to_raw();
@@ -451,8 +1031,18 @@ impl EthDev {
         mempool: &MemPool,
         conf: &RxQueueConf,
     ) -> Result<()> {
-        let conf_ptr = conf
-            .conf
+        // Deferred start needs a full rxconf; start from the device defaults
+        let raw_conf = if conf.deferred_start {
+            let mut raw = match conf.conf {
+                Some(raw) => raw,
+                None => self.info()?.default_rxconf,
+            };
+            raw.rx_deferred_start = 1;
+            Some(raw)
+        } else {
+            conf.conf
+        };
+        let conf_ptr = raw_conf
             .as_ref()
             .map_or(std::ptr::null(), |c| c as *const _);
         let socket_id = if conf.socket_id < 0 {
@@ -475,8 +1065,17 @@ impl EthDev {
 
     /// Setup a TX queue
     pub fn tx_queue_setup(&self, queue_id: QueueId, conf: &TxQueueConf) -> Result<()> {
-        let conf_ptr = conf
-            .conf
+        let raw_conf = if conf.deferred_start {
+            let mut raw = match conf.conf {
+                Some(raw) => raw,
+                None => self.info()?.default_txconf,
+            };
+            raw.tx_deferred_start = 1;
+            Some(raw)
+        } else {
+            conf.conf
+        };
+        let conf_ptr = raw_conf
             .as_ref()
             .map_or(std::ptr::null(), |c| c as *const _);
         let socket_id = if conf.socket_id < 0 {
@@ -508,6 +1107,46 @@ impl EthDev {
         check_rte_success(ret)
     }
 
+    /// Start a single RX queue on a started port.
+    ///
+    /// Used for queues set up with [`RxQueueConf::deferred_start`] or stopped
+    /// with [`rx_queue_stop`](Self::rx_queue_stop). Fails with `ENOTSUP` if the
+    /// driver has no per-queue control.
+    pub fn rx_queue_start(&self, queue_id: QueueId) -> Result<()> {
+        let ret = unsafe { ffi::rte_eth_dev_rx_queue_start(self.port_id, queue_id) };
+        check_errno(ret)
+    }
+
+    /// Stop a single RX queue; the rest of the port keeps running.
+    ///
+    /// Remove the queue from the RETA first (see
+    /// [`configure_rss_reta_queues`](Self::configure_rss_reta_queues)) so
+    /// RSS stops steering traffic to it.
+    pub fn rx_queue_stop(&self, queue_id: QueueId) -> Result<()> {
+        let ret = unsafe { ffi::rte_eth_dev_rx_queue_stop(self.port_id, queue_id) };
+        check_errno(ret)
+    }
+
+    /// Start a single TX queue on a started port.
+    pub fn tx_queue_start(&self, queue_id: QueueId) -> Result<()> {
+        let ret = unsafe { ffi::rte_eth_dev_tx_queue_start(self.port_id, queue_id) };
+        check_errno(ret)
+    }
+
+    /// Stop a single TX queue; the rest of the port keeps running.
+    ///
+    /// No thread may be transmitting on the queue.
+    pub fn tx_queue_stop(&self, queue_id: QueueId) -> Result<()> {
+        let ret = unsafe { ffi::rte_eth_dev_tx_queue_stop(self.port_id, queue_id) };
+        check_errno(ret)
+    }
+
+    /// Get the number of configured RX and TX queues.
+    pub fn nb_queues(&self) -> Result<(u16, u16)> {
+        let info = self.info()?;
+        Ok((info.nb_rx_queues, info.nb_tx_queues))
+    }
+
     /// Enable promiscuous mode
     pub fn promiscuous_enable(&self) -> Result<()> {
         let ret = unsafe { ffi::rte_eth_promiscuous_enable(self.port_id) };
@@ -554,11 +1193,85 @@ impl EthDev {
         Ok((rss_hf & tcp_flags) != 0)
     }
 
+    /// Get the current port configuration.
+    fn conf(&self) -> Result<ffi::rte_eth_conf> {
+        let mut conf = MaybeUninit::<ffi::rte_eth_conf>::uninit();
+        let ret = unsafe { ffi::rte_eth_dev_conf_get(self.port_id, conf.as_mut_ptr()) };
+        check_errno(ret)?;
+        Ok(unsafe { conf.assume_init() })
+    }
+
+    /// Get the checksum offloads enabled in the current port configuration.
+    pub fn checksum_offload(&self) -> Result<ChecksumOffload> {
+        let conf = self.conf()?;
+        Ok(ChecksumOffload::from_offloads(
+            conf.rxmode.offloads,
+            conf.txmode.offloads,
+        ))
+    }
+
+    /// Get the VLAN offloads enabled in the current port configuration.
+    pub fn vlan_offload(&self) -> Result<VlanOffload> {
+        let conf = self.conf()?;
+        Ok(VlanOffload::from_offloads(
+            conf.rxmode.offloads,
+            conf.txmode.offloads,
+        ))
+    }
+
+    /// Accept (`on`) or stop accepting frames for VLAN `vlan_id`.
+    ///
+    /// Only has an effect with [`rx_offload::VLAN_FILTER`] enabled; without
+    /// it the port receives all VLANs.
+    pub fn vlan_filter(&self, vlan_id: u16, on: bool) -> Result<()> {
+        let ret = unsafe { ffi::rte_eth_dev_vlan_filter(self.port_id, vlan_id, on as i32) };
+        check_errno(ret)
+    }
+
+    /// Get the port MTU (L3 payload size, excluding the Ethernet header).
+    pub fn mtu(&self) -> Result<u16> {
+        let mut mtu = 0u16;
+        let ret = unsafe { ffi::rte_eth_dev_get_mtu(self.port_id, &mut mtu) };
+        check_errno(ret)?;
+        Ok(mtu)
+    }
+
+    /// Change the port MTU.
+    ///
+    /// Frames larger than one mbuf need [`rx_offload::SCATTER`] on most
+    /// PMDs, which can only be enabled at configure time.
+    pub fn set_mtu(&self, mtu: u16) -> Result<()> {
+        let ret = unsafe { ffi::rte_eth_dev_set_mtu(self.port_id, mtu) };
+        check_errno(ret)
+    }
+
+    /// Check whether TCP segmentation offload is enabled on the port.
+    ///
+    /// If TSO was requested with [`EthDevBuilder::tso`] but this returns
+    /// `false`, the NIC lacks the capability and segmentation has to be done
+    /// in software (see [`TsoConfig`](crate::device::TsoConfig)).
+    pub fn tso_enabled(&self) -> Result<bool> {
+        Ok(self.conf()?.txmode.offloads & tx_offload::TCP_TSO != 0)
+    }
+
     /// Configure RSS Redirection Table (RETA) to distribute packets across queues.
     ///
     /// This sets up the RETA to evenly distribute traffic across the specified
     /// number of RX queues using round-robin assignment.
     pub fn configure_rss_reta(&self, nb_rx_queues: u16) -> Result<()> {
+        let queues: Vec<QueueId> = (0..nb_rx_queues).collect();
+        self.configure_rss_reta_queues(&queues)
+    }
+
+    /// Configure the RETA to spread traffic round-robin over `queues`.
+    ///
+    /// Most drivers accept RETA updates on a started port, so this can move
+    /// traffic off a queue before [`rx_queue_stop`](Self::rx_queue_stop) or
+    /// onto a newly started one.
+    pub fn configure_rss_reta_queues(&self, queues: &[QueueId]) -> Result<()> {
+        if queues.is_empty() {
+            return Err(crate::api::Errno::EINVAL);
+        }
         // Get device info to find RETA size
         let info = self.info()?;
         let reta_size = info.reta_size;
@@ -581,7 +1294,7 @@ impl EthDev {
             for i in 0..64 {
                 let entry_idx = group_idx * 64 + i;
                 if entry_idx < reta_size as usize {
-                    group.reta[i] = (entry_idx % nb_rx_queues as usize) as u16;
+                    group.reta[i] = queues[entry_idx % queues.len()];
                 }
             }
         }
@@ -662,6 +1375,9 @@ pub struct EthDevBuilder {
     rx_queue_conf: RxQueueConf,
     tx_queue_conf: TxQueueConf,
     promiscuous: bool,
+    checksum_offload: bool,
+    tso: bool,
+    vlan_offload: bool,
 }
 
 impl EthDevBuilder {
@@ -675,6 +1391,9 @@ impl EthDevBuilder {
             rx_queue_conf: RxQueueConf::default(),
             tx_queue_conf: TxQueueConf::default(),
             promiscuous: false,
+            checksum_offload: false,
+            tso: false,
+            vlan_offload: false,
         }
     }
 
@@ -714,19 +1433,122 @@ impl EthDevBuilder {
         self
     }
 
+    /// Enable or disable checksum offload negotiation (default: disabled)
+    ///
+    /// When enabled, every IPv4/TCP/UDP checksum offload the device supports
+    /// is added to the configured RX/TX offloads. Pass the result of
+    /// [`EthDev::checksum_offload`] to
+    /// [`DpdkDevice::with_checksum_offload`](crate::device::DpdkDevice::with_checksum_offload),
+    /// which leaves those checksums to the NIC.
+    pub fn checksum_offload(mut self, enabled: bool) -> Self {
+        self.checksum_offload = enabled;
+        self
+    }
+
+    /// Enable or disable TCP segmentation offload negotiation (default: disabled)
+    ///
+    /// When enabled and supported, `TCP_TSO` (plus `MULTI_SEGS`, needed for
+    /// chained mbufs) is added to the configured TX offloads. Check the result
+    /// with [`EthDev::tso_enabled`]; without NIC support, only `MULTI_SEGS` is
+    /// requested so software GSO output can be transmitted.
+    pub fn tso(mut self, enabled: bool) -> Self {
+        self.tso = enabled;
+        self
+    }
+
+    /// Request VLAN strip/insert offload (default: disabled).
+    ///
+    /// When enabled, whatever of `VLAN_STRIP`, `VLAN_FILTER` and
+    /// `VLAN_INSERT` the NIC supports is added to the configured offloads.
+    /// Check the result with [`EthDev::vlan_offload`]; `DpdkDevice` tags and
+    /// untags in software where the NIC cannot. With `VLAN_FILTER` enabled,
+    /// add each VLAN with [`EthDev::vlan_filter`].
+    pub fn vlan_offload(mut self, enabled: bool) -> Self {
+        self.vlan_offload = enabled;
+        self
+    }
+
     /// Build and start the device
     ///
     /// This will:
-    /// 1. Configure the device
-    /// 2. Setup all RX queues
-    /// 3. Setup all TX queues
-    /// 4. Configure RSS RETA (if multi-queue)
-    /// 5. Update RSS hash configuration (if multi-queue)
-    /// 6. Enable promiscuous mode (if set)
-    /// 7. Start the device
+    /// 1. Negotiate checksum offloads (if enabled)
+    /// 2. Negotiate TCP segmentation offload (if enabled)
+    /// 3. Negotiate VLAN offloads (if enabled) and enable scatter RX / multi-segment TX if the MTU exceeds one mbuf
+    /// 4. Configure the device
+    /// 5. Setup all RX queues
+    /// 6. Setup all TX queues
+    /// 7. Configure RSS RETA (if multi-queue)
+    /// 8. Update RSS hash configuration (if multi-queue)
+    /// 9. Enable promiscuous mode (if set)
+    /// 10. Start the device
+    ///
+    /// Queues with `deferred_start` stay stopped.
     pub fn build(self, mempool: &MemPool) -> Result<EthDev> {
+        let dev = self.configure(mempool)?;
+        dev.start()?;
+        Ok(dev)
+    }
+
+    /// Configure the device without starting it (steps 1-9 of [`build`](Self::build)).
+    ///
+    /// Also the supported way to change the queue or descriptor counts of a
+    /// port at runtime: [`stop`](EthDev::stop) it, run a builder with the new
+    /// settings through `configure`, then [`start`](EthDev::start) it again.
+    /// Every queue is set up anew and the RETA is rebuilt for the new RX
+    /// queue count. Mbufs held by the application stay valid, but queue
+    /// handles for removed queues must no longer be polled.
+    pub fn configure(mut self, mempool: &MemPool) -> Result<EthDev> {
         let dev = EthDev::new(self.port_id);
 
+        // Negotiate checksum offloads from device capabilities
+        if self.checksum_offload {
+            let info = dev.info()?;
+            let offload =
+                ChecksumOffload::from_offloads(info.rx_offload_capa, info.tx_offload_capa);
+            self.eth_conf.rx_mode.offloads |= offload.rx_offloads();
+            self.eth_conf.tx_mode.offloads |= offload.tx_offloads();
+            debug!(?offload, "Checksum offload negotiated");
+        }
+
+        // Negotiate TSO; software GSO is used when the NIC lacks it
+        if self.tso {
+            let capa = dev.info()?.tx_offload_capa;
+            self.eth_conf.tx_mode.offloads |= capa & (tx_offload::TCP_TSO | tx_offload::MULTI_SEGS);
+            if capa & tx_offload::TCP_TSO != 0 {
+                debug!("TCP segmentation offload enabled");
+            } else {
+                debug!("TCP segmentation offload not supported, use software GSO");
+            }
+        }
+
+        // Negotiate VLAN offloads; DpdkDevice tags in software otherwise
+        if self.vlan_offload {
+            let info = dev.info()?;
+            self.eth_conf.rx_mode.offloads |=
+                info.rx_offload_capa & (rx_offload::VLAN_STRIP | rx_offload::VLAN_FILTER);
+            self.eth_conf.tx_mode.offloads |= info.tx_offload_capa & tx_offload::VLAN_INSERT;
+            let offload = VlanOffload::from_offloads(info.rx_offload_capa, info.tx_offload_capa);
+            debug!(?offload, "VLAN offload negotiated");
+        }
+
+        // Jumbo frames on small mbufs arrive and leave as segment chains
+        let max_frame_len = self.eth_conf.rx_mode.mtu as usize + ETHER_OVERHEAD;
+        let mbuf_size =
+            (mempool.data_room_size() as usize).saturating_sub(ffi::RTE_PKTMBUF_HEADROOM as usize);
+        if max_frame_len > mbuf_size {
+            let info = dev.info()?;
+            if info.rx_offload_capa & rx_offload::SCATTER != 0 {
+                self.eth_conf.rx_mode.offloads |= rx_offload::SCATTER;
+            } else {
+                warn!(
+                    max_frame_len,
+                    mbuf_size, "Frames exceed mbuf size but scatter RX is not supported"
+                );
+            }
+            self.eth_conf.tx_mode.offloads |= info.tx_offload_capa & tx_offload::MULTI_SEGS;
+            debug!(max_frame_len, mbuf_size, "Multi-segment RX/TX enabled");
+        }
+
         // Configure device
         dev.configure(self.nb_rx_queues, self.nb_tx_queues, &self.eth_conf)?;
 
@@ -769,9 +1591,6 @@ impl EthDevBuilder {
             dev.promiscuous_enable()?;
         }
 
-        // Start the device
-        dev.start()?;
-
         Ok(dev)
     }
 }
@@ -793,3 +1612,61 @@ pub fn format_mac_addr(addr: &ffi::rte_ether_addr) -> String {
         addr.addr_bytes[5]
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xstat(id: u64, name: &str, value: u64) -> XStat {
+        XStat {
+            id,
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_parse_queue_xstat_name() {
+        assert_eq!(
+            parse_queue_xstat_name("rx_q0_packets"),
+            Some((true, 0, "packets"))
+        );
+        assert_eq!(
+            parse_queue_xstat_name("tx_q12_bytes"),
+            Some((false, 12, "bytes"))
+        );
+        assert_eq!(
+            parse_queue_xstat_name("rx_q3_errors"),
+            Some((true, 3, "errors"))
+        );
+        assert_eq!(parse_queue_xstat_name("rx_missed_errors"), None);
+        assert_eq!(parse_queue_xstat_name("rx_qx_packets"), None);
+        assert_eq!(parse_queue_xstat_name("rx_q0_mbuf_allocation_errors"), None);
+    }
+
+    #[test]
+    fn test_apply_queue_xstats() {
+        let mut stats = EthStats::default();
+        stats.apply_queue_xstats(&[
+            xstat(0, "rx_good_packets", 100),
+            xstat(1, "rx_q0_packets", 60),
+            xstat(2, "rx_q1_packets", 40),
+            xstat(3, "rx_q1_bytes", 4000),
+            xstat(4, "rx_q1_errors", 2),
+            xstat(5, "tx_q0_packets", 7),
+        ]);
+
+        assert_eq!(stats.rx_queues.len(), 2);
+        assert_eq!(stats.rx_queues[0].packets, 60);
+        assert_eq!(
+            stats.rx_queues[1],
+            QueueStats {
+                packets: 40,
+                bytes: 4000,
+                errors: 2
+            }
+        );
+        assert_eq!(stats.tx_queues.len(), 1);
+        assert_eq!(stats.tx_queues[0].packets, 7);
+    }
+}
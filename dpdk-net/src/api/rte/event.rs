@@ -0,0 +1,267 @@
+// DPDK Ethernet device event callbacks
+// See: /usr/local/include/rte_ethdev.h (rte_eth_dev_callback_register)
+//
+// DPDK invokes event callbacks from its interrupt thread. EthEventStream
+// queues those events and wakes the consumer, so link changes and device
+// removal can be awaited from any async runtime (only std::task is used).
+
+use std::collections::VecDeque;
+use std::ffi::c_void;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use dpdk_net_sys::ffi;
+use tracing::warn;
+
+use super::eth::{EthDev, LinkStatus, PortId};
+use crate::api::{Errno, Result, check_errno};
+
+/// Maximum number of undelivered events kept per stream.
+/// When full, the oldest event is dropped.
+const EVENT_QUEUE_CAPACITY: usize = 64;
+
+/// Ethernet device event types supported by [`EthEventStream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EthEventType {
+    /// Link status change (RTE_ETH_EVENT_INTR_LSC)
+    LinkStatusChange,
+    /// Device removal (RTE_ETH_EVENT_INTR_RMV)
+    Removal,
+    /// Port created, e.g. by [`EthDev::attach`] (RTE_ETH_EVENT_NEW)
+    New,
+    /// Port released, e.g. by [`EthDev::detach`] (RTE_ETH_EVENT_DESTROY)
+    Destroy,
+}
+
+impl EthEventType {
+    fn to_raw(self) -> ffi::rte_eth_event_type {
+        match self {
+            Self::LinkStatusChange => ffi::rte_eth_event_type_RTE_ETH_EVENT_INTR_LSC,
+            Self::Removal => ffi::rte_eth_event_type_RTE_ETH_EVENT_INTR_RMV,
+            Self::New => ffi::rte_eth_event_type_RTE_ETH_EVENT_NEW,
+            Self::Destroy => ffi::rte_eth_event_type_RTE_ETH_EVENT_DESTROY,
+        }
+    }
+
+    fn from_raw(raw: ffi::rte_eth_event_type) -> Option<Self> {
+        match raw {
+            ffi::rte_eth_event_type_RTE_ETH_EVENT_INTR_LSC => Some(Self::LinkStatusChange),
+            ffi::rte_eth_event_type_RTE_ETH_EVENT_INTR_RMV => Some(Self::Removal),
+            ffi::rte_eth_event_type_RTE_ETH_EVENT_NEW => Some(Self::New),
+            ffi::rte_eth_event_type_RTE_ETH_EVENT_DESTROY => Some(Self::Destroy),
+            _ => None,
+        }
+    }
+}
+
+/// An event raised by an Ethernet device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EthEvent {
+    /// Port that raised the event
+    pub port_id: PortId,
+    /// Event type
+    pub event_type: EthEventType,
+    /// Link status read when the event was delivered
+    /// (only set for [`EthEventType::LinkStatusChange`])
+    pub link: Option<LinkStatus>,
+}
+
+#[derive(Default)]
+struct Queue {
+    events: VecDeque<EthEvent>,
+    waker: Option<Waker>,
+    dropped: u64,
+}
+
+/// State shared with the DPDK callback via `cb_arg`.
+struct Shared {
+    queue: Mutex<Queue>,
+}
+
+impl Shared {
+    fn push(&self, event: EthEvent) {
+        let waker = {
+            let mut q = self.queue.lock().unwrap();
+            if q.events.len() == EVENT_QUEUE_CAPACITY {
+                q.events.pop_front();
+                q.dropped += 1;
+            }
+            q.events.push_back(event);
+            q.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Callback invoked by DPDK on its interrupt thread.
+unsafe extern "C" fn eth_event_callback(
+    port_id: u16,
+    event: ffi::rte_eth_event_type,
+    cb_arg: *mut c_void,
+    _ret_param: *mut c_void,
+) -> i32 {
+    let Some(event_type) = EthEventType::from_raw(event) else {
+        return 0;
+    };
+    // SAFETY: cb_arg points to the Shared owned by the EthEventStream, which
+    // unregisters this callback before releasing it.
+    let shared = unsafe { &*(cb_arg as *const Shared) };
+    let link = match event_type {
+        EthEventType::LinkStatusChange => EthDev::new(port_id).link_nowait().ok(),
+        EthEventType::Removal | EthEventType::New | EthEventType::Destroy => None,
+    };
+    shared.push(EthEvent {
+        port_id,
+        event_type,
+        link,
+    });
+    0
+}
+
+/// Asynchronous stream of Ethernet device events.
+///
+/// Registers a DPDK callback for each requested event type on creation and
+/// unregisters it on drop.
+///
+/// Link status change events are only raised if the port was configured
+/// with [`EthConf::lsc_interrupt`](super::eth::EthConf::lsc_interrupt);
+/// removal events need [`EthConf::rmv_interrupt`](super::eth::EthConf::rmv_interrupt).
+/// Port creation and release events are raised by ethdev itself, typically
+/// observed with [`EthEventStream::all_ports`] around hotplug.
+///
+/// # Example
+/// ```no_run
+/// use dpdk_net::api::rte::event::{EthEventStream, EthEventType};
+///
+/// # async fn example() {
+/// let mut events = EthEventStream::new(0, &[EthEventType::LinkStatusChange]).unwrap();
+/// loop {
+///     let event = events.recv().await;
+///     println!("port {} link {:?}", event.port_id, event.link);
+/// }
+/// # }
+/// ```
+pub struct EthEventStream {
+    port_id: u16,
+    event_types: Vec<EthEventType>,
+    shared: Arc<Shared>,
+}
+
+impl EthEventStream {
+    /// Subscribe to events on a single port.
+    pub fn new(port_id: PortId, event_types: &[EthEventType]) -> Result<Self> {
+        Self::register(port_id, event_types)
+    }
+
+    /// Subscribe to events on all ports, including ports probed later.
+    pub fn all_ports(event_types: &[EthEventType]) -> Result<Self> {
+        // RTE_ETH_ALL == RTE_MAX_ETHPORTS
+        Self::register(ffi::RTE_MAX_ETHPORTS as u16, event_types)
+    }
+
+    fn register(port_id: u16, event_types: &[EthEventType]) -> Result<Self> {
+        let mut stream = Self {
+            port_id,
+            event_types: Vec::with_capacity(event_types.len()),
+            shared: Arc::new(Shared {
+                queue: Mutex::new(Queue::default()),
+            }),
+        };
+        for &event_type in event_types {
+            if stream.event_types.contains(&event_type) {
+                continue;
+            }
+            let ret = unsafe {
+                ffi::rte_eth_dev_callback_register(
+                    port_id,
+                    event_type.to_raw(),
+                    Some(eth_event_callback),
+                    stream.cb_arg(),
+                )
+            };
+            // On failure, Drop unregisters the types registered so far
+            check_errno(ret)?;
+            stream.event_types.push(event_type);
+        }
+        Ok(stream)
+    }
+
+    fn cb_arg(&self) -> *mut c_void {
+        Arc::as_ptr(&self.shared) as *mut c_void
+    }
+
+    /// Take the next queued event without waiting.
+    pub fn try_recv(&mut self) -> Option<EthEvent> {
+        self.shared.queue.lock().unwrap().events.pop_front()
+    }
+
+    /// Poll for the next event, registering the waker if none is queued.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<EthEvent> {
+        let mut q = self.shared.queue.lock().unwrap();
+        match q.events.pop_front() {
+            Some(event) => Poll::Ready(event),
+            None => {
+                q.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Wait for the next event.
+    pub fn recv(&mut self) -> EthEventFuture<'_> {
+        EthEventFuture { stream: self }
+    }
+
+    /// Number of events dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.shared.queue.lock().unwrap().dropped
+    }
+}
+
+impl Drop for EthEventStream {
+    fn drop(&mut self) {
+        for &event_type in &self.event_types {
+            loop {
+                let ret = unsafe {
+                    ffi::rte_eth_dev_callback_unregister(
+                        self.port_id,
+                        event_type.to_raw(),
+                        Some(eth_event_callback),
+                        self.cb_arg(),
+                    )
+                };
+                // EAGAIN: the callback is currently executing, retry
+                if ret == -(Errno::EAGAIN as i32) {
+                    std::thread::yield_now();
+                    continue;
+                }
+                if ret < 0 {
+                    warn!(
+                        port_id = self.port_id,
+                        ?event_type,
+                        "Failed to unregister event callback: {}",
+                        Errno::from_raw(-ret)
+                    );
+                }
+                break;
+            }
+        }
+    }
+}
+
+/// Future returned by [`EthEventStream::recv`].
+pub struct EthEventFuture<'a> {
+    stream: &'a mut EthEventStream,
+}
+
+impl Future for EthEventFuture<'_> {
+    type Output = EthEvent;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.stream.poll_recv(cx)
+    }
+}
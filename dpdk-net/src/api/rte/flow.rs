@@ -0,0 +1,1005 @@
+// DPDK Generic Flow API (rte_flow)
+// See: /usr/local/include/rte_flow.h
+//
+// Rules are described with the safe `FlowRule` builder and lowered to raw
+// rte_flow items/actions only for the duration of a validate/create call.
+
+use std::ffi::{CStr, c_void};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ptr::{self, NonNull};
+
+use dpdk_net_sys::ffi;
+use tracing::warn;
+
+use super::eth::{PortId, QueueId};
+use crate::api::{Errno, rte_errno};
+
+/// Result type for flow operations
+pub type FlowResult<T> = std::result::Result<T, FlowError>;
+
+/// Which part of a rule a flow error refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowErrorKind {
+    /// No specific cause reported
+    Unspecified,
+    /// Invalid flow handle
+    Handle,
+    /// Group attribute not supported
+    AttrGroup,
+    /// Priority attribute not supported
+    AttrPriority,
+    /// Ingress attribute not supported
+    AttrIngress,
+    /// Egress attribute not supported
+    AttrEgress,
+    /// Transfer attribute not supported
+    AttrTransfer,
+    /// Attributes in general
+    Attr,
+    /// Pattern length
+    ItemNum,
+    /// Item spec
+    ItemSpec,
+    /// Item last (ranges)
+    ItemLast,
+    /// Item mask
+    ItemMask,
+    /// Item in general
+    Item,
+    /// Number of actions
+    ActionNum,
+    /// Action configuration
+    ActionConf,
+    /// Action in general
+    Action,
+    /// Current device state (e.g. port not started)
+    State,
+}
+
+impl FlowErrorKind {
+    fn from_raw(raw: ffi::rte_flow_error_type) -> Self {
+        match raw {
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_HANDLE => Self::Handle,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ATTR_GROUP => Self::AttrGroup,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ATTR_PRIORITY => Self::AttrPriority,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ATTR_INGRESS => Self::AttrIngress,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ATTR_EGRESS => Self::AttrEgress,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ATTR_TRANSFER => Self::AttrTransfer,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ATTR => Self::Attr,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ITEM_NUM => Self::ItemNum,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ITEM_SPEC => Self::ItemSpec,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ITEM_LAST => Self::ItemLast,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ITEM_MASK => Self::ItemMask,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ITEM => Self::Item,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ACTION_NUM => Self::ActionNum,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ACTION_CONF => Self::ActionConf,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ACTION => Self::Action,
+            ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_STATE => Self::State,
+            _ => Self::Unspecified,
+        }
+    }
+
+    fn is_item(self) -> bool {
+        matches!(
+            self,
+            Self::ItemSpec | Self::ItemLast | Self::ItemMask | Self::Item
+        )
+    }
+
+    fn is_action(self) -> bool {
+        matches!(self, Self::ActionConf | Self::Action)
+    }
+}
+
+/// Structured error returned by rte_flow operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowError {
+    /// Error code (e.g. ENOTSUP when the PMD cannot offload the rule)
+    pub errno: Errno,
+    /// Which part of the rule was rejected
+    pub kind: FlowErrorKind,
+    /// Index into the rule's pattern items or actions, if the driver
+    /// pointed at a specific one (see `kind`)
+    pub index: Option<usize>,
+    /// Human-readable message from the driver
+    pub message: Option<String>,
+}
+
+impl FlowError {
+    /// Build from a raw rte_flow_error, resolving the cause pointer against
+    /// the items/actions that were passed to DPDK.
+    fn from_raw(
+        errno: Errno,
+        raw: &ffi::rte_flow_error,
+        items: &[ffi::rte_flow_item],
+        actions: &[ffi::rte_flow_action],
+    ) -> Self {
+        let kind = FlowErrorKind::from_raw(raw.type_);
+        let index = if raw.cause.is_null() {
+            None
+        } else if kind.is_item() {
+            items
+                .iter()
+                .position(|i| ptr::eq(i as *const _ as *const c_void, raw.cause))
+        } else if kind.is_action() {
+            actions
+                .iter()
+                .position(|a| ptr::eq(a as *const _ as *const c_void, raw.cause))
+        } else {
+            None
+        };
+        let message = if raw.message.is_null() {
+            None
+        } else {
+            Some(
+                unsafe { CStr::from_ptr(raw.message) }
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        };
+        Self {
+            errno,
+            kind,
+            index,
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for FlowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "flow error ({:?}", self.kind)?;
+        if let Some(index) = self.index {
+            write!(f, " #{}", index)?;
+        }
+        write!(f, "): {}", self.errno)?;
+        if let Some(message) = &self.message {
+            write!(f, ": {}", message)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FlowError {}
+
+impl From<FlowError> for Errno {
+    fn from(e: FlowError) -> Self {
+        e.errno
+    }
+}
+
+/// Host-order mask for an IPv4 prefix length.
+fn ipv4_prefix_mask(prefix_len: u8) -> u32 {
+    match prefix_len {
+        0 => 0,
+        n => u32::MAX << (32 - n.min(32) as u32),
+    }
+}
+
+/// Mask bytes for an IPv6 prefix length.
+fn ipv6_prefix_mask(prefix_len: u8) -> [u8; 16] {
+    let bits = match prefix_len {
+        0 => 0,
+        n => u128::MAX << (128 - n.min(128) as u32),
+    };
+    bits.to_be_bytes()
+}
+
+/// Ethernet header match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EthPattern {
+    dst: Option<[u8; 6]>,
+    src: Option<[u8; 6]>,
+    ether_type: Option<u16>,
+}
+
+impl EthPattern {
+    /// Match any Ethernet frame
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match destination MAC address
+    pub fn dst(mut self, mac: [u8; 6]) -> Self {
+        self.dst = Some(mac);
+        self
+    }
+
+    /// Match source MAC address
+    pub fn src(mut self, mac: [u8; 6]) -> Self {
+        self.src = Some(mac);
+        self
+    }
+
+    /// Match EtherType (e.g. 0x0806 for ARP)
+    pub fn ether_type(mut self, ether_type: u16) -> Self {
+        self.ether_type = Some(ether_type);
+        self
+    }
+
+    fn is_any(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// IPv4 header match. Addresses are matched by prefix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ipv4Pattern {
+    src: Option<(Ipv4Addr, u8)>,
+    dst: Option<(Ipv4Addr, u8)>,
+    proto: Option<u8>,
+}
+
+impl Ipv4Pattern {
+    /// Match any IPv4 packet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match source address prefix (32 for an exact host)
+    pub fn src(mut self, addr: Ipv4Addr, prefix_len: u8) -> Self {
+        self.src = Some((addr, prefix_len));
+        self
+    }
+
+    /// Match destination address prefix (32 for an exact host)
+    pub fn dst(mut self, addr: Ipv4Addr, prefix_len: u8) -> Self {
+        self.dst = Some((addr, prefix_len));
+        self
+    }
+
+    /// Match next protocol (e.g. 6 for TCP, 17 for UDP)
+    pub fn proto(mut self, proto: u8) -> Self {
+        self.proto = Some(proto);
+        self
+    }
+
+    fn is_any(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// IPv6 header match. Addresses are matched by prefix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ipv6Pattern {
+    src: Option<(Ipv6Addr, u8)>,
+    dst: Option<(Ipv6Addr, u8)>,
+    proto: Option<u8>,
+}
+
+impl Ipv6Pattern {
+    /// Match any IPv6 packet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match source address prefix (128 for an exact host)
+    pub fn src(mut self, addr: Ipv6Addr, prefix_len: u8) -> Self {
+        self.src = Some((addr, prefix_len));
+        self
+    }
+
+    /// Match destination address prefix (128 for an exact host)
+    pub fn dst(mut self, addr: Ipv6Addr, prefix_len: u8) -> Self {
+        self.dst = Some((addr, prefix_len));
+        self
+    }
+
+    /// Match next header (e.g. 6 for TCP, 17 for UDP)
+    pub fn proto(mut self, proto: u8) -> Self {
+        self.proto = Some(proto);
+        self
+    }
+
+    fn is_any(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// TCP or UDP port match.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct L4Pattern {
+    src_port: Option<u16>,
+    dst_port: Option<u16>,
+}
+
+impl L4Pattern {
+    /// Match any port
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match source port
+    pub fn src_port(mut self, port: u16) -> Self {
+        self.src_port = Some(port);
+        self
+    }
+
+    /// Match destination port
+    pub fn dst_port(mut self, port: u16) -> Self {
+        self.dst_port = Some(port);
+        self
+    }
+
+    fn is_any(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A single pattern item, matched from the outermost header inwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowItem {
+    /// Ethernet header
+    Eth(EthPattern),
+    /// IPv4 header
+    Ipv4(Ipv4Pattern),
+    /// IPv6 header
+    Ipv6(Ipv6Pattern),
+    /// TCP header
+    Tcp(L4Pattern),
+    /// UDP header
+    Udp(L4Pattern),
+}
+
+/// An action applied to packets matching the pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlowAction {
+    /// Deliver to a single RX queue
+    Queue(QueueId),
+    /// Spread across the given queues using RSS
+    /// (`types` are `rss_hf` flags, 0 = driver default)
+    Rss { queues: Vec<QueueId>, types: u64 },
+    /// Drop the packet
+    Drop,
+    /// Tag the packet with a mark (reported in the mbuf FDIR ID)
+    Mark(u32),
+    /// Count hits/bytes, readable with [`Flow::query_count`]
+    Count,
+}
+
+/// Counter values returned by [`Flow::query_count`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlowCount {
+    /// Number of matched packets (None if the PMD does not report it)
+    pub hits: Option<u64>,
+    /// Number of matched bytes (None if the PMD does not report it)
+    pub bytes: Option<u64>,
+}
+
+/// A flow rule: attributes, match pattern and actions.
+///
+/// # Example
+/// ```no_run
+/// use dpdk_net::api::rte::flow::{FlowRule, Ipv4Pattern, L4Pattern};
+///
+/// // Steer TCP port 8080 to queue 3
+/// let flow = FlowRule::new()
+///     .eth_any()
+///     .ipv4(Ipv4Pattern::new())
+///     .tcp(L4Pattern::new().dst_port(8080))
+///     .queue(3)
+///     .count()
+///     .create(0)
+///     .expect("Failed to create flow");
+/// ```
+///
+/// PMDs cannot replicate a packet to several queues, so "ARP to every queue"
+/// has to be done in software; a rule can only pick the queue that sees it
+/// (e.g. `EthPattern::new().ether_type(0x0806)` with `.queue(0)`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlowRule {
+    group: u32,
+    priority: u32,
+    ingress: bool,
+    egress: bool,
+    pattern: Vec<FlowItem>,
+    actions: Vec<FlowAction>,
+}
+
+impl Default for FlowRule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlowRule {
+    /// Create an empty ingress rule in group 0 with priority 0
+    pub fn new() -> Self {
+        Self {
+            group: 0,
+            priority: 0,
+            ingress: true,
+            egress: false,
+            pattern: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+
+    /// Set the flow group
+    pub fn group(mut self, group: u32) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// Set the rule priority (lower value = higher priority)
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Apply the rule to egress traffic instead of ingress
+    pub fn egress(mut self) -> Self {
+        self.ingress = false;
+        self.egress = true;
+        self
+    }
+
+    /// Append a pattern item
+    pub fn item(mut self, item: FlowItem) -> Self {
+        self.pattern.push(item);
+        self
+    }
+
+    /// Match any Ethernet frame
+    pub fn eth_any(self) -> Self {
+        self.item(FlowItem::Eth(EthPattern::new()))
+    }
+
+    /// Match Ethernet header fields
+    pub fn eth(self, pattern: EthPattern) -> Self {
+        self.item(FlowItem::Eth(pattern))
+    }
+
+    /// Match IPv4 header fields
+    pub fn ipv4(self, pattern: Ipv4Pattern) -> Self {
+        self.item(FlowItem::Ipv4(pattern))
+    }
+
+    /// Match IPv6 header fields
+    pub fn ipv6(self, pattern: Ipv6Pattern) -> Self {
+        self.item(FlowItem::Ipv6(pattern))
+    }
+
+    /// Match TCP ports
+    pub fn tcp(self, pattern: L4Pattern) -> Self {
+        self.item(FlowItem::Tcp(pattern))
+    }
+
+    /// Match UDP ports
+    pub fn udp(self, pattern: L4Pattern) -> Self {
+        self.item(FlowItem::Udp(pattern))
+    }
+
+    /// Append an action
+    pub fn action(mut self, action: FlowAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    /// Deliver matching packets to a queue
+    pub fn queue(self, queue_id: QueueId) -> Self {
+        self.action(FlowAction::Queue(queue_id))
+    }
+
+    /// Spread matching packets across queues with RSS
+    pub fn rss(self, queues: &[QueueId], types: u64) -> Self {
+        self.action(FlowAction::Rss {
+            queues: queues.to_vec(),
+            types,
+        })
+    }
+
+    /// Drop matching packets
+    pub fn drop_packets(self) -> Self {
+        self.action(FlowAction::Drop)
+    }
+
+    /// Mark matching packets
+    pub fn mark(self, id: u32) -> Self {
+        self.action(FlowAction::Mark(id))
+    }
+
+    /// Count matching packets
+    pub fn count(self) -> Self {
+        self.action(FlowAction::Count)
+    }
+
+    /// Pattern items of this rule
+    pub fn pattern(&self) -> &[FlowItem] {
+        &self.pattern
+    }
+
+    /// Actions of this rule
+    pub fn actions(&self) -> &[FlowAction] {
+        &self.actions
+    }
+
+    /// Check whether the device would accept this rule, without creating it.
+    pub fn validate(&self, port_id: PortId) -> FlowResult<()> {
+        let raw = RawRule::new(self);
+        let mut error: ffi::rte_flow_error = unsafe { std::mem::zeroed() };
+        let ret = unsafe {
+            ffi::rte_flow_validate(
+                port_id,
+                &raw.attr,
+                raw.items.as_ptr(),
+                raw.actions.as_ptr(),
+                &mut error,
+            )
+        };
+        if ret < 0 {
+            return Err(raw.error(Errno::from_raw(-ret), &error));
+        }
+        Ok(())
+    }
+
+    /// Create the rule on the device.
+    ///
+    /// The rule is removed when the returned [`Flow`] is dropped.
+    pub fn create(&self, port_id: PortId) -> FlowResult<Flow> {
+        let raw = RawRule::new(self);
+        let mut error: ffi::rte_flow_error = unsafe { std::mem::zeroed() };
+        let flow = unsafe {
+            ffi::rte_flow_create(
+                port_id,
+                &raw.attr,
+                raw.items.as_ptr(),
+                raw.actions.as_ptr(),
+                &mut error,
+            )
+        };
+        match NonNull::new(flow) {
+            Some(flow) => Ok(Flow {
+                port_id,
+                flow: Some(flow),
+            }),
+            None => Err(raw.error(rte_errno(), &error)),
+        }
+    }
+}
+
+/// Backing storage for an item's spec and mask.
+enum ItemStorage {
+    Eth(Box<[ffi::rte_flow_item_eth; 2]>),
+    Ipv4(Box<[ffi::rte_flow_item_ipv4; 2]>),
+    Ipv6(Box<[ffi::rte_flow_item_ipv6; 2]>),
+    Tcp(Box<[ffi::rte_flow_item_tcp; 2]>),
+    Udp(Box<[ffi::rte_flow_item_udp; 2]>),
+}
+
+impl ItemStorage {
+    fn spec_mask(&self) -> (*const c_void, *const c_void) {
+        fn ptrs<T>(b: &[T; 2]) -> (*const c_void, *const c_void) {
+            (
+                &b[0] as *const T as *const c_void,
+                &b[1] as *const T as *const c_void,
+            )
+        }
+        match self {
+            Self::Eth(b) => ptrs(b),
+            Self::Ipv4(b) => ptrs(b),
+            Self::Ipv6(b) => ptrs(b),
+            Self::Tcp(b) => ptrs(b),
+            Self::Udp(b) => ptrs(b),
+        }
+    }
+}
+
+/// Backing storage for an action's configuration.
+enum ActionStorage {
+    Queue(Box<ffi::rte_flow_action_queue>),
+    Rss {
+        conf: Box<ffi::rte_flow_action_rss>,
+        // Referenced by conf.queue
+        _queues: Vec<QueueId>,
+    },
+    Mark(Box<ffi::rte_flow_action_mark>),
+    Count(Box<ffi::rte_flow_action_count>),
+}
+
+impl ActionStorage {
+    fn conf(&self) -> *const c_void {
+        match self {
+            Self::Queue(c) => &**c as *const _ as *const c_void,
+            Self::Rss { conf, .. } => &**conf as *const _ as *const c_void,
+            Self::Mark(c) => &**c as *const _ as *const c_void,
+            Self::Count(c) => &**c as *const _ as *const c_void,
+        }
+    }
+}
+
+/// A rule lowered to rte_flow structures.
+///
+/// Items and actions point into the boxed storage, which stays in place
+/// while this struct is alive.
+struct RawRule {
+    attr: ffi::rte_flow_attr,
+    items: Vec<ffi::rte_flow_item>,
+    actions: Vec<ffi::rte_flow_action>,
+    _item_storage: Vec<ItemStorage>,
+    _action_storage: Vec<ActionStorage>,
+}
+
+impl RawRule {
+    fn new(rule: &FlowRule) -> Self {
+        let mut attr: ffi::rte_flow_attr = unsafe { std::mem::zeroed() };
+        attr.group = rule.group;
+        attr.priority = rule.priority;
+        attr.set_ingress(rule.ingress as u32);
+        attr.set_egress(rule.egress as u32);
+
+        let mut items = Vec::with_capacity(rule.pattern.len() + 1);
+        let mut item_storage = Vec::with_capacity(rule.pattern.len());
+        for item in &rule.pattern {
+            let (type_, storage) = Self::lower_item(item);
+            let mut raw: ffi::rte_flow_item = unsafe { std::mem::zeroed() };
+            raw.type_ = type_;
+            if let Some(storage) = storage {
+                (raw.spec, raw.mask) = storage.spec_mask();
+                item_storage.push(storage);
+            }
+            items.push(raw);
+        }
+        let mut end: ffi::rte_flow_item = unsafe { std::mem::zeroed() };
+        end.type_ = ffi::rte_flow_item_type_RTE_FLOW_ITEM_TYPE_END;
+        items.push(end);
+
+        let mut actions = Vec::with_capacity(rule.actions.len() + 1);
+        let mut action_storage = Vec::with_capacity(rule.actions.len());
+        for action in &rule.actions {
+            let (type_, storage) = Self::lower_action(action);
+            let mut raw: ffi::rte_flow_action = unsafe { std::mem::zeroed() };
+            raw.type_ = type_;
+            if let Some(storage) = storage {
+                raw.conf = storage.conf();
+                action_storage.push(storage);
+            }
+            actions.push(raw);
+        }
+        let mut end: ffi::rte_flow_action = unsafe { std::mem::zeroed() };
+        end.type_ = ffi::rte_flow_action_type_RTE_FLOW_ACTION_TYPE_END;
+        actions.push(end);
+
+        Self {
+            attr,
+            items,
+            actions,
+            _item_storage: item_storage,
+            _action_storage: action_storage,
+        }
+    }
+
+    fn lower_item(item: &FlowItem) -> (ffi::rte_flow_item_type, Option<ItemStorage>) {
+        match item {
+            FlowItem::Eth(p) => {
+                let storage = (!p.is_any()).then(|| {
+                    let mut b: Box<[ffi::rte_flow_item_eth; 2]> =
+                        Box::new(unsafe { std::mem::zeroed() });
+                    let [spec, mask] = &mut *b;
+                    unsafe {
+                        ffi::rust_flow_item_eth_set(
+                            spec,
+                            p.dst.unwrap_or_default().as_ptr(),
+                            p.src.unwrap_or_default().as_ptr(),
+                            p.ether_type.unwrap_or(0),
+                        );
+                        ffi::rust_flow_item_eth_set(
+                            mask,
+                            [if p.dst.is_some() { 0xff } else { 0 }; 6].as_ptr(),
+                            [if p.src.is_some() { 0xff } else { 0 }; 6].as_ptr(),
+                            if p.ether_type.is_some() { 0xffff } else { 0 },
+                        );
+                    }
+                    ItemStorage::Eth(b)
+                });
+                (ffi::rte_flow_item_type_RTE_FLOW_ITEM_TYPE_ETH, storage)
+            }
+            FlowItem::Ipv4(p) => {
+                let storage = (!p.is_any()).then(|| {
+                    let mut b: Box<[ffi::rte_flow_item_ipv4; 2]> =
+                        Box::new(unsafe { std::mem::zeroed() });
+                    let [spec, mask] = &mut *b;
+                    let (src, src_len) = p.src.unwrap_or((Ipv4Addr::UNSPECIFIED, 0));
+                    let (dst, dst_len) = p.dst.unwrap_or((Ipv4Addr::UNSPECIFIED, 0));
+                    unsafe {
+                        ffi::rust_flow_item_ipv4_set(
+                            spec,
+                            src.to_bits(),
+                            dst.to_bits(),
+                            p.proto.unwrap_or(0),
+                        );
+                        ffi::rust_flow_item_ipv4_set(
+                            mask,
+                            ipv4_prefix_mask(src_len),
+                            ipv4_prefix_mask(dst_len),
+                            if p.proto.is_some() { 0xff } else { 0 },
+                        );
+                    }
+                    ItemStorage::Ipv4(b)
+                });
+                (ffi::rte_flow_item_type_RTE_FLOW_ITEM_TYPE_IPV4, storage)
+            }
+            FlowItem::Ipv6(p) => {
+                let storage = (!p.is_any()).then(|| {
+                    let mut b: Box<[ffi::rte_flow_item_ipv6; 2]> =
+                        Box::new(unsafe { std::mem::zeroed() });
+                    let [spec, mask] = &mut *b;
+                    let (src, src_len) = p.src.unwrap_or((Ipv6Addr::UNSPECIFIED, 0));
+                    let (dst, dst_len) = p.dst.unwrap_or((Ipv6Addr::UNSPECIFIED, 0));
+                    unsafe {
+                        ffi::rust_flow_item_ipv6_set(
+                            spec,
+                            src.octets().as_ptr(),
+                            dst.octets().as_ptr(),
+                            p.proto.unwrap_or(0),
+                        );
+                        ffi::rust_flow_item_ipv6_set(
+                            mask,
+                            ipv6_prefix_mask(src_len).as_ptr(),
+                            ipv6_prefix_mask(dst_len).as_ptr(),
+                            if p.proto.is_some() { 0xff } else { 0 },
+                        );
+                    }
+                    ItemStorage::Ipv6(b)
+                });
+                (ffi::rte_flow_item_type_RTE_FLOW_ITEM_TYPE_IPV6, storage)
+            }
+            FlowItem::Tcp(p) => {
+                let storage = (!p.is_any()).then(|| {
+                    let mut b: Box<[ffi::rte_flow_item_tcp; 2]> =
+                        Box::new(unsafe { std::mem::zeroed() });
+                    let [spec, mask] = &mut *b;
+                    let (src_mask, dst_mask) = p.port_masks();
+                    unsafe {
+                        ffi::rust_flow_item_tcp_set(
+                            spec,
+                            p.src_port.unwrap_or(0),
+                            p.dst_port.unwrap_or(0),
+                        );
+                        ffi::rust_flow_item_tcp_set(mask, src_mask, dst_mask);
+                    }
+                    ItemStorage::Tcp(b)
+                });
+                (ffi::rte_flow_item_type_RTE_FLOW_ITEM_TYPE_TCP, storage)
+            }
+            FlowItem::Udp(p) => {
+                let storage = (!p.is_any()).then(|| {
+                    let mut b: Box<[ffi::rte_flow_item_udp; 2]> =
+                        Box::new(unsafe { std::mem::zeroed() });
+                    let [spec, mask] = &mut *b;
+                    let (src_mask, dst_mask) = p.port_masks();
+                    unsafe {
+                        ffi::rust_flow_item_udp_set(
+                            spec,
+                            p.src_port.unwrap_or(0),
+                            p.dst_port.unwrap_or(0),
+                        );
+                        ffi::rust_flow_item_udp_set(mask, src_mask, dst_mask);
+                    }
+                    ItemStorage::Udp(b)
+                });
+                (ffi::rte_flow_item_type_RTE_FLOW_ITEM_TYPE_UDP, storage)
+            }
+        }
+    }
+
+    fn lower_action(action: &FlowAction) -> (ffi::rte_flow_action_type, Option<ActionStorage>) {
+        match action {
+            FlowAction::Queue(queue_id) => {
+                let mut conf: Box<ffi::rte_flow_action_queue> =
+                    Box::new(unsafe { std::mem::zeroed() });
+                conf.index = *queue_id;
+                (
+                    ffi::rte_flow_action_type_RTE_FLOW_ACTION_TYPE_QUEUE,
+                    Some(ActionStorage::Queue(conf)),
+                )
+            }
+            FlowAction::Rss { queues, types } => {
+                let queues = queues.clone();
+                let mut conf: Box<ffi::rte_flow_action_rss> =
+                    Box::new(unsafe { std::mem::zeroed() });
+                // func/level/key left zeroed = driver defaults
+                conf.types = *types;
+                conf.queue_num = queues.len() as u32;
+                conf.queue = queues.as_ptr();
+                (
+                    ffi::rte_flow_action_type_RTE_FLOW_ACTION_TYPE_RSS,
+                    Some(ActionStorage::Rss {
+                        conf,
+                        _queues: queues,
+                    }),
+                )
+            }
+            FlowAction::Drop => (ffi::rte_flow_action_type_RTE_FLOW_ACTION_TYPE_DROP, None),
+            FlowAction::Mark(id) => {
+                let mut conf: Box<ffi::rte_flow_action_mark> =
+                    Box::new(unsafe { std::mem::zeroed() });
+                conf.id = *id;
+                (
+                    ffi::rte_flow_action_type_RTE_FLOW_ACTION_TYPE_MARK,
+                    Some(ActionStorage::Mark(conf)),
+                )
+            }
+            FlowAction::Count => (
+                ffi::rte_flow_action_type_RTE_FLOW_ACTION_TYPE_COUNT,
+                Some(ActionStorage::Count(Box::new(unsafe {
+                    std::mem::zeroed()
+                }))),
+            ),
+        }
+    }
+
+    fn error(&self, errno: Errno, error: &ffi::rte_flow_error) -> FlowError {
+        FlowError::from_raw(errno, error, &self.items, &self.actions)
+    }
+}
+
+impl L4Pattern {
+    fn port_masks(&self) -> (u16, u16) {
+        (
+            if self.src_port.is_some() { 0xffff } else { 0 },
+            if self.dst_port.is_some() { 0xffff } else { 0 },
+        )
+    }
+}
+
+/// A flow rule installed on a device.
+///
+/// The rule is destroyed when this handle is dropped. Closing the port
+/// also removes all of its rules, so drop handles before `EthDev::close`.
+pub struct Flow {
+    port_id: PortId,
+    flow: Option<NonNull<ffi::rte_flow>>,
+}
+
+// rte_flow handles are plain pointers owned by the PMD and
+// the rte_flow API is thread-safe unless the PMD says otherwise.
+unsafe impl Send for Flow {}
+
+impl Flow {
+    /// Port this rule is installed on
+    pub fn port_id(&self) -> PortId {
+        self.port_id
+    }
+
+    /// Read the counters of a rule created with a [`FlowAction::Count`] action.
+    pub fn query_count(&self, reset: bool) -> FlowResult<FlowCount> {
+        // Only None after destroy(), which consumes the handle
+        let flow = self.flow.expect("flow handle used after destroy");
+        let count_conf: ffi::rte_flow_action_count = unsafe { std::mem::zeroed() };
+        let mut action: ffi::rte_flow_action = unsafe { std::mem::zeroed() };
+        action.type_ = ffi::rte_flow_action_type_RTE_FLOW_ACTION_TYPE_COUNT;
+        action.conf = &count_conf as *const _ as *const c_void;
+
+        let mut data: ffi::rte_flow_query_count = unsafe { std::mem::zeroed() };
+        data.set_reset(reset as u32);
+        let mut error: ffi::rte_flow_error = unsafe { std::mem::zeroed() };
+        let ret = unsafe {
+            ffi::rte_flow_query(
+                self.port_id,
+                flow.as_ptr(),
+                &action,
+                &mut data as *mut _ as *mut c_void,
+                &mut error,
+            )
+        };
+        if ret < 0 {
+            return Err(FlowError::from_raw(Errno::from_raw(-ret), &error, &[], &[]));
+        }
+        Ok(FlowCount {
+            hits: (data.hits_set() != 0).then_some(data.hits),
+            bytes: (data.bytes_set() != 0).then_some(data.bytes),
+        })
+    }
+
+    /// Remove the rule from the device.
+    pub fn destroy(mut self) -> FlowResult<()> {
+        self.destroy_inner()
+    }
+
+    fn destroy_inner(&mut self) -> FlowResult<()> {
+        let Some(flow) = self.flow.take() else {
+            return Ok(());
+        };
+        let mut error: ffi::rte_flow_error = unsafe { std::mem::zeroed() };
+        let ret = unsafe { ffi::rte_flow_destroy(self.port_id, flow.as_ptr(), &mut error) };
+        if ret < 0 {
+            return Err(FlowError::from_raw(Errno::from_raw(-ret), &error, &[], &[]));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Flow {
+    fn drop(&mut self) {
+        if let Err(e) = self.destroy_inner() {
+            warn!(port_id = self.port_id, "Failed to destroy flow rule: {}", e);
+        }
+    }
+}
+
+/// Remove all flow rules on a port, including rules not created through
+/// [`FlowRule::create`].
+///
+/// Outstanding [`Flow`] handles become stale; dropping them afterwards logs
+/// a warning.
+pub fn flush(port_id: PortId) -> FlowResult<()> {
+    let mut error: ffi::rte_flow_error = unsafe { std::mem::zeroed() };
+    let ret = unsafe { ffi::rte_flow_flush(port_id, &mut error) };
+    if ret < 0 {
+        return Err(FlowError::from_raw(Errno::from_raw(-ret), &error, &[], &[]));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ipv4_prefix_mask() {
+        assert_eq!(ipv4_prefix_mask(0), 0);
+        assert_eq!(ipv4_prefix_mask(8), 0xff00_0000);
+        assert_eq!(ipv4_prefix_mask(24), 0xffff_ff00);
+        assert_eq!(ipv4_prefix_mask(32), 0xffff_ffff);
+        assert_eq!(ipv4_prefix_mask(40), 0xffff_ffff);
+    }
+
+    #[test]
+    fn test_ipv6_prefix_mask() {
+        assert_eq!(ipv6_prefix_mask(0), [0; 16]);
+        assert_eq!(ipv6_prefix_mask(128), [0xff; 16]);
+        let m = ipv6_prefix_mask(68);
+        assert_eq!(&m[..8], &[0xff; 8]);
+        assert_eq!(m[8], 0xf0);
+        assert_eq!(&m[9..], &[0; 7]);
+    }
+
+    #[test]
+    fn test_flow_error_cause_index() {
+        let items: Vec<ffi::rte_flow_item> = vec![unsafe { std::mem::zeroed() }; 3];
+        let actions: Vec<ffi::rte_flow_action> = vec![unsafe { std::mem::zeroed() }; 2];
+        let mut raw: ffi::rte_flow_error = unsafe { std::mem::zeroed() };
+        raw.type_ = ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ITEM;
+        raw.cause = &items[1] as *const _ as *const c_void;
+        let e = FlowError::from_raw(Errno::ENOTSUP, &raw, &items, &actions);
+        assert_eq!(e.kind, FlowErrorKind::Item);
+        assert_eq!(e.index, Some(1));
+        assert_eq!(e.message, None);
+
+        raw.type_ = ffi::rte_flow_error_type_RTE_FLOW_ERROR_TYPE_ACTION_CONF;
+        raw.cause = &actions[0] as *const _ as *const c_void;
+        let e = FlowError::from_raw(Errno::EINVAL, &raw, &items, &actions);
+        assert_eq!(e.kind, FlowErrorKind::ActionConf);
+        assert_eq!(e.index, Some(0));
+    }
+
+    #[test]
+    fn test_raw_rule_layout() {
+        let rule = FlowRule::new()
+            .eth_any()
+            .ipv4(Ipv4Pattern::new().dst(Ipv4Addr::new(10, 0, 0, 0), 8))
+            .tcp(L4Pattern::new().dst_port(8080))
+            .queue(2)
+            .count();
+        let raw = RawRule::new(&rule);
+        // 3 items + END, 2 actions + END
+        assert_eq!(raw.items.len(), 4);
+        assert_eq!(raw.actions.len(), 3);
+        assert!(raw.items[0].spec.is_null());
+        assert!(!raw.items[1].spec.is_null());
+        assert!(!raw.items[2].mask.is_null());
+        assert_eq!(
+            raw.items[3].type_,
+            ffi::rte_flow_item_type_RTE_FLOW_ITEM_TYPE_END
+        );
+        assert_eq!(
+            raw.actions[0].type_,
+            ffi::rte_flow_action_type_RTE_FLOW_ACTION_TYPE_QUEUE
+        );
+        assert_eq!(
+            raw.actions[2].type_,
+            ffi::rte_flow_action_type_RTE_FLOW_ACTION_TYPE_END
+        );
+    }
+}
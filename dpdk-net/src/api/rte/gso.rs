@@ -0,0 +1,100 @@
+// Generic Segmentation Offload (software TSO)
+// See: /usr/local/include/rte_gso.h
+//
+// Splits a TCP/IPv4 packet marked with RTE_MBUF_F_TX_TCP_SEG into MSS-sized
+// packets. Each output packet is a chain of a direct mbuf holding a copy of
+// the headers and an indirect mbuf pointing into the original payload, so
+// the port needs RTE_ETH_TX_OFFLOAD_MULTI_SEGS.
+
+use std::sync::Arc;
+
+use arrayvec::ArrayVec;
+use dpdk_net_sys::ffi;
+
+use super::mbuf::Mbuf;
+use super::pktmbuf::{MemPool, MemPoolConfig};
+use crate::api::{Errno, Result, rte_errno};
+
+/// Maximum number of packets a single GSO call can produce
+pub const MAX_GSO_SEGMENTS: usize = 64;
+
+/// Software segmentation context for TCP/IPv4.
+#[derive(Clone)]
+pub struct GsoContext {
+    direct_pool: Arc<MemPool>,
+    indirect_pool: Arc<MemPool>,
+}
+
+impl GsoContext {
+    /// Create a context.
+    ///
+    /// * `direct_pool` - Pool for the header mbufs (the regular pktmbuf pool)
+    /// * `indirect_pool` - Pool for the indirect payload mbufs (see [`GsoContext::indirect_pool`])
+    pub fn new(direct_pool: Arc<MemPool>, indirect_pool: Arc<MemPool>) -> Self {
+        Self {
+            direct_pool,
+            indirect_pool,
+        }
+    }
+
+    /// Create a pool suitable for indirect mbufs (no data room).
+    pub fn indirect_pool<S>(name: S, num_mbufs: u32) -> Result<MemPool>
+    where
+        S: Into<Vec<u8>>,
+    {
+        let config = MemPoolConfig::new().num_mbufs(num_mbufs).data_room_size(0);
+        MemPool::create(name, &config)
+    }
+
+    /// Segment `pkt` into packets of at most `gso_size` bytes (headers included).
+    ///
+    /// `pkt` must have `TX_TCP_SEG` set along with `l2_len`, `l3_len` and `l4_len`.
+    /// Output packets are appended to `out`. If `pkt` is not larger than
+    /// `gso_size` it is appended unchanged. Output segments carry no
+    /// checksum offload request; the caller sets one or computes checksums.
+    ///
+    /// Fails with `ENOBUFS` if `out` is full, and with `EINVAL` if `pkt`
+    /// needs more segments than `out` has room for; `pkt` is freed.
+    pub fn segment<const N: usize>(
+        &self,
+        pkt: Mbuf,
+        gso_size: u16,
+        out: &mut ArrayVec<Mbuf, N>,
+    ) -> Result<usize> {
+        if out.is_full() {
+            return Err(Errno::ENOBUFS);
+        }
+        let mut raw_out: [*mut ffi::rte_mbuf; MAX_GSO_SEGMENTS] =
+            [std::ptr::null_mut(); MAX_GSO_SEGMENTS];
+        let nb_out = out.remaining_capacity().min(MAX_GSO_SEGMENTS) as u16;
+
+        let ret = unsafe {
+            ffi::rust_gso_segment(
+                pkt.as_ptr(),
+                self.direct_pool.as_ptr(),
+                self.indirect_pool.as_ptr(),
+                gso_size,
+                raw_out.as_mut_ptr(),
+                nb_out,
+            )
+        };
+        if ret < 0 {
+            return Err(rte_errno());
+        }
+        if ret == 0 {
+            // No segmentation needed (room checked above)
+            out.push(pkt);
+            return Ok(1);
+        }
+
+        // Output packets hold references to the payload; release ours
+        drop(pkt);
+        for raw in raw_out.iter().take(ret as usize) {
+            if let Some(mbuf) = unsafe { Mbuf::from_raw(*raw) } {
+                // rte_gso_segment never returns more than nb_out packets
+                out.push(mbuf);
+            }
+        }
+        Ok(ret as usize)
+    }
+}
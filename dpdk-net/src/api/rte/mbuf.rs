@@ -2,6 +2,7 @@
 // See: /usr/local/include/rte_mbuf_core.h
 // and /usr/local/include/rte_mbuf.h
 
+use std::marker::PhantomData;
 use std::ptr::NonNull;
 use std::slice;
 
@@ -9,10 +10,59 @@ use dpdk_net_sys::ffi;
 
 use super::pktmbuf::MemPool;
 
+/// Mbuf offload flags (RTE_MBUF_F_*)
+/// Re-exported from generated bindings (from wrapper.h static consts)
+pub mod ol_flags {
+    use dpdk_net_sys::ffi;
+
+    /// Mask of the RX IP checksum status bits
+    pub const RX_IP_CKSUM_MASK: u64 = ffi::RUST_RTE_MBUF_F_RX_IP_CKSUM_MASK;
+    /// IP checksum verified good by the NIC
+    pub const RX_IP_CKSUM_GOOD: u64 = ffi::RUST_RTE_MBUF_F_RX_IP_CKSUM_GOOD;
+    /// IP checksum verified bad by the NIC
+    pub const RX_IP_CKSUM_BAD: u64 = ffi::RUST_RTE_MBUF_F_RX_IP_CKSUM_BAD;
+    /// IP checksum not correct in the packet, but the IP data is valid
+    pub const RX_IP_CKSUM_NONE: u64 = ffi::RUST_RTE_MBUF_F_RX_IP_CKSUM_NONE;
+    /// Mask of the RX L4 checksum status bits
+    pub const RX_L4_CKSUM_MASK: u64 = ffi::RUST_RTE_MBUF_F_RX_L4_CKSUM_MASK;
+    /// L4 checksum verified good by the NIC
+    pub const RX_L4_CKSUM_GOOD: u64 = ffi::RUST_RTE_MBUF_F_RX_L4_CKSUM_GOOD;
+    /// L4 checksum verified bad by the NIC
+    pub const RX_L4_CKSUM_BAD: u64 = ffi::RUST_RTE_MBUF_F_RX_L4_CKSUM_BAD;
+    /// L4 checksum not correct in the packet, but the L4 data is valid
+    pub const RX_L4_CKSUM_NONE: u64 = ffi::RUST_RTE_MBUF_F_RX_L4_CKSUM_NONE;
+    /// Packet is IPv4 (required for any IPv4 TX offload)
+    pub const TX_IPV4: u64 = ffi::RUST_RTE_MBUF_F_TX_IPV4;
+    /// Packet is IPv6 (required for any IPv6 TX offload)
+    pub const TX_IPV6: u64 = ffi::RUST_RTE_MBUF_F_TX_IPV6;
+    /// Compute the IPv4 header checksum
+    pub const TX_IP_CKSUM: u64 = ffi::RUST_RTE_MBUF_F_TX_IP_CKSUM;
+    /// Compute the TCP checksum
+    pub const TX_TCP_CKSUM: u64 = ffi::RUST_RTE_MBUF_F_TX_TCP_CKSUM;
+    /// Compute the UDP checksum
+    pub const TX_UDP_CKSUM: u64 = ffi::RUST_RTE_MBUF_F_TX_UDP_CKSUM;
+    /// TCP segmentation offload (implies TX_TCP_CKSUM, needs `tso_segsz`)
+    pub const TX_TCP_SEG: u64 = ffi::RUST_RTE_MBUF_F_TX_TCP_SEG;
+    /// `vlan_tci` holds the packet's VLAN tag
+    pub const RX_VLAN: u64 = ffi::RUST_RTE_MBUF_F_RX_VLAN;
+    /// The VLAN tag was removed from the packet data (see `vlan_tci`)
+    pub const RX_VLAN_STRIPPED: u64 = ffi::RUST_RTE_MBUF_F_RX_VLAN_STRIPPED;
+    /// Insert `vlan_tci` as an 802.1Q tag on transmit
+    pub const TX_VLAN: u64 = ffi::RUST_RTE_MBUF_F_TX_VLAN;
+    /// The NIC stored the packet's RSS hash (see `rss_hash`)
+    pub const RX_RSS_HASH: u64 = ffi::RUST_RTE_MBUF_F_RX_RSS_HASH;
+}
+
 /// A wrapper around DPDK's rte_mbuf.
 ///
 /// This provides a safe, buffer-like interface for packet data.
 /// The mbuf is automatically freed when dropped.
+///
+/// A packet may be a chain of segments (jumbo frames with scatter RX, or
+/// packets built with [`chain`](Self::chain)). [`data`](Self::data) and
+/// [`data_len`](Self::data_len) cover the first segment only; use
+/// [`pkt_len`](Self::pkt_len), [`segments`](Self::segments) or
+/// [`copy_to`](Self::copy_to) for the whole packet.
 pub struct Mbuf {
     inner: NonNull<ffi::rte_mbuf>,
 }
@@ -37,7 +87,33 @@ impl Mbuf {
     #[inline]
     pub fn alloc(mempool: &MemPool) -> Option<Self> {
         let ptr = unsafe { ffi::rust_pktmbuf_alloc(mempool.as_ptr()) };
-        NonNull::new(ptr).map(|inner| Mbuf { inner })
+        let mbuf = NonNull::new(ptr).map(|inner| Mbuf { inner });
+        if mbuf.is_none() {
+            mempool.record_alloc_failure();
+        }
+        mbuf
+    }
+
+    /// Allocate a packet holding a copy of `data`, chaining as many mbufs as needed.
+    ///
+    /// Returns `None` if the pool is exhausted or the chain would be too long.
+    pub fn alloc_from_slice(mempool: &MemPool, data: &[u8]) -> Option<Self> {
+        let mut head = Self::alloc(mempool)?;
+        let room = head.tailroom().min(u16::MAX as usize);
+        if room == 0 && !data.is_empty() {
+            return None;
+        }
+        let (first, rest) = data.split_at(room.min(data.len()));
+        head.append(first.len())?.copy_from_slice(first);
+        if rest.is_empty() {
+            return Some(head);
+        }
+        for chunk in rest.chunks(room) {
+            let mut seg = Self::alloc(mempool)?;
+            seg.append(chunk.len())?.copy_from_slice(chunk);
+            head.chain(seg).ok()?;
+        }
+        Some(head)
     }
 
     /// Get the raw pointer to the underlying rte_mbuf.
@@ -56,7 +132,7 @@ impl Mbuf {
         ptr
     }
 
-    /// Get the current data length (bytes of valid data).
+    /// Get the data length of the first segment (bytes of valid data).
     #[inline]
     pub fn data_len(&self) -> usize {
         unsafe { ffi::rust_pktmbuf_data_len(self.inner.as_ptr()) as usize }
@@ -86,7 +162,7 @@ impl Mbuf {
         self.data_len() + self.tailroom()
     }
 
-    /// Get an immutable slice of the packet data.
+    /// Get an immutable slice of the first segment's data.
     #[inline]
     pub fn data(&self) -> &[u8] {
         let ptr = unsafe { ffi::rust_pktmbuf_mtod(self.inner.as_ptr()) };
@@ -98,7 +174,7 @@ impl Mbuf {
         }
     }
 
-    /// Get a mutable slice of the packet data.
+    /// Get a mutable slice of the first segment's data.
     #[inline]
     pub fn data_mut(&mut self) -> &mut [u8] {
         let ptr = unsafe { ffi::rust_pktmbuf_mtod(self.inner.as_ptr()) };
@@ -110,10 +186,10 @@ impl Mbuf {
         }
     }
 
-    /// Append space to the end of the packet data.
+    /// Append space to the end of the packet data (in the last segment).
     ///
     /// Returns a mutable slice to the newly appended region,
-    /// or `None` if there's not enough tailroom.
+    /// or `None` if there's not enough tailroom in the last segment.
     #[inline]
     pub fn append(&mut self, len: usize) -> Option<&mut [u8]> {
         if len > u16::MAX as usize {
@@ -201,6 +277,196 @@ impl Mbuf {
         }
     }
 
+    /// Get the offload flags (see [`ol_flags`]).
+    #[inline]
+    pub fn ol_flags(&self) -> u64 {
+        unsafe { (*self.inner.as_ptr()).ol_flags }
+    }
+
+    /// Request TX offloads for this packet.
+    ///
+    /// ORs `flags` into `ol_flags` and sets the header lengths the NIC needs
+    /// to locate the L3/L4 headers.
+    #[inline]
+    pub fn set_tx_offload(&mut self, flags: u64, l2_len: u16, l3_len: u16, l4_len: u16) {
+        unsafe {
+            ffi::rust_pktmbuf_set_tx_offload(self.inner.as_ptr(), flags, l2_len, l3_len, l4_len)
+        }
+    }
+
+    /// Set bits in the offload flags.
+    #[inline]
+    pub fn set_ol_flags(&mut self, flags: u64) {
+        unsafe { (*self.inner.as_ptr()).ol_flags |= flags }
+    }
+
+    /// VLAN tag control information (valid with [`ol_flags::RX_VLAN`] or
+    /// [`ol_flags::TX_VLAN`]).
+    #[inline]
+    pub fn vlan_tci(&self) -> u16 {
+        unsafe { (*self.inner.as_ptr()).vlan_tci }
+    }
+
+    /// Set the VLAN tag control information.
+    #[inline]
+    pub fn set_vlan_tci(&mut self, tci: u16) {
+        unsafe { (*self.inner.as_ptr()).vlan_tci = tci }
+    }
+
+    /// L2 header length used by TX offloads.
+    #[inline]
+    pub fn l2_len(&self) -> u16 {
+        unsafe { ffi::rust_pktmbuf_l2_len(self.inner.as_ptr()) }
+    }
+
+    /// Change the L2 header length used by TX offloads, e.g. after
+    /// inserting a VLAN tag.
+    #[inline]
+    pub fn set_l2_len(&mut self, l2_len: u16) {
+        unsafe { ffi::rust_pktmbuf_set_l2_len(self.inner.as_ptr(), l2_len) }
+    }
+
+    /// RSS hash the NIC computed on receive, if it stored one
+    /// ([`ol_flags::RX_RSS_HASH`]).
+    #[inline]
+    pub fn rss_hash(&self) -> Option<u32> {
+        (self.ol_flags() & ol_flags::RX_RSS_HASH != 0)
+            .then(|| unsafe { ffi::rust_pktmbuf_rss_hash(self.inner.as_ptr()) })
+    }
+
+    /// Clear bits in the offload flags.
+    #[inline]
+    pub fn clear_ol_flags(&mut self, mask: u64) {
+        unsafe { (*self.inner.as_ptr()).ol_flags &= !mask }
+    }
+
+    /// Set the TCP segment size for [`ol_flags::TX_TCP_SEG`] (payload bytes per segment).
+    #[inline]
+    pub fn set_tso_segsz(&mut self, tso_segsz: u16) {
+        unsafe { ffi::rust_pktmbuf_set_tso_segsz(self.inner.as_ptr(), tso_segsz) }
+    }
+
+    /// Compute the IPv4 header and TCP/UDP checksums in software.
+    ///
+    /// Uses the `l2_len`/`l3_len` set by [`set_tx_offload`](Self::set_tx_offload)
+    /// and clears the checksum and segmentation offload flags. Works on
+    /// chained mbufs as long as the headers are in the first segment.
+    #[inline]
+    pub fn compute_tx_cksum(&mut self) {
+        unsafe { ffi::rust_pktmbuf_sw_cksum(self.inner.as_ptr()) }
+    }
+
+    /// Append `tail` as the last segment(s) of this packet.
+    ///
+    /// Returns `tail` back if the chain would exceed the maximum number of segments.
+    #[inline]
+    pub fn chain(&mut self, tail: Mbuf) -> std::result::Result<(), Mbuf> {
+        let ret = unsafe { ffi::rust_pktmbuf_chain(self.inner.as_ptr(), tail.as_ptr()) };
+        if ret == 0 {
+            // The head owns the tail segments now
+            std::mem::forget(tail);
+            Ok(())
+        } else {
+            Err(tail)
+        }
+    }
+
+    /// Get the number of segments in the packet.
+    #[inline]
+    pub fn nb_segs(&self) -> u16 {
+        unsafe { ffi::rust_pktmbuf_nb_segs(self.inner.as_ptr()) }
+    }
+
+    /// Check whether the whole packet is in a single segment.
+    #[inline]
+    pub fn is_contiguous(&self) -> bool {
+        self.nb_segs() == 1
+    }
+
+    /// Iterate over the data of each segment.
+    #[inline]
+    pub fn segments(&self) -> Segments<'_> {
+        Segments {
+            seg: self.inner.as_ptr(),
+            _mbuf: PhantomData,
+        }
+    }
+
+    /// Copy the packet data (all segments) into `out`.
+    ///
+    /// Returns the number of bytes copied: `min(pkt_len, out.len())`.
+    pub fn copy_to(&self, out: &mut [u8]) -> usize {
+        let mut copied = 0;
+        for seg in self.segments() {
+            let n = seg.len().min(out.len() - copied);
+            out[copied..copied + n].copy_from_slice(&seg[..n]);
+            copied += n;
+            if copied == out.len() {
+                break;
+            }
+        }
+        copied
+    }
+
+    /// Copy the packet data (all segments) into a new vector.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut out = vec![0u8; self.pkt_len()];
+        self.copy_to(&mut out);
+        out
+    }
+
+    /// Move all data into the first segment, freeing the others.
+    ///
+    /// Returns `false` if the first segment does not have room for the
+    /// whole packet (e.g. a jumbo frame on a 2KB mempool).
+    #[inline]
+    pub fn linearize(&mut self) -> bool {
+        unsafe { ffi::rust_pktmbuf_linearize(self.inner.as_ptr()) == 0 }
+    }
+
+    /// Append data to the packet, chaining new segments from `mempool` as needed.
+    ///
+    /// Returns `false` (leaving the packet unchanged) if the pool is
+    /// exhausted or the chain would be too long.
+    pub fn extend_from_slice(&mut self, mempool: &MemPool, data: &[u8]) -> bool {
+        let last = unsafe { ffi::rust_pktmbuf_lastseg(self.inner.as_ptr()) };
+        let room = unsafe { ffi::rust_pktmbuf_tailroom(last) } as usize;
+        let (fits, rest) = data.split_at(room.min(data.len()));
+
+        // Build the new segments first so failure leaves the packet untouched
+        let tail = if rest.is_empty() {
+            None
+        } else {
+            match Self::alloc_from_slice(mempool, rest) {
+                Some(tail) => Some(tail),
+                None => return false,
+            }
+        };
+        if !fits.is_empty() {
+            match self.append(fits.len()) {
+                Some(buf) => buf.copy_from_slice(fits),
+                None => return false,
+            }
+        }
+        if let Some(tail) = tail
+            && self.chain(tail).is_err()
+        {
+            self.trim(fits.len());
+            return false;
+        }
+        true
+    }
+
+    /// Prepare header checksum fields for TX checksum offload.
+    ///
+    /// Must be called after [`set_tx_offload`](Self::set_tx_offload): zeroes
+    /// the IPv4 header checksum and writes the L4 pseudo-header checksum.
+    /// Returns `false` if the headers are not in the first segment.
+    #[inline]
+    pub fn prepare_tx_cksum(&mut self) -> bool {
+        unsafe { ffi::rust_pktmbuf_tx_cksum_prepare(self.inner.as_ptr()) == 0 }
+    }
+
     /// Copy data from a slice, resetting the mbuf first.
     pub fn copy_from_slice(&mut self, data: &[u8]) -> bool {
         self.reset();
@@ -213,6 +479,35 @@ impl Mbuf {
     }
 }
 
+/// Iterator over the segments of a packet, returned by [`Mbuf::segments`].
+pub struct Segments<'a> {
+    seg: *mut ffi::rte_mbuf,
+    _mbuf: PhantomData<&'a Mbuf>,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.seg.is_null() {
+            return None;
+        }
+        let seg = self.seg;
+        let (ptr, len) = unsafe {
+            self.seg = ffi::rust_pktmbuf_next(seg);
+            (
+                ffi::rust_pktmbuf_mtod(seg),
+                ffi::rust_pktmbuf_data_len(seg) as usize,
+            )
+        };
+        if ptr.is_null() || len == 0 {
+            Some(&[])
+        } else {
+            Some(unsafe { slice::from_raw_parts(ptr as *const u8, len) })
+        }
+    }
+}
+
 impl Drop for Mbuf {
     fn drop(&mut self) {
         unsafe {
@@ -238,6 +533,7 @@ impl std::fmt::Debug for Mbuf {
         f.debug_struct("Mbuf")
             .field("data_len", &self.data_len())
             .field("pkt_len", &self.pkt_len())
+            .field("nb_segs", &self.nb_segs())
             .field("headroom", &self.headroom())
             .field("tailroom", &self.tailroom())
             .finish()
@@ -6,8 +6,16 @@ pub mod pktmbuf;
 
 pub mod eth;
 
+pub mod event;
+
+pub mod flow;
+
+pub mod gso;
+
 pub mod mbuf;
 
 pub mod queue;
 
+pub mod ring;
+
 pub mod thread;
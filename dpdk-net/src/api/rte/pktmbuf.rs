@@ -1,14 +1,102 @@
 // pktmbuf API
 // See: /usr/local/include/rte_mbuf.h
 
-use std::ffi::CString;
+use std::collections::HashSet;
+use std::ffi::{CStr, CString, c_uint, c_void};
 use std::ptr::NonNull;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use dpdk_net_sys::ffi;
+use tracing::warn;
+
+/// Counters shared by all handles to one pool.
+#[derive(Debug, Default)]
+struct PoolCounters {
+    alloc_failures: AtomicU64,
+}
+
+/// Counters by pool address.
+///
+/// Entries are leaked so that the `Copy` [`MemPoolRef`] can hold a plain
+/// reference; a pool costs one small allocation for the life of the process.
+static POOL_COUNTERS: Mutex<Vec<(usize, &'static PoolCounters)>> = Mutex::new(Vec::new());
+
+/// Get (or create) the counters of a pool.
+fn pool_counters(mp: *mut ffi::rte_mempool) -> &'static PoolCounters {
+    let mut pools = POOL_COUNTERS.lock().unwrap();
+    if let Some(&(_, counters)) = pools.iter().find(|(addr, _)| *addr == mp as usize) {
+        return counters;
+    }
+    let counters: &'static PoolCounters = Box::leak(Box::default());
+    pools.push((mp as usize, counters));
+    counters
+}
+
+/// Forget the counters of a freed pool so a new pool at the same address starts at zero.
+fn release_pool_counters(mp: *mut ffi::rte_mempool) {
+    POOL_COUNTERS
+        .lock()
+        .unwrap()
+        .retain(|(addr, _)| *addr != mp as usize);
+}
+
+/// Point-in-time usage of a mempool.
+///
+/// `avail + in_use == size`. `avail` includes objects sitting in per-lcore
+/// caches (`cached`), which other lcores cannot allocate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemPoolStats {
+    /// Total number of mbufs in the pool
+    pub size: u32,
+    /// Free mbufs, including those in per-lcore caches
+    pub avail: u32,
+    /// Mbufs currently allocated (held by the application or the NIC)
+    pub in_use: u32,
+    /// Per-lcore cache size (0 if caching is disabled)
+    pub cache_size: u32,
+    /// Free mbufs currently held in per-lcore caches
+    pub cached: u32,
+    /// Allocations through this crate that found the pool empty.
+    /// RX refill failures in the PMD are counted in `EthStats::rx_nombuf`.
+    pub alloc_failures: u64,
+}
+
+/// An mbuf that has not been returned to its pool, see [`MemPool::outstanding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutstandingMbuf {
+    /// Address of the `rte_mbuf`
+    pub addr: usize,
+    /// Packet length
+    pub pkt_len: u32,
+    /// Number of segments
+    pub nb_segs: u16,
+}
+
+fn pool_stats(mp: *mut ffi::rte_mempool, counters: &PoolCounters) -> MemPoolStats {
+    let cache_size = unsafe { ffi::rust_mempool_cache_size(mp) };
+    let cached = if cache_size == 0 {
+        0
+    } else {
+        (0..ffi::RTE_MAX_LCORE)
+            .map(|lcore| unsafe { ffi::rust_mempool_cache_count(mp, lcore) })
+            .sum()
+    };
+    MemPoolStats {
+        size: unsafe { ffi::rust_mempool_size(mp) },
+        avail: unsafe { ffi::rte_mempool_avail_count(mp) },
+        in_use: unsafe { ffi::rte_mempool_in_use_count(mp) },
+        cache_size,
+        cached,
+        alloc_failures: counters.alloc_failures.load(Ordering::Relaxed),
+    }
+}
 
 /// Wrapper for DPDK rte_mempool for packet mbufs (owning)
 pub struct MemPool {
     inner: NonNull<ffi::rte_mempool>,
+    counters: &'static PoolCounters,
+    track_leaks: bool,
 }
 
 // DPDK mempools are thread-safe
@@ -22,6 +110,7 @@ unsafe impl Sync for MemPool {}
 #[derive(Clone, Copy)]
 pub struct MemPoolRef {
     inner: NonNull<ffi::rte_mempool>,
+    counters: &'static PoolCounters,
 }
 
 // DPDK mempools are thread-safe
@@ -41,7 +130,11 @@ impl MemPoolRef {
     #[inline]
     pub fn try_alloc(&self) -> Option<super::mbuf::Mbuf> {
         let ptr = unsafe { ffi::rust_pktmbuf_alloc(self.inner.as_ptr()) };
-        unsafe { super::mbuf::Mbuf::from_raw(ptr) }
+        let mbuf = unsafe { super::mbuf::Mbuf::from_raw(ptr) };
+        if mbuf.is_none() {
+            self.counters.alloc_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        mbuf
     }
 
     /// Fill a batch of mbufs up to the remaining capacity of the ArrayVec.
@@ -61,6 +154,16 @@ impl MemPoolRef {
         }
         count
     }
+
+    /// Get a snapshot of the pool usage.
+    pub fn stats(&self) -> MemPoolStats {
+        pool_stats(self.inner.as_ptr(), self.counters)
+    }
+
+    /// Number of allocations that found the pool empty.
+    pub fn alloc_failures(&self) -> u64 {
+        self.counters.alloc_failures.load(Ordering::Relaxed)
+    }
 }
 
 /// Configuration for creating a MemPool
@@ -76,6 +179,8 @@ pub struct MemPoolConfig {
     pub data_room_size: u16,
     /// NUMA socket ID (-1 for SOCKET_ID_ANY)
     pub socket_id: i32,
+    /// Report mbufs still outstanding when the pool is dropped (debug aid)
+    pub track_leaks: bool,
 }
 
 impl Default for MemPoolConfig {
@@ -87,6 +192,7 @@ impl Default for MemPoolConfig {
             data_room_size: ffi::RTE_MBUF_DEFAULT_DATAROOM as u16
                 + ffi::RTE_PKTMBUF_HEADROOM as u16,
             socket_id: -1, // SOCKET_ID_ANY
+            track_leaks: false,
         }
     }
 }
@@ -134,6 +240,16 @@ impl MemPoolConfig {
         self.socket_id = id;
         self
     }
+
+    /// Check for outstanding mbufs when the pool is dropped.
+    ///
+    /// Logs the number of mbufs still in use; [`MemPool::outstanding`]
+    /// lists them once the ports are stopped. Intended for tests and
+    /// debugging.
+    pub fn track_leaks(mut self, enabled: bool) -> Self {
+        self.track_leaks = enabled;
+        self
+    }
 }
 
 impl MemPool {
@@ -157,9 +273,14 @@ impl MemPool {
                 config.socket_id,
             )
         };
-        NonNull::new(ptr)
-            .map(|inner| MemPool { inner })
-            .ok_or_else(crate::api::rte_errno)
+        let inner = NonNull::new(ptr).ok_or_else(crate::api::rte_errno)?;
+        // A previous pool may have lived at this address
+        release_pool_counters(ptr);
+        Ok(MemPool {
+            inner,
+            counters: pool_counters(ptr),
+            track_leaks: config.track_leaks,
+        })
     }
 
     /// Create a mempool with default configuration
@@ -186,7 +307,10 @@ impl MemPool {
         let c_name = CString::new(name).map_err(|_| nix::errno::Errno::EINVAL)?;
         let ptr = unsafe { ffi::rte_mempool_lookup(c_name.as_ptr()) };
         NonNull::new(ptr)
-            .map(|inner| MemPoolRef { inner })
+            .map(|inner| MemPoolRef {
+                inner,
+                counters: pool_counters(ptr),
+            })
             .ok_or_else(crate::api::rte_errno)
     }
 
@@ -196,12 +320,123 @@ impl MemPool {
         self.inner.as_ptr()
     }
 
+    /// Get the pool name
+    pub fn name(&self) -> String {
+        // Safety: rte_mempool names are NUL-terminated
+        unsafe { CStr::from_ptr((*self.inner.as_ptr()).name.as_ptr()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
     /// Get the number of available (free) objects in the pool
     #[inline]
     pub fn avail_count(&self) -> u32 {
         unsafe { ffi::rte_mempool_avail_count(self.inner.as_ptr()) }
     }
 
+    /// Get the number of allocated objects (held by the application or the NIC)
+    #[inline]
+    pub fn in_use_count(&self) -> u32 {
+        unsafe { ffi::rte_mempool_in_use_count(self.inner.as_ptr()) }
+    }
+
+    /// Get the number of free objects cached by `lcore_id`.
+    ///
+    /// Cached objects are only available to that lcore; a pool can look
+    /// exhausted to one lcore while others hold up to `cache_size * 1.5` each.
+    pub fn cache_count(&self, lcore_id: u32) -> u32 {
+        unsafe { ffi::rust_mempool_cache_count(self.inner.as_ptr(), lcore_id) }
+    }
+
+    /// Get `(lcore_id, count)` for every lcore with a non-empty cache.
+    pub fn cache_counts(&self) -> Vec<(u32, u32)> {
+        (0..ffi::RTE_MAX_LCORE)
+            .map(|lcore| (lcore, self.cache_count(lcore)))
+            .filter(|&(_, count)| count > 0)
+            .collect()
+    }
+
+    /// Number of allocations that found the pool empty.
+    ///
+    /// Counts failures of [`try_alloc`](Self::try_alloc) and everything built
+    /// on it, across all handles to this pool (including [`MemPoolRef`]s).
+    pub fn alloc_failures(&self) -> u64 {
+        self.counters.alloc_failures.load(Ordering::Relaxed)
+    }
+
+    /// Record an allocation failure made with the raw pool pointer.
+    #[inline]
+    pub(crate) fn record_alloc_failure(&self) {
+        self.counters.alloc_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get a snapshot of the pool usage.
+    pub fn stats(&self) -> MemPoolStats {
+        pool_stats(self.inner.as_ptr(), self.counters)
+    }
+
+    /// List the mbufs that have not been returned to the pool.
+    ///
+    /// Flushes every lcore cache, temporarily takes all free objects and
+    /// walks the pool.
+    ///
+    /// # Safety
+    /// No other thread may use the pool during the call (allocations would
+    /// fail and the cache flush races the other lcores), and no started
+    /// port may have queues on it: stop the ports first.
+    pub unsafe fn outstanding(&self) -> Vec<OutstandingMbuf> {
+        let mp = self.inner.as_ptr();
+        unsafe { ffi::rust_mempool_flush_caches(mp) };
+
+        let mut free = Vec::new();
+        let mut obj: *mut c_void = std::ptr::null_mut();
+        while unsafe { ffi::rust_mempool_get(mp, &mut obj) } == 0 {
+            free.push(obj);
+        }
+
+        let mut all: Vec<*mut c_void> = Vec::new();
+        unsafe {
+            ffi::rte_mempool_obj_iter(
+                mp,
+                Some(collect_obj),
+                &mut all as *mut Vec<*mut c_void> as *mut c_void,
+            );
+        }
+
+        let free_set: HashSet<usize> = free.iter().map(|&obj| obj as usize).collect();
+        let outstanding = all
+            .into_iter()
+            .filter(|&obj| !free_set.contains(&(obj as usize)))
+            .map(|obj| {
+                let m = obj as *const ffi::rte_mbuf;
+                OutstandingMbuf {
+                    addr: obj as usize,
+                    pkt_len: unsafe { ffi::rust_pktmbuf_pkt_len(m) },
+                    nb_segs: unsafe { ffi::rust_pktmbuf_nb_segs(m) },
+                }
+            })
+            .collect();
+
+        for obj in free {
+            unsafe { ffi::rust_mempool_put(mp, obj) };
+        }
+        outstanding
+    }
+
+    fn report_leaks(&self) {
+        // Listing the mbufs takes the pool over, which is not safe while
+        // ports or other handles may still use it: only count them here
+        let in_use = self.in_use_count();
+        if in_use == 0 {
+            return;
+        }
+        warn!(
+            pool = %self.name(),
+            count = in_use,
+            "Mempool dropped with outstanding mbufs (list them with MemPool::outstanding)"
+        );
+    }
+
     /// Try to allocate an mbuf from this pool.
     ///
     /// Returns `None` if the pool is exhausted.
@@ -241,8 +476,24 @@ impl MemPool {
     }
 }
 
+/// `rte_mempool_obj_iter` callback collecting object pointers into a Vec.
+unsafe extern "C" fn collect_obj(
+    _mp: *mut ffi::rte_mempool,
+    opaque: *mut c_void,
+    obj: *mut c_void,
+    _obj_idx: c_uint,
+) {
+    // SAFETY: opaque is the &mut Vec passed by MemPool::outstanding
+    let all = unsafe { &mut *(opaque as *mut Vec<*mut c_void>) };
+    all.push(obj);
+}
+
 impl Drop for MemPool {
     fn drop(&mut self) {
+        if self.track_leaks {
+            self.report_leaks();
+        }
+        release_pool_counters(self.inner.as_ptr());
         unsafe {
             ffi::rte_mempool_free(self.inner.as_ptr());
         }
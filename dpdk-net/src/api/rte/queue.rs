@@ -5,8 +5,11 @@ use dpdk_net_sys::ffi;
 
 use super::eth::{PortId, QueueId};
 use super::mbuf::Mbuf;
+use crate::api::{Result, check_errno};
 
-/// Maximum burst size for RX/TX operations
+/// Largest number of packets passed to one driver call.
+///
+/// Larger bursts take several calls, see [`RxQueue::rx_up_to`] and [`TxQueue::tx`].
 pub const MAX_BURST_SIZE: usize = 64;
 
 /// RX Queue handle for receiving packets
@@ -43,32 +46,67 @@ impl RxQueue {
     /// Packets are appended to the `mbufs` vector (up to its remaining capacity).
     #[inline]
     pub fn rx<const N: usize>(&self, mbufs: &mut arrayvec::ArrayVec<Mbuf, N>) -> usize {
-        let capacity = mbufs.capacity() - mbufs.len();
-        if capacity == 0 {
-            return 0;
-        }
-
-        let max_pkts = capacity.min(u16::MAX as usize) as u16;
+        self.rx_up_to(mbufs, usize::MAX)
+    }
 
+    /// Receive at most `max` packets into the remaining capacity of `mbufs`.
+    ///
+    /// Bursts over [`MAX_BURST_SIZE`] are read in several driver calls,
+    /// stopping as soon as the queue runs dry. Returns the number of
+    /// packets received.
+    #[inline]
+    pub fn rx_up_to<const N: usize>(
+        &self,
+        mbufs: &mut arrayvec::ArrayVec<Mbuf, N>,
+        max: usize,
+    ) -> usize {
         // Allocate temporary buffer for raw pointers
         let mut raw_mbufs: [*mut ffi::rte_mbuf; MAX_BURST_SIZE] =
             [std::ptr::null_mut(); MAX_BURST_SIZE];
+        let mut total = 0;
+
+        loop {
+            let nb_pkts = (max - total)
+                .min(mbufs.remaining_capacity())
+                .min(MAX_BURST_SIZE);
+            if nb_pkts == 0 {
+                break;
+            }
 
-        let nb_pkts = max_pkts.min(MAX_BURST_SIZE as u16);
-
-        let received = unsafe {
-            ffi::rust_eth_rx_burst(self.port_id, self.queue_id, raw_mbufs.as_mut_ptr(), nb_pkts)
-        };
+            let received = unsafe {
+                ffi::rust_eth_rx_burst(
+                    self.port_id,
+                    self.queue_id,
+                    raw_mbufs.as_mut_ptr(),
+                    nb_pkts as u16,
+                )
+            } as usize;
+
+            // Convert raw pointers to Mbuf and push to the vector
+            for raw_mbuf in &raw_mbufs[..received] {
+                if let Some(mbuf) = unsafe { Mbuf::from_raw(*raw_mbuf) } {
+                    // Safety: ArrayVec has capacity (checked above)
+                    let _ = mbufs.try_push(mbuf);
+                }
+            }
 
-        // Convert raw pointers to Mbuf and push to the vector
-        for raw_mbuf in raw_mbufs.iter().take(received as usize) {
-            if let Some(mbuf) = unsafe { Mbuf::from_raw(*raw_mbuf) } {
-                // Safety: ArrayVec has capacity (checked above)
-                let _ = mbufs.try_push(mbuf);
+            total += received;
+            if received < nb_pkts {
+                break;
             }
         }
+        total
+    }
 
-        received as usize
+    /// Number of received packets waiting in the queue
+    /// (`rte_eth_rx_queue_count`).
+    ///
+    /// Fails with `ENOTSUP` on drivers that cannot tell.
+    #[inline]
+    pub fn count(&self) -> Result<usize> {
+        let ret = unsafe { ffi::rust_eth_rx_queue_count(self.port_id, self.queue_id) };
+        check_errno(ret)?;
+        Ok(ret as usize)
     }
 
     /// Receive a burst of packets, returning them as a new ArrayVec.
@@ -111,39 +149,46 @@ impl TxQueue {
     /// Transmit a burst of packets from the provided buffer.
     ///
     /// Successfully transmitted packets are removed from the front of `mbufs`.
-    /// Returns the number of packets transmitted.
+    /// Returns the number of packets transmitted. Batches over
+    /// [`MAX_BURST_SIZE`] are sent in several driver calls, until the TX
+    /// ring is full.
     ///
     /// Note: Packets that are successfully transmitted are freed by DPDK.
     /// Packets that fail to transmit remain in the buffer (caller must handle).
     #[inline]
     pub fn tx<const N: usize>(&self, mbufs: &mut arrayvec::ArrayVec<Mbuf, N>) -> usize {
-        if mbufs.is_empty() {
-            return 0;
-        }
-
-        let nb_pkts = mbufs.len().min(MAX_BURST_SIZE).min(u16::MAX as usize) as u16;
-
         // Build array of raw pointers (without consuming the Mbufs yet)
         let mut raw_mbufs: [*mut ffi::rte_mbuf; MAX_BURST_SIZE] =
             [std::ptr::null_mut(); MAX_BURST_SIZE];
+        let mut total = 0;
 
-        for (i, mbuf) in mbufs.iter().take(nb_pkts as usize).enumerate() {
-            raw_mbufs[i] = mbuf.as_ptr();
-        }
+        while !mbufs.is_empty() {
+            let nb_pkts = mbufs.len().min(MAX_BURST_SIZE);
+            for (raw, mbuf) in raw_mbufs.iter_mut().zip(mbufs.iter()) {
+                *raw = mbuf.as_ptr();
+            }
 
-        let sent = unsafe {
-            ffi::rust_eth_tx_burst(self.port_id, self.queue_id, raw_mbufs.as_mut_ptr(), nb_pkts)
-        };
+            let sent = unsafe {
+                ffi::rust_eth_tx_burst(
+                    self.port_id,
+                    self.queue_id,
+                    raw_mbufs.as_mut_ptr(),
+                    nb_pkts as u16,
+                )
+            } as usize;
+
+            // Remove sent packets from the buffer.
+            // We need to forget them since DPDK has taken ownership and will free them.
+            for mbuf in mbufs.drain(..sent) {
+                std::mem::forget(mbuf);
+            }
 
-        // Remove sent packets from the buffer.
-        // We need to forget them since DPDK has taken ownership and will free them.
-        for _ in 0..sent {
-            let mbuf = mbufs.remove(0);
-            // Don't drop - DPDK owns the mbuf now
-            std::mem::forget(mbuf);
+            total += sent;
+            if sent < nb_pkts {
+                break;
+            }
         }
-
-        sent as usize
+        total
     }
 
     /// Transmit a single packet.
@@ -0,0 +1,178 @@
+// Ring API
+// See: /usr/local/include/rte_ring.h
+
+use std::ffi::CString;
+use std::ptr::NonNull;
+
+use arrayvec::ArrayVec;
+use dpdk_net_sys::ffi;
+
+use super::mbuf::Mbuf;
+use super::queue::MAX_BURST_SIZE;
+
+/// Ring creation flags
+pub mod ring_flags {
+    use dpdk_net_sys::ffi;
+
+    /// Single producer: only one thread enqueues
+    pub const SP_ENQ: u32 = ffi::RUST_RING_F_SP_ENQ;
+    /// Single consumer: only one thread dequeues
+    pub const SC_DEQ: u32 = ffi::RUST_RING_F_SC_DEQ;
+    /// Capacity is exactly the requested count (not rounded down to 2^n - 1)
+    pub const EXACT_SZ: u32 = ffi::RUST_RING_F_EXACT_SZ;
+}
+
+/// Lock-free ring of mbufs, for handing packets between lcores.
+///
+/// The ring owns the mbufs it holds: enqueued mbufs are freed when the ring is
+/// dropped without dequeuing them. Thread safety for multiple producers or
+/// consumers depends on the [`ring_flags`] it was created with; `MbufRing` is
+/// `Send + Sync` so it can be shared in an `Arc`, and callers must respect
+/// the single-producer/consumer flags they chose.
+pub struct MbufRing {
+    inner: NonNull<ffi::rte_ring>,
+}
+
+// Safety: rte_ring is designed for cross-lcore use; SP/SC discipline is
+// documented as the caller's responsibility.
+unsafe impl Send for MbufRing {}
+unsafe impl Sync for MbufRing {}
+
+impl MbufRing {
+    /// Create a ring holding up to `count` mbufs.
+    ///
+    /// `count` must be a power of two unless `EXACT_SZ` is in `flags`
+    /// (usable capacity is then `count - 1`). The name must be unique.
+    pub fn create<S>(name: S, count: u32, socket_id: i32, flags: u32) -> crate::api::Result<Self>
+    where
+        S: Into<Vec<u8>>,
+    {
+        let c_name = CString::new(name).map_err(|_| nix::errno::Errno::EINVAL)?;
+        let ptr = unsafe { ffi::rte_ring_create(c_name.as_ptr(), count, socket_id, flags) };
+        let inner = NonNull::new(ptr).ok_or_else(crate::api::rte_errno)?;
+        Ok(Self { inner })
+    }
+
+    /// Raw pointer to the ring
+    #[inline]
+    pub fn as_ptr(&self) -> *mut ffi::rte_ring {
+        self.inner.as_ptr()
+    }
+
+    /// Number of mbufs in the ring
+    #[inline]
+    pub fn len(&self) -> usize {
+        unsafe { ffi::rust_ring_count(self.inner.as_ptr()) as usize }
+    }
+
+    /// Check if the ring is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Maximum number of mbufs the ring can hold
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        unsafe { ffi::rust_ring_capacity(self.inner.as_ptr()) as usize }
+    }
+
+    /// Enqueue as many mbufs from the front of `mbufs` as fit.
+    ///
+    /// Enqueued mbufs are removed from `mbufs`; the rest stay for the caller
+    /// to retry or drop. Returns the number enqueued.
+    #[inline]
+    pub fn enqueue<const N: usize>(&self, mbufs: &mut ArrayVec<Mbuf, N>) -> usize {
+        if mbufs.is_empty() {
+            return 0;
+        }
+        let nb = mbufs.len().min(MAX_BURST_SIZE);
+
+        let mut raw: [*mut std::ffi::c_void; MAX_BURST_SIZE] =
+            [std::ptr::null_mut(); MAX_BURST_SIZE];
+        for (slot, mbuf) in raw.iter_mut().zip(mbufs.iter()) {
+            *slot = mbuf.as_ptr().cast();
+        }
+
+        let enqueued = unsafe {
+            ffi::rust_ring_enqueue_burst(self.inner.as_ptr(), raw.as_ptr(), nb as u32) as usize
+        };
+
+        // The ring owns the enqueued mbufs now
+        for mbuf in mbufs.drain(..enqueued) {
+            std::mem::forget(mbuf);
+        }
+        enqueued
+    }
+
+    /// Enqueue a single mbuf, handing it back if the ring is full.
+    #[inline]
+    pub fn enqueue_one(&self, mbuf: Mbuf) -> Result<(), Mbuf> {
+        let raw: *mut std::ffi::c_void = mbuf.as_ptr().cast();
+        let enqueued = unsafe { ffi::rust_ring_enqueue_burst(self.inner.as_ptr(), &raw, 1) };
+        if enqueued == 1 {
+            std::mem::forget(mbuf);
+            Ok(())
+        } else {
+            Err(mbuf)
+        }
+    }
+
+    /// Dequeue mbufs into the remaining capacity of `mbufs`.
+    ///
+    /// Returns the number dequeued.
+    #[inline]
+    pub fn dequeue<const N: usize>(&self, mbufs: &mut ArrayVec<Mbuf, N>) -> usize {
+        self.dequeue_up_to(mbufs, usize::MAX)
+    }
+
+    /// Dequeue at most `max` mbufs into the remaining capacity of `mbufs`,
+    /// [`MAX_BURST_SIZE`] at a time.
+    ///
+    /// Returns the number dequeued.
+    #[inline]
+    pub fn dequeue_up_to<const N: usize>(
+        &self,
+        mbufs: &mut ArrayVec<Mbuf, N>,
+        max: usize,
+    ) -> usize {
+        let mut raw: [*mut std::ffi::c_void; MAX_BURST_SIZE] =
+            [std::ptr::null_mut(); MAX_BURST_SIZE];
+        let mut total = 0;
+
+        loop {
+            let nb = (max - total)
+                .min(mbufs.remaining_capacity())
+                .min(MAX_BURST_SIZE);
+            if nb == 0 {
+                break;
+            }
+            let dequeued = unsafe {
+                ffi::rust_ring_dequeue_burst(self.inner.as_ptr(), raw.as_mut_ptr(), nb as u32)
+                    as usize
+            };
+
+            for &ptr in &raw[..dequeued] {
+                if let Some(mbuf) = unsafe { Mbuf::from_raw(ptr.cast()) } {
+                    mbufs.push(mbuf);
+                }
+            }
+            total += dequeued;
+            if dequeued < nb {
+                break;
+            }
+        }
+        total
+    }
+}
+
+impl Drop for MbufRing {
+    fn drop(&mut self) {
+        // Free mbufs still in flight
+        let mut batch: ArrayVec<Mbuf, MAX_BURST_SIZE> = ArrayVec::new();
+        while self.dequeue(&mut batch) > 0 {
+            batch.clear();
+        }
+        unsafe { ffi::rte_ring_free(self.inner.as_ptr()) };
+    }
+}
@@ -0,0 +1,212 @@
+//! Proactive ARP at startup and on address changes.
+//!
+//! Without this, nobody ARPs for the gateway until the first packet needs it,
+//! and in a multi-queue setup that first packet is often a SYN-ACK on queue N
+//! that times out while queue 0 resolves the gateway. With an
+//! [`ArpAnnounceConfig`], queue 0 sends ARP requests for the gateway and any
+//! extra neighbors as soon as it starts polling, and gratuitous ARPs for our
+//! own address so upstream switches and hosts learn it immediately. The
+//! replies reach the other queues through the [`SharedArpCache`].
+//!
+//! [`SharedArpCache`]: super::SharedArpCache
+
+use smoltcp::time::{Duration, Instant};
+use std::net::Ipv4Addr;
+
+use super::arp_cache::{MacAddress, build_arp_request, build_gratuitous_arp};
+
+/// Default number of gratuitous ARPs per announcement (RFC 5227 ANNOUNCE_NUM).
+pub const DEFAULT_GRATUITOUS_COUNT: u32 = 2;
+
+/// Default maximum number of ARP requests per neighbor.
+pub const DEFAULT_REQUEST_COUNT: u32 = 3;
+
+/// Default interval between repeated ARPs.
+pub const DEFAULT_ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What to ARP for when a [`DpdkDevice`](super::DpdkDevice) starts.
+///
+/// # Example
+///
+/// ```
+/// # use dpdk_net::device::ArpAnnounceConfig;
+/// # use std::net::Ipv4Addr;
+/// let config = ArpAnnounceConfig::new()
+///     .gateway(Ipv4Addr::new(10, 0, 0, 1))
+///     .neighbor(Ipv4Addr::new(10, 0, 0, 20));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArpAnnounceConfig {
+    /// Neighbors to resolve, gateway first
+    pub neighbors: Vec<Ipv4Addr>,
+    /// Gratuitous ARPs sent per announcement (0 = none)
+    pub gratuitous_count: u32,
+    /// Maximum ARP requests per neighbor; stops early once resolved
+    pub request_count: u32,
+    /// Interval between repeated requests and announcements
+    pub interval: Duration,
+}
+
+impl Default for ArpAnnounceConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArpAnnounceConfig {
+    /// Gratuitous ARP only, no neighbors to resolve.
+    pub fn new() -> Self {
+        Self {
+            neighbors: Vec::new(),
+            gratuitous_count: DEFAULT_GRATUITOUS_COUNT,
+            request_count: DEFAULT_REQUEST_COUNT,
+            interval: DEFAULT_ANNOUNCE_INTERVAL,
+        }
+    }
+
+    /// Resolve the default gateway at startup.
+    pub fn gateway(mut self, gateway: Ipv4Addr) -> Self {
+        self.neighbors.insert(0, gateway);
+        self
+    }
+
+    /// Also resolve `ip` at startup.
+    pub fn neighbor(mut self, ip: Ipv4Addr) -> Self {
+        self.neighbors.push(ip);
+        self
+    }
+
+    /// Number of gratuitous ARPs per announcement (0 disables them).
+    pub fn gratuitous_count(mut self, count: u32) -> Self {
+        self.gratuitous_count = count;
+        self
+    }
+
+    /// Maximum number of ARP requests per neighbor.
+    pub fn request_count(mut self, count: u32) -> Self {
+        self.request_count = count;
+        self
+    }
+
+    /// Interval between repeated ARPs.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// Announcement progress for one device.
+pub(crate) struct ArpAnnouncer {
+    config: ArpAnnounceConfig,
+    /// Rounds sent since the last (re)start
+    round: u32,
+    /// When the next round is due (None = immediately)
+    next_at: Option<Instant>,
+}
+
+impl ArpAnnouncer {
+    pub(crate) fn new(config: ArpAnnounceConfig) -> Self {
+        Self {
+            config,
+            round: 0,
+            next_at: None,
+        }
+    }
+
+    /// Announce again, e.g. after our address changed.
+    pub(crate) fn restart(&mut self) {
+        self.round = 0;
+        self.next_at = None;
+    }
+
+    fn rounds(&self) -> u32 {
+        let requests = if self.config.neighbors.is_empty() {
+            0
+        } else {
+            self.config.request_count
+        };
+        requests.max(self.config.gratuitous_count)
+    }
+
+    /// Whether a round is due at `now`.
+    #[inline]
+    pub(crate) fn is_due(&self, now: Instant) -> bool {
+        self.round < self.rounds() && self.next_at.is_none_or(|at| now >= at)
+    }
+
+    /// Frames for the round due at `now`.
+    ///
+    /// `resolved` tells whether a neighbor is already known; resolved
+    /// neighbors are not asked for again.
+    pub(crate) fn next_round(
+        &mut self,
+        now: Instant,
+        our_mac: MacAddress,
+        our_ip: Ipv4Addr,
+        resolved: impl Fn(&Ipv4Addr) -> bool,
+    ) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        if self.round < self.config.gratuitous_count {
+            frames.push(build_gratuitous_arp(our_mac, our_ip));
+        }
+        if self.round < self.config.request_count {
+            frames.extend(
+                self.config
+                    .neighbors
+                    .iter()
+                    .filter(|ip| !resolved(ip))
+                    .map(|&ip| build_arp_request(our_mac, our_ip, ip)),
+            );
+        }
+        self.round += 1;
+        self.next_at = Some(now + self.config.interval);
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: MacAddress = [0x02, 0, 0, 0, 0, 1];
+    const OUR_IP: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 5);
+    const GATEWAY: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+    const PEER: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 20);
+
+    #[test]
+    fn test_announce_rounds() {
+        let config = ArpAnnounceConfig::new()
+            .neighbor(PEER)
+            .gateway(GATEWAY)
+            .gratuitous_count(1);
+        assert_eq!(config.neighbors, [GATEWAY, PEER]);
+        let mut announcer = ArpAnnouncer::new(config);
+        let t0 = Instant::from_secs(100);
+
+        // Round 1: gratuitous + both requests
+        assert!(announcer.is_due(t0));
+        let frames = announcer.next_round(t0, MAC, OUR_IP, |_| false);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0], build_gratuitous_arp(MAC, OUR_IP));
+
+        // Not due before the interval
+        assert!(!announcer.is_due(t0 + Duration::from_millis(500)));
+
+        // Round 2: gateway resolved, only the peer is asked again
+        let t1 = t0 + DEFAULT_ANNOUNCE_INTERVAL;
+        assert!(announcer.is_due(t1));
+        let frames = announcer.next_round(t1, MAC, OUR_IP, |ip| *ip == GATEWAY);
+        assert_eq!(frames, [build_arp_request(MAC, OUR_IP, PEER)]);
+
+        let t2 = t1 + DEFAULT_ANNOUNCE_INTERVAL;
+        announcer.next_round(t2, MAC, OUR_IP, |_| true);
+        assert!(!announcer.is_due(t2 + DEFAULT_ANNOUNCE_INTERVAL));
+
+        // Address change announces again
+        announcer.restart();
+        assert!(announcer.is_due(t2));
+        let new_ip = Ipv4Addr::new(10, 0, 0, 6);
+        let frames = announcer.next_round(t2, MAC, new_ip, |_| true);
+        assert_eq!(frames, [build_gratuitous_arp(MAC, new_ip)]);
+    }
+}
@@ -26,29 +26,122 @@
 //!
 //! Implementation uses `arc-swap` for lock-free reads:
 //! - Reads: Single atomic load, no contention
-//! - Writes: Clone + atomic store (single producer, no concurrent writes)
+//! - Refreshes of known IPs: atomic stores into the entry, no copy
+//! - New IPs / removals: Clone + atomic store
+//!
+//! # Aging
+//!
+//! Learned entries expire [`DEFAULT_ARP_TTL`] (or the TTL given to
+//! [`SharedArpCache::with_ttl`]) after their last ARP reply, so a MAC that
+//! moved (e.g. VM migration) and stopped answering does not linger forever.
+//! Static entries ([`SharedArpCache::insert_static`]) never expire.
 
 use arc_swap::ArcSwap;
 use std::collections::HashMap;
 use std::net::Ipv4Addr;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// A MAC address (6 bytes).
 pub type MacAddress = [u8; 6];
 
+const BROADCAST_MAC: MacAddress = [0xff; 6];
+const ARP_OP_REQUEST: u8 = 1;
+const ARP_OP_REPLY: u8 = 2;
+
+/// Default lifetime of a learned entry that is not refreshed.
+///
+/// smoltcp re-resolves its own entries every 60s while a peer is in use, so
+/// live peers are refreshed well within this.
+pub const DEFAULT_ARP_TTL: Duration = Duration::from_secs(300);
+
+/// One cache entry, as returned by [`SharedArpCache::snapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpEntry {
+    pub ip: Ipv4Addr,
+    pub mac: MacAddress,
+    /// Time since the entry was last inserted or refreshed
+    pub age: Duration,
+    /// Pinned with [`SharedArpCache::insert_static`]; never expires
+    pub is_static: bool,
+}
+
+fn pack_mac(mac: MacAddress) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes[..6].copy_from_slice(&mac);
+    u64::from_le_bytes(bytes)
+}
+
+fn unpack_mac(packed: u64) -> MacAddress {
+    let bytes = packed.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5]]
+}
+
+/// Mutable part of an entry, updated in place on refresh.
+struct Slot {
+    /// MAC packed into the low 48 bits
+    mac: AtomicU64,
+    /// Last refresh, in nanoseconds since the cache epoch
+    updated: AtomicU64,
+    is_static: bool,
+}
+
+impl Slot {
+    fn new(mac: MacAddress, now: u64, is_static: bool) -> Arc<Self> {
+        Arc::new(Self {
+            mac: AtomicU64::new(pack_mac(mac)),
+            updated: AtomicU64::new(now),
+            is_static,
+        })
+    }
+
+    #[inline]
+    fn mac(&self) -> MacAddress {
+        unpack_mac(self.mac.load(Ordering::Acquire))
+    }
+
+    #[inline]
+    fn age(&self, now: u64) -> Duration {
+        Duration::from_nanos(now.saturating_sub(self.updated.load(Ordering::Acquire)))
+    }
+
+    #[inline]
+    fn is_live(&self, now: u64, ttl: Duration) -> bool {
+        self.is_static || self.age(now) < ttl
+    }
+}
+
+type ArpMap = HashMap<Ipv4Addr, Arc<Slot>>;
+
 /// Thread-safe shared ARP cache using lock-free SPMC pattern.
 ///
 /// Optimized for single-producer (queue 0) multi-consumer (all queues):
 /// - Reads: Lock-free atomic load
-/// - Writes: Copy-on-write with atomic store (no concurrent writer synchronization)
-/// - Length: Relaxed atomic for eventual consistency (avoids Arc load on hot path)
+/// - Refreshes of known IPs (same or new MAC): in-place atomic stores, no
+///   copy; taken under the writer lock once the entry is past half its TTL
+/// - New IPs, removals and flushes: copy-on-write, serialized by a writer
+///   lock so control-plane calls can run alongside queue 0
+///
+/// Learned entries expire after the TTL unless refreshed; expired entries are
+/// invisible to readers and dropped on the next structural change or
+/// [`evict_expired`](Self::evict_expired). Static entries never expire and are
+/// not overwritten by learned replies.
+///
+/// Removing or expiring an entry does not purge it from the per-queue smoltcp
+/// caches that already learned it; those drop it after smoltcp's own 60s
+/// lifetime, and re-resolve through queue 0.
 #[derive(Clone)]
 pub struct SharedArpCache {
-    inner: Arc<ArcSwap<HashMap<Ipv4Addr, MacAddress>>>,
+    inner: Arc<ArcSwap<ArpMap>>,
     /// Version counter that increments on every insert (even updates).
     /// Used by consumers to detect any change, including MAC updates for existing IPs.
     version: Arc<AtomicUsize>,
+    /// Serializes copy-on-write updates
+    writer: Arc<Mutex<()>>,
+    /// Origin for entry timestamps
+    epoch: Instant,
+    ttl: Duration,
 }
 
 impl Default for SharedArpCache {
@@ -58,61 +151,165 @@ impl Default for SharedArpCache {
 }
 
 impl SharedArpCache {
-    /// Create a new empty shared ARP cache.
+    /// Create a new empty shared ARP cache with [`DEFAULT_ARP_TTL`].
     pub fn new() -> Self {
+        Self::with_ttl(DEFAULT_ARP_TTL)
+    }
+
+    /// Create a new empty shared ARP cache whose learned entries expire
+    /// `ttl` after their last refresh.
+    pub fn with_ttl(ttl: Duration) -> Self {
         Self {
             inner: Arc::new(ArcSwap::from_pointee(HashMap::new())),
             version: Arc::new(AtomicUsize::new(0)),
+            writer: Arc::new(Mutex::new(())),
+            epoch: Instant::now(),
+            ttl,
         }
     }
 
+    /// Lifetime of learned entries.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    #[inline]
+    fn now(&self) -> u64 {
+        self.epoch.elapsed().as_nanos() as u64
+    }
+
+    #[inline]
+    fn bump_version(&self) {
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
     /// Look up a MAC address for an IP.
     ///
-    /// Lock-free: single atomic load.
+    /// Lock-free: single atomic load. Expired entries are not returned.
     #[inline]
     pub fn get(&self, ip: &Ipv4Addr) -> Option<MacAddress> {
-        self.inner.load().get(ip).copied()
+        let map = self.inner.load();
+        let slot = map.get(ip)?;
+        slot.is_live(self.now(), self.ttl).then(|| slot.mac())
     }
 
-    /// Insert or update a MAC address for an IP.
+    /// Insert or refresh a learned MAC address for an IP.
     ///
-    /// SPMC optimization: Since only queue 0 writes, we use simple
-    /// copy-on-write with atomic store (no rcu needed for concurrent writers).
+    /// Refreshing a known IP only touches that entry. Static entries are
+    /// left as they are.
     ///
-    /// # Safety
-    /// Only call this from the single producer (queue 0).
+    /// Meant to be called from the single producer (queue 0).
     pub fn insert(&self, ip: Ipv4Addr, mac: MacAddress) {
-        // Load current map
+        let now = self.now();
         let current = self.inner.load();
 
-        // Check if already present with same value
-        // TODO: if number of queue is large, this is expensive.
-        if current.get(&ip) == Some(&mac) {
-            // MAC unchanged, but still bump version so consumers re-inject.
-            // This is needed because smoltcp's internal neighbor cache expires
-            // independently (60s) and needs periodic ARP refreshes.
-            self.version.fetch_add(1, Ordering::Release);
-            return;
+        if let Some(slot) = current.get(&ip) {
+            // Refresh in place only while the entry is far from expiry: a
+            // concurrent `update` could otherwise judge it expired and drop
+            // it, losing the refresh. Entries close to expiry are refreshed
+            // under the writer lock instead.
+            if slot.is_static || slot.age(now) < self.ttl / 2 {
+                if !slot.is_static {
+                    slot.mac.store(pack_mac(mac), Ordering::Release);
+                    slot.updated.store(now, Ordering::Release);
+                }
+                // Bump version even if unchanged so consumers re-inject.
+                // This is needed because smoltcp's internal neighbor cache expires
+                // independently (60s) and needs periodic ARP refreshes.
+                self.bump_version();
+                return;
+            }
+        }
+        drop(current);
+
+        self.update(|map| {
+            // Entry may have appeared or expired while we waited for the
+            // writer lock
+            match map.get(&ip) {
+                Some(slot) if slot.is_static => {}
+                Some(slot) => {
+                    slot.mac.store(pack_mac(mac), Ordering::Release);
+                    slot.updated.store(now, Ordering::Release);
+                }
+                None => {
+                    map.insert(ip, Slot::new(mac, now, false));
+                }
+            }
+        });
+    }
+
+    /// Pin a MAC address for an IP (e.g. the gateway).
+    ///
+    /// Static entries never expire, are not changed by learned ARP replies
+    /// and survive [`flush`](Self::flush). Replaces any existing entry.
+    pub fn insert_static(&self, ip: Ipv4Addr, mac: MacAddress) {
+        let now = self.now();
+        self.update(|map| {
+            map.insert(ip, Slot::new(mac, now, true));
+        });
+    }
+
+    /// Remove an entry, learned or static.
+    ///
+    /// Returns `true` if the IP was present.
+    pub fn remove(&self, ip: &Ipv4Addr) -> bool {
+        if !self.inner.load().contains_key(ip) {
+            return false;
         }
+        let mut removed = false;
+        self.update(|map| removed = map.remove(ip).is_some());
+        removed
+    }
 
-        // Copy-on-write: clone and update
-        let mut new_map = (**current).clone();
-        new_map.insert(ip, mac);
+    /// Remove all learned entries. Static entries are kept.
+    pub fn flush(&self) {
+        self.update(|map| map.retain(|_, slot| slot.is_static));
+    }
 
-        // Atomic store - safe because we're the only writer (SPMC)
-        self.inner.store(Arc::new(new_map));
+    /// Drop expired learned entries now.
+    ///
+    /// Returns the number of entries removed. Expired entries are already
+    /// hidden from readers; this only reclaims them.
+    pub fn evict_expired(&self) -> usize {
+        let now = self.now();
+        let expired = self
+            .inner
+            .load()
+            .values()
+            .filter(|slot| !slot.is_live(now, self.ttl))
+            .count();
+        if expired == 0 {
+            return 0;
+        }
+        self.update(|_| {})
+    }
 
+    /// Copy-on-write structural update; also drops expired entries.
+    ///
+    /// Returns the number of expired entries dropped.
+    fn update(&self, f: impl FnOnce(&mut ArpMap)) -> usize {
+        let _guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let now = self.now();
+        let current = self.inner.load();
+        let mut new_map: ArpMap = current
+            .iter()
+            .filter(|(_, slot)| slot.is_live(now, self.ttl))
+            .map(|(ip, slot)| (*ip, slot.clone()))
+            .collect();
+        let expired = current.len() - new_map.len();
+        f(&mut new_map);
+        self.inner.store(Arc::new(new_map));
         // Always bump version so consumers know to re-inject
-        // (even if it was just an update of existing entry)
-        self.version.fetch_add(1, Ordering::Release);
+        self.bump_version();
+        expired
     }
 
-    /// Check if an IP is in the cache.
+    /// Check if an unexpired entry exists for an IP.
     ///
     /// Lock-free: single atomic load.
     #[inline]
     pub fn contains(&self, ip: &Ipv4Addr) -> bool {
-        self.inner.load().contains_key(ip)
+        self.get(ip).is_some()
     }
 
     /// Get the version counter (increments on every insert/update).
@@ -123,18 +320,38 @@ impl SharedArpCache {
         self.version.load(Ordering::Relaxed)
     }
 
-    /// Check if the cache is empty.
+    /// Number of unexpired entries.
+    pub fn len(&self) -> usize {
+        let now = self.now();
+        self.inner
+            .load()
+            .values()
+            .filter(|slot| slot.is_live(now, self.ttl))
+            .count()
+    }
+
+    /// Check if the cache has no unexpired entries.
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.inner.load().is_empty()
+        self.len() == 0
     }
 
-    /// Get a snapshot of all entries for iteration.
+    /// Get all unexpired entries with their age.
     ///
-    /// Lock-free: single atomic load, returns Arc to shared data.
-    #[inline]
-    pub fn snapshot(&self) -> arc_swap::Guard<Arc<HashMap<Ipv4Addr, MacAddress>>> {
-        self.inner.load()
+    /// Lock-free: single atomic load, then a copy of the live entries.
+    pub fn snapshot(&self) -> Vec<ArpEntry> {
+        let now = self.now();
+        self.inner
+            .load()
+            .iter()
+            .filter(|(_, slot)| slot.is_live(now, self.ttl))
+            .map(|(&ip, slot)| ArpEntry {
+                ip,
+                mac: slot.mac(),
+                age: slot.age(now),
+                is_static: slot.is_static,
+            })
+            .collect()
     }
 }
 
@@ -147,33 +364,63 @@ impl SharedArpCache {
 /// `Some((sender_ip, sender_mac))` if this is an ARP reply, `None` otherwise.
 #[inline(always)]
 pub fn parse_arp_reply(packet: &[u8]) -> Option<(Ipv4Addr, MacAddress)> {
-    // Minimum ARP packet: Ethernet header (14) + ARP (28) = 42 bytes
-    if packet.len() < 42 {
+    // Check ethertype is ARP (0x0806), behind an 802.1Q tag or not
+    let (ethertype, l2_len) = super::vlan::ethertype(packet)?;
+    if ethertype != 0x0806 {
         return None;
     }
 
-    // Check ethertype is ARP (0x0806)
-    if packet[12] != 0x08 || packet[13] != 0x06 {
-        return None;
-    }
+    // Minimum ARP packet: Ethernet header + ARP (28)
+    let arp = packet.get(l2_len..l2_len + 28)?;
 
     // Check it's an ARP reply (operation = 2)
-    // ARP header starts at offset 14
-    // Operation is at offset 20-21 (6-7 within ARP header)
-    if packet[20] != 0x00 || packet[21] != 0x02 {
+    // Operation is at offset 6-7 within ARP header
+    if arp[6] != 0x00 || arp[7] != 0x02 {
         return None;
     }
 
-    // Sender MAC is at offset 22-27 (8-13 within ARP header)
+    // Sender MAC is at offset 8-13 within ARP header
     let mut sender_mac = [0u8; 6];
-    sender_mac.copy_from_slice(&packet[22..28]);
+    sender_mac.copy_from_slice(&arp[8..14]);
 
-    // Sender IP is at offset 28-31 (14-17 within ARP header)
-    let sender_ip = Ipv4Addr::new(packet[28], packet[29], packet[30], packet[31]);
+    // Sender IP is at offset 14-17 within ARP header
+    let sender_ip = Ipv4Addr::new(arp[14], arp[15], arp[16], arp[17]);
 
     Some((sender_ip, sender_mac))
 }
 
+/// Build an Ethernet frame carrying an ARP packet.
+fn build_arp(
+    op: u8,
+    eth_dst: MacAddress,
+    sender: (MacAddress, Ipv4Addr),
+    target: (MacAddress, Ipv4Addr),
+) -> Vec<u8> {
+    let mut packet = vec![0u8; 42]; // Ethernet (14) + ARP (28)
+
+    // Ethernet header
+    packet[0..6].copy_from_slice(&eth_dst);
+    packet[6..12].copy_from_slice(&sender.0);
+    packet[12..14].copy_from_slice(&[0x08, 0x06]); // EtherType: ARP
+
+    // ARP header
+    packet[14..16].copy_from_slice(&[0x00, 0x01]); // Hardware type: Ethernet
+    packet[16..18].copy_from_slice(&[0x08, 0x00]); // Protocol type: IPv4
+    packet[18] = 6; // Hardware address length
+    packet[19] = 4; // Protocol address length
+    packet[20..22].copy_from_slice(&[0x00, op]); // Operation
+
+    // Sender hardware and protocol address
+    packet[22..28].copy_from_slice(&sender.0);
+    packet[28..32].copy_from_slice(&sender.1.octets());
+
+    // Target hardware and protocol address
+    packet[32..38].copy_from_slice(&target.0);
+    packet[38..42].copy_from_slice(&target.1.octets());
+
+    packet
+}
+
 /// Build an ARP reply packet for injection into smoltcp.
 ///
 /// This creates a fake ARP reply that looks like it came from the specified
@@ -194,29 +441,42 @@ pub fn build_arp_reply_for_injection(
     peer_mac: MacAddress,
     peer_ip: Ipv4Addr,
 ) -> Vec<u8> {
-    let mut packet = vec![0u8; 42]; // Ethernet (14) + ARP (28)
-
-    // Ethernet header
-    packet[0..6].copy_from_slice(&our_mac); // Destination MAC (us)
-    packet[6..12].copy_from_slice(&peer_mac); // Source MAC (peer)
-    packet[12..14].copy_from_slice(&[0x08, 0x06]); // EtherType: ARP
-
-    // ARP header
-    packet[14..16].copy_from_slice(&[0x00, 0x01]); // Hardware type: Ethernet
-    packet[16..18].copy_from_slice(&[0x08, 0x00]); // Protocol type: IPv4
-    packet[18] = 6; // Hardware address length
-    packet[19] = 4; // Protocol address length
-    packet[20..22].copy_from_slice(&[0x00, 0x02]); // Operation: ARP Reply
-
-    // Sender (peer) hardware and protocol address
-    packet[22..28].copy_from_slice(&peer_mac);
-    packet[28..32].copy_from_slice(&peer_ip.octets());
+    build_arp(
+        ARP_OP_REPLY,
+        our_mac,
+        (peer_mac, peer_ip),
+        (our_mac, our_ip),
+    )
+}
 
-    // Target (us) hardware and protocol address
-    packet[32..38].copy_from_slice(&our_mac);
-    packet[38..42].copy_from_slice(&our_ip.octets());
+/// Build a broadcast ARP request asking for `target_ip`.
+///
+/// # Returns
+/// A complete Ethernet frame, ready to transmit.
+pub fn build_arp_request(our_mac: MacAddress, our_ip: Ipv4Addr, target_ip: Ipv4Addr) -> Vec<u8> {
+    build_arp(
+        ARP_OP_REQUEST,
+        BROADCAST_MAC,
+        (our_mac, our_ip),
+        ([0; 6], target_ip),
+    )
+}
 
-    packet
+/// Build a gratuitous ARP announcing `our_ip` at `our_mac`.
+///
+/// Uses the request form with sender and target IP both set to our IP
+/// (RFC 5227 announcement), which switches and hosts accept for updating
+/// existing entries.
+///
+/// # Returns
+/// A complete Ethernet frame, ready to transmit.
+pub fn build_gratuitous_arp(our_mac: MacAddress, our_ip: Ipv4Addr) -> Vec<u8> {
+    build_arp(
+        ARP_OP_REQUEST,
+        BROADCAST_MAC,
+        (our_mac, our_ip),
+        ([0; 6], our_ip),
+    )
 }
 
 #[cfg(test)]
@@ -239,6 +499,89 @@ mod tests {
         assert!(cache.contains(&ip));
     }
 
+    #[test]
+    fn test_refresh_in_place() {
+        let cache = SharedArpCache::new();
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        cache.insert(ip, [1; 6]);
+        let map = cache.inner.load_full();
+
+        // Refresh and MAC change reuse the map
+        cache.insert(ip, [1; 6]);
+        cache.insert(ip, [2; 6]);
+        assert!(Arc::ptr_eq(&map, &cache.inner.load_full()));
+        assert_eq!(cache.get(&ip), Some([2; 6]));
+        assert_eq!(cache.version(), 3);
+    }
+
+    /// Move the cache's clock `by` into the future, instead of sleeping.
+    fn advance(cache: &mut SharedArpCache, by: Duration) {
+        cache.epoch = cache.epoch.checked_sub(by).unwrap();
+    }
+
+    #[test]
+    fn test_refresh_near_expiry() {
+        let mut cache = SharedArpCache::with_ttl(Duration::from_secs(40));
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        cache.insert(ip, [1; 6]);
+        let map = cache.inner.load_full();
+
+        // Past half the TTL the refresh goes through the writer lock
+        advance(&mut cache, Duration::from_secs(25));
+        cache.insert(ip, [2; 6]);
+        assert!(!Arc::ptr_eq(&map, &cache.inner.load_full()));
+        assert_eq!(cache.get(&ip), Some([2; 6]));
+
+        advance(&mut cache, Duration::from_secs(25));
+        assert_eq!(cache.evict_expired(), 0);
+        assert_eq!(cache.get(&ip), Some([2; 6]));
+    }
+
+    #[test]
+    fn test_ttl_and_static() {
+        let mut cache = SharedArpCache::with_ttl(Duration::from_secs(20));
+        let peer = Ipv4Addr::new(10, 0, 0, 1);
+        let gateway = Ipv4Addr::new(10, 0, 0, 254);
+        cache.insert(peer, [1; 6]);
+        cache.insert_static(gateway, [9; 6]);
+
+        // Learned replies don't override a static entry
+        cache.insert(gateway, [8; 6]);
+        assert_eq!(cache.get(&gateway), Some([9; 6]));
+        assert_eq!(cache.len(), 2);
+
+        advance(&mut cache, Duration::from_secs(30));
+        assert_eq!(cache.get(&peer), None);
+        assert_eq!(cache.get(&gateway), Some([9; 6]));
+        assert_eq!(cache.evict_expired(), 1);
+
+        let snapshot = cache.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert!(snapshot[0].is_static);
+        assert!(snapshot[0].age >= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_remove_and_flush() {
+        let cache = SharedArpCache::new();
+        let a = Ipv4Addr::new(10, 0, 0, 1);
+        let b = Ipv4Addr::new(10, 0, 0, 2);
+        let gateway = Ipv4Addr::new(10, 0, 0, 254);
+        cache.insert(a, [1; 6]);
+        cache.insert(b, [2; 6]);
+        cache.insert_static(gateway, [9; 6]);
+
+        assert!(cache.remove(&a));
+        assert!(!cache.remove(&a));
+
+        cache.flush();
+        assert_eq!(cache.get(&b), None);
+        assert_eq!(cache.get(&gateway), Some([9; 6]));
+
+        assert!(cache.remove(&gateway));
+        assert!(cache.is_empty());
+    }
+
     #[test]
     fn test_parse_arp_reply() {
         // Build a test ARP reply
@@ -253,6 +596,25 @@ mod tests {
         assert_eq!(result, Some((peer_ip, peer_mac)));
     }
 
+    #[test]
+    fn test_build_arp_request() {
+        let our_mac = [0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff];
+        let our_ip = Ipv4Addr::new(10, 0, 0, 5);
+        let gateway = Ipv4Addr::new(10, 0, 0, 1);
+
+        let request = build_arp_request(our_mac, our_ip, gateway);
+        assert_eq!(&request[0..6], &BROADCAST_MAC);
+        assert_eq!(&request[20..22], &[0, 1]);
+        assert_eq!(&request[28..32], &our_ip.octets());
+        assert_eq!(&request[38..42], &gateway.octets());
+        assert!(parse_arp_reply(&request).is_none());
+
+        let garp = build_gratuitous_arp(our_mac, our_ip);
+        assert_eq!(&garp[22..28], &our_mac);
+        assert_eq!(&garp[28..32], &our_ip.octets());
+        assert_eq!(&garp[38..42], &our_ip.octets());
+    }
+
     #[test]
     fn test_parse_non_arp_packet() {
         // IPv4 packet (not ARP)
@@ -274,4 +636,25 @@ mod tests {
 
         assert!(parse_arp_reply(&packet).is_none());
     }
+
+    #[test]
+    fn test_parse_tagged_arp_reply() {
+        let peer_mac = [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc];
+        let peer_ip = Ipv4Addr::new(10, 0, 0, 1);
+        let untagged = build_arp_reply_for_injection(
+            [2, 0, 0, 0, 0, 1],
+            Ipv4Addr::new(10, 0, 0, 5),
+            peer_mac,
+            peer_ip,
+        );
+
+        // VLAN 100 tag between the MAC addresses and the ethertype
+        let mut packet = untagged[..12].to_vec();
+        packet.extend_from_slice(&[0x81, 0x00, 0x00, 100]);
+        packet.extend_from_slice(&untagged[12..]);
+        assert_eq!(parse_arp_reply(&packet), Some((peer_ip, peer_mac)));
+
+        // Truncated inside the ARP header
+        assert!(parse_arp_reply(&packet[..packet.len() - 1]).is_none());
+    }
 }
@@ -0,0 +1,479 @@
+//! Classic BPF programs for capture filters.
+//!
+//! Programs use the `struct sock_filter` encoding, so a pcap expression can be
+//! compiled offline with `tcpdump -dd <expr>` (for an Ethernet interface) and
+//! loaded with [`BpfProgram::parse_dd`]. Programs are validated once and then
+//! interpreted per packet; loads past the end of the packet reject it, as in
+//! the kernel.
+
+use std::fmt;
+
+/// Maximum program length (BPF_MAXINSNS)
+const MAX_INSNS: usize = 4096;
+/// Number of scratch memory words (BPF_MEMWORDS)
+const MEM_WORDS: usize = 16;
+
+// Instruction classes
+const LD: u16 = 0x00;
+const LDX: u16 = 0x01;
+const ST: u16 = 0x02;
+const STX: u16 = 0x03;
+const ALU: u16 = 0x04;
+const JMP: u16 = 0x05;
+const RET: u16 = 0x06;
+const MISC: u16 = 0x07;
+
+// Load sizes
+const W: u16 = 0x00;
+const H: u16 = 0x08;
+const B: u16 = 0x10;
+
+// Load modes
+const IMM: u16 = 0x00;
+const ABS: u16 = 0x20;
+const IND: u16 = 0x40;
+const MEM: u16 = 0x60;
+const LEN: u16 = 0x80;
+const MSH: u16 = 0xa0;
+
+// ALU operations
+const ADD: u16 = 0x00;
+const SUB: u16 = 0x10;
+const MUL: u16 = 0x20;
+const DIV: u16 = 0x30;
+const OR: u16 = 0x40;
+const AND: u16 = 0x50;
+const LSH: u16 = 0x60;
+const RSH: u16 = 0x70;
+const NEG: u16 = 0x80;
+const MOD: u16 = 0x90;
+const XOR: u16 = 0xa0;
+
+// Jumps
+const JA: u16 = 0x00;
+const JEQ: u16 = 0x10;
+const JGT: u16 = 0x20;
+const JGE: u16 = 0x30;
+const JSET: u16 = 0x40;
+
+// Operand source
+const K: u16 = 0x00;
+const X: u16 = 0x08;
+// RET operand
+const RET_A: u16 = 0x10;
+
+// MISC operations
+const TAX: u16 = 0x00;
+const TXA: u16 = 0x80;
+
+/// One BPF instruction (`struct sock_filter`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BpfInsn {
+    /// Opcode
+    pub code: u16,
+    /// Jump offset if true
+    pub jt: u8,
+    /// Jump offset if false
+    pub jf: u8,
+    /// Generic operand
+    pub k: u32,
+}
+
+impl BpfInsn {
+    /// Create an instruction.
+    pub const fn new(code: u16, jt: u8, jf: u8, k: u32) -> Self {
+        Self { code, jt, jf, k }
+    }
+}
+
+/// Reason a BPF program was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BpfError {
+    /// Program is empty or longer than 4096 instructions
+    InvalidLength(usize),
+    /// Unknown opcode
+    InvalidOpcode { pc: usize, code: u16 },
+    /// Jump target past the end of the program
+    JumpOutOfRange { pc: usize },
+    /// Scratch memory index out of range
+    InvalidMemory { pc: usize },
+    /// Division or modulo by constant zero
+    DivisionByZero { pc: usize },
+    /// Last instruction is not a return
+    NoReturn,
+    /// `tcpdump -dd` text could not be parsed
+    Parse { line: usize },
+}
+
+impl fmt::Display for BpfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength(len) => write!(f, "invalid program length {}", len),
+            Self::InvalidOpcode { pc, code } => {
+                write!(f, "invalid opcode {:#06x} at {}", code, pc)
+            }
+            Self::JumpOutOfRange { pc } => write!(f, "jump out of range at {}", pc),
+            Self::InvalidMemory { pc } => write!(f, "invalid memory index at {}", pc),
+            Self::DivisionByZero { pc } => write!(f, "division by zero at {}", pc),
+            Self::NoReturn => write!(f, "program does not end with a return"),
+            Self::Parse { line } => write!(f, "cannot parse instruction on line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for BpfError {}
+
+/// A validated classic BPF program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BpfProgram {
+    insns: Vec<BpfInsn>,
+}
+
+impl BpfProgram {
+    /// Validate a program.
+    pub fn new(insns: Vec<BpfInsn>) -> Result<Self, BpfError> {
+        if insns.is_empty() || insns.len() > MAX_INSNS {
+            return Err(BpfError::InvalidLength(insns.len()));
+        }
+        for (pc, insn) in insns.iter().enumerate() {
+            validate(pc, insn, insns.len())?;
+        }
+        if insns.last().map(|insn| insn.code & 0x07) != Some(RET) {
+            return Err(BpfError::NoReturn);
+        }
+        Ok(Self { insns })
+    }
+
+    /// Parse the C array printed by `tcpdump -dd`.
+    ///
+    /// Each instruction is a `{ code, jt, jf, k }` group; numbers may be
+    /// decimal or `0x` hexadecimal.
+    pub fn parse_dd(text: &str) -> Result<Self, BpfError> {
+        let mut insns = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let parse_err = || BpfError::Parse { line: idx + 1 };
+            let body = line
+                .trim_end_matches(',')
+                .strip_prefix('{')
+                .and_then(|l| l.strip_suffix('}'))
+                .ok_or_else(parse_err)?;
+            let fields: Vec<u32> = body
+                .split(',')
+                .map(|f| parse_number(f.trim()))
+                .collect::<Option<_>>()
+                .ok_or_else(parse_err)?;
+            let [code, jt, jf, k] = fields[..] else {
+                return Err(parse_err());
+            };
+            let (Ok(code), Ok(jt), Ok(jf)) = (code.try_into(), jt.try_into(), jf.try_into()) else {
+                return Err(parse_err());
+            };
+            insns.push(BpfInsn { code, jt, jf, k });
+        }
+        Self::new(insns)
+    }
+
+    /// The program's instructions.
+    pub fn insns(&self) -> &[BpfInsn] {
+        &self.insns
+    }
+
+    /// Run the program on a packet.
+    ///
+    /// `packet` is the captured data and `wire_len` the original length
+    /// (`BPF_LEN`). Returns the number of bytes to keep; 0 rejects the packet.
+    pub fn run(&self, packet: &[u8], wire_len: u32) -> u32 {
+        let mut a: u32 = 0;
+        let mut x: u32 = 0;
+        let mut mem = [0u32; MEM_WORDS];
+        let mut pc = 0;
+        loop {
+            // Validation guarantees forward jumps within the program ending in RET
+            let insn = self.insns[pc];
+            pc += 1;
+            let k = insn.k;
+            match insn.code & 0x07 {
+                LD => {
+                    a = match insn.code & 0xe0 {
+                        IMM => k,
+                        ABS => match load(packet, k as usize, insn.code & 0x18) {
+                            Some(v) => v,
+                            None => return 0,
+                        },
+                        IND => {
+                            let Some(offset) = x.checked_add(k) else {
+                                return 0;
+                            };
+                            match load(packet, offset as usize, insn.code & 0x18) {
+                                Some(v) => v,
+                                None => return 0,
+                            }
+                        }
+                        MEM => mem[k as usize],
+                        _ => wire_len, // LEN
+                    }
+                }
+                LDX => {
+                    x = match insn.code & 0xe0 {
+                        IMM => k,
+                        MEM => mem[k as usize],
+                        LEN => wire_len,
+                        _ => match packet.get(k as usize) {
+                            // MSH: IPv4 header length
+                            Some(&b) => 4 * (b & 0x0f) as u32,
+                            None => return 0,
+                        },
+                    }
+                }
+                ST => mem[k as usize] = a,
+                STX => mem[k as usize] = x,
+                ALU => {
+                    let operand = if insn.code & X != 0 { x } else { k };
+                    a = match insn.code & 0xf0 {
+                        ADD => a.wrapping_add(operand),
+                        SUB => a.wrapping_sub(operand),
+                        MUL => a.wrapping_mul(operand),
+                        DIV => match a.checked_div(operand) {
+                            Some(v) => v,
+                            None => return 0,
+                        },
+                        MOD => match a.checked_rem(operand) {
+                            Some(v) => v,
+                            None => return 0,
+                        },
+                        OR => a | operand,
+                        AND => a & operand,
+                        XOR => a ^ operand,
+                        LSH => a.checked_shl(operand).unwrap_or(0),
+                        RSH => a.checked_shr(operand).unwrap_or(0),
+                        _ => a.wrapping_neg(), // NEG
+                    }
+                }
+                JMP => {
+                    let operand = if insn.code & X != 0 { x } else { k };
+                    let taken = match insn.code & 0xf0 {
+                        JA => {
+                            pc += k as usize;
+                            continue;
+                        }
+                        JEQ => a == operand,
+                        JGT => a > operand,
+                        JGE => a >= operand,
+                        _ => a & operand != 0, // JSET
+                    };
+                    pc += if taken { insn.jt } else { insn.jf } as usize;
+                }
+                RET => {
+                    return match insn.code & 0x18 {
+                        RET_A => a,
+                        X => x,
+                        _ => k,
+                    };
+                }
+                _ => {
+                    // MISC
+                    if insn.code & 0xf8 == TXA {
+                        a = x;
+                    } else {
+                        x = a;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check whether the program accepts a packet.
+    pub fn matches(&self, packet: &[u8], wire_len: u32) -> bool {
+        self.run(packet, wire_len) != 0
+    }
+}
+
+fn parse_number(s: &str) -> Option<u32> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// Big-endian load of 1, 2 or 4 bytes.
+fn load(packet: &[u8], offset: usize, size: u16) -> Option<u32> {
+    let len = match size {
+        W => 4,
+        H => 2,
+        _ => 1,
+    };
+    let bytes = packet.get(offset..offset.checked_add(len)?)?;
+    Some(bytes.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32))
+}
+
+fn validate(pc: usize, insn: &BpfInsn, len: usize) -> Result<(), BpfError> {
+    let invalid = || BpfError::InvalidOpcode {
+        pc,
+        code: insn.code,
+    };
+    let check_mem = || {
+        if (insn.k as usize) < MEM_WORDS {
+            Ok(())
+        } else {
+            Err(BpfError::InvalidMemory { pc })
+        }
+    };
+    let check_target = |offset: usize| {
+        if pc + 1 + offset < len {
+            Ok(())
+        } else {
+            Err(BpfError::JumpOutOfRange { pc })
+        }
+    };
+    let code = insn.code;
+    if code > 0xff {
+        return Err(invalid());
+    }
+    match code & 0x07 {
+        LD => match code & 0xe0 {
+            IMM | LEN if code & 0x18 == W => Ok(()),
+            ABS | IND if code & 0x18 != 0x18 => Ok(()),
+            MEM if code & 0x18 == W => check_mem(),
+            _ => Err(invalid()),
+        },
+        LDX => match code & 0xf8 {
+            c if c == IMM | W || c == LEN | W => Ok(()),
+            c if c == MEM | W => check_mem(),
+            c if c == MSH | B => Ok(()),
+            _ => Err(invalid()),
+        },
+        ST | STX if code & 0xf8 == 0 => check_mem(),
+        ALU => match code & 0xf0 {
+            NEG if code & X == 0 => Ok(()),
+            DIV | MOD if code & X == K && insn.k == 0 => Err(BpfError::DivisionByZero { pc }),
+            ADD | SUB | MUL | DIV | MOD | OR | AND | XOR | LSH | RSH => Ok(()),
+            _ => Err(invalid()),
+        },
+        JMP => match code & 0xf0 {
+            JA if code & X == 0 => {
+                if pc + 1 + insn.k as usize >= len {
+                    Err(BpfError::JumpOutOfRange { pc })
+                } else {
+                    Ok(())
+                }
+            }
+            JEQ | JGT | JGE | JSET => {
+                check_target(insn.jt as usize)?;
+                check_target(insn.jf as usize)
+            }
+            _ => Err(invalid()),
+        },
+        RET if matches!(code & 0xf8, 0x00 | X | RET_A) => Ok(()),
+        MISC if matches!(code & 0xf8, TAX | TXA) => Ok(()),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `ip and udp dst port 5678`
+    const UDP_DST_5678: &str = "
+        { 0x28, 0, 0, 0x0000000c },
+        { 0x15, 0, 6, 0x00000800 },
+        { 0x30, 0, 0, 0x00000017 },
+        { 0x15, 0, 4, 0x00000011 },
+        { 0xb1, 0, 0, 0x0000000e },
+        { 0x48, 0, 0, 0x00000010 },
+        { 0x15, 0, 1, 0x0000162e },
+        { 0x6, 0, 0, 0x00040000 },
+        { 0x6, 0, 0, 0x00000000 },
+    ";
+
+    /// Minimal Ethernet/IPv4/UDP frame (checksums are not inspected)
+    fn udp_frame(dst_port: u16) -> Vec<u8> {
+        let mut frame = vec![0u8; 14 + 20 + 8];
+        frame[12..14].copy_from_slice(&[0x08, 0x00]);
+        frame[14] = 0x45;
+        frame[23] = 17;
+        frame[34..36].copy_from_slice(&1234u16.to_be_bytes());
+        frame[36..38].copy_from_slice(&dst_port.to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn test_parse_and_run() {
+        let prog = BpfProgram::parse_dd(UDP_DST_5678).unwrap();
+        assert_eq!(prog.insns().len(), 9);
+
+        let frame = udp_frame(5678);
+        assert_eq!(prog.run(&frame, frame.len() as u32), 0x40000);
+        assert!(!prog.matches(&udp_frame(80), 42));
+
+        // TCP
+        let mut tcp = udp_frame(5678);
+        tcp[23] = 6;
+        assert!(!prog.matches(&tcp, 42));
+
+        // Truncated packet: out-of-bounds load rejects
+        assert!(!prog.matches(&frame[..30], 42));
+    }
+
+    #[test]
+    fn test_alu_and_scratch_memory() {
+        let prog = BpfProgram::new(vec![
+            BpfInsn::new(LD | LEN, 0, 0, 0),
+            BpfInsn::new(ST, 0, 0, 3),
+            BpfInsn::new(LDX | MEM, 0, 0, 3),
+            BpfInsn::new(ALU | MUL | X, 0, 0, 0),
+            BpfInsn::new(ALU | SUB | K, 0, 0, 1),
+            BpfInsn::new(RET | RET_A, 0, 0, 0),
+        ])
+        .unwrap();
+        assert_eq!(prog.run(&[], 10), 99);
+
+        // Division by X = 0 rejects
+        let prog = BpfProgram::new(vec![
+            BpfInsn::new(LD | IMM, 0, 0, 7),
+            BpfInsn::new(ALU | DIV | X, 0, 0, 0),
+            BpfInsn::new(RET | K, 0, 0, 1),
+        ])
+        .unwrap();
+        assert_eq!(prog.run(&[], 0), 0);
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(BpfProgram::new(vec![]), Err(BpfError::InvalidLength(0)));
+        assert_eq!(
+            BpfProgram::new(vec![BpfInsn::new(LD | IMM, 0, 0, 0)]),
+            Err(BpfError::NoReturn)
+        );
+        assert_eq!(
+            BpfProgram::new(vec![
+                BpfInsn::new(JMP | JEQ, 0, 5, 0),
+                BpfInsn::new(RET, 0, 0, 0),
+            ]),
+            Err(BpfError::JumpOutOfRange { pc: 0 })
+        );
+        assert_eq!(
+            BpfProgram::new(vec![BpfInsn::new(ST, 0, 0, 16), BpfInsn::new(RET, 0, 0, 0)]),
+            Err(BpfError::InvalidMemory { pc: 0 })
+        );
+        assert_eq!(
+            BpfProgram::new(vec![
+                BpfInsn::new(ALU | DIV | K, 0, 0, 0),
+                BpfInsn::new(RET, 0, 0, 0),
+            ]),
+            Err(BpfError::DivisionByZero { pc: 0 })
+        );
+        assert!(matches!(
+            BpfProgram::new(vec![BpfInsn::new(0xff, 0, 0, 0)]),
+            Err(BpfError::InvalidOpcode { pc: 0, .. })
+        ));
+        assert_eq!(
+            BpfProgram::parse_dd("{ 0x6, 0, 0 }"),
+            Err(BpfError::Parse { line: 1 })
+        );
+    }
+}
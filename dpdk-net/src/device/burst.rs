@@ -0,0 +1,277 @@
+//! RX burst and TX batch sizes of a [`DpdkDevice`](super::DpdkDevice).
+//!
+//! Small bursts keep per-poll work short, so the reactor gets back to
+//! egress and other tasks sooner; large ones amortize driver calls and cache
+//! misses over more packets. [`BurstConfig`] picks the sizes per device,
+//! and [`AdaptiveBurst`] lets the RX burst follow the load: it grows while
+//! bursts come back full, shrinks while they come back mostly empty, and
+//! jumps to fit the RX queue depth (`rte_eth_rx_queue_count`, or the ring
+//! length with software RSS) when the source can report it.
+
+/// Default number of packets read per RX burst.
+pub const DEFAULT_RX_BURST: usize = 64;
+
+/// Default number of packets the TX batch holds.
+pub const DEFAULT_TX_BATCH: usize = 256;
+
+/// Largest RX burst a device supports.
+pub const MAX_RX_BURST: usize = 512;
+
+/// Largest TX batch a device supports.
+pub const MAX_TX_BATCH: usize = 1024;
+
+/// Default number of polls between two queue depth samples.
+pub const DEFAULT_ADAPTIVE_SAMPLE_INTERVAL: u32 = 64;
+
+/// Bounds of an adaptive RX burst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveBurst {
+    /// Smallest burst
+    pub min: usize,
+    /// Largest burst
+    pub max: usize,
+}
+
+/// Burst and batch sizes for a [`DpdkDevice`](super::DpdkDevice).
+///
+/// Sizes are clamped to `1..=`[`MAX_RX_BURST`] and `1..=`[`MAX_TX_BATCH`].
+///
+/// # Example
+///
+/// ```
+/// # use dpdk_net::device::BurstConfig;
+/// // Answer quickly: read a few packets at a time, send each at once
+/// let latency = BurstConfig::new().rx_burst(8).tx_flush_threshold(1);
+/// // Move lots of data: large bursts, sized to the queue depth
+/// let bulk = BurstConfig::new().tx_batch(1024).adaptive(32, 512);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurstConfig {
+    /// Packets read per RX burst (the starting point when adaptive)
+    pub rx_burst: usize,
+    /// Packets the TX batch holds before `transmit` waits for the ring
+    pub tx_batch: usize,
+    /// Flush the TX batch to the ring as soon as it holds this many packets
+    /// (None = only when full, or at the next poll)
+    pub tx_flush_threshold: Option<usize>,
+    /// Tune the RX burst to the load (None = fixed `rx_burst`)
+    pub adaptive: Option<AdaptiveBurst>,
+    /// Polls between queue depth samples when adaptive (reading the depth
+    /// costs a driver call)
+    pub sample_interval: u32,
+}
+
+impl Default for BurstConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BurstConfig {
+    /// The default sizes: [`DEFAULT_RX_BURST`], [`DEFAULT_TX_BATCH`], no
+    /// early flush, fixed burst.
+    pub fn new() -> Self {
+        Self {
+            rx_burst: DEFAULT_RX_BURST,
+            tx_batch: DEFAULT_TX_BATCH,
+            tx_flush_threshold: None,
+            adaptive: None,
+            sample_interval: DEFAULT_ADAPTIVE_SAMPLE_INTERVAL,
+        }
+    }
+
+    /// Packets read per RX burst.
+    pub fn rx_burst(mut self, size: usize) -> Self {
+        self.rx_burst = size;
+        self
+    }
+
+    /// Packets the TX batch holds.
+    pub fn tx_batch(mut self, size: usize) -> Self {
+        self.tx_batch = size;
+        self
+    }
+
+    /// Flush the TX batch once it holds `count` packets (1 = every packet).
+    pub fn tx_flush_threshold(mut self, count: usize) -> Self {
+        self.tx_flush_threshold = Some(count);
+        self
+    }
+
+    /// Let the RX burst move between `min` and `max` with the load.
+    pub fn adaptive(mut self, min: usize, max: usize) -> Self {
+        self.adaptive = Some(AdaptiveBurst { min, max });
+        self
+    }
+
+    /// Sample the queue depth every `polls` polls once
+    /// [`adaptive`](Self::adaptive), in whichever order the two are set.
+    pub fn sample_interval(mut self, polls: u32) -> Self {
+        self.sample_interval = polls;
+        self
+    }
+}
+
+/// Current sizes of one device.
+pub(crate) struct BurstState {
+    rx_burst: usize,
+    tx_batch: usize,
+    tx_flush_threshold: usize,
+    adaptive: Option<AdaptiveBurst>,
+    sample_interval: u32,
+    /// Polls since the last depth sample
+    polls: u32,
+}
+
+impl BurstState {
+    pub(crate) fn new(config: BurstConfig) -> Self {
+        let tx_batch = config.tx_batch.clamp(1, MAX_TX_BATCH);
+        let adaptive = config.adaptive.map(|adaptive| {
+            let max = adaptive.max.clamp(1, MAX_RX_BURST);
+            AdaptiveBurst {
+                min: adaptive.min.clamp(1, max),
+                max,
+            }
+        });
+        let rx_burst = match adaptive {
+            Some(adaptive) => config.rx_burst.clamp(adaptive.min, adaptive.max),
+            None => config.rx_burst.clamp(1, MAX_RX_BURST),
+        };
+        Self {
+            rx_burst,
+            tx_batch,
+            tx_flush_threshold: config
+                .tx_flush_threshold
+                .map_or(tx_batch, |threshold| threshold.clamp(1, tx_batch)),
+            adaptive,
+            sample_interval: config.sample_interval.max(1),
+            polls: 0,
+        }
+    }
+
+    /// Packets to read in the next RX burst
+    #[inline]
+    pub(crate) fn rx_burst(&self) -> usize {
+        self.rx_burst
+    }
+
+    #[inline]
+    pub(crate) fn tx_batch(&self) -> usize {
+        self.tx_batch
+    }
+
+    #[inline]
+    pub(crate) fn tx_flush_threshold(&self) -> usize {
+        self.tx_flush_threshold
+    }
+
+    /// Adapt the RX burst after a poll that received `received` packets.
+    ///
+    /// `depth` is only called when a sample is due.
+    #[inline]
+    pub(crate) fn after_rx(&mut self, received: usize, depth: impl FnOnce() -> Option<usize>) {
+        let Some(adaptive) = self.adaptive else {
+            return;
+        };
+        self.polls += 1;
+        if self.polls >= self.sample_interval {
+            self.polls = 0;
+            if let Some(depth) = depth() {
+                // One burst for what just came and what is still waiting
+                self.rx_burst = (received + depth)
+                    .next_power_of_two()
+                    .clamp(adaptive.min, adaptive.max);
+                return;
+            }
+        }
+        if received >= self.rx_burst {
+            self.rx_burst = (self.rx_burst * 2).min(adaptive.max);
+        } else if received > 0 && received * 4 <= self.rx_burst {
+            // Idle polls say nothing about the size of the next burst
+            self.rx_burst = (self.rx_burst / 2).max(adaptive.min);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamping() {
+        let state = BurstState::new(BurstConfig::new());
+        assert_eq!(
+            (
+                state.rx_burst(),
+                state.tx_batch(),
+                state.tx_flush_threshold()
+            ),
+            (DEFAULT_RX_BURST, DEFAULT_TX_BATCH, DEFAULT_TX_BATCH)
+        );
+
+        let config = BurstConfig::new()
+            .rx_burst(0)
+            .tx_batch(4096)
+            .tx_flush_threshold(2048);
+        let state = BurstState::new(config);
+        assert_eq!(
+            (
+                state.rx_burst(),
+                state.tx_batch(),
+                state.tx_flush_threshold()
+            ),
+            (1, MAX_TX_BATCH, MAX_TX_BATCH)
+        );
+
+        let state = BurstState::new(BurstConfig::new().rx_burst(4).adaptive(16, 4096));
+        assert_eq!(state.rx_burst(), 16);
+    }
+
+    #[test]
+    fn test_adaptive_fill() {
+        let config = BurstConfig::new().rx_burst(32).adaptive(8, 128);
+        let mut state = BurstState::new(config);
+        // No depth: full bursts double, quarter-full ones halve
+        state.after_rx(32, || None);
+        assert_eq!(state.rx_burst(), 64);
+        state.after_rx(64, || None);
+        state.after_rx(128, || None);
+        assert_eq!(state.rx_burst(), 128);
+        state.after_rx(0, || None);
+        assert_eq!(state.rx_burst(), 128);
+        state.after_rx(32, || None);
+        assert_eq!(state.rx_burst(), 64);
+        state.after_rx(20, || None);
+        assert_eq!(state.rx_burst(), 64);
+        for _ in 0..4 {
+            state.after_rx(1, || None);
+        }
+        assert_eq!(state.rx_burst(), 8);
+    }
+
+    #[test]
+    fn test_adaptive_depth() {
+        let config = BurstConfig::new().adaptive(8, 256).sample_interval(2);
+        // Order doesn't matter
+        assert_eq!(
+            config,
+            BurstConfig::new().sample_interval(2).adaptive(8, 256)
+        );
+        let mut state = BurstState::new(config);
+        let mut sampled = 0;
+        state.after_rx(20, || {
+            sampled += 1;
+            Some(500)
+        });
+        assert_eq!((sampled, state.rx_burst()), (0, DEFAULT_RX_BURST));
+        // 10 received + 90 waiting: room for 100 in one burst
+        state.after_rx(10, || {
+            sampled += 1;
+            Some(90)
+        });
+        assert_eq!((sampled, state.rx_burst()), (1, 128));
+        state.after_rx(10, || None);
+        state.after_rx(0, || Some(0));
+        assert_eq!(state.rx_burst(), 8);
+    }
+}
@@ -0,0 +1,617 @@
+//! Packet capture for [`DpdkDevice`](super::DpdkDevice) traffic.
+//!
+//! A [`Capture`] is attached to a device with
+//! [`DpdkDevice::with_capture`](super::DpdkDevice::with_capture) and, while
+//! started, copies matching RX/TX frames into a pcapng stream or an in-memory
+//! ring. One capture can be shared by the devices of several queues; each
+//! port/queue becomes its own pcapng interface (`dpdk<port>q<queue>`) and
+//! packets carry the inbound/outbound direction flag.
+//!
+//! Frames are captured as smoltcp sees them: RX before checksum validation
+//! (so bad packets show up), TX before NIC offloads. Checksums left to the
+//! NIC are therefore not filled in, and TSO frames appear unsegmented.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::bpf::BpfProgram;
+
+/// Default snapshot length (same as tcpdump)
+pub const DEFAULT_SNAPLEN: u32 = 262144;
+
+// pcapng block types and options
+const SHB_TYPE: u32 = 0x0A0D_0D0A;
+const IDB_TYPE: u32 = 0x0000_0001;
+const EPB_TYPE: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+const LINKTYPE_ETHERNET: u16 = 1;
+const OPT_ENDOFOPT: u16 = 0;
+const IF_NAME: u16 = 2;
+const IF_TSRESOL: u16 = 9;
+const EPB_FLAGS: u16 = 2;
+/// if_tsresol value: 10^-9 (nanoseconds)
+const TSRESOL_NANOS: u8 = 9;
+
+/// Direction of a captured frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Received from the NIC
+    Rx,
+    /// Sent to the NIC
+    Tx,
+}
+
+impl Direction {
+    /// pcapng `epb_flags` direction bits
+    fn epb_flags(self) -> u32 {
+        match self {
+            Self::Rx => 0b01,
+            Self::Tx => 0b10,
+        }
+    }
+}
+
+/// Predicate deciding whether a frame is captured
+type FilterFn = dyn Fn(&[u8], Direction) -> bool + Send + Sync;
+
+/// Selects which frames are captured.
+#[derive(Clone)]
+pub enum CaptureFilter {
+    /// Classic BPF program; its return value also caps the snapshot length
+    Bpf(BpfProgram),
+    /// Arbitrary predicate on the frame
+    Fn(Arc<FilterFn>),
+}
+
+impl CaptureFilter {
+    /// Filter with a closure.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&[u8], Direction) -> bool + Send + Sync + 'static,
+    {
+        Self::Fn(Arc::new(f))
+    }
+
+    /// Number of bytes to keep, 0 to skip the frame.
+    fn snap(&self, frame: &[u8], direction: Direction) -> u32 {
+        match self {
+            Self::Bpf(prog) => prog.run(frame, frame.len() as u32),
+            Self::Fn(f) => {
+                if f(frame, direction) {
+                    u32::MAX
+                } else {
+                    0
+                }
+            }
+        }
+    }
+}
+
+impl From<BpfProgram> for CaptureFilter {
+    fn from(prog: BpfProgram) -> Self {
+        Self::Bpf(prog)
+    }
+}
+
+impl fmt::Debug for CaptureFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bpf(prog) => f.debug_tuple("Bpf").field(&prog.insns().len()).finish(),
+            Self::Fn(_) => f.write_str("Fn(..)"),
+        }
+    }
+}
+
+/// Capture configuration.
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    /// Maximum number of bytes kept per frame
+    pub snaplen: u32,
+    /// Capture received frames
+    pub rx: bool,
+    /// Capture transmitted frames
+    pub tx: bool,
+    /// Optional filter (None captures everything)
+    pub filter: Option<CaptureFilter>,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            snaplen: DEFAULT_SNAPLEN,
+            rx: true,
+            tx: true,
+            filter: None,
+        }
+    }
+}
+
+impl CaptureConfig {
+    /// Create a configuration capturing both directions in full.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the snapshot length.
+    pub fn snaplen(mut self, snaplen: u32) -> Self {
+        self.snaplen = snaplen;
+        self
+    }
+
+    /// Capture received frames (default: true).
+    pub fn rx(mut self, enabled: bool) -> Self {
+        self.rx = enabled;
+        self
+    }
+
+    /// Capture transmitted frames (default: true).
+    pub fn tx(mut self, enabled: bool) -> Self {
+        self.tx = enabled;
+        self
+    }
+
+    /// Set the filter.
+    pub fn filter(mut self, filter: impl Into<CaptureFilter>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+}
+
+/// A frame held by a ring capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedPacket {
+    /// Capture time since the Unix epoch
+    pub timestamp: Duration,
+    /// Port the frame was seen on
+    pub port_id: u16,
+    /// Queue the frame was seen on
+    pub queue_id: u16,
+    /// Direction
+    pub direction: Direction,
+    /// Length of the frame on the wire
+    pub orig_len: u32,
+    /// Captured bytes (at most the snapshot length)
+    pub data: Vec<u8>,
+}
+
+/// Capture counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CaptureStats {
+    /// Frames written to the sink
+    pub captured: u64,
+    /// Frames rejected by the filter
+    pub filtered: u64,
+    /// Frames lost: evicted from a full ring or failed to write
+    pub dropped: u64,
+}
+
+/// Minimal pcapng writer (one section, Ethernet interfaces).
+struct PcapngWriter<W: Write> {
+    out: W,
+    snaplen: u32,
+    interfaces: HashMap<(u16, u16), u32>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> PcapngWriter<W> {
+    /// Write the section header.
+    fn new(mut out: W, snaplen: u32) -> io::Result<Self> {
+        let mut buf = Vec::with_capacity(28);
+        buf.extend_from_slice(&SHB_TYPE.to_le_bytes());
+        buf.extend_from_slice(&28u32.to_le_bytes());
+        buf.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        buf.extend_from_slice(&1u16.to_le_bytes()); // major
+        buf.extend_from_slice(&0u16.to_le_bytes()); // minor
+        buf.extend_from_slice(&(-1i64).to_le_bytes()); // section length unknown
+        buf.extend_from_slice(&28u32.to_le_bytes());
+        out.write_all(&buf)?;
+        Ok(Self {
+            out,
+            snaplen,
+            interfaces: HashMap::new(),
+            buf,
+        })
+    }
+
+    /// Interface ID for a port/queue, writing its description block on first use.
+    fn interface(&mut self, port_id: u16, queue_id: u16) -> io::Result<u32> {
+        if let Some(&id) = self.interfaces.get(&(port_id, queue_id)) {
+            return Ok(id);
+        }
+        self.buf.clear();
+        self.buf.extend_from_slice(&IDB_TYPE.to_le_bytes());
+        self.buf.extend_from_slice(&0u32.to_le_bytes()); // patched below
+        self.buf.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+        self.buf.extend_from_slice(&0u16.to_le_bytes());
+        self.buf.extend_from_slice(&self.snaplen.to_le_bytes());
+        let name = format!("dpdk{}q{}", port_id, queue_id);
+        push_option(&mut self.buf, IF_NAME, name.as_bytes());
+        push_option(&mut self.buf, IF_TSRESOL, &[TSRESOL_NANOS]);
+        push_option(&mut self.buf, OPT_ENDOFOPT, &[]);
+        finish_block(&mut self.buf);
+        self.out.write_all(&self.buf)?;
+
+        let id = self.interfaces.len() as u32;
+        self.interfaces.insert((port_id, queue_id), id);
+        Ok(id)
+    }
+
+    fn write_packet(&mut self, pkt: &CapturedPacket) -> io::Result<()> {
+        let interface_id = self.interface(pkt.port_id, pkt.queue_id)?;
+        let ts = pkt.timestamp.as_nanos() as u64;
+        self.buf.clear();
+        self.buf.extend_from_slice(&EPB_TYPE.to_le_bytes());
+        self.buf.extend_from_slice(&0u32.to_le_bytes()); // patched below
+        self.buf.extend_from_slice(&interface_id.to_le_bytes());
+        self.buf
+            .extend_from_slice(&((ts >> 32) as u32).to_le_bytes());
+        self.buf.extend_from_slice(&(ts as u32).to_le_bytes());
+        self.buf
+            .extend_from_slice(&(pkt.data.len() as u32).to_le_bytes());
+        self.buf.extend_from_slice(&pkt.orig_len.to_le_bytes());
+        self.buf.extend_from_slice(&pkt.data);
+        pad4(&mut self.buf);
+        push_option(
+            &mut self.buf,
+            EPB_FLAGS,
+            &pkt.direction.epb_flags().to_le_bytes(),
+        );
+        push_option(&mut self.buf, OPT_ENDOFOPT, &[]);
+        finish_block(&mut self.buf);
+        self.out.write_all(&self.buf)
+    }
+}
+
+fn pad4(buf: &mut Vec<u8>) {
+    buf.resize(buf.len().next_multiple_of(4), 0);
+}
+
+fn push_option(buf: &mut Vec<u8>, code: u16, value: &[u8]) {
+    buf.extend_from_slice(&code.to_le_bytes());
+    buf.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    buf.extend_from_slice(value);
+    pad4(buf);
+}
+
+/// Append the trailing block length and patch the leading one.
+fn finish_block(buf: &mut Vec<u8>) {
+    let total = (buf.len() + 4) as u32;
+    buf.extend_from_slice(&total.to_le_bytes());
+    buf[4..8].copy_from_slice(&total.to_le_bytes());
+}
+
+/// Write ring-captured packets as a pcapng stream.
+pub fn write_pcapng<W: Write>(out: W, packets: &[CapturedPacket]) -> io::Result<()> {
+    // Snaplen 0: no limit recorded
+    let mut writer = PcapngWriter::new(out, 0)?;
+    for pkt in packets {
+        writer.write_packet(pkt)?;
+    }
+    writer.out.flush()
+}
+
+enum Sink {
+    Pcapng(PcapngWriter<Box<dyn Write + Send>>),
+    Ring {
+        packets: VecDeque<CapturedPacket>,
+        capacity: usize,
+    },
+}
+
+struct Shared {
+    running: AtomicBool,
+    config: CaptureConfig,
+    sink: Mutex<Sink>,
+    captured: AtomicU64,
+    filtered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// A packet capture, shared by the devices it is attached to.
+///
+/// Captures are created stopped; call [`start`](Self::start) to begin.
+/// A stopped capture costs one atomic load per frame.
+#[derive(Clone)]
+pub struct Capture {
+    shared: Arc<Shared>,
+}
+
+impl Capture {
+    fn with_sink(sink: Sink, config: CaptureConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                running: AtomicBool::new(false),
+                config,
+                sink: Mutex::new(sink),
+                captured: AtomicU64::new(0),
+                filtered: AtomicU64::new(0),
+                dropped: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Capture into a pcapng stream.
+    ///
+    /// Frames are written from the polling thread, so prefer a buffered writer.
+    pub fn pcapng<W>(out: W, config: CaptureConfig) -> io::Result<Self>
+    where
+        W: Write + Send + 'static,
+    {
+        let writer = PcapngWriter::new(Box::new(out) as Box<dyn Write + Send>, config.snaplen)?;
+        Ok(Self::with_sink(Sink::Pcapng(writer), config))
+    }
+
+    /// Capture into a pcapng file (created or truncated).
+    pub fn pcapng_file(path: impl AsRef<Path>, config: CaptureConfig) -> io::Result<Self> {
+        Self::pcapng(BufWriter::new(File::create(path)?), config)
+    }
+
+    /// Capture into an in-memory ring keeping the latest `capacity` frames.
+    pub fn ring(capacity: usize, config: CaptureConfig) -> Self {
+        let sink = Sink::Ring {
+            packets: VecDeque::with_capacity(capacity),
+            capacity,
+        };
+        Self::with_sink(sink, config)
+    }
+
+    /// Start capturing.
+    pub fn start(&self) {
+        self.shared.running.store(true, Ordering::Relaxed);
+    }
+
+    /// Stop capturing. Frames already captured are kept.
+    pub fn stop(&self) {
+        self.shared.running.store(false, Ordering::Relaxed);
+    }
+
+    /// Check whether the capture is running.
+    pub fn is_running(&self) -> bool {
+        self.shared.running.load(Ordering::Relaxed)
+    }
+
+    /// Get the capture counters.
+    pub fn stats(&self) -> CaptureStats {
+        CaptureStats {
+            captured: self.shared.captured.load(Ordering::Relaxed),
+            filtered: self.shared.filtered.load(Ordering::Relaxed),
+            dropped: self.shared.dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Take the frames held by a ring capture (empty for pcapng captures).
+    pub fn drain(&self) -> Vec<CapturedPacket> {
+        match &mut *self.shared.sink.lock().unwrap() {
+            Sink::Ring { packets, .. } => packets.drain(..).collect(),
+            Sink::Pcapng(_) => Vec::new(),
+        }
+    }
+
+    /// Flush a pcapng capture's writer.
+    pub fn flush(&self) -> io::Result<()> {
+        match &mut *self.shared.sink.lock().unwrap() {
+            Sink::Pcapng(writer) => writer.out.flush(),
+            Sink::Ring { .. } => Ok(()),
+        }
+    }
+
+    /// Record a frame seen on `port_id`/`queue_id`.
+    #[inline]
+    pub(crate) fn record(&self, port_id: u16, queue_id: u16, direction: Direction, frame: &[u8]) {
+        if self.shared.running.load(Ordering::Relaxed) {
+            self.record_started(port_id, queue_id, direction, frame);
+        }
+    }
+
+    fn record_started(&self, port_id: u16, queue_id: u16, direction: Direction, frame: &[u8]) {
+        let config = &self.shared.config;
+        let wanted = match direction {
+            Direction::Rx => config.rx,
+            Direction::Tx => config.tx,
+        };
+        if !wanted {
+            return;
+        }
+        let mut keep = (config.snaplen as usize).min(frame.len());
+        if let Some(filter) = &config.filter {
+            let snap = filter.snap(frame, direction);
+            if snap == 0 {
+                self.shared.filtered.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            keep = keep.min(snap as usize);
+        }
+        let pkt = CapturedPacket {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+            port_id,
+            queue_id,
+            direction,
+            orig_len: frame.len() as u32,
+            data: frame[..keep].to_vec(),
+        };
+
+        match &mut *self.shared.sink.lock().unwrap() {
+            Sink::Pcapng(writer) => {
+                if let Err(e) = writer.write_packet(&pkt) {
+                    tracing::warn!(error = %e, "Failed to write captured frame");
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            }
+            Sink::Ring { packets, capacity } => {
+                if *capacity == 0 {
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                if packets.len() == *capacity {
+                    packets.pop_front();
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                packets.push_back(pkt);
+            }
+        }
+        self.shared.captured.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A capture bound to one device queue.
+pub(crate) struct CaptureTap {
+    capture: Capture,
+    port_id: u16,
+    queue_id: u16,
+}
+
+impl CaptureTap {
+    pub(crate) fn new(capture: Capture, port_id: u16, queue_id: u16) -> Self {
+        Self {
+            capture,
+            port_id,
+            queue_id,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn rx(&self, frame: &[u8]) {
+        self.capture
+            .record(self.port_id, self.queue_id, Direction::Rx, frame);
+    }
+
+    #[inline]
+    pub(crate) fn tx(&self, frame: &[u8]) {
+        self.capture
+            .record(self.port_id, self.queue_id, Direction::Tx, frame);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::bpf::BpfInsn;
+
+    fn frame(len: usize, tag: u8) -> Vec<u8> {
+        vec![tag; len]
+    }
+
+    fn read_u32(buf: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_ring_start_stop_and_eviction() {
+        let capture = Capture::ring(2, CaptureConfig::new().snaplen(4));
+        capture.record(0, 1, Direction::Rx, &frame(10, 1));
+        assert_eq!(capture.stats(), CaptureStats::default());
+
+        capture.start();
+        for tag in 1..=3 {
+            capture.record(0, 1, Direction::Rx, &frame(10, tag));
+        }
+        capture.stop();
+        capture.record(0, 1, Direction::Tx, &frame(10, 4));
+
+        let stats = capture.stats();
+        assert_eq!((stats.captured, stats.dropped), (3, 1));
+        let packets = capture.drain();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].data, vec![2; 4]);
+        assert_eq!(packets[0].orig_len, 10);
+        assert_eq!(packets[1].queue_id, 1);
+        assert!(capture.drain().is_empty());
+    }
+
+    #[test]
+    fn test_direction_and_filters() {
+        let config = CaptureConfig::new()
+            .rx(false)
+            .filter(CaptureFilter::new(|frame, _| frame[0] == 7));
+        let capture = Capture::ring(16, config);
+        capture.start();
+        capture.record(0, 0, Direction::Rx, &frame(8, 7));
+        capture.record(0, 0, Direction::Tx, &frame(8, 7));
+        capture.record(0, 0, Direction::Tx, &frame(8, 8));
+        let stats = capture.stats();
+        assert_eq!((stats.captured, stats.filtered), (1, 1));
+        assert_eq!(capture.drain()[0].direction, Direction::Tx);
+
+        // BPF return value caps the snapshot: "ret #3" for frames longer than 5
+        let prog = BpfProgram::new(vec![
+            BpfInsn::new(0x80, 0, 0, 0), // ld len
+            BpfInsn::new(0x25, 0, 1, 5), // jgt #5
+            BpfInsn::new(0x06, 0, 0, 3), // ret #3
+            BpfInsn::new(0x06, 0, 0, 0), // ret #0
+        ])
+        .unwrap();
+        let capture = Capture::ring(16, CaptureConfig::new().filter(prog));
+        capture.start();
+        capture.record(0, 0, Direction::Rx, &frame(4, 1));
+        capture.record(0, 0, Direction::Rx, &frame(9, 1));
+        let packets = capture.drain();
+        assert_eq!(packets.len(), 1);
+        assert_eq!((packets[0].data.len(), packets[0].orig_len), (3, 9));
+    }
+
+    #[test]
+    fn test_pcapng_layout() {
+        let packets = vec![
+            CapturedPacket {
+                timestamp: Duration::from_nanos(0x1_0000_0002),
+                port_id: 0,
+                queue_id: 0,
+                direction: Direction::Rx,
+                orig_len: 60,
+                data: frame(5, 0xaa),
+            },
+            CapturedPacket {
+                timestamp: Duration::from_nanos(3),
+                port_id: 0,
+                queue_id: 2,
+                direction: Direction::Tx,
+                orig_len: 8,
+                data: frame(8, 0xbb),
+            },
+        ];
+        let mut out = Vec::new();
+        write_pcapng(&mut out, &packets).unwrap();
+
+        // Walk the blocks: SHB, IDB(q0), EPB, IDB(q2), EPB
+        let mut blocks = Vec::new();
+        let mut offset = 0;
+        while offset < out.len() {
+            let len = read_u32(&out, offset + 4) as usize;
+            assert_eq!(len % 4, 0);
+            assert_eq!(read_u32(&out, offset + len - 4) as usize, len);
+            blocks.push((read_u32(&out, offset), offset));
+            offset += len;
+        }
+        assert_eq!(offset, out.len());
+        let types: Vec<u32> = blocks.iter().map(|&(t, _)| t).collect();
+        assert_eq!(types, [SHB_TYPE, IDB_TYPE, EPB_TYPE, IDB_TYPE, EPB_TYPE]);
+        assert_eq!(read_u32(&out, 8), BYTE_ORDER_MAGIC);
+
+        let idb = blocks[1].1;
+        assert_eq!(&out[idb + 8..idb + 10], &LINKTYPE_ETHERNET.to_le_bytes());
+        assert_eq!(&out[idb + 20..idb + 26], b"dpdk0q");
+
+        let epb = blocks[2].1;
+        assert_eq!(read_u32(&out, epb + 8), 0); // interface
+        assert_eq!(read_u32(&out, epb + 12), 1); // ts high
+        assert_eq!(read_u32(&out, epb + 16), 2); // ts low
+        assert_eq!(read_u32(&out, epb + 20), 5); // captured
+        assert_eq!(read_u32(&out, epb + 24), 60); // original
+        // Data padded to 8 bytes, then epb_flags = inbound
+        assert_eq!(&out[epb + 36..epb + 40], &[2, 0, 4, 0]);
+        assert_eq!(read_u32(&out, epb + 40), 0b01);
+
+        let epb = blocks[4].1;
+        assert_eq!(read_u32(&out, epb + 8), 1);
+        assert_eq!(read_u32(&out, epb + 40), 0b10);
+    }
+}
@@ -0,0 +1,321 @@
+//! Checksum offload glue between DPDK mbufs and smoltcp.
+//!
+//! smoltcp's [`ChecksumCapabilities`] are per device, not per packet. When RX
+//! verification is offloaded we turn smoltcp's verification off and check the
+//! NIC's verdict in `ol_flags` here instead: bad packets are dropped, and
+//! packets the NIC could not classify are verified in software.
+
+use smoltcp::phy::{Checksum, ChecksumCapabilities};
+use smoltcp::wire::{
+    EthernetFrame, EthernetProtocol, IpAddress, IpProtocol, Ipv4Packet, Ipv6Packet, TcpPacket,
+    UdpPacket,
+};
+
+use crate::api::rte::eth::ChecksumOffload;
+use crate::api::rte::mbuf::{Mbuf, ol_flags};
+
+/// Ethernet header length (no VLAN tag)
+pub(super) const ETH_HDR_LEN: usize = 14;
+
+/// IPv6 fixed header length
+const IPV6_HDR_LEN: u16 = 40;
+
+/// Map per-direction offload into smoltcp's checksum behavior.
+fn checksum_mode(rx_offloaded: bool, tx_offloaded: bool) -> Checksum {
+    match (rx_offloaded, tx_offloaded) {
+        (false, false) => Checksum::Both,
+        (true, false) => Checksum::Tx,
+        (false, true) => Checksum::Rx,
+        (true, true) => Checksum::None,
+    }
+}
+
+/// smoltcp checksum capabilities for the given offloads.
+pub(crate) fn checksum_capabilities(offload: &ChecksumOffload) -> ChecksumCapabilities {
+    let mut caps = ChecksumCapabilities::default();
+    caps.ipv4 = checksum_mode(offload.rx_ipv4, offload.tx_ipv4);
+    caps.tcp = checksum_mode(offload.rx_tcp, offload.tx_tcp);
+    caps.udp = checksum_mode(offload.rx_udp, offload.tx_udp);
+    caps
+}
+
+/// Check a received packet whose checksum verification smoltcp skips.
+///
+/// `flags` are the mbuf's `ol_flags`, `frame` the whole (linear) packet.
+/// Returns `false` if the packet should be dropped.
+pub(crate) fn rx_checksum_ok(flags: u64, frame: &[u8], offload: &ChecksumOffload) -> bool {
+    let ip_status = flags & ol_flags::RX_IP_CKSUM_MASK;
+    let l4_status = flags & ol_flags::RX_L4_CKSUM_MASK;
+    if ip_status == ol_flags::RX_IP_CKSUM_BAD || l4_status == ol_flags::RX_L4_CKSUM_BAD {
+        return false;
+    }
+    // NONE: checksum field is not valid but the NIC checked data integrity
+    let ip_verified =
+        ip_status == ol_flags::RX_IP_CKSUM_GOOD || ip_status == ol_flags::RX_IP_CKSUM_NONE;
+    let l4_verified =
+        l4_status == ol_flags::RX_L4_CKSUM_GOOD || l4_status == ol_flags::RX_L4_CKSUM_NONE;
+    if ip_verified && l4_verified {
+        return true;
+    }
+    verify_sw(frame, offload, !ip_verified, !l4_verified)
+}
+
+/// Software verification for the checksums smoltcp was told to skip.
+///
+/// Malformed packets pass; smoltcp rejects them when parsing. So do IPv6
+/// packets with extension headers, which smoltcp does not use for TCP/UDP.
+fn verify_sw(frame: &[u8], offload: &ChecksumOffload, check_ip: bool, check_l4: bool) -> bool {
+    let Ok(eth) = EthernetFrame::new_checked(frame) else {
+        return true;
+    };
+    match eth.ethertype() {
+        EthernetProtocol::Ipv4 => {
+            let Ok(ip) = Ipv4Packet::new_checked(eth.payload()) else {
+                return true;
+            };
+            if check_ip && offload.rx_ipv4 && !ip.verify_checksum() {
+                return false;
+            }
+            let (src, dst) = (ip.src_addr().into(), ip.dst_addr().into());
+            !check_l4 || verify_l4(ip.next_header(), ip.payload(), &src, &dst, offload)
+        }
+        // No header checksum: only L4 to check
+        EthernetProtocol::Ipv6 => {
+            let Ok(ip) = Ipv6Packet::new_checked(eth.payload()) else {
+                return true;
+            };
+            let (src, dst) = (ip.src_addr().into(), ip.dst_addr().into());
+            !check_l4 || verify_l4(ip.next_header(), ip.payload(), &src, &dst, offload)
+        }
+        _ => true,
+    }
+}
+
+/// Verify an offloaded TCP/UDP checksum against the IP pseudo-header.
+fn verify_l4(
+    protocol: IpProtocol,
+    payload: &[u8],
+    src: &IpAddress,
+    dst: &IpAddress,
+    offload: &ChecksumOffload,
+) -> bool {
+    match protocol {
+        IpProtocol::Tcp if offload.rx_tcp => TcpPacket::new_checked(payload)
+            .map(|tcp| tcp.verify_checksum(src, dst))
+            .unwrap_or(true),
+        IpProtocol::Udp if offload.rx_udp => UdpPacket::new_checked(payload)
+            .map(|udp| udp.verify_checksum(src, dst))
+            .unwrap_or(true),
+        _ => true,
+    }
+}
+
+/// Compute TX offload flags and L3 header length for an outgoing frame.
+///
+/// Returns `None` if nothing is offloaded for this frame. IPv6 packets with
+/// extension headers are left alone: smoltcp only emits those for ICMPv6.
+fn tx_offload_flags(frame: &[u8], offload: &ChecksumOffload) -> Option<(u64, u16)> {
+    if frame.len() < ETH_HDR_LEN + 20 {
+        return None;
+    }
+    let ip = &frame[ETH_HDR_LEN..];
+    let (ip_flag, l3_len, protocol) = match frame[12..14] {
+        [0x08, 0x00] => (ol_flags::TX_IPV4, ((ip[0] & 0x0f) as u16) * 4, ip[9]),
+        [0x86, 0xdd] if ip.len() >= IPV6_HDR_LEN as usize => {
+            (ol_flags::TX_IPV6, IPV6_HDR_LEN, ip[6])
+        }
+        _ => return None,
+    };
+    let mut flags = 0;
+    if offload.tx_ipv4 && ip_flag == ol_flags::TX_IPV4 {
+        flags |= ol_flags::TX_IP_CKSUM;
+    }
+    match protocol {
+        6 if offload.tx_tcp => flags |= ol_flags::TX_TCP_CKSUM,
+        17 if offload.tx_udp => flags |= ol_flags::TX_UDP_CKSUM,
+        _ => {}
+    }
+    (flags != 0).then_some((ip_flag | flags, l3_len))
+}
+
+/// Request TX checksum offload for a frame smoltcp has just written.
+///
+/// smoltcp left the offloaded checksum fields zeroed; the NIC fills them in.
+pub(crate) fn prepare_tx_offload(mbuf: &mut Mbuf, offload: &ChecksumOffload) {
+    let Some((flags, l3_len)) = tx_offload_flags(mbuf.data(), offload) else {
+        return;
+    };
+    mbuf.set_tx_offload(flags, ETH_HDR_LEN as u16, l3_len, 0);
+    mbuf.prepare_tx_cksum();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smoltcp::phy::ChecksumCapabilities;
+    use smoltcp::wire::{
+        EthernetAddress, EthernetRepr, Ipv4Address, Ipv4Repr, Ipv6Address, Ipv6Repr, UdpRepr,
+    };
+
+    const SRC: Ipv4Address = Ipv4Address::new(10, 0, 0, 1);
+    const DST: Ipv4Address = Ipv4Address::new(10, 0, 0, 2);
+    const SRC6: Ipv6Address = Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 1);
+    const DST6: Ipv6Address = Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 2);
+
+    /// Build an Ethernet/IPv4/UDP frame with valid checksums.
+    fn udp_frame(payload: &[u8]) -> Vec<u8> {
+        let udp = UdpRepr {
+            src_port: 1234,
+            dst_port: 5678,
+        };
+        let ip = Ipv4Repr {
+            src_addr: SRC,
+            dst_addr: DST,
+            next_header: IpProtocol::Udp,
+            payload_len: 8 + payload.len(),
+            hop_limit: 64,
+        };
+        let eth = EthernetRepr {
+            src_addr: EthernetAddress([0x02, 0, 0, 0, 0, 1]),
+            dst_addr: EthernetAddress([0x02, 0, 0, 0, 0, 2]),
+            ethertype: EthernetProtocol::Ipv4,
+        };
+        let caps = ChecksumCapabilities::default();
+        let mut buf = vec![0u8; ETH_HDR_LEN + 20 + 8 + payload.len()];
+        let mut frame = EthernetFrame::new_unchecked(&mut buf);
+        eth.emit(&mut frame);
+        let mut ip_packet = Ipv4Packet::new_unchecked(frame.payload_mut());
+        ip.emit(&mut ip_packet, &caps);
+        let mut udp_packet = UdpPacket::new_unchecked(ip_packet.payload_mut());
+        udp.emit(
+            &mut udp_packet,
+            &IpAddress::Ipv4(SRC),
+            &IpAddress::Ipv4(DST),
+            payload.len(),
+            |p| p.copy_from_slice(payload),
+            &caps,
+        );
+        buf
+    }
+
+    /// Build an Ethernet/IPv6/UDP frame with a valid checksum.
+    fn udp6_frame(payload: &[u8]) -> Vec<u8> {
+        let udp = UdpRepr {
+            src_port: 1234,
+            dst_port: 5678,
+        };
+        let ip = Ipv6Repr {
+            src_addr: SRC6,
+            dst_addr: DST6,
+            next_header: IpProtocol::Udp,
+            payload_len: 8 + payload.len(),
+            hop_limit: 64,
+        };
+        let eth = EthernetRepr {
+            src_addr: EthernetAddress([0x02, 0, 0, 0, 0, 1]),
+            dst_addr: EthernetAddress([0x02, 0, 0, 0, 0, 2]),
+            ethertype: EthernetProtocol::Ipv6,
+        };
+        let caps = ChecksumCapabilities::default();
+        let mut buf = vec![0u8; ETH_HDR_LEN + 40 + 8 + payload.len()];
+        let mut frame = EthernetFrame::new_unchecked(&mut buf);
+        eth.emit(&mut frame);
+        let mut ip_packet = Ipv6Packet::new_unchecked(frame.payload_mut());
+        ip.emit(&mut ip_packet);
+        let mut udp_packet = UdpPacket::new_unchecked(ip_packet.payload_mut());
+        udp.emit(
+            &mut udp_packet,
+            &IpAddress::Ipv6(SRC6),
+            &IpAddress::Ipv6(DST6),
+            payload.len(),
+            |p| p.copy_from_slice(payload),
+            &caps,
+        );
+        buf
+    }
+
+    fn all_offloaded() -> ChecksumOffload {
+        ChecksumOffload::from_offloads(u64::MAX, u64::MAX)
+    }
+
+    #[test]
+    fn test_checksum_capabilities() {
+        let caps = checksum_capabilities(&ChecksumOffload::default());
+        assert!(caps.ipv4.rx() && caps.ipv4.tx());
+
+        let offload = ChecksumOffload {
+            rx_tcp: true,
+            tx_udp: true,
+            ..Default::default()
+        };
+        let caps = checksum_capabilities(&offload);
+        assert!(!caps.tcp.rx() && caps.tcp.tx());
+        assert!(caps.udp.rx() && !caps.udp.tx());
+        assert!(caps.ipv4.rx() && caps.ipv4.tx());
+    }
+
+    #[test]
+    fn test_verify_sw() {
+        let mut frame = udp_frame(b"hello");
+        assert!(verify_sw(&frame, &all_offloaded(), true, true));
+
+        // Corrupt the payload: UDP checksum no longer matches
+        let last = frame.len() - 1;
+        frame[last] ^= 0xff;
+        assert!(!verify_sw(&frame, &all_offloaded(), true, true));
+        // Not checked if L4 verification was not offloaded
+        assert!(verify_sw(&frame, &ChecksumOffload::default(), true, true));
+        assert!(verify_sw(&frame, &all_offloaded(), true, false));
+    }
+
+    #[test]
+    fn test_verify_sw_ipv6() {
+        let mut frame = udp6_frame(b"hello");
+        assert!(verify_sw(&frame, &all_offloaded(), true, true));
+
+        let last = frame.len() - 1;
+        frame[last] ^= 0xff;
+        assert!(!verify_sw(&frame, &all_offloaded(), true, true));
+        assert!(verify_sw(&frame, &ChecksumOffload::default(), true, true));
+    }
+
+    #[test]
+    fn test_tx_offload_flags() {
+        let frame = udp_frame(b"hello");
+        let offload = ChecksumOffload {
+            tx_udp: true,
+            ..Default::default()
+        };
+        let (flags, l3_len) = tx_offload_flags(&frame, &offload).unwrap();
+        assert_eq!(l3_len, 20);
+        assert_eq!(flags, ol_flags::TX_IPV4 | ol_flags::TX_UDP_CKSUM);
+
+        // Only TCP offloaded: nothing to do for UDP
+        let offload = ChecksumOffload {
+            tx_tcp: true,
+            ..Default::default()
+        };
+        assert_eq!(tx_offload_flags(&frame, &offload), None);
+
+        // Non-IPv4 frame (ARP)
+        let mut arp = frame.clone();
+        arp[12..14].copy_from_slice(&[0x08, 0x06]);
+        assert_eq!(tx_offload_flags(&arp, &all_offloaded()), None);
+    }
+
+    #[test]
+    fn test_tx_offload_flags_ipv6() {
+        let frame = udp6_frame(b"hello");
+        let (flags, l3_len) = tx_offload_flags(&frame, &all_offloaded()).unwrap();
+        assert_eq!(l3_len, 40);
+        // No IP header checksum to offload
+        assert_eq!(flags, ol_flags::TX_IPV6 | ol_flags::TX_UDP_CKSUM);
+
+        let offload = ChecksumOffload {
+            tx_ipv4: true,
+            tx_tcp: true,
+            ..Default::default()
+        };
+        assert_eq!(tx_offload_flags(&frame, &offload), None);
+    }
+}
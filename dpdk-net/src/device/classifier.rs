@@ -0,0 +1,354 @@
+//! Ingress classification ahead of smoltcp.
+//!
+//! A [`Classifier`] installed with
+//! [`DpdkDevice::with_classifier`](super::DpdkDevice::with_classifier) sees
+//! every packet of an RX burst (after VLAN filtering, before anything else
+//! looks at it) and returns a [`Verdict`]: hand it on to the stack, drop it,
+//! or divert it to a [`DivertQueue`] where other code picks up the owned
+//! mbuf. That covers ACLs, L2 protocols smoltcp does not speak (LLDP, LACP),
+//! and flows served by a custom fast path.
+//!
+//! Classifiers run on the device's thread for every packet, so they should
+//! stay cheap: [`Packet`] parses the 5-tuple only when asked.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use arrayvec::ArrayVec;
+
+use crate::api::rte::mbuf::{Mbuf, ol_flags};
+
+use super::vlan;
+
+/// Default number of packets a [`DivertQueue`] holds.
+pub const DEFAULT_DIVERT_QUEUE_LEN: usize = 1024;
+
+/// What to do with a received packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Pass it on to smoltcp (and the device's other RX stages)
+    Accept,
+    /// Free it
+    Drop,
+    /// Move it to the device's [`DivertQueue`] (dropped if there is none)
+    Divert,
+}
+
+/// Addresses, ports and protocol of an IP packet.
+///
+/// Ports are 0 for protocols without them and for non-first fragments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FiveTuple {
+    /// IP protocol number (6 = TCP, 17 = UDP, ...)
+    pub protocol: u8,
+    /// Sender's address
+    pub src_addr: IpAddr,
+    /// Sender's port
+    pub src_port: u16,
+    /// Receiver's address (usually ours)
+    pub dst_addr: IpAddr,
+    /// Receiver's port
+    pub dst_port: u16,
+}
+
+impl FiveTuple {
+    /// Parse an Ethernet frame carrying IPv4 or IPv6, behind an 802.1Q tag
+    /// or not.
+    ///
+    /// IPv6 extension headers are not followed; the protocol is then the
+    /// first next-header value and the ports are 0.
+    pub fn parse(frame: &[u8]) -> Option<Self> {
+        const TCP: u8 = 6;
+        const UDP: u8 = 17;
+        const SCTP: u8 = 132;
+
+        let (ethertype, l2_len) = vlan::ethertype(frame)?;
+        let ip = frame.get(l2_len..)?;
+        let (protocol, src_addr, dst_addr, l4) = match ethertype {
+            0x0800 => {
+                if ip.len() < 20 {
+                    return None;
+                }
+                let ihl = (ip[0] & 0x0f) as usize * 4;
+                let src: [u8; 4] = ip[12..16].try_into().ok()?;
+                let dst: [u8; 4] = ip[16..20].try_into().ok()?;
+                // Later fragments carry no L4 header
+                let first = u16::from_be_bytes([ip[6], ip[7]]) & 0x1fff == 0;
+                let l4 = if first { ip.get(ihl..) } else { None };
+                (
+                    ip[9],
+                    IpAddr::V4(Ipv4Addr::from(src)),
+                    IpAddr::V4(Ipv4Addr::from(dst)),
+                    l4,
+                )
+            }
+            0x86dd => {
+                if ip.len() < 40 {
+                    return None;
+                }
+                let src: [u8; 16] = ip[8..24].try_into().ok()?;
+                let dst: [u8; 16] = ip[24..40].try_into().ok()?;
+                (
+                    ip[6],
+                    IpAddr::V6(Ipv6Addr::from(src)),
+                    IpAddr::V6(Ipv6Addr::from(dst)),
+                    Some(&ip[40..]),
+                )
+            }
+            _ => return None,
+        };
+        let (src_port, dst_port) = match l4 {
+            Some(l4) if matches!(protocol, TCP | UDP | SCTP) && l4.len() >= 4 => (
+                u16::from_be_bytes([l4[0], l4[1]]),
+                u16::from_be_bytes([l4[2], l4[3]]),
+            ),
+            _ => (0, 0),
+        };
+        Some(Self {
+            protocol,
+            src_addr,
+            src_port,
+            dst_addr,
+            dst_port,
+        })
+    }
+}
+
+/// A received packet as a [`Classifier`] sees it.
+pub struct Packet<'a> {
+    mbuf: &'a Mbuf,
+}
+
+impl<'a> Packet<'a> {
+    /// Frame data of the first segment (headers of multi-segment packets
+    /// are in the first segment)
+    pub fn data(&self) -> &'a [u8] {
+        self.mbuf.data()
+    }
+
+    /// Length of the whole packet
+    pub fn len(&self) -> usize {
+        self.mbuf.pkt_len()
+    }
+
+    /// Whether the packet has no data
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Ethertype of the payload, skipping an 802.1Q tag
+    pub fn ethertype(&self) -> Option<u16> {
+        vlan::ethertype(self.data()).map(|(ethertype, _)| ethertype)
+    }
+
+    /// VLAN ID, whether the tag was stripped (by the NIC or a VLAN
+    /// sub-interface) or is still in the data
+    pub fn vlan_id(&self) -> Option<u16> {
+        if self.mbuf.ol_flags() & ol_flags::RX_VLAN_STRIPPED != 0 {
+            return Some(self.mbuf.vlan_tci() & 0x0fff);
+        }
+        vlan::vlan_id(self.data())
+    }
+
+    /// Addresses, ports and protocol, for IP packets
+    pub fn five_tuple(&self) -> Option<FiveTuple> {
+        FiveTuple::parse(self.data())
+    }
+
+    /// RSS hash the NIC computed, if it stored one
+    pub fn rss_hash(&self) -> Option<u32> {
+        self.mbuf.rss_hash()
+    }
+
+    /// The underlying mbuf
+    pub fn mbuf(&self) -> &'a Mbuf {
+        self.mbuf
+    }
+}
+
+/// Decides the fate of each received packet.
+///
+/// Implemented for closures, so `|packet: &Packet<'_>| Verdict::Accept`
+/// works as a classifier.
+pub trait Classifier: Send {
+    /// Verdict for one packet
+    fn classify(&mut self, packet: &Packet<'_>) -> Verdict;
+}
+
+impl<F> Classifier for F
+where
+    F: FnMut(&Packet<'_>) -> Verdict + Send,
+{
+    fn classify(&mut self, packet: &Packet<'_>) -> Verdict {
+        self(packet)
+    }
+}
+
+struct DivertInner {
+    packets: VecDeque<Mbuf>,
+    waker: Option<Waker>,
+    limit: usize,
+    dropped: u64,
+}
+
+/// Packets a classifier diverted, as owned mbufs.
+///
+/// Cloning gives another handle to the same queue, so several devices (one
+/// per RX queue) can divert into one consumer. Packets arriving while the
+/// queue is full are dropped and counted in [`dropped`](Self::dropped).
+#[derive(Clone)]
+pub struct DivertQueue {
+    inner: Arc<Mutex<DivertInner>>,
+}
+
+impl Default for DivertQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_DIVERT_QUEUE_LEN)
+    }
+}
+
+impl DivertQueue {
+    /// Queue holding at most `limit` packets.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(DivertInner {
+                packets: VecDeque::new(),
+                waker: None,
+                limit,
+                dropped: 0,
+            })),
+        }
+    }
+
+    /// Next diverted packet, if any.
+    pub fn try_recv(&self) -> Option<Mbuf> {
+        self.inner.lock().unwrap().packets.pop_front()
+    }
+
+    /// Wait for the next diverted packet.
+    ///
+    /// The devices feeding the queue must keep being polled (by their
+    /// reactor) for packets to arrive.
+    pub fn recv(&self) -> DivertRecvFuture<'_> {
+        DivertRecvFuture { queue: self }
+    }
+
+    /// Number of packets waiting
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().packets.len()
+    }
+
+    /// Whether no packets are waiting
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Packets dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.inner.lock().unwrap().dropped
+    }
+
+    fn push(&self, mbuf: Mbuf) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.packets.len() >= inner.limit {
+            inner.dropped += 1;
+            return;
+        }
+        inner.packets.push_back(mbuf);
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Future for the next packet of a [`DivertQueue`]
+pub struct DivertRecvFuture<'a> {
+    queue: &'a DivertQueue,
+}
+
+impl Future for DivertRecvFuture<'_> {
+    type Output = Mbuf;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut inner = self.queue.inner.lock().unwrap();
+        match inner.packets.pop_front() {
+            Some(mbuf) => Poll::Ready(mbuf),
+            None => {
+                inner.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Run `classifier` over an RX burst, keeping the accepted packets in
+/// order and diverting to `divert`.
+pub(crate) fn classify<const N: usize>(
+    classifier: &mut dyn Classifier,
+    batch: &mut ArrayVec<Mbuf, N>,
+    divert: Option<&DivertQueue>,
+) {
+    for mbuf in std::mem::take(batch) {
+        match classifier.classify(&Packet { mbuf: &mbuf }) {
+            Verdict::Accept => batch.push(mbuf),
+            Verdict::Drop => {}
+            Verdict::Divert => {
+                if let Some(divert) = divert {
+                    divert.push(mbuf);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ipv4_frame(protocol: u8, flags_frag: u16, tag: bool) -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        if tag {
+            frame.extend_from_slice(&[0x81, 0x00, 0x00, 0x64]);
+        }
+        frame.extend_from_slice(&[0x08, 0x00]);
+        let mut ip = [0u8; 20];
+        ip[0] = 0x45;
+        ip[6..8].copy_from_slice(&flags_frag.to_be_bytes());
+        ip[9] = protocol;
+        ip[12..16].copy_from_slice(&[10, 0, 0, 1]);
+        ip[16..20].copy_from_slice(&[10, 0, 0, 2]);
+        frame.extend_from_slice(&ip);
+        frame.extend_from_slice(&5000u16.to_be_bytes());
+        frame.extend_from_slice(&80u16.to_be_bytes());
+        frame.extend_from_slice(&[0u8; 16]);
+        frame
+    }
+
+    #[test]
+    fn test_five_tuple() {
+        let expected = FiveTuple {
+            protocol: 6,
+            src_addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            src_port: 5000,
+            dst_addr: IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+            dst_port: 80,
+        };
+        assert_eq!(FiveTuple::parse(&ipv4_frame(6, 0, false)), Some(expected));
+        assert_eq!(FiveTuple::parse(&ipv4_frame(6, 0, true)), Some(expected));
+
+        // ICMP and later fragments have no ports
+        let icmp = FiveTuple::parse(&ipv4_frame(1, 0, false)).unwrap();
+        assert_eq!((icmp.protocol, icmp.src_port, icmp.dst_port), (1, 0, 0));
+        let fragment = FiveTuple::parse(&ipv4_frame(17, 0x00b9, false)).unwrap();
+        assert_eq!((fragment.src_port, fragment.dst_port), (0, 0));
+
+        // ARP
+        let mut arp = ipv4_frame(6, 0, false);
+        arp[12..14].copy_from_slice(&[0x08, 0x06]);
+        assert_eq!(FiveTuple::parse(&arp), None);
+    }
+}
@@ -0,0 +1,221 @@
+//! Datapath counters of a [`DpdkDevice`](super::DpdkDevice).
+//!
+//! The device owns its counters through an `Arc`, so a monitoring thread can
+//! hold [`DpdkDevice::counters`](super::DpdkDevice::counters) and take
+//! [`snapshot`](DeviceCounters::snapshot)s while the device runs. Counters
+//! are relaxed atomics written only by the device's thread, and packet
+//! counts are added once per burst, so they cost next to nothing on the
+//! datapath. A snapshot is not atomic as a whole: fields may be a burst
+//! apart.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::burst::MAX_RX_BURST;
+
+/// Number of buckets in [`DeviceStats::rx_bursts`].
+pub const RX_BURST_BUCKETS: usize = rx_burst_bucket(MAX_RX_BURST) + 1;
+
+/// Histogram bucket of an RX burst of `size` packets.
+///
+/// Bucket 0 counts empty polls, bucket `i` bursts of `2^(i-1)..2^i`
+/// packets; the last bucket holds [`MAX_RX_BURST`].
+#[inline]
+pub const fn rx_burst_bucket(size: usize) -> usize {
+    let bucket = (usize::BITS - size.leading_zeros()) as usize;
+    let last = (usize::BITS - MAX_RX_BURST.leading_zeros()) as usize;
+    if bucket < last { bucket } else { last }
+}
+
+/// Point-in-time copy of a device's counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeviceStats {
+    /// Packets taken from the RX source (hardware queue or ring)
+    pub rx_packets: u64,
+    /// Bytes of those packets, Ethernet header included
+    pub rx_bytes: u64,
+    /// RX bursts by size, see [`rx_burst_bucket`]
+    pub rx_bursts: [u64; RX_BURST_BUCKETS],
+    /// Packets dropped because of a bad checksum
+    pub rx_checksum_errors: u64,
+    /// Packets the TX queue accepted
+    pub tx_packets: u64,
+    /// Bytes of those packets
+    pub tx_bytes: u64,
+    /// Times `transmit` refused a token because the TX batch stayed full
+    /// after flushing to the hardware ring
+    pub tx_batch_full: u64,
+    /// Times `transmit` refused a token because the mempool was empty
+    pub tx_mempool_empty: u64,
+    /// TX mbuf (or mbuf chain) allocations that found the mempool empty
+    pub tx_alloc_failures: u64,
+    /// Frames lost on TX: replies smoltcp wrote without an mbuf or batch
+    /// slot to go into, frames without headroom for the VLAN tag, and
+    /// frames built by the device with no room to send them
+    pub tx_dropped: u64,
+    /// ARP replies injected from the shared ARP cache
+    pub arp_injected: u64,
+    /// ARP injections that failed (RX batch full or mempool empty) and
+    /// will be retried
+    pub arp_inject_failures: u64,
+    /// Neighbor Advertisements injected from the shared NDP cache
+    pub ndp_injected: u64,
+    /// NA injections that failed and will be retried
+    pub ndp_inject_failures: u64,
+}
+
+/// Live counters of one device, see the [module docs](self).
+#[derive(Debug, Default)]
+pub struct DeviceCounters {
+    rx_packets: AtomicU64,
+    rx_bytes: AtomicU64,
+    rx_bursts: [AtomicU64; RX_BURST_BUCKETS],
+    rx_checksum_errors: AtomicU64,
+    tx_packets: AtomicU64,
+    tx_bytes: AtomicU64,
+    tx_batch_full: AtomicU64,
+    tx_mempool_empty: AtomicU64,
+    tx_alloc_failures: AtomicU64,
+    tx_dropped: AtomicU64,
+    arp_injected: AtomicU64,
+    arp_inject_failures: AtomicU64,
+    ndp_injected: AtomicU64,
+    ndp_inject_failures: AtomicU64,
+}
+
+#[inline]
+fn add(counter: &AtomicU64, n: u64) {
+    counter.fetch_add(n, Ordering::Relaxed);
+}
+
+#[inline]
+fn load(counter: &AtomicU64) -> u64 {
+    counter.load(Ordering::Relaxed)
+}
+
+impl DeviceCounters {
+    /// Copy the current values.
+    pub fn snapshot(&self) -> DeviceStats {
+        DeviceStats {
+            rx_packets: load(&self.rx_packets),
+            rx_bytes: load(&self.rx_bytes),
+            rx_bursts: std::array::from_fn(|i| load(&self.rx_bursts[i])),
+            rx_checksum_errors: load(&self.rx_checksum_errors),
+            tx_packets: load(&self.tx_packets),
+            tx_bytes: load(&self.tx_bytes),
+            tx_batch_full: load(&self.tx_batch_full),
+            tx_mempool_empty: load(&self.tx_mempool_empty),
+            tx_alloc_failures: load(&self.tx_alloc_failures),
+            tx_dropped: load(&self.tx_dropped),
+            arp_injected: load(&self.arp_injected),
+            arp_inject_failures: load(&self.arp_inject_failures),
+            ndp_injected: load(&self.ndp_injected),
+            ndp_inject_failures: load(&self.ndp_inject_failures),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn rx_burst(&self, packets: usize, bytes: usize) {
+        add(&self.rx_bursts[rx_burst_bucket(packets)], 1);
+        if packets > 0 {
+            add(&self.rx_packets, packets as u64);
+            add(&self.rx_bytes, bytes as u64);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn rx_checksum_error(&self) {
+        add(&self.rx_checksum_errors, 1);
+    }
+
+    #[inline]
+    pub(crate) fn tx_sent(&self, packets: usize, bytes: usize) {
+        if packets > 0 {
+            add(&self.tx_packets, packets as u64);
+            add(&self.tx_bytes, bytes as u64);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn tx_batch_full(&self) {
+        add(&self.tx_batch_full, 1);
+    }
+
+    #[inline]
+    pub(crate) fn tx_mempool_empty(&self) {
+        add(&self.tx_mempool_empty, 1);
+    }
+
+    #[inline]
+    pub(crate) fn tx_alloc_failure(&self) {
+        add(&self.tx_alloc_failures, 1);
+    }
+
+    #[inline]
+    pub(crate) fn tx_dropped(&self) {
+        add(&self.tx_dropped, 1);
+    }
+
+    #[inline]
+    pub(crate) fn arp_injected(&self, ok: bool) {
+        add(
+            if ok {
+                &self.arp_injected
+            } else {
+                &self.arp_inject_failures
+            },
+            1,
+        );
+    }
+
+    #[inline]
+    pub(crate) fn ndp_injected(&self, ok: bool) {
+        add(
+            if ok {
+                &self.ndp_injected
+            } else {
+                &self.ndp_inject_failures
+            },
+            1,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rx_burst_bucket() {
+        let buckets: Vec<usize> = [0, 1, 2, 3, 4, 7, 8, 32, 63, 64, 511, 512, 4096]
+            .into_iter()
+            .map(rx_burst_bucket)
+            .collect();
+        assert_eq!(buckets, [0, 1, 2, 2, 3, 3, 4, 6, 6, 7, 9, 10, 10]);
+        assert_eq!(RX_BURST_BUCKETS, 11);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let counters = DeviceCounters::default();
+        counters.rx_burst(0, 0);
+        counters.rx_burst(3, 180);
+        counters.tx_sent(2, 120);
+        counters.arp_injected(true);
+        counters.arp_injected(false);
+        counters.tx_dropped();
+
+        let mut expected = DeviceStats {
+            rx_packets: 3,
+            rx_bytes: 180,
+            tx_packets: 2,
+            tx_bytes: 120,
+            tx_dropped: 1,
+            arp_injected: 1,
+            arp_inject_failures: 1,
+            ..Default::default()
+        };
+        expected.rx_bursts[0] = 1;
+        expected.rx_bursts[2] = 1;
+        assert_eq!(counters.snapshot(), expected);
+    }
+}
@@ -1,14 +1,30 @@
 use arrayvec::ArrayVec;
 use smoltcp::phy::{self, Device, DeviceCapabilities, Medium};
 use smoltcp::time::Instant;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::sync::Arc;
 
+use crate::api::rte::eth::{ChecksumOffload, VlanOffload};
 use crate::api::rte::mbuf::Mbuf;
 use crate::api::rte::pktmbuf::MemPool;
 use crate::api::rte::queue::{RxQueue, TxQueue};
+use crate::runtime::ReactorDevice;
 
-use super::arp_cache::{SharedArpCache, parse_arp_reply};
+use super::arp_announce::{ArpAnnounceConfig, ArpAnnouncer};
+use super::arp_cache::{ArpEntry, SharedArpCache, parse_arp_reply};
+use super::burst::{BurstConfig, BurstState, MAX_RX_BURST, MAX_TX_BATCH};
+use super::capture::{Capture, CaptureTap};
+use super::checksum::{checksum_capabilities, prepare_tx_offload, rx_checksum_ok};
+use super::classifier::{self, Classifier, DivertQueue};
+use super::counters::{DeviceCounters, DeviceStats};
+use super::flow_redirect::{FlowRedirect, FlowRedirectState};
+use super::ndp_cache::{
+    SharedNdpCache, build_neighbor_advert_for_injection, parse_neighbor_advert,
+};
+use super::soft_rss::{RxSource, SoftRssQueue};
+use super::tso::{TsoConfig, TsoState};
+use super::udp_zero_copy::UdpZeroCopy;
+use super::vlan::{VlanQueue, VlanState};
 
 /// Default headroom reserved at the front of each mbuf (matches RTE_PKTMBUF_HEADROOM)
 pub const DEFAULT_MBUF_HEADROOM: usize = 128;
@@ -16,33 +32,68 @@ pub const DEFAULT_MBUF_HEADROOM: usize = 128;
 /// Default data room size for mbufs (2048 bytes of usable space + headroom)
 pub const DEFAULT_MBUF_DATA_ROOM_SIZE: usize = 2048 + DEFAULT_MBUF_HEADROOM;
 
-/// Maximum packet overhead: Ethernet (14) + IP (20) + TCP with options (60)
-const MAX_PACKET_OVERHEAD: usize = 14 + 20 + 60;
-
-pub struct DpdkRxToken {
+pub struct DpdkRxToken<'a> {
     mbuf: Mbuf,
+    /// Linear copy of a multi-segment packet
+    linear: Option<&'a [u8]>,
 }
 
-impl phy::RxToken for DpdkRxToken {
+impl phy::RxToken for DpdkRxToken<'_> {
     fn consume<R, F>(self, f: F) -> R
     where
         F: FnOnce(&[u8]) -> R,
     {
         // Smoltcp reads the received packet data (immutable reference)
-        f(self.mbuf.data())
+        match self.linear {
+            Some(frame) => f(frame),
+            None => f(self.mbuf.data()),
+        }
+    }
+}
+
+/// Send what the TX ring takes from `batch`, counting it.
+#[inline]
+fn flush<const N: usize>(txq: TxQueue, batch: &mut ArrayVec<Mbuf, N>, counters: &DeviceCounters) {
+    if !batch.is_empty() {
+        let queued = batch_bytes(batch);
+        let sent = txq.tx(batch);
+        counters.tx_sent(sent, queued - batch_bytes(batch));
     }
 }
 
+/// Why a [`DpdkDevice`] last refused to hand out a TX token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxBlocked {
+    /// The mempool had no mbuf for the frame
+    MempoolEmpty,
+    /// The TX batch stayed full after flushing it to the hardware ring
+    RingFull,
+}
+
+/// Total length of the packets in a batch
+#[inline]
+fn batch_bytes<const N: usize>(batch: &ArrayVec<Mbuf, N>) -> usize {
+    batch.iter().map(Mbuf::pkt_len).sum()
+}
+
 /// More complete implementation with mempool access
 pub struct DpdkDevice {
-    rxq: RxQueue,
+    rx: RxSource,
     txq: TxQueue,
     mempool: Arc<MemPool>,
-    rx_batch: ArrayVec<Mbuf, 64>,
-    tx_batch: ArrayVec<Mbuf, 256>,
+    /// Sized for the largest burst; `burst` says how much of it to use.
+    /// Boxed, so the builder methods move the device cheaply
+    rx_batch: Box<ArrayVec<Mbuf, MAX_RX_BURST>>,
+    tx_batch: Box<ArrayVec<Mbuf, MAX_TX_BATCH>>,
+    /// RX burst, TX batch and flush threshold
+    burst: BurstState,
     mtu: usize,
-    #[allow(dead_code)] // Validated in constructor, stored for debugging/future use
+    /// Frames larger than this are sent as segment chains
     mbuf_capacity: usize,
+    /// Contiguous copy of the current multi-segment RX packet
+    rx_scratch: Vec<u8>,
+    /// Buffer smoltcp writes frames larger than one mbuf into
+    tx_scratch: Vec<u8>,
     /// Queue ID (0 = producer for shared ARP cache)
     queue_id: u16,
     /// Shared ARP cache for multi-queue setups (optional)
@@ -53,6 +104,34 @@ pub struct DpdkDevice {
     our_ip: Option<Ipv4Addr>,
     /// Last seen cache version (skip injection if unchanged)
     last_cache_version: usize,
+    /// Shared IPv6 neighbor cache for multi-queue setups (optional)
+    shared_ndp_cache: Option<SharedNdpCache>,
+    /// Our IPv6 address (for building NA injection packets)
+    our_ipv6: Option<Ipv6Addr>,
+    /// Last seen NDP cache version
+    last_ndp_cache_version: usize,
+    /// Checksum offloads enabled on the port
+    checksum: ChecksumOffload,
+    /// TCP segmentation offload state (None = disabled)
+    tso: Option<TsoState>,
+    /// Packet capture (None = disabled)
+    capture: Option<CaptureTap>,
+    /// Startup / address-change ARPs (None = disabled)
+    arp_announce: Option<ArpAnnouncer>,
+    /// Cross-queue redirection of misrouted TCP segments (None = disabled)
+    flow_redirect: Option<FlowRedirectState>,
+    /// 802.1Q sub-interface (None = untagged only, tags passed through)
+    vlan: Option<VlanState>,
+    /// UDP ports whose datagrams bypass smoltcp (None = disabled)
+    udp_zero_copy: Option<UdpZeroCopy>,
+    /// Per-packet ingress verdicts (None = accept everything)
+    classifier: Option<Box<dyn Classifier>>,
+    /// Where diverted packets go (None = dropped)
+    divert: Option<DivertQueue>,
+    /// Datapath counters, shared with monitoring
+    counters: Arc<DeviceCounters>,
+    /// Set when a frame could not be queued, until there is room again
+    tx_blocked: Option<TxBlocked>,
 }
 
 impl DpdkDevice {
@@ -65,8 +144,11 @@ impl DpdkDevice {
     /// * `mtu` - Maximum transmission unit (payload size, typically 1500)
     /// * `mbuf_capacity` - Usable capacity of mbufs (data_room_size - headroom)
     ///
-    /// # Panics
-    /// Panics if MTU + maximum packet overhead exceeds mbuf capacity.
+    /// The MTU may exceed the mbuf capacity (e.g. 9000-byte jumbo frames on
+    /// a 2KB mempool): such frames are received and sent as segment chains,
+    /// so the port needs scatter RX and multi-segment TX, which
+    /// [`EthDevBuilder::build`](crate::api::rte::eth::EthDevBuilder::build)
+    /// enables when the port MTU requires it.
     pub fn new(
         rxq: RxQueue,
         txq: TxQueue,
@@ -74,28 +156,262 @@ impl DpdkDevice {
         mtu: usize,
         mbuf_capacity: usize,
     ) -> Self {
-        assert!(
-            mtu + MAX_PACKET_OVERHEAD <= mbuf_capacity,
-            "MTU ({}) + max overhead ({}) = {} exceeds mbuf capacity ({})",
-            mtu,
-            MAX_PACKET_OVERHEAD,
-            mtu + MAX_PACKET_OVERHEAD,
-            mbuf_capacity
-        );
         Self {
-            rxq,
+            rx: RxSource::Queue(rxq),
             txq,
             mempool,
-            rx_batch: ArrayVec::new(),
-            tx_batch: ArrayVec::new(),
+            rx_batch: Box::default(),
+            tx_batch: Box::default(),
+            burst: BurstState::new(BurstConfig::default()),
             mtu,
             mbuf_capacity,
+            rx_scratch: Vec::new(),
+            tx_scratch: Vec::new(),
             queue_id: 0,
             shared_arp_cache: None,
             our_mac: None,
             our_ip: None,
             last_cache_version: 0,
+            shared_ndp_cache: None,
+            our_ipv6: None,
+            last_ndp_cache_version: 0,
+            checksum: ChecksumOffload::default(),
+            tso: None,
+            capture: None,
+            arp_announce: None,
+            flow_redirect: None,
+            vlan: None,
+            udp_zero_copy: None,
+            classifier: None,
+            divert: None,
+            counters: Arc::default(),
+            tx_blocked: None,
+        }
+    }
+
+    /// Use the port's checksum offloads.
+    ///
+    /// Pass the value of [`EthDev::checksum_offload`](crate::api::rte::eth::EthDev::checksum_offload)
+    /// for the port this device's queues belong to. smoltcp then skips
+    /// the offloaded checksum work and TX mbufs request it from the NIC.
+    pub fn with_checksum_offload(mut self, offload: ChecksumOffload) -> Self {
+        self.checksum = offload;
+        self
+    }
+
+    /// Use `config`'s RX burst, TX batch and flush threshold instead of
+    /// the defaults.
+    ///
+    /// See [`BurstConfig`] for the adaptive RX burst.
+    pub fn with_burst(mut self, config: BurstConfig) -> Self {
+        self.burst = BurstState::new(config);
+        self
+    }
+
+    /// Number of packets the next RX burst reads (changes over time in
+    /// adaptive mode)
+    pub fn rx_burst_size(&self) -> usize {
+        self.burst.rx_burst()
+    }
+
+    /// Enable TCP segmentation offload.
+    ///
+    /// smoltcp sees an MTU of [`TsoConfig::max_frame_size`] and emits large
+    /// TCP segments, which are split to the real MTU by the NIC or by rte_gso
+    /// (see [`TsoConfig`]). Frames larger than the real MTU are sent as
+    /// chained mbufs, so the port needs `MULTI_SEGS`, which
+    /// [`EthDevBuilder::tso`](crate::api::rte::eth::EthDevBuilder::tso) requests.
+    pub fn with_tso(mut self, config: TsoConfig) -> Self {
+        self.tso = Some(TsoState::new(config, self.mtu));
+        self
+    }
+
+    /// Receive from a software RSS ring instead of the hardware RX queue.
+    ///
+    /// TX still uses this device's TX queue. Call before
+    /// [`with_capture`](Self::with_capture) so captures carry the ring's queue ID.
+    pub fn with_soft_rss(mut self, queue: SoftRssQueue) -> Self {
+        self.rx = RxSource::SoftRss(queue);
+        self
+    }
+
+    /// Redirect TCP segments of flows owned by other queues to them.
+    ///
+    /// `queue_id` is this device's queue in `redirect`. Segments this queue
+    /// receives for another queue's connection are passed on instead of
+    /// reaching smoltcp, and segments other queues receive for ours arrive
+    /// through this queue's redirect ring. See [`FlowRedirect`].
+    pub fn with_flow_redirect(mut self, redirect: FlowRedirect, queue_id: u16) -> Self {
+        self.flow_redirect = Some(FlowRedirectState::new(redirect, queue_id));
+        self
+    }
+
+    /// Forget owned flows after `timeout` without traffic instead of
+    /// [`DEFAULT_FLOW_IDLE_TIMEOUT`](super::DEFAULT_FLOW_IDLE_TIMEOUT).
+    ///
+    /// Has no effect unless [`with_flow_redirect`](Self::with_flow_redirect)
+    /// was called first.
+    pub fn with_flow_idle_timeout(mut self, timeout: smoltcp::time::Duration) -> Self {
+        if let Some(redirect) = &mut self.flow_redirect {
+            redirect.set_idle_timeout(timeout);
         }
+        self
+    }
+
+    /// Make this device a sub-interface of VLAN `vid` (0 = untagged).
+    ///
+    /// Received frames are stripped of their tag and frames of other VLANs
+    /// are dropped; transmitted frames are tagged with `vid`. `offload` is
+    /// [`EthDev::vlan_offload`](crate::api::rte::eth::EthDev::vlan_offload)
+    /// of the port: tags the NIC does not strip or insert are handled in
+    /// software. To run several VLANs on one RX queue, use
+    /// [`with_vlan_queue`](Self::with_vlan_queue) instead.
+    pub fn with_vlan(mut self, vid: u16, offload: VlanOffload) -> Self {
+        self.vlan = Some(VlanState::new(vid, offload));
+        self
+    }
+
+    /// Receive one VLAN's share of a [`VlanDemux`](super::VlanDemux) queue
+    /// and tag transmitted frames with its VLAN ID.
+    ///
+    /// Like [`with_vlan`](Self::with_vlan), with the hardware RX queue
+    /// shared between the demux's sub-interfaces. TX uses this device's own
+    /// TX queue. Call before [`with_capture`](Self::with_capture).
+    pub fn with_vlan_queue(mut self, queue: VlanQueue, offload: VlanOffload) -> Self {
+        self.vlan = Some(VlanState::new(queue.vid(), offload));
+        self.rx = RxSource::Vlan(queue);
+        self
+    }
+
+    /// Deliver UDP datagrams to zero-copy sockets without smoltcp's copies.
+    ///
+    /// Datagrams for ports bound with
+    /// [`UdpSocket::bind_zero_copy`](crate::socket::UdpSocket::bind_zero_copy)
+    /// are taken out of the RX burst and queued in their mbufs, up to
+    /// `queue_len` per port (see [`DEFAULT_UDP_ZERO_COPY_QUEUE_LEN`](super::DEFAULT_UDP_ZERO_COPY_QUEUE_LEN)).
+    /// The same sockets can send mbufs the caller filled in place, up to
+    /// `queue_len` queued for this device. The
+    /// [`Reactor`](crate::runtime::Reactor) driving this device makes the
+    /// sockets available.
+    pub fn with_udp_zero_copy(mut self, queue_len: usize) -> Self {
+        self.udp_zero_copy = Some(UdpZeroCopy::new(queue_len, self.mempool.clone(), self.mtu));
+        self
+    }
+
+    /// Accept, drop or divert each received packet with `classifier`.
+    ///
+    /// Runs on every packet of an RX burst after VLAN filtering and before
+    /// the other RX stages (flow redirection, zero-copy UDP, neighbor
+    /// snooping) and smoltcp. Diverted packets go to the queue set with
+    /// [`with_divert_queue`](Self::with_divert_queue), or are dropped.
+    pub fn with_classifier(mut self, classifier: impl Classifier + 'static) -> Self {
+        self.classifier = Some(Box::new(classifier));
+        self
+    }
+
+    /// Hand packets the classifier diverts to `queue`.
+    pub fn with_divert_queue(mut self, queue: DivertQueue) -> Self {
+        self.divert = Some(queue);
+        self
+    }
+
+    /// Counters of this device, readable from any thread.
+    ///
+    /// Keep the `Arc` to [`snapshot`](DeviceCounters::snapshot) the counters
+    /// while the device runs on another thread.
+    pub fn counters(&self) -> Arc<DeviceCounters> {
+        self.counters.clone()
+    }
+
+    /// Snapshot of this device's counters
+    pub fn stats(&self) -> DeviceStats {
+        self.counters.snapshot()
+    }
+
+    /// Copy this queue's frames into `capture` while it is started.
+    ///
+    /// Frames are tagged with the RX queue's port and queue ID. On a VLAN
+    /// sub-interface the captured frames are untagged, as smoltcp sees them.
+    pub fn with_capture(mut self, capture: Capture) -> Self {
+        self.capture = Some(CaptureTap::new(
+            capture,
+            self.rx.port_id(),
+            self.rx.queue_id(),
+        ));
+        self
+    }
+
+    /// Number of free TX batch slots needed to hand out a TX token.
+    #[inline]
+    fn tx_slots_needed(&self) -> usize {
+        self.tso.as_ref().map_or(1, TsoState::tx_slots)
+    }
+
+    /// Whether the TX batch has `slots` free within its configured size
+    /// (a TSO frame may always fill an empty batch).
+    #[inline]
+    fn tx_has_room(&self, slots: usize) -> bool {
+        self.tx_batch.len() + slots <= self.burst.tx_batch().max(slots)
+    }
+
+    /// TX token writing into `mbuf`, or into one allocated on `consume`.
+    #[inline]
+    fn tx_token(&mut self, mbuf: Option<Mbuf>) -> DpdkTxTokenWithPool<'_> {
+        self.tx_token_and_rx_scratch(mbuf).0
+    }
+
+    /// [`tx_token`](Self::tx_token), plus the RX scratch buffer the RX
+    /// token of the same `receive` borrows.
+    #[inline]
+    fn tx_token_and_rx_scratch(&mut self, mbuf: Option<Mbuf>) -> (DpdkTxTokenWithPool<'_>, &[u8]) {
+        let tx_token = DpdkTxTokenWithPool {
+            mbuf,
+            mempool: &self.mempool,
+            txq: self.txq,
+            batch_size: self.burst.tx_batch(),
+            flush_threshold: self.burst.tx_flush_threshold(),
+            tx_batch: &mut self.tx_batch,
+            scratch: &mut self.tx_scratch,
+            checksum: self.checksum,
+            mtu: self.mtu,
+            mbuf_capacity: self.mbuf_capacity,
+            tso: self.tso.as_mut(),
+            capture: self.capture.as_ref(),
+            flow_redirect: self.flow_redirect.as_mut(),
+            vlan: self.vlan.as_ref(),
+            counters: &self.counters,
+            tx_blocked: &mut self.tx_blocked,
+        };
+        (tx_token, &self.rx_scratch)
+    }
+
+    /// Why the last frame could not be queued, if there is still no room.
+    ///
+    /// While blocked, `transmit` returns no token and smoltcp keeps its data
+    /// queued in the sockets instead of losing it.
+    pub fn tx_blocked(&self) -> Option<TxBlocked> {
+        self.tx_blocked
+    }
+
+    /// Whether a frame could be queued now.
+    ///
+    /// When blocked, flushes the TX batch and checks for ring space or a
+    /// free mbuf again, clearing the block if there is room. The
+    /// [`Reactor`](crate::runtime::Reactor) holds egress back until this
+    /// returns `true`.
+    pub fn tx_ready(&mut self) -> bool {
+        let Some(blocked) = self.tx_blocked else {
+            return true;
+        };
+        self.flush_tx();
+        let ready = match blocked {
+            TxBlocked::RingFull => self.tx_has_room(self.tx_slots_needed()),
+            TxBlocked::MempoolEmpty => self.mempool.avail_count() > 0,
+        };
+        if ready {
+            self.tx_blocked = None;
+        }
+        ready
     }
 
     /// Configure shared ARP cache for multi-queue support.
@@ -122,26 +438,199 @@ impl DpdkDevice {
         self
     }
 
+    /// Configure shared IPv6 neighbor cache for multi-queue support.
+    ///
+    /// # Arguments
+    /// * `queue_id` - This queue's ID (queue 0 is the NDP producer)
+    /// * `cache` - Shared neighbor cache
+    /// * `our_mac` - Our interface MAC address
+    /// * `our_ip` - Our interface IPv6 address (unicast, assigned to the interface)
+    ///
+    /// Queue 0 will update the cache when it receives Neighbor Advertisements.
+    /// Other queues will inject synthetic advertisements into smoltcp. Can be
+    /// combined with [`with_shared_arp_cache`](Self::with_shared_arp_cache)
+    /// for dual-stack interfaces.
+    pub fn with_shared_ndp_cache(
+        mut self,
+        queue_id: u16,
+        cache: SharedNdpCache,
+        our_mac: [u8; 6],
+        our_ip: Ipv6Addr,
+    ) -> Self {
+        self.queue_id = queue_id;
+        self.shared_ndp_cache = Some(cache);
+        self.our_mac = Some(our_mac);
+        self.our_ipv6 = Some(our_ip);
+        self
+    }
+
+    /// ARP for the gateway and neighbors, and announce our address, at startup.
+    ///
+    /// # Arguments
+    /// * `config` - Neighbors to resolve and how often to (re)send
+    /// * `our_mac` - Our interface MAC address
+    /// * `our_ip` - Our interface IP address
+    ///
+    /// Only queue 0 sends (see [`with_shared_arp_cache`](Self::with_shared_arp_cache));
+    /// other queues learn the replies through the shared cache. Requests stop
+    /// early for neighbors the shared cache already has. The announcement is
+    /// repeated when the address changes through
+    /// [`set_ipv4_addr`](Self::set_ipv4_addr), which the
+    /// [`Reactor`](crate::runtime::Reactor) calls when the interface address changes.
+    pub fn with_arp_announce(
+        mut self,
+        config: ArpAnnounceConfig,
+        our_mac: [u8; 6],
+        our_ip: Ipv4Addr,
+    ) -> Self {
+        self.arp_announce = Some(ArpAnnouncer::new(config));
+        self.our_mac = Some(our_mac);
+        self.our_ip = Some(our_ip);
+        self
+    }
+
+    /// Update our IPv4 address.
+    ///
+    /// Re-injects the shared ARP cache towards the new address and repeats
+    /// the gratuitous ARP announcement. No-op if the address is unchanged.
+    pub fn set_ipv4_addr(&mut self, ip: Ipv4Addr) {
+        if self.our_ip == Some(ip) {
+            return;
+        }
+        self.our_ip = Some(ip);
+        if let Some(cache) = &self.shared_arp_cache {
+            self.last_cache_version = cache.version().wrapping_sub(1);
+        }
+        if let Some(announcer) = &mut self.arp_announce {
+            announcer.restart();
+        }
+    }
+
+    /// Send any proactive ARPs that are due.
+    #[inline]
+    fn poll_arp_announce(&mut self, now: Instant) {
+        if self.queue_id != 0 {
+            return;
+        }
+        let (Some(announcer), Some(our_mac), Some(our_ip)) =
+            (&mut self.arp_announce, self.our_mac, self.our_ip)
+        else {
+            return;
+        };
+        if !announcer.is_due(now) {
+            return;
+        }
+        let cache = self.shared_arp_cache.as_ref();
+        let frames = announcer.next_round(now, our_mac, our_ip, |ip| {
+            cache.is_some_and(|cache| cache.contains(ip))
+        });
+        for frame in frames {
+            if !self.send_frame(&frame) {
+                self.counters.tx_dropped();
+                tracing::warn!("Failed to send proactive ARP, TX batch full or mempool empty");
+                return;
+            }
+        }
+    }
+
+    /// Queue a frame built by the device itself for transmission.
+    fn send_frame(&mut self, frame: &[u8]) -> bool {
+        if !self.tx_has_room(1) {
+            return false;
+        }
+        let Some(mut mbuf) = self.mempool.try_alloc() else {
+            self.counters.tx_alloc_failure();
+            return false;
+        };
+        if !mbuf.copy_from_slice(frame) {
+            return false;
+        }
+        if let Some(capture) = &self.capture {
+            capture.tx(frame);
+        }
+        if let Some(vlan) = &self.vlan
+            && !vlan.tag_tx(&mut mbuf)
+        {
+            return false;
+        }
+        self.tx_batch.push(mbuf);
+        true
+    }
+
     fn poll_rx(&mut self) {
-        // First flush any pending TX packets
+        // First flush any pending TX packets, including zero-copy datagrams
+        if let Some(udp) = &self.udp_zero_copy {
+            udp.drain_tx(
+                &mut self.tx_batch,
+                self.burst.tx_batch(),
+                &self.checksum,
+                self.capture.as_ref(),
+                self.vlan.as_ref(),
+            );
+        }
         self.flush_tx();
 
         // Poll from network only when rx_batch is empty (drain-then-refill pattern).
         // This minimizes DPDK API calls and improves cache locality.
         if self.rx_batch.is_empty() {
-            self.rxq.rx(&mut self.rx_batch);
+            // Keep room for segments other queues redirected to us: a burst
+            // filling the whole batch would starve them until their ring
+            // overflows. Up to half the burst (at least one packet) goes to
+            // them.
+            let rx_burst = self.burst.rx_burst();
+            let reserved = self
+                .flow_redirect
+                .as_ref()
+                .map_or(0, |redirect| redirect.pending().min((rx_burst / 2).max(1)));
+            let received = self.rx.rx(&mut self.rx_batch, rx_burst - reserved);
+            self.counters
+                .rx_burst(received, batch_bytes(&self.rx_batch));
+            let rx = &self.rx;
+            self.burst.after_rx(received, || rx.depth());
+            // Only our VLAN's packets, untagged, go any further
+            if let Some(vlan) = &self.vlan {
+                self.rx_batch.retain(|mbuf| vlan.accept_rx(mbuf));
+            }
+            if let Some(classifier) = &mut self.classifier {
+                classifier::classify(
+                    classifier.as_mut(),
+                    &mut self.rx_batch,
+                    self.divert.as_ref(),
+                );
+            }
+            // Redirected segments passed the owning queue's VLAN filter and
+            // classifier already. Only what was reserved for them, so the
+            // burst stays within its size.
+            if let Some(redirect) = &self.flow_redirect {
+                redirect.rx(&mut self.rx_batch, reserved);
+            }
+            // Datagrams for zero-copy sockets skip smoltcp
+            if let Some(udp) = &self.udp_zero_copy {
+                udp.intercept(&mut self.rx_batch, &self.checksum, self.capture.as_ref());
+            }
 
             // If we have a shared ARP cache, process received packets
             if let Some(ref cache) = self.shared_arp_cache {
                 // Queue 0: scan for ARP replies and update shared cache
                 if self.queue_id == 0 {
-                    for mbuf in &self.rx_batch {
+                    for mbuf in self.rx_batch.iter() {
                         if let Some((ip, mac)) = parse_arp_reply(mbuf.data()) {
                             cache.insert(ip, mac);
                         }
                     }
                 }
             }
+
+            // Same for IPv6 Neighbor Advertisements
+            if let Some(ref cache) = self.shared_ndp_cache
+                && self.queue_id == 0
+            {
+                for mbuf in self.rx_batch.iter() {
+                    if let Some((ip, mac)) = parse_neighbor_advert(mbuf.data()) {
+                        cache.insert(ip, mac);
+                    }
+                }
+            }
         }
     }
 
@@ -175,12 +664,12 @@ impl DpdkDevice {
             return;
         }
 
-        // Load the current cache snapshot (lock-free)
+        // Copy the live (unexpired) entries (lock-free)
         let cache_snapshot = cache.snapshot();
 
         // Inject all entries (we only get here when there are new/updated ones)
         // Re-injecting already-known entries is harmless - smoltcp deduplicates
-        for (&ip, &mac) in cache_snapshot.iter() {
+        for ArpEntry { ip, mac, .. } in cache_snapshot {
             let arp_packet = build_arp_reply_for_injection(our_mac, our_ip, mac, ip);
 
             if self.rx_batch.len() < self.rx_batch.capacity()
@@ -188,9 +677,11 @@ impl DpdkDevice {
                 && mbuf.copy_from_slice(&arp_packet)
             {
                 self.rx_batch.push(mbuf);
+                self.counters.arp_injected(true);
             } else {
                 // Injection failed (batch full, alloc failed, or copy failed).
                 // Return without updating cache version so we retry next iteration.
+                self.counters.arp_injected(false);
                 tracing::warn!("Failed to inject ARP entry for {}, will retry", ip);
                 return;
             }
@@ -199,14 +690,46 @@ impl DpdkDevice {
         self.last_cache_version = current_version;
     }
 
+    /// Check shared NDP cache and inject Neighbor Advertisements for any
+    /// changes, like [`inject_from_shared_cache`](Self::inject_from_shared_cache).
+    #[inline(always)]
+    fn inject_from_shared_ndp_cache(&mut self) {
+        if self.queue_id == 0 {
+            return;
+        }
+
+        let (Some(cache), Some(our_mac), Some(our_ip)) =
+            (&self.shared_ndp_cache, self.our_mac, self.our_ipv6)
+        else {
+            return;
+        };
+
+        let current_version = cache.version();
+        if current_version == self.last_ndp_cache_version {
+            return;
+        }
+
+        let cache_snapshot = cache.snapshot();
+        for (&ip, &mac) in cache_snapshot.iter() {
+            let na_packet = build_neighbor_advert_for_injection(our_mac, our_ip, mac, ip);
+            let injected = self.inject_rx_packet(&na_packet);
+            self.counters.ndp_injected(injected);
+            if !injected {
+                // Retry next iteration, version not updated
+                tracing::warn!("Failed to inject NDP entry for {}, will retry", ip);
+                return;
+            }
+        }
+
+        self.last_ndp_cache_version = current_version;
+    }
+
     /// Flush pending TX packets to the hardware.
     ///
     /// This tries to send packets from tx_batch but doesn't spin if the TX ring is full.
     /// Remaining packets stay in tx_batch and will be retried on next call.
     pub(crate) fn flush_tx(&mut self) {
-        if !self.tx_batch.is_empty() {
-            self.txq.tx(&mut self.tx_batch);
-        }
+        flush(self.txq, &mut self.tx_batch, &self.counters);
     }
 
     /// Inject a packet into the receive path.
@@ -237,7 +760,7 @@ impl DpdkDevice {
 
 impl Device for DpdkDevice {
     type RxToken<'a>
-        = DpdkRxToken
+        = DpdkRxToken<'a>
     where
         Self: 'a;
     type TxToken<'a>
@@ -245,87 +768,277 @@ impl Device for DpdkDevice {
     where
         Self: 'a;
 
-    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        // Before poll_rx, which flushes them
+        self.poll_arp_announce(timestamp);
+        if let Some(redirect) = &mut self.flow_redirect {
+            redirect.poll(timestamp);
+        }
         self.poll_rx();
 
         // Inject ARP entries after poll_rx (which may have reversed the batch).
         // This ensures injected ARPs are at the back, processed first by pop() = high priority.
         // Critical for queue 1+ to resolve gateway MAC quickly for SYN-ACKs.
         self.inject_from_shared_cache();
+        self.inject_from_shared_ndp_cache();
 
-        if let Some(mbuf) = self.rx_batch.pop() {
-            let rx_token = DpdkRxToken { mbuf };
-            let tx_token = DpdkTxTokenWithPool {
-                mempool: &self.mempool,
-                tx_batch: &mut self.tx_batch,
+        while let Some(mut mbuf) = self.rx_batch.pop() {
+            // Segments of another queue's connection go to that queue
+            // (headers are in the first segment)
+            if let Some(redirect) = &mut self.flow_redirect
+                && let Some(owner) = redirect.on_rx(mbuf.data())
+            {
+                redirect.forward(owner, mbuf);
+                continue;
+            }
+
+            // smoltcp needs contiguous bytes: copy multi-segment packets out
+            let chained = !mbuf.is_contiguous();
+            let ol_flags = mbuf.ol_flags();
+            let frame = if chained {
+                self.rx_scratch.resize(mbuf.pkt_len(), 0);
+                mbuf.copy_to(&mut self.rx_scratch);
+                &mut self.rx_scratch[..]
+            } else {
+                mbuf.data_mut()
             };
-            Some((rx_token, tx_token))
-        } else {
-            None
+            if let Some(capture) = &self.capture {
+                capture.rx(frame);
+            }
+
+            // Drop packets whose checksum the NIC (or our software check) rejected
+            if self.checksum.any_rx() && !rx_checksum_ok(ol_flags, frame, &self.checksum) {
+                tracing::trace!("Dropping RX packet with bad checksum");
+                self.counters.rx_checksum_error();
+                continue;
+            }
+            if let Some(tso) = &mut self.tso {
+                tso.on_rx(frame);
+            }
+            // Neighbors zero-copy datagrams can be sent to
+            if let Some(udp) = &self.udp_zero_copy {
+                udp.learn(frame);
+            }
+            // Replies allocate when written: most received packets get none
+            let (tx_token, rx_scratch) = self.tx_token_and_rx_scratch(None);
+            let rx_token = DpdkRxToken {
+                mbuf,
+                linear: chained.then_some(rx_scratch),
+            };
+            return Some((rx_token, tx_token));
         }
+        None
     }
 
     fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
-        if self.tx_batch.len() < self.tx_batch.capacity() {
-            Some(DpdkTxTokenWithPool {
-                mempool: &self.mempool,
-                tx_batch: &mut self.tx_batch,
-            })
-        } else {
+        let slots = self.tx_slots_needed();
+        if !self.tx_has_room(slots) {
             // TX batch is full - try to flush to hardware.
-            // With a 256-packet batch and 1024-descriptor TX ring, this should
+            // With the default 256-packet batch and a 1024-descriptor TX ring, this should
             // rarely fail unless under extreme load.
             self.flush_tx();
-            if self.tx_batch.len() < self.tx_batch.capacity() {
-                Some(DpdkTxTokenWithPool {
-                    mempool: &self.mempool,
-                    tx_batch: &mut self.tx_batch,
-                })
-            } else {
-                // Hardware TX ring is full - caller will have to wait
-                None
+            if !self.tx_has_room(slots) {
+                // Hardware TX ring is full - smoltcp keeps the frame until
+                // tx_ready() sees room
+                self.counters.tx_batch_full();
+                self.tx_blocked = Some(TxBlocked::RingFull);
+                return None;
             }
         }
+        // Allocate before smoltcp writes, so an empty pool holds the frame
+        // back instead of losing it
+        let Some(mbuf) = self.mempool.try_alloc() else {
+            self.counters.tx_mempool_empty();
+            self.tx_blocked = Some(TxBlocked::MempoolEmpty);
+            return None;
+        };
+        self.tx_blocked = None;
+        Some(self.tx_token(Some(mbuf)))
     }
 
     fn capabilities(&self) -> DeviceCapabilities {
         let mut caps = DeviceCapabilities::default();
-        caps.max_transmission_unit = self.mtu;
+        caps.max_transmission_unit = self.tso.as_ref().map_or(self.mtu, TsoState::max_frame_size);
         caps.medium = Medium::Ethernet;
+        caps.checksum = checksum_capabilities(&self.checksum);
         caps
     }
 }
 
+impl ReactorDevice for DpdkDevice {
+    fn set_ipv4_addr(&mut self, ip: Ipv4Addr) {
+        DpdkDevice::set_ipv4_addr(self, ip);
+    }
+
+    fn udp_zero_copy(&self) -> Option<&UdpZeroCopy> {
+        self.udp_zero_copy.as_ref()
+    }
+
+    fn tx_ready(&mut self) -> bool {
+        DpdkDevice::tx_ready(self)
+    }
+}
+
 pub struct DpdkTxTokenWithPool<'a> {
+    /// Allocated by `transmit` (None for the reply token of `receive`)
+    mbuf: Option<Mbuf>,
     mempool: &'a MemPool,
-    tx_batch: &'a mut ArrayVec<Mbuf, 256>,
+    txq: TxQueue,
+    /// Configured TX batch size
+    batch_size: usize,
+    /// Flush the batch once it holds this many packets
+    flush_threshold: usize,
+    tx_batch: &'a mut ArrayVec<Mbuf, MAX_TX_BATCH>,
+    scratch: &'a mut Vec<u8>,
+    checksum: ChecksumOffload,
+    mtu: usize,
+    mbuf_capacity: usize,
+    tso: Option<&'a mut TsoState>,
+    capture: Option<&'a CaptureTap>,
+    flow_redirect: Option<&'a mut FlowRedirectState>,
+    vlan: Option<&'a VlanState>,
+    counters: &'a DeviceCounters,
+    tx_blocked: &'a mut Option<TxBlocked>,
+}
+
+impl DpdkTxTokenWithPool<'_> {
+    /// Apply per-packet TX processing and add the packet to the batch.
+    fn queue(&mut self, mut mbuf: Mbuf) {
+        // Keep the MSS announced to peers within the real MTU
+        if let Some(tso) = self.tso.as_deref_mut() {
+            tso.on_tx(mbuf.data_mut(), !self.checksum.tx_tcp);
+        }
+        // Claim the connections we open or accept
+        if let Some(redirect) = self.flow_redirect.as_deref_mut() {
+            redirect.on_tx(mbuf.data());
+        }
+        if let Some(capture) = self.capture {
+            if mbuf.is_contiguous() {
+                capture.tx(mbuf.data());
+            } else {
+                capture.tx(&mbuf.to_vec());
+            }
+        }
+
+        // Ask the NIC to fill in the checksums smoltcp skipped
+        if self.checksum.any_tx() {
+            prepare_tx_offload(&mut mbuf, &self.checksum);
+        }
+        // Tag last, so the offload header lengths above cover the tag
+        if let Some(vlan) = self.vlan
+            && !vlan.tag_tx(&mut mbuf)
+        {
+            self.counters.tx_dropped();
+            tracing::warn!("Dropping TX frame: no headroom for VLAN tag");
+            return;
+        }
+
+        // Add to tx batch (will be flushed later). transmit() only returns
+        // a token when there is space, but the reply token of receive() may
+        // find the batch at its configured size: make room first
+        if self.tx_batch.len() >= self.batch_size {
+            flush(self.txq, self.tx_batch, self.counters);
+        }
+        if self.tx_batch.len() >= self.batch_size {
+            self.counters.tx_dropped();
+            if self.tx_blocked.replace(TxBlocked::RingFull) != Some(TxBlocked::RingFull) {
+                tracing::warn!("Dropping TX frames: TX batch full");
+            }
+            return;
+        }
+        self.tx_batch.push(mbuf);
+        self.flush_if_due();
+    }
+
+    /// Flush early once the batch reaches the configured threshold.
+    #[inline]
+    fn flush_if_due(&mut self) {
+        if self.tx_batch.len() >= self.flush_threshold {
+            flush(self.txq, self.tx_batch, self.counters);
+        }
+    }
 }
 
 impl<'a> phy::TxToken for DpdkTxTokenWithPool<'a> {
-    fn consume<R, F>(self, len: usize, f: F) -> R
+    fn consume<R, F>(mut self, len: usize, f: F) -> R
     where
         F: FnOnce(&mut [u8]) -> R,
     {
-        // Allocate mbuf from mempool
-        if let Some(mut mbuf) = self.mempool.try_alloc() {
+        // Larger than the real MTU: a TSO segment to split
+        if len > self.mtu
+            && let Some(tso) = self.tso.as_deref_mut()
+        {
+            let queued = self.tx_batch.len();
+            self.scratch.resize(len, 0);
+            let result = f(&mut self.scratch[..len]);
+            tso.queue_large(
+                &self.scratch[..len],
+                self.mempool,
+                self.tx_batch,
+                &self.checksum,
+                self.counters,
+            );
+            if let Some(capture) = self.capture {
+                capture.tx(&self.scratch[..len]);
+            }
+            if let Some(vlan) = self.vlan {
+                // Segments without headroom for a tag must not leave untagged
+                let mut index = queued;
+                while index < self.tx_batch.len() {
+                    if vlan.tag_tx(&mut self.tx_batch[index]) {
+                        index += 1;
+                    } else {
+                        self.tx_batch.remove(index);
+                        self.counters.tx_dropped();
+                    }
+                }
+            }
+            self.flush_if_due();
+            return result;
+        }
+
+        // Larger than one mbuf: write contiguously, then copy into a chain
+        if len > self.mbuf_capacity {
+            self.scratch.resize(len, 0);
+            let result = f(&mut self.scratch[..len]);
+            match Mbuf::alloc_from_slice(self.mempool, &self.scratch[..len]) {
+                Some(mbuf) => self.queue(mbuf),
+                None => {
+                    self.counters.tx_alloc_failure();
+                    self.counters.tx_dropped();
+                    tracing::warn!(len, "Dropping TX frame: mbuf chain allocation failed");
+                }
+            }
+            return result;
+        }
+
+        // Use the mbuf transmit() allocated, or allocate one for a reply
+        if let Some(mut mbuf) = self.mbuf.take().or_else(|| self.mempool.try_alloc()) {
             unsafe {
                 mbuf.extend(len);
             }
 
             // Let smoltcp write directly to the mbuf
             let result = f(mbuf.data_mut());
-
-            // Add to tx batch (will be flushed later)
-            // Safety: transmit() only returns a token when tx_batch has space
-            self.tx_batch
-                .try_push(mbuf)
-                .expect("tx_batch should have space (checked in transmit())");
-
+            self.queue(mbuf);
             result
         } else {
-            // Fallback if allocation fails - packet data is lost
-            let mut buffer = vec![0u8; len];
-            f(&mut buffer)
+            // Pool exhausted while replying to a received packet: smoltcp
+            // still needs somewhere to write, and the frame is lost (also
+            // counted in MemPool::alloc_failures). Egress waits for mbufs.
+            // Counted per frame, logged once per exhaustion: at line rate a
+            // log line per frame would flood the log
+            self.counters.tx_alloc_failure();
+            self.counters.tx_dropped();
+            if self.tx_blocked.replace(TxBlocked::MempoolEmpty) != Some(TxBlocked::MempoolEmpty) {
+                tracing::warn!(
+                    len,
+                    in_use = self.mempool.in_use_count(),
+                    "Dropping TX frames: mempool exhausted"
+                );
+            }
+            self.scratch.resize(len, 0);
+            f(&mut self.scratch[..len])
         }
     }
 }
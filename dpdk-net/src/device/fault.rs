@@ -0,0 +1,623 @@
+//! Fault injection for resilience testing.
+//!
+//! [`FaultyDevice`] wraps any smoltcp [`Device`] (usually a
+//! [`DpdkDevice`](super::DpdkDevice)) and, per direction, drops, corrupts,
+//! duplicates, delays and reorders frames. Random decisions come from a
+//! seeded generator per direction, so a test sees the same faults for the
+//! same sequence of frames on every run.
+//!
+//! The wrapper works on copies of the frames: received frames are taken out
+//! of the inner device as soon as they arrive and handed to smoltcp when
+//! due, transmitted ones are held until due and then written into the inner
+//! device's TX tokens. Timing follows the timestamps smoltcp passes in, so
+//! it works unchanged under the [`Reactor`](crate::runtime::Reactor).
+//! The faults can be changed while the device is in use through a
+//! [`FaultHandle`].
+
+use std::collections::VecDeque;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex};
+
+use smoltcp::phy::{self, Device, DeviceCapabilities};
+use smoltcp::time::{Duration, Instant};
+
+use super::UdpZeroCopy;
+use crate::runtime::ReactorDevice;
+
+/// Frames taken from the inner device per `receive` call
+const RX_BURST: usize = 64;
+
+/// Frames waiting to be sent before `transmit` reports the device busy
+const TX_PENDING_LIMIT: usize = 4096;
+
+/// Default extra delay of reordered frames.
+pub const DEFAULT_REORDER_DELAY: Duration = Duration::from_millis(1);
+
+/// Faults applied to one direction.
+///
+/// Rates are probabilities per frame, between 0.0 and 1.0. The default
+/// passes every frame through unchanged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Faults {
+    /// Probability a frame is dropped
+    pub drop_rate: f64,
+    /// Probability one random bit of a frame is flipped
+    pub corrupt_rate: f64,
+    /// Probability a frame is delivered twice
+    pub duplicate_rate: f64,
+    /// Probability a frame is held back by `reorder_delay`, letting the
+    /// frames behind it overtake
+    pub reorder_rate: f64,
+    /// Extra delay of reordered frames
+    pub reorder_delay: Duration,
+    /// Delay of every frame
+    pub latency: Duration,
+    /// Maximum random delay added to `latency`
+    pub jitter: Duration,
+}
+
+impl Default for Faults {
+    fn default() -> Self {
+        Self {
+            drop_rate: 0.0,
+            corrupt_rate: 0.0,
+            duplicate_rate: 0.0,
+            reorder_rate: 0.0,
+            reorder_delay: DEFAULT_REORDER_DELAY,
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+        }
+    }
+}
+
+impl Faults {
+    /// No faults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop frames with probability `rate`.
+    pub fn drop_rate(mut self, rate: f64) -> Self {
+        self.drop_rate = rate;
+        self
+    }
+
+    /// Flip a bit in frames with probability `rate`.
+    pub fn corrupt_rate(mut self, rate: f64) -> Self {
+        self.corrupt_rate = rate;
+        self
+    }
+
+    /// Duplicate frames with probability `rate`.
+    pub fn duplicate_rate(mut self, rate: f64) -> Self {
+        self.duplicate_rate = rate;
+        self
+    }
+
+    /// Hold frames back by `delay` with probability `rate`.
+    pub fn reorder(mut self, rate: f64, delay: Duration) -> Self {
+        self.reorder_rate = rate;
+        self.reorder_delay = delay;
+        self
+    }
+
+    /// Delay every frame by `latency`.
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Add up to `jitter` of random delay to every frame.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+}
+
+/// Fault configuration of a [`FaultyDevice`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaultConfig {
+    /// Seed of the random decisions
+    pub seed: u64,
+    /// Faults of received frames (inner device -> smoltcp)
+    pub rx: Faults,
+    /// Faults of transmitted frames (smoltcp -> inner device)
+    pub tx: Faults,
+}
+
+impl FaultConfig {
+    /// No faults, with random decisions seeded by `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Self::default()
+        }
+    }
+
+    /// Set the faults of received frames.
+    pub fn rx(mut self, faults: Faults) -> Self {
+        self.rx = faults;
+        self
+    }
+
+    /// Set the faults of transmitted frames.
+    pub fn tx(mut self, faults: Faults) -> Self {
+        self.tx = faults;
+        self
+    }
+
+    /// Apply `faults` in both directions.
+    pub fn both(self, faults: Faults) -> Self {
+        self.rx(faults).tx(faults)
+    }
+}
+
+/// Frame counters of one direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultCounters {
+    /// Frames that entered the direction
+    pub frames: u64,
+    /// Frames dropped
+    pub dropped: u64,
+    /// Frames with a flipped bit
+    pub corrupted: u64,
+    /// Extra copies delivered
+    pub duplicated: u64,
+    /// Frames held back for reordering
+    pub reordered: u64,
+}
+
+/// Frame counters of a [`FaultyDevice`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FaultStats {
+    /// Received frames
+    pub rx: FaultCounters,
+    /// Transmitted frames
+    pub tx: FaultCounters,
+}
+
+struct Shared {
+    config: FaultConfig,
+    stats: FaultStats,
+}
+
+/// Changes the faults of a [`FaultyDevice`] and reads its counters, e.g.
+/// from a test while the device runs under a reactor.
+#[derive(Clone)]
+pub struct FaultHandle {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl FaultHandle {
+    /// Apply `faults` to frames received from now on.
+    pub fn set_rx(&self, faults: Faults) {
+        self.shared.lock().unwrap().config.rx = faults;
+    }
+
+    /// Apply `faults` to frames transmitted from now on.
+    pub fn set_tx(&self, faults: Faults) {
+        self.shared.lock().unwrap().config.tx = faults;
+    }
+
+    /// Snapshot of the counters
+    pub fn stats(&self) -> FaultStats {
+        self.shared.lock().unwrap().stats
+    }
+}
+
+/// SplitMix64: small, fast and plenty for fault decisions.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// `true` with probability `p`
+    fn chance(&mut self, p: f64) -> bool {
+        // Disabled faults take no draw from the sequence
+        p > 0.0 && ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    /// Uniform in `0..=max`
+    fn up_to(&mut self, max: u64) -> u64 {
+        if max == 0 {
+            return 0;
+        }
+        self.next_u64() % (max + 1)
+    }
+}
+
+/// Frame waiting for its delivery time.
+struct Delayed {
+    due: Instant,
+    frame: Vec<u8>,
+}
+
+/// Faults and delay queue of one direction.
+struct Direction {
+    rng: Rng,
+    /// Sorted by due time; frames due at the same time keep their order
+    pending: VecDeque<Delayed>,
+}
+
+impl Direction {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng(seed),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Apply `faults` to a frame entering this direction and queue it.
+    fn push(
+        &mut self,
+        faults: &Faults,
+        counters: &mut FaultCounters,
+        now: Instant,
+        mut frame: Vec<u8>,
+    ) {
+        counters.frames += 1;
+        if self.rng.chance(faults.drop_rate) {
+            counters.dropped += 1;
+            return;
+        }
+        if !frame.is_empty() && self.rng.chance(faults.corrupt_rate) {
+            let bit = self.rng.up_to(frame.len() as u64 * 8 - 1) as usize;
+            frame[bit / 8] ^= 1 << (bit % 8);
+            counters.corrupted += 1;
+        }
+        if self.rng.chance(faults.duplicate_rate) {
+            counters.duplicated += 1;
+            self.schedule(faults, counters, now, frame.clone());
+        }
+        self.schedule(faults, counters, now, frame);
+    }
+
+    fn schedule(
+        &mut self,
+        faults: &Faults,
+        counters: &mut FaultCounters,
+        now: Instant,
+        frame: Vec<u8>,
+    ) {
+        let mut due = now + faults.latency;
+        due += Duration::from_micros(self.rng.up_to(faults.jitter.total_micros()));
+        if self.rng.chance(faults.reorder_rate) {
+            due += faults.reorder_delay;
+            counters.reordered += 1;
+        }
+        let index = self.pending.partition_point(|delayed| delayed.due <= due);
+        self.pending.insert(index, Delayed { due, frame });
+    }
+
+    /// Next frame whose time has come
+    fn pop_due(&mut self, now: Instant) -> Option<Vec<u8>> {
+        if self.pending.front()?.due > now {
+            return None;
+        }
+        self.pending.pop_front().map(|delayed| delayed.frame)
+    }
+}
+
+/// A device that injects faults into the traffic of another.
+///
+/// Checksums offloaded to the NIC are not checked again after corruption,
+/// so corrupted frames only get rejected where smoltcp verifies them.
+pub struct FaultyDevice<D: Device> {
+    inner: D,
+    shared: Arc<Mutex<Shared>>,
+    rx: Direction,
+    tx: Direction,
+}
+
+impl<D: Device> FaultyDevice<D> {
+    /// Wrap `inner`, applying `config`.
+    pub fn new(inner: D, config: FaultConfig) -> Self {
+        // Independent streams, so one direction's traffic does not shift
+        // the other's decisions
+        let rx = Direction::new(config.seed);
+        let tx = Direction::new(config.seed ^ 0x5555_5555_5555_5555);
+        Self {
+            inner,
+            shared: Arc::new(Mutex::new(Shared {
+                config,
+                stats: FaultStats::default(),
+            })),
+            rx,
+            tx,
+        }
+    }
+
+    /// Handle for changing the faults and reading the counters later.
+    pub fn handle(&self) -> FaultHandle {
+        FaultHandle {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Snapshot of the counters
+    pub fn stats(&self) -> FaultStats {
+        self.shared.lock().unwrap().stats
+    }
+
+    /// The wrapped device
+    pub fn inner(&self) -> &D {
+        &self.inner
+    }
+
+    /// The wrapped device, mutably
+    pub fn inner_mut(&mut self) -> &mut D {
+        &mut self.inner
+    }
+
+    /// Unwrap the device, discarding frames still delayed.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+
+    /// Write the transmitted frames that are due into the inner device.
+    fn flush_tx(&mut self, timestamp: Instant) {
+        while let Some(frame) = self.tx.pop_due(timestamp) {
+            let Some(token) = self.inner.transmit(timestamp) else {
+                self.tx.pending.push_front(Delayed {
+                    due: timestamp,
+                    frame,
+                });
+                return;
+            };
+            phy::TxToken::consume(token, frame.len(), |buf| buf.copy_from_slice(&frame));
+        }
+    }
+
+    /// Take newly received frames out of the inner device.
+    fn fill_rx(&mut self, timestamp: Instant) {
+        for _ in 0..RX_BURST {
+            let Some((token, _)) = self.inner.receive(timestamp) else {
+                break;
+            };
+            let frame = phy::RxToken::consume(token, |frame| frame.to_vec());
+            let mut shared = self.shared.lock().unwrap();
+            let Shared { config, stats } = &mut *shared;
+            self.rx.push(&config.rx, &mut stats.rx, timestamp, frame);
+        }
+    }
+
+    fn tx_token(&mut self, timestamp: Instant) -> FaultyTxToken<'_> {
+        FaultyTxToken {
+            direction: &mut self.tx,
+            shared: &self.shared,
+            timestamp,
+        }
+    }
+}
+
+impl<D: Device> Device for FaultyDevice<D> {
+    type RxToken<'a>
+        = FaultyRxToken
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = FaultyTxToken<'a>
+    where
+        Self: 'a;
+
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        self.flush_tx(timestamp);
+        self.fill_rx(timestamp);
+        let frame = self.rx.pop_due(timestamp)?;
+        Some((FaultyRxToken { frame }, self.tx_token(timestamp)))
+    }
+
+    fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        self.flush_tx(timestamp);
+        (self.tx.pending.len() < TX_PENDING_LIMIT).then(|| self.tx_token(timestamp))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+/// Forwards every hook to the wrapped device. Zero-copy UDP datagrams are
+/// taken and sent by the wrapped device itself, so they bypass the faults.
+impl<D: ReactorDevice> ReactorDevice for FaultyDevice<D> {
+    fn set_ipv4_addr(&mut self, ip: Ipv4Addr) {
+        self.inner.set_ipv4_addr(ip);
+    }
+
+    fn udp_zero_copy(&self) -> Option<&UdpZeroCopy> {
+        self.inner.udp_zero_copy()
+    }
+
+    fn tx_ready(&mut self) -> bool {
+        self.inner.tx_ready()
+    }
+}
+
+/// A received frame that made it through the faults.
+pub struct FaultyRxToken {
+    frame: Vec<u8>,
+}
+
+impl phy::RxToken for FaultyRxToken {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        f(&self.frame)
+    }
+}
+
+/// Collects a frame to transmit and subjects it to the faults.
+pub struct FaultyTxToken<'a> {
+    direction: &'a mut Direction,
+    shared: &'a Mutex<Shared>,
+    timestamp: Instant,
+}
+
+impl phy::TxToken for FaultyTxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut frame = vec![0u8; len];
+        let result = f(&mut frame);
+        let mut shared = self.shared.lock().unwrap();
+        let Shared { config, stats } = &mut *shared;
+        self.direction
+            .push(&config.tx, &mut stats.tx, self.timestamp, frame);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smoltcp::phy::{Loopback, Medium, RxToken, TxToken};
+
+    fn faulty(config: FaultConfig) -> FaultyDevice<Loopback> {
+        FaultyDevice::new(Loopback::new(Medium::Ethernet), config)
+    }
+
+    fn send(device: &mut FaultyDevice<Loopback>, now: Instant, id: u8) {
+        let token = device.transmit(now).unwrap();
+        token.consume(60, |buf| {
+            buf.fill(0);
+            buf[59] = id;
+        });
+    }
+
+    /// IDs of the frames received at `now`
+    fn receive(device: &mut FaultyDevice<Loopback>, now: Instant) -> Vec<u8> {
+        let mut ids = Vec::new();
+        while let Some((token, _)) = device.receive(now) {
+            ids.push(token.consume(|frame| frame[59]));
+        }
+        ids
+    }
+
+    #[test]
+    fn test_no_faults() {
+        let mut device = faulty(FaultConfig::new(1));
+        let now = Instant::from_millis(0);
+        for id in 0..10 {
+            send(&mut device, now, id);
+        }
+        assert_eq!(receive(&mut device, now), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_drop_is_deterministic() {
+        let run = |seed| {
+            let faults = Faults::new().drop_rate(0.5);
+            let mut device = faulty(FaultConfig::new(seed).tx(faults));
+            let now = Instant::from_millis(0);
+            for id in 0..100 {
+                send(&mut device, now, id);
+            }
+            let ids = receive(&mut device, now);
+            assert_eq!(ids.len() as u64, 100 - device.stats().tx.dropped);
+            ids
+        };
+        let ids = run(7);
+        assert!((20..80).contains(&ids.len()));
+        assert_eq!(run(7), ids);
+        assert_ne!(run(8), ids);
+    }
+
+    #[test]
+    fn test_latency_and_reorder() {
+        let faults = Faults::new().latency(Duration::from_millis(10));
+        let mut device = faulty(FaultConfig::new(1).tx(faults));
+        send(&mut device, Instant::from_millis(0), 1);
+        assert!(receive(&mut device, Instant::from_millis(5)).is_empty());
+        send(&mut device, Instant::from_millis(5), 2);
+        assert_eq!(receive(&mut device, Instant::from_millis(10)), [1]);
+        assert_eq!(receive(&mut device, Instant::from_millis(15)), [2]);
+
+        // Every frame held back: with nothing to overtake, order is kept
+        let faults = Faults::new().reorder(1.0, Duration::from_millis(1));
+        let mut device = faulty(FaultConfig::new(1).tx(faults));
+        send(&mut device, Instant::from_millis(0), 1);
+        assert!(receive(&mut device, Instant::from_millis(0)).is_empty());
+        assert_eq!(receive(&mut device, Instant::from_millis(1)), [1]);
+        assert_eq!(device.stats().tx.reordered, 1);
+    }
+
+    #[test]
+    fn test_corrupt_and_duplicate() {
+        let faults = Faults::new().corrupt_rate(1.0).duplicate_rate(1.0);
+        let mut device = faulty(FaultConfig::new(3).tx(faults));
+        let now = Instant::from_millis(0);
+        send(&mut device, now, 0);
+
+        let mut frames = Vec::new();
+        while let Some((token, _)) = device.receive(now) {
+            frames.push(token.consume(|frame| frame.to_vec()));
+        }
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], frames[1]);
+        let flipped: u32 = frames[0].iter().map(|byte| byte.count_ones()).sum();
+        assert_eq!(flipped, 1);
+
+        let stats = device.handle().stats();
+        assert_eq!((stats.tx.corrupted, stats.tx.duplicated), (1, 1));
+        assert_eq!(stats.rx.frames, 2);
+    }
+
+    /// Loopback that records the reactor hooks it gets
+    struct Hooked {
+        device: Loopback,
+        ip: Option<Ipv4Addr>,
+        ready: bool,
+    }
+
+    impl Device for Hooked {
+        type RxToken<'a> = <Loopback as Device>::RxToken<'a>;
+        type TxToken<'a> = <Loopback as Device>::TxToken<'a>;
+
+        fn receive(&mut self, now: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+            self.device.receive(now)
+        }
+
+        fn transmit(&mut self, now: Instant) -> Option<Self::TxToken<'_>> {
+            self.device.transmit(now)
+        }
+
+        fn capabilities(&self) -> DeviceCapabilities {
+            self.device.capabilities()
+        }
+    }
+
+    impl ReactorDevice for Hooked {
+        fn set_ipv4_addr(&mut self, ip: Ipv4Addr) {
+            self.ip = Some(ip);
+        }
+
+        fn tx_ready(&mut self) -> bool {
+            self.ready
+        }
+    }
+
+    #[test]
+    fn test_forwards_reactor_hooks() {
+        let hooked = Hooked {
+            device: Loopback::new(Medium::Ethernet),
+            ip: None,
+            ready: false,
+        };
+        let mut device = FaultyDevice::new(hooked, FaultConfig::new(1));
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        ReactorDevice::set_ipv4_addr(&mut device, ip);
+        assert_eq!(device.inner().ip, Some(ip));
+        assert!(!device.tx_ready());
+        device.inner_mut().ready = true;
+        assert!(device.tx_ready());
+        assert!(device.udp_zero_copy().is_none());
+    }
+}
@@ -0,0 +1,489 @@
+//! Cross-queue redirection of TCP segments that land on the wrong queue.
+//!
+//! Each queue runs its own smoltcp instance, so a segment RSS delivers to a
+//! queue that does not hold the connection is answered with an RST. That
+//! happens with asymmetric hashing, hash collisions, NICs whose RSS key is
+//! not symmetric, and for every client connection: the SYN-ACK of a
+//! `TcpStream::connect` made on queue N is hashed independently of the SYN.
+//!
+//! [`FlowRedirect`] keeps a flow-ownership table shared by all queues and one
+//! multi-producer ring per queue. A queue becomes the owner of a flow when it
+//! transmits the flow's SYN or SYN-ACK, and gives it up when it sends or
+//! receives an RST or the flow has been idle for
+//! [`DEFAULT_FLOW_IDLE_TIMEOUT`]. On receive, a segment for a flow the queue
+//! does not own is looked up in the table and, if another queue owns it,
+//! pushed onto that queue's ring instead of reaching smoltcp.
+//!
+//! The fast path is a lookup in the queue's own flow map; the shared table is
+//! only consulted for segments of flows the queue does not know.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use arrayvec::ArrayVec;
+use smoltcp::time::{Duration, Instant};
+
+use crate::api::rte::eth::{PortId, QueueId};
+use crate::api::rte::mbuf::Mbuf;
+use crate::api::rte::ring::{MbufRing, ring_flags};
+
+/// Default number of mbufs each per-queue redirect ring holds.
+pub const DEFAULT_FLOW_REDIRECT_RING_SIZE: u32 = 1024;
+
+/// Default time after which a flow with no traffic is forgotten.
+pub const DEFAULT_FLOW_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Number of independently locked shards in the ownership table.
+const TABLE_SHARDS: usize = 64;
+
+/// How often each queue drops its idle flows.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+const TCP_FLAG_RST: u8 = 0x04;
+const TCP_FLAG_SYN: u8 = 0x02;
+
+/// A TCP connection, seen from our side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlowKey {
+    /// Our address
+    pub local_addr: IpAddr,
+    /// Our port
+    pub local_port: u16,
+    /// Peer address
+    pub remote_addr: IpAddr,
+    /// Peer port
+    pub remote_port: u16,
+}
+
+/// Addresses, ports and flags of a TCP segment.
+struct TcpSegment {
+    src: IpAddr,
+    dst: IpAddr,
+    src_port: u16,
+    dst_port: u16,
+    flags: u8,
+}
+
+impl TcpSegment {
+    /// Parse an Ethernet/IPv4 or Ethernet/IPv6 TCP frame (no extension
+    /// headers, no fragments).
+    fn parse(frame: &[u8]) -> Option<Self> {
+        const TCP: u8 = 6;
+
+        let ip = frame.get(14..)?;
+        let (src, dst, tcp) = match frame.get(12..14)? {
+            [0x08, 0x00] => {
+                if ip.len() < 20 || ip[9] != TCP {
+                    return None;
+                }
+                // Later fragments carry no TCP header
+                if u16::from_be_bytes([ip[6], ip[7]]) & 0x1fff != 0 {
+                    return None;
+                }
+                let ihl = (ip[0] & 0x0f) as usize * 4;
+                let src: [u8; 4] = ip[12..16].try_into().ok()?;
+                let dst: [u8; 4] = ip[16..20].try_into().ok()?;
+                (
+                    IpAddr::V4(Ipv4Addr::from(src)),
+                    IpAddr::V4(Ipv4Addr::from(dst)),
+                    ip.get(ihl..)?,
+                )
+            }
+            [0x86, 0xdd] => {
+                if ip.len() < 40 || ip[6] != TCP {
+                    return None;
+                }
+                let src: [u8; 16] = ip[8..24].try_into().ok()?;
+                let dst: [u8; 16] = ip[24..40].try_into().ok()?;
+                (
+                    IpAddr::V6(Ipv6Addr::from(src)),
+                    IpAddr::V6(Ipv6Addr::from(dst)),
+                    &ip[40..],
+                )
+            }
+            _ => return None,
+        };
+        if tcp.len() < 14 {
+            return None;
+        }
+        Some(Self {
+            src,
+            dst,
+            src_port: u16::from_be_bytes([tcp[0], tcp[1]]),
+            dst_port: u16::from_be_bytes([tcp[2], tcp[3]]),
+            flags: tcp[13],
+        })
+    }
+
+    /// Key of a segment we received
+    fn rx_key(&self) -> FlowKey {
+        FlowKey {
+            local_addr: self.dst,
+            local_port: self.dst_port,
+            remote_addr: self.src,
+            remote_port: self.src_port,
+        }
+    }
+
+    /// Key of a segment we send
+    fn tx_key(&self) -> FlowKey {
+        FlowKey {
+            local_addr: self.src,
+            local_port: self.src_port,
+            remote_addr: self.dst,
+            remote_port: self.dst_port,
+        }
+    }
+}
+
+/// Flow-to-queue ownership, shared by all queues.
+struct FlowTable {
+    hasher: RandomState,
+    shards: Box<[Mutex<HashMap<FlowKey, QueueId>>]>,
+}
+
+impl FlowTable {
+    fn new() -> Self {
+        Self {
+            hasher: RandomState::new(),
+            shards: (0..TABLE_SHARDS)
+                .map(|_| Mutex::new(HashMap::new()))
+                .collect(),
+        }
+    }
+
+    #[inline]
+    fn shard(&self, key: &FlowKey) -> &Mutex<HashMap<FlowKey, QueueId>> {
+        &self.shards[self.hasher.hash_one(key) as usize % TABLE_SHARDS]
+    }
+
+    fn owner(&self, key: &FlowKey) -> Option<QueueId> {
+        self.shard(key).lock().unwrap().get(key).copied()
+    }
+
+    fn register(&self, key: FlowKey, queue_id: QueueId) {
+        self.shard(&key).lock().unwrap().insert(key, queue_id);
+    }
+
+    /// Remove `key` if `queue_id` still owns it.
+    fn unregister(&self, key: &FlowKey, queue_id: QueueId) {
+        let mut shard = self.shard(key).lock().unwrap();
+        if shard.get(key) == Some(&queue_id) {
+            shard.remove(key);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.lock().unwrap().len()).sum()
+    }
+}
+
+/// Per-queue redirect counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlowRedirectStats {
+    /// Segments other queues pushed onto this queue's ring
+    pub redirected: u64,
+    /// Segments for this queue dropped because its ring was full
+    pub dropped: u64,
+}
+
+#[derive(Default)]
+struct QueueCounters {
+    redirected: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Flow-ownership table and redirect rings for one port.
+///
+/// Create one per port and pass a clone to every queue's device with
+/// [`DpdkDevice::with_flow_redirect`](super::DpdkDevice::with_flow_redirect).
+#[derive(Clone)]
+pub struct FlowRedirect {
+    table: Arc<FlowTable>,
+    rings: Arc<[MbufRing]>,
+    counters: Arc<[QueueCounters]>,
+}
+
+impl FlowRedirect {
+    /// Create the table and `nb_queues` rings of `ring_size` mbufs each
+    /// (a power of two).
+    ///
+    /// Rings are named `flow_redirect_p{port}_q{queue}`, so one instance per
+    /// port can exist at a time.
+    pub fn new(port_id: PortId, nb_queues: u16, ring_size: u32) -> crate::api::Result<Self> {
+        if nb_queues == 0 {
+            return Err(crate::api::Errno::EINVAL);
+        }
+        // Any queue may enqueue; only the owning queue dequeues
+        let rings = (0..nb_queues)
+            .map(|queue_id| {
+                MbufRing::create(
+                    format!("flow_redirect_p{port_id}_q{queue_id}"),
+                    ring_size,
+                    -1,
+                    ring_flags::SC_DEQ,
+                )
+            })
+            .collect::<crate::api::Result<Vec<_>>>()?;
+        Ok(Self::from_rings(rings))
+    }
+
+    fn from_rings(rings: Vec<MbufRing>) -> Self {
+        Self {
+            table: Arc::new(FlowTable::new()),
+            counters: rings.iter().map(|_| QueueCounters::default()).collect(),
+            rings: rings.into(),
+        }
+    }
+
+    /// Number of queues
+    pub fn nb_queues(&self) -> u16 {
+        self.rings.len() as u16
+    }
+
+    /// Queue that owns `key`, if any.
+    pub fn owner(&self, key: &FlowKey) -> Option<QueueId> {
+        self.table.owner(key)
+    }
+
+    /// Make `queue_id` the owner of `key`.
+    ///
+    /// Devices register their flows themselves; this is for flows set up
+    /// outside smoltcp.
+    pub fn register(&self, key: FlowKey, queue_id: QueueId) {
+        self.table.register(key, queue_id);
+    }
+
+    /// Number of flows in the table
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    /// Check if no flows are registered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Per-queue counters, indexed by queue ID.
+    pub fn stats(&self) -> Vec<FlowRedirectStats> {
+        self.counters
+            .iter()
+            .map(|c| FlowRedirectStats {
+                redirected: c.redirected.load(Ordering::Relaxed),
+                dropped: c.dropped.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    /// Push `mbuf` onto `queue_id`'s ring, dropping it if the ring is full.
+    fn forward(&self, queue_id: QueueId, mbuf: Mbuf) {
+        let counters = &self.counters[queue_id as usize];
+        match self.rings[queue_id as usize].enqueue_one(mbuf) {
+            Ok(()) => counters.redirected.fetch_add(1, Ordering::Relaxed),
+            Err(_) => counters.dropped.fetch_add(1, Ordering::Relaxed),
+        };
+    }
+}
+
+/// One queue's view of flow ownership.
+pub(crate) struct FlowRedirectState {
+    redirect: FlowRedirect,
+    queue_id: QueueId,
+    /// Flows this queue owns, with the time of their last segment
+    flows: HashMap<FlowKey, Instant>,
+    idle_timeout: Duration,
+    /// Time of the current poll, for segments sent from TX tokens
+    now: Instant,
+    next_sweep: Instant,
+}
+
+impl FlowRedirectState {
+    pub(crate) fn new(redirect: FlowRedirect, queue_id: QueueId) -> Self {
+        Self {
+            redirect,
+            queue_id,
+            flows: HashMap::new(),
+            idle_timeout: DEFAULT_FLOW_IDLE_TIMEOUT,
+            now: Instant::ZERO,
+            next_sweep: Instant::ZERO,
+        }
+    }
+
+    pub(crate) fn set_idle_timeout(&mut self, timeout: Duration) {
+        self.idle_timeout = timeout;
+    }
+
+    /// Advance the clock and forget idle flows.
+    pub(crate) fn poll(&mut self, now: Instant) {
+        self.now = now;
+        if now < self.next_sweep {
+            return;
+        }
+        self.next_sweep = now + SWEEP_INTERVAL;
+        let idle_timeout = self.idle_timeout;
+        let table = &self.redirect.table;
+        let queue_id = self.queue_id;
+        self.flows.retain(|key, last_seen| {
+            let live = now - *last_seen < idle_timeout;
+            if !live {
+                table.unregister(key, queue_id);
+            }
+            live
+        });
+    }
+
+    /// Segments other queues redirected to us, waiting in our ring.
+    #[inline]
+    pub(crate) fn pending(&self) -> usize {
+        self.redirect.rings[self.queue_id as usize].len()
+    }
+
+    /// Dequeue at most `max` segments other queues redirected to us.
+    #[inline]
+    pub(crate) fn rx<const N: usize>(&self, mbufs: &mut ArrayVec<Mbuf, N>, max: usize) -> usize {
+        let ring = &self.redirect.rings[self.queue_id as usize];
+        if max == 0 || ring.is_empty() {
+            return 0;
+        }
+        ring.dequeue_up_to(mbufs, max)
+    }
+
+    /// Queue that should process a received frame instead of us, if any.
+    #[inline]
+    pub(crate) fn on_rx(&mut self, frame: &[u8]) -> Option<QueueId> {
+        let segment = TcpSegment::parse(frame)?;
+        let key = segment.rx_key();
+        if let Some(last_seen) = self.flows.get_mut(&key) {
+            if segment.flags & TCP_FLAG_RST != 0 {
+                self.forget(&key);
+            } else {
+                *last_seen = self.now;
+            }
+            return None;
+        }
+        self.redirect
+            .table
+            .owner(&key)
+            .filter(|&owner| owner != self.queue_id)
+    }
+
+    /// Track ownership from a frame we send.
+    #[inline]
+    pub(crate) fn on_tx(&mut self, frame: &[u8]) {
+        let Some(segment) = TcpSegment::parse(frame) else {
+            return;
+        };
+        let key = segment.tx_key();
+        if segment.flags & TCP_FLAG_RST != 0 {
+            self.forget(&key);
+        } else if segment.flags & TCP_FLAG_SYN != 0 {
+            // SYN (connect) or SYN-ACK (accept): the connection lives here
+            self.flows.insert(key, self.now);
+            self.redirect.table.register(key, self.queue_id);
+        } else if let Some(last_seen) = self.flows.get_mut(&key) {
+            *last_seen = self.now;
+        }
+    }
+
+    /// Hand a received frame to the queue that owns it.
+    #[inline]
+    pub(crate) fn forward(&self, queue_id: QueueId, mbuf: Mbuf) {
+        self.redirect.forward(queue_id, mbuf);
+    }
+
+    fn forget(&mut self, key: &FlowKey) {
+        self.flows.remove(key);
+        self.redirect.table.unregister(key, self.queue_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: [u8; 4] = [10, 0, 0, 1];
+    const SERVER: [u8; 4] = [10, 0, 0, 2];
+
+    /// Ethernet/IPv4/TCP frame
+    fn tcp_frame(src: [u8; 4], src_port: u16, dst: [u8; 4], dst_port: u16, flags: u8) -> Vec<u8> {
+        let mut frame = vec![0u8; 14 + 20 + 20];
+        frame[12..14].copy_from_slice(&[0x08, 0x00]);
+        frame[14] = 0x45;
+        frame[16..18].copy_from_slice(&40u16.to_be_bytes());
+        frame[22] = 64;
+        frame[23] = 6;
+        frame[26..30].copy_from_slice(&src);
+        frame[30..34].copy_from_slice(&dst);
+        frame[34..36].copy_from_slice(&src_port.to_be_bytes());
+        frame[36..38].copy_from_slice(&dst_port.to_be_bytes());
+        frame[46] = 0x50;
+        frame[47] = flags;
+        frame
+    }
+
+    #[test]
+    fn test_parse_segment() {
+        let frame = tcp_frame(CLIENT, 40000, SERVER, 80, TCP_FLAG_SYN);
+        let segment = TcpSegment::parse(&frame).unwrap();
+        assert_eq!(segment.flags, TCP_FLAG_SYN);
+        let key = segment.rx_key();
+        assert_eq!(key.local_addr, IpAddr::from(SERVER));
+        assert_eq!(key.local_port, 80);
+        assert_eq!(key.remote_addr, IpAddr::from(CLIENT));
+        assert_eq!(key.remote_port, 40000);
+
+        // UDP and truncated frames are not TCP segments
+        let mut udp = frame.clone();
+        udp[23] = 17;
+        assert!(TcpSegment::parse(&udp).is_none());
+        assert!(TcpSegment::parse(&frame[..40]).is_none());
+    }
+
+    #[test]
+    fn test_ownership() {
+        let redirect = FlowRedirect::from_rings(Vec::new());
+        let mut queue0 = FlowRedirectState::new(redirect.clone(), 0);
+        let mut queue1 = FlowRedirectState::new(redirect.clone(), 1);
+        let t0 = Instant::from_secs(10);
+        queue0.poll(t0);
+        queue1.poll(t0);
+
+        // Queue 1 connects: its SYN makes it the owner
+        queue1.on_tx(&tcp_frame(SERVER, 40000, CLIENT, 443, TCP_FLAG_SYN));
+        let syn_ack = tcp_frame(CLIENT, 443, SERVER, 40000, TCP_FLAG_SYN | 0x10);
+        assert_eq!(queue0.on_rx(&syn_ack), Some(1));
+        assert_eq!(queue1.on_rx(&syn_ack), None);
+
+        // Unknown flows stay where they arrived
+        let other = tcp_frame(CLIENT, 443, SERVER, 40001, 0x10);
+        assert_eq!(queue0.on_rx(&other), None);
+
+        // An RST gives the flow up
+        queue1.on_rx(&tcp_frame(CLIENT, 443, SERVER, 40000, TCP_FLAG_RST));
+        assert_eq!(queue0.on_rx(&syn_ack), None);
+        assert!(redirect.is_empty());
+    }
+
+    #[test]
+    fn test_idle_flows_expire() {
+        let redirect = FlowRedirect::from_rings(Vec::new());
+        let mut queue0 = FlowRedirectState::new(redirect.clone(), 0);
+        queue0.set_idle_timeout(Duration::from_secs(5));
+        let t0 = Instant::from_secs(10);
+        queue0.poll(t0);
+        queue0.on_tx(&tcp_frame(SERVER, 80, CLIENT, 40000, TCP_FLAG_SYN | 0x10));
+        assert_eq!(redirect.len(), 1);
+
+        // Traffic keeps the flow alive
+        queue0.poll(t0 + Duration::from_secs(4));
+        queue0.on_rx(&tcp_frame(CLIENT, 40000, SERVER, 80, 0x10));
+        queue0.poll(t0 + Duration::from_secs(8));
+        assert_eq!(redirect.len(), 1);
+
+        queue0.poll(t0 + Duration::from_secs(10));
+        assert!(redirect.is_empty());
+    }
+}
@@ -3,6 +3,17 @@
 //! This module provides:
 //! - [`DpdkDevice`]: A smoltcp `Device` implementation backed by DPDK RX/TX queues
 //! - [`SharedArpCache`]: Thread-safe ARP cache for multi-queue DPDK setups
+//! - [`SharedNdpCache`]: The same for IPv6 Neighbor Discovery
+//! - [`ArpAnnounceConfig`]: Gateway resolution and gratuitous ARP at startup
+//! - [`SoftRss`]: Software RSS dispatcher for NICs without hardware RSS
+//! - [`FlowRedirect`]: Hands TCP segments that land on the wrong queue to their owner
+//! - [`VlanDemux`]: Splits one RX queue between 802.1Q VLAN sub-interfaces
+//! - [`UdpBuf`]: Zero-copy UDP payload, backed by the mbuf it arrived in
+//! - [`Capture`]: pcapng / in-memory capture of a device's RX and TX frames
+//! - [`Classifier`]: Accepts, drops or diverts received packets before smoltcp
+//! - [`BurstConfig`]: RX burst and TX batch sizes, fixed or adapting to the load
+//! - [`DeviceCounters`]: Per-device datapath counters, readable from other threads
+//! - [`FaultyDevice`]: Drops, corrupts, delays and reorders frames of any device for testing
 //!
 //! # Multi-Queue ARP Sharing
 //!
@@ -16,9 +27,68 @@
 //! 2. Create [`DpdkDevice`] for each queue, passing the shared cache
 //! 3. Queue 0 will update the cache when it receives ARP replies
 //! 4. Other queues will check the cache and inject ARP packets into smoltcp
+//!
+//! IPv6 works the same way with [`SharedNdpCache`] and
+//! [`DpdkDevice::with_shared_ndp_cache`]: queue 0 records Neighbor
+//! Advertisements and other queues inject synthetic ones.
 
+mod arp_announce;
 mod arp_cache;
+mod bpf;
+mod burst;
+mod capture;
+mod checksum;
+mod classifier;
+mod counters;
 mod dpdk_device;
+mod fault;
+mod flow_redirect;
+mod ndp_cache;
+mod soft_rss;
+mod tso;
+mod udp_zero_copy;
+mod vlan;
 
-pub use arp_cache::{MacAddress, SharedArpCache, build_arp_reply_for_injection, parse_arp_reply};
+pub use arp_announce::{
+    ArpAnnounceConfig, DEFAULT_ANNOUNCE_INTERVAL, DEFAULT_GRATUITOUS_COUNT, DEFAULT_REQUEST_COUNT,
+};
+pub use arp_cache::{
+    ArpEntry, DEFAULT_ARP_TTL, MacAddress, SharedArpCache, build_arp_reply_for_injection,
+    build_arp_request, build_gratuitous_arp, parse_arp_reply,
+};
+pub use bpf::{BpfError, BpfInsn, BpfProgram};
+pub use burst::{
+    AdaptiveBurst, BurstConfig, DEFAULT_ADAPTIVE_SAMPLE_INTERVAL, DEFAULT_RX_BURST,
+    DEFAULT_TX_BATCH, MAX_RX_BURST, MAX_TX_BATCH,
+};
+pub use capture::{
+    Capture, CaptureConfig, CaptureFilter, CaptureStats, CapturedPacket, DEFAULT_SNAPLEN,
+    Direction, write_pcapng,
+};
+pub use classifier::{
+    Classifier, DEFAULT_DIVERT_QUEUE_LEN, DivertQueue, DivertRecvFuture, FiveTuple, Packet, Verdict,
+};
+pub use counters::{DeviceCounters, DeviceStats, RX_BURST_BUCKETS, rx_burst_bucket};
 pub use dpdk_device::*;
+pub use fault::{
+    DEFAULT_REORDER_DELAY, FaultConfig, FaultCounters, FaultHandle, FaultStats, Faults,
+    FaultyDevice, FaultyRxToken, FaultyTxToken,
+};
+pub use flow_redirect::{
+    DEFAULT_FLOW_IDLE_TIMEOUT, DEFAULT_FLOW_REDIRECT_RING_SIZE, FlowKey, FlowRedirect,
+    FlowRedirectStats,
+};
+pub use ndp_cache::{SharedNdpCache, build_neighbor_advert_for_injection, parse_neighbor_advert};
+pub use soft_rss::{
+    DEFAULT_SOFT_RSS_RING_SIZE, RSS_INPUT_MAX_LEN, SOFT_RSS_RETA_SIZE, SoftRss, SoftRssQueue,
+    SoftRssQueueStats, rss_input, toeplitz_hash,
+};
+pub use tso::{DEFAULT_TSO_MAX_FRAME_SIZE, TsoConfig, TsoMode};
+pub(crate) use udp_zero_copy::DEFAULT_HOP_LIMIT;
+pub use udp_zero_copy::{
+    DEFAULT_UDP_ZERO_COPY_QUEUE_LEN, UDP_ZERO_COPY_TX_HEADROOM, UdpBuf, UdpZeroCopy,
+};
+pub use vlan::{
+    DEFAULT_VLAN_QUEUE_LIMIT, ETHERTYPE_VLAN, VLAN_TAG_LEN, VlanDemux, VlanDemuxStats, VlanQueue,
+    ethertype, vlan_id,
+};
@@ -0,0 +1,357 @@
+//! Shared IPv6 neighbor cache for multi-queue DPDK setups.
+//!
+//! The IPv6 counterpart of [`SharedArpCache`](super::SharedArpCache):
+//! Neighbor Advertisements are ICMPv6 and land on a single queue, so
+//! connections on other queues would stall waiting for a neighbor they
+//! never see resolved.
+//!
+//! # Solution
+//!
+//! 1. Queue 0 detects Neighbor Advertisements and updates the shared cache
+//! 2. Other queues inject synthetic Neighbor Advertisements into their local
+//!    smoltcp interface whenever the cache changes
+//!
+//! Route ICMPv6 to queue 0 (e.g. hash IPv6 on TCP/UDP only, or an rte_flow
+//! rule), the same way ARP reaches queue 0 in the IPv4 setup.
+//!
+//! # Performance
+//!
+//! Same SPMC pattern as the ARP cache: queue 0 is the only writer, readers
+//! do a single atomic load via `arc-swap`.
+
+use arc_swap::ArcSwap;
+use std::collections::HashMap;
+use std::net::Ipv6Addr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::arp_cache::MacAddress;
+use super::checksum::ETH_HDR_LEN;
+
+/// Ethernet (14) + IPv6 (40)
+const ICMPV6_OFFSET: usize = 54;
+/// ICMPv6 header (4) + flags/reserved (4) + target address (16)
+const NA_LEN: usize = 24;
+/// Neighbor Advertisement with a target link-layer address option
+const NA_FRAME_LEN: usize = ICMPV6_OFFSET + NA_LEN + 8;
+
+const ICMPV6_NEXT_HEADER: u8 = 58;
+const ICMPV6_NEIGHBOR_ADVERT: u8 = 136;
+const NDP_OPT_TARGET_LLADDR: u8 = 2;
+/// Solicited and Override flags
+const NA_FLAGS_SOLICITED_OVERRIDE: u8 = 0x60;
+
+/// Thread-safe shared IPv6 neighbor cache using lock-free SPMC pattern.
+///
+/// Same semantics as [`SharedArpCache`](super::SharedArpCache): lock-free
+/// reads, copy-on-write inserts from the single producer (queue 0) and a
+/// version counter that bumps on every insert.
+#[derive(Clone)]
+pub struct SharedNdpCache {
+    inner: Arc<ArcSwap<HashMap<Ipv6Addr, MacAddress>>>,
+    /// Version counter that increments on every insert (even updates).
+    version: Arc<AtomicUsize>,
+}
+
+impl Default for SharedNdpCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedNdpCache {
+    /// Create a new empty shared neighbor cache.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(ArcSwap::from_pointee(HashMap::new())),
+            version: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Look up a MAC address for an IPv6 address.
+    ///
+    /// Lock-free: single atomic load.
+    #[inline]
+    pub fn get(&self, ip: &Ipv6Addr) -> Option<MacAddress> {
+        self.inner.load().get(ip).copied()
+    }
+
+    /// Insert or update a MAC address for an IPv6 address.
+    ///
+    /// Meant to be called from the single producer (queue 0): concurrent
+    /// inserts may lose an update.
+    pub fn insert(&self, ip: Ipv6Addr, mac: MacAddress) {
+        let current = self.inner.load();
+
+        if current.get(&ip) != Some(&mac) {
+            let mut new_map = (**current).clone();
+            new_map.insert(ip, mac);
+            self.inner.store(Arc::new(new_map));
+        }
+
+        // Bump even if unchanged: smoltcp's neighbor cache expires entries
+        // on its own, so consumers re-inject on every advertisement.
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    /// Check if an IPv6 address is in the cache.
+    #[inline]
+    pub fn contains(&self, ip: &Ipv6Addr) -> bool {
+        self.inner.load().contains_key(ip)
+    }
+
+    /// Get the version counter (increments on every insert/update).
+    #[inline(always)]
+    pub fn version(&self) -> usize {
+        self.version.load(Ordering::Relaxed)
+    }
+
+    /// Check if the cache is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.load().is_empty()
+    }
+
+    /// Get a snapshot of all entries for iteration.
+    #[inline]
+    pub fn snapshot(&self) -> arc_swap::Guard<Arc<HashMap<Ipv6Addr, MacAddress>>> {
+        self.inner.load()
+    }
+}
+
+fn ipv6_at(packet: &[u8], offset: usize) -> Ipv6Addr {
+    let mut octets = [0u8; 16];
+    octets.copy_from_slice(&packet[offset..offset + 16]);
+    Ipv6Addr::from(octets)
+}
+
+/// Check if a packet is a Neighbor Advertisement and extract the target.
+///
+/// # Arguments
+/// * `packet` - Raw Ethernet frame
+///
+/// # Returns
+/// `Some((target_ip, target_mac))` if this is a Neighbor Advertisement,
+/// `None` otherwise. The MAC comes from the target link-layer address option,
+/// or the Ethernet source if the option is absent.
+#[inline(always)]
+pub fn parse_neighbor_advert(packet: &[u8]) -> Option<(Ipv6Addr, MacAddress)> {
+    // Ethertype IPv6 (0x86DD), behind an 802.1Q tag or not
+    let (ethertype, l2_len) = super::vlan::ethertype(packet)?;
+    if ethertype != 0x86dd {
+        return None;
+    }
+    let mut mac = [0u8; 6];
+    mac.copy_from_slice(&packet[6..12]);
+    // Offsets below assume an untagged header
+    let packet = &packet[l2_len - ETH_HDR_LEN..];
+
+    // ICMPv6 without extension headers
+    if packet.len() < ICMPV6_OFFSET + NA_LEN || packet[20] != ICMPV6_NEXT_HEADER {
+        return None;
+    }
+
+    // RFC 4861: hop limit must be 255, code 0
+    if packet[21] != 255
+        || packet[ICMPV6_OFFSET] != ICMPV6_NEIGHBOR_ADVERT
+        || packet[ICMPV6_OFFSET + 1] != 0
+    {
+        return None;
+    }
+
+    let target_ip = ipv6_at(packet, ICMPV6_OFFSET + 8);
+
+    // Options are (type, length in 8-byte units, data)
+    let payload_len = u16::from_be_bytes([packet[18], packet[19]]) as usize;
+    let end = packet.len().min(ICMPV6_OFFSET + payload_len);
+    let mut offset = ICMPV6_OFFSET + NA_LEN;
+    while offset + 8 <= end {
+        let len = packet[offset + 1] as usize * 8;
+        if len == 0 {
+            return None;
+        }
+        if packet[offset] == NDP_OPT_TARGET_LLADDR {
+            mac.copy_from_slice(&packet[offset + 2..offset + 8]);
+            break;
+        }
+        offset += len;
+    }
+
+    Some((target_ip, mac))
+}
+
+/// ICMPv6 checksum over the IPv6 pseudo-header and the ICMPv6 message.
+fn icmpv6_checksum(src: &Ipv6Addr, dst: &Ipv6Addr, message: &[u8]) -> u16 {
+    let mut sum: u32 = 0;
+    let mut add = |bytes: &[u8]| {
+        for chunk in bytes.chunks(2) {
+            let word = match chunk {
+                [hi, lo] => u16::from_be_bytes([*hi, *lo]),
+                [hi] => u16::from_be_bytes([*hi, 0]),
+                _ => 0,
+            };
+            sum += word as u32;
+        }
+    };
+    add(&src.octets());
+    add(&dst.octets());
+    add(&(message.len() as u32).to_be_bytes());
+    add(&[0, 0, 0, ICMPV6_NEXT_HEADER]);
+    add(message);
+
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Build a Neighbor Advertisement for injection into smoltcp.
+///
+/// The advertisement appears to come from `peer_ip`/`peer_mac` and is sent to
+/// our unicast address with the Solicited and Override flags, so smoltcp
+/// updates its neighbor cache even if it already has an entry.
+///
+/// # Arguments
+/// * `our_mac` - Our interface's MAC address
+/// * `our_ip` - Our interface's IPv6 address
+/// * `peer_mac` - The peer's MAC address (to be cached)
+/// * `peer_ip` - The peer's IPv6 address (to be cached)
+///
+/// # Returns
+/// A complete Ethernet frame containing the Neighbor Advertisement.
+pub fn build_neighbor_advert_for_injection(
+    our_mac: MacAddress,
+    our_ip: Ipv6Addr,
+    peer_mac: MacAddress,
+    peer_ip: Ipv6Addr,
+) -> Vec<u8> {
+    let mut packet = vec![0u8; NA_FRAME_LEN];
+    let icmp_len = NA_FRAME_LEN - ICMPV6_OFFSET;
+
+    // Ethernet header
+    packet[0..6].copy_from_slice(&our_mac);
+    packet[6..12].copy_from_slice(&peer_mac);
+    packet[12..14].copy_from_slice(&[0x86, 0xdd]);
+
+    // IPv6 header
+    packet[14] = 0x60; // Version 6
+    packet[18..20].copy_from_slice(&(icmp_len as u16).to_be_bytes());
+    packet[20] = ICMPV6_NEXT_HEADER;
+    packet[21] = 255; // Hop limit
+    packet[22..38].copy_from_slice(&peer_ip.octets());
+    packet[38..54].copy_from_slice(&our_ip.octets());
+
+    // Neighbor Advertisement
+    let icmp = &mut packet[ICMPV6_OFFSET..];
+    icmp[0] = ICMPV6_NEIGHBOR_ADVERT;
+    icmp[4] = NA_FLAGS_SOLICITED_OVERRIDE;
+    icmp[8..24].copy_from_slice(&peer_ip.octets());
+
+    // Target link-layer address option
+    icmp[24] = NDP_OPT_TARGET_LLADDR;
+    icmp[25] = 1;
+    icmp[26..32].copy_from_slice(&peer_mac);
+
+    let checksum = icmpv6_checksum(&peer_ip, &our_ip, icmp);
+    icmp[2..4].copy_from_slice(&checksum.to_be_bytes());
+
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smoltcp::phy::ChecksumCapabilities;
+    use smoltcp::wire::{
+        EthernetFrame, Icmpv6Packet, Icmpv6Repr, Ipv6Packet, NdiscNeighborFlags, NdiscRepr,
+    };
+
+    const OUR_MAC: MacAddress = [0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff];
+    const PEER_MAC: MacAddress = [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc];
+
+    fn our_ip() -> Ipv6Addr {
+        "2001:db8::5".parse().unwrap()
+    }
+
+    fn peer_ip() -> Ipv6Addr {
+        "2001:db8::1".parse().unwrap()
+    }
+
+    #[test]
+    fn test_shared_ndp_cache() {
+        let cache = SharedNdpCache::new();
+        assert!(cache.is_empty());
+
+        cache.insert(peer_ip(), PEER_MAC);
+        cache.insert(peer_ip(), PEER_MAC);
+
+        assert_eq!(cache.get(&peer_ip()), Some(PEER_MAC));
+        assert!(cache.contains(&peer_ip()));
+        assert_eq!(cache.version(), 2);
+        assert_eq!(cache.snapshot().len(), 1);
+    }
+
+    #[test]
+    fn test_neighbor_advert_round_trip() {
+        let packet = build_neighbor_advert_for_injection(OUR_MAC, our_ip(), PEER_MAC, peer_ip());
+        assert_eq!(parse_neighbor_advert(&packet), Some((peer_ip(), PEER_MAC)));
+
+        // smoltcp accepts it, including the checksum
+        let frame = EthernetFrame::new_checked(&packet[..]).unwrap();
+        let ip = Ipv6Packet::new_checked(frame.payload()).unwrap();
+        assert_eq!(ip.hop_limit(), 255);
+        let icmp = Icmpv6Packet::new_checked(ip.payload()).unwrap();
+        let repr = Icmpv6Repr::parse(
+            &peer_ip(),
+            &our_ip(),
+            &icmp,
+            &ChecksumCapabilities::default(),
+        )
+        .unwrap();
+        let Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+            flags,
+            target_addr,
+            lladdr,
+        }) = repr
+        else {
+            panic!("Not a neighbor advertisement: {repr:?}");
+        };
+        assert_eq!(target_addr, peer_ip());
+        assert!(flags.contains(NdiscNeighborFlags::OVERRIDE));
+        assert!(lladdr.is_some());
+    }
+
+    #[test]
+    fn test_parse_neighbor_advert_without_option() {
+        let mut packet =
+            build_neighbor_advert_for_injection(OUR_MAC, our_ip(), PEER_MAC, peer_ip());
+        packet.truncate(ICMPV6_OFFSET + NA_LEN);
+        packet[18..20].copy_from_slice(&(NA_LEN as u16).to_be_bytes());
+        // Falls back to the Ethernet source
+        packet[6..12].copy_from_slice(&[2, 0, 0, 0, 0, 9]);
+        assert_eq!(
+            parse_neighbor_advert(&packet),
+            Some((peer_ip(), [2, 0, 0, 0, 0, 9]))
+        );
+    }
+
+    #[test]
+    fn test_parse_non_na_packet() {
+        let mut packet =
+            build_neighbor_advert_for_injection(OUR_MAC, our_ip(), PEER_MAC, peer_ip());
+        // Neighbor Solicitation
+        packet[ICMPV6_OFFSET] = 135;
+        assert!(parse_neighbor_advert(&packet).is_none());
+
+        // Hop limit other than 255
+        let mut packet =
+            build_neighbor_advert_for_injection(OUR_MAC, our_ip(), PEER_MAC, peer_ip());
+        packet[21] = 64;
+        assert!(parse_neighbor_advert(&packet).is_none());
+
+        // ARP
+        let mut packet = vec![0u8; 90];
+        packet[12..14].copy_from_slice(&[0x08, 0x06]);
+        assert!(parse_neighbor_advert(&packet).is_none());
+    }
+}
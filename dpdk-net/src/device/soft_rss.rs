@@ -0,0 +1,481 @@
+//! Software RSS for NICs without hardware RSS.
+//!
+//! virtio, net_tap, af_packet and most vdevs report `reta_size == 0`: all
+//! traffic arrives on RX queue 0 and multi-queue TCP cannot work, since each
+//! queue runs its own TCP stack. [`SoftRss`] fills the gap: one thread reads
+//! RX queue 0, hashes each packet's 5-tuple with the same Toeplitz function
+//! and key ([`RSS_KEY_40`]) the hardware path uses, and pushes it onto a
+//! per-queue ring. Each queue's [`DpdkDevice`](super::DpdkDevice) then reads
+//! from its ring ([`DpdkDevice::with_soft_rss`](super::DpdkDevice::with_soft_rss))
+//! instead of a hardware queue, while still transmitting on its own TX queue.
+//!
+//! Only TCP and UDP over IPv4/IPv6 are spread. Everything else (ARP, ICMP,
+//! NDP, fragments) goes to queue 0, as with hardware TCP RSS, so the shared
+//! ARP/NDP caches work unchanged.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use arrayvec::ArrayVec;
+
+use crate::api::rte::eth::{PortId, QueueId, RSS_KEY_40};
+use crate::api::rte::mbuf::Mbuf;
+use crate::api::rte::queue::{MAX_BURST_SIZE, RxQueue};
+use crate::api::rte::ring::{MbufRing, ring_flags};
+
+use super::vlan::VlanQueue;
+
+/// Default number of mbufs each per-queue ring holds.
+pub const DEFAULT_SOFT_RSS_RING_SIZE: u32 = 1024;
+
+/// Redirection table size; matches the common hardware RETA size so the
+/// hash-to-queue mapping is the same as with hardware RSS.
+pub const SOFT_RSS_RETA_SIZE: usize = 128;
+
+/// Longest input [`rss_input`] builds: two IPv6 addresses and the ports.
+pub const RSS_INPUT_MAX_LEN: usize = 36;
+
+// Every input SoftRss hashes is covered by its 40-byte keys
+const _: () = assert!(RSS_KEY_40.len() >= RSS_INPUT_MAX_LEN + 4);
+
+/// Toeplitz hash of `input` with `key`, as computed by RSS hardware.
+///
+/// Panics if `key` is shorter than `input.len() + 4` bytes; hardware never
+/// pads the key, so a shorter one would give hashes no NIC computes.
+pub fn toeplitz_hash(key: &[u8], input: &[u8]) -> u32 {
+    assert!(
+        key.len() >= input.len() + 4,
+        "Toeplitz key of {} bytes is too short for a {}-byte input",
+        key.len(),
+        input.len()
+    );
+    let mut hash = 0u32;
+    let mut window = u32::from_be_bytes([key[0], key[1], key[2], key[3]]);
+    let mut next_key_bit = 32;
+    for &byte in input {
+        for bit in (0..8).rev() {
+            if byte & (1 << bit) != 0 {
+                hash ^= window;
+            }
+            let incoming = (key[next_key_bit / 8] >> (7 - next_key_bit % 8)) & 1;
+            window = (window << 1) | incoming as u32;
+            next_key_bit += 1;
+        }
+    }
+    hash
+}
+
+/// Build the RSS hash input for a TCP or UDP frame.
+///
+/// Returns the input bytes (source address, destination address, source
+/// port, destination port) and their length, or `None` for anything that is
+/// not unfragmented TCP/UDP over IPv4/IPv6.
+pub fn rss_input(frame: &[u8]) -> Option<([u8; RSS_INPUT_MAX_LEN], usize)> {
+    const TCP: u8 = 6;
+    const UDP: u8 = 17;
+
+    let mut input = [0u8; RSS_INPUT_MAX_LEN];
+    if frame.len() < 14 {
+        return None;
+    }
+    let ip = &frame[14..];
+    let (addrs, l4) = match [frame[12], frame[13]] {
+        [0x08, 0x00] => {
+            if ip.len() < 20 {
+                return None;
+            }
+            let ihl = (ip[0] & 0x0f) as usize * 4;
+            // More fragments flag or fragment offset set
+            let fragmented = u16::from_be_bytes([ip[6], ip[7]]) & 0x3fff != 0;
+            if fragmented || !matches!(ip[9], TCP | UDP) {
+                return None;
+            }
+            (&ip[12..20], ip.get(ihl..)?)
+        }
+        [0x86, 0xdd] => {
+            if ip.len() < 40 || !matches!(ip[6], TCP | UDP) {
+                return None;
+            }
+            (&ip[8..40], &ip[40..])
+        }
+        _ => return None,
+    };
+    if l4.len() < 4 {
+        return None;
+    }
+    let len = addrs.len() + 4;
+    input[..addrs.len()].copy_from_slice(addrs);
+    input[addrs.len()..len].copy_from_slice(&l4[..4]);
+    Some((input, len))
+}
+
+/// RETA entry for a frame's hash; non-TCP/UDP goes to queue 0.
+#[inline]
+fn dispatch_queue(key: &[u8], reta: &[QueueId; SOFT_RSS_RETA_SIZE], frame: &[u8]) -> QueueId {
+    match rss_input(frame) {
+        Some((input, len)) => {
+            let hash = toeplitz_hash(key, &input[..len]);
+            reta[hash as usize % SOFT_RSS_RETA_SIZE]
+        }
+        None => 0,
+    }
+}
+
+/// Per-queue dispatch counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SoftRssQueueStats {
+    /// Packets pushed onto the queue's ring
+    pub dispatched: u64,
+    /// Packets dropped because the ring was full
+    pub dropped: u64,
+}
+
+#[derive(Default)]
+struct QueueCounters {
+    dispatched: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Receive side of one software RSS queue.
+///
+/// Pass to [`DpdkDevice::with_soft_rss`](super::DpdkDevice::with_soft_rss).
+#[derive(Clone)]
+pub struct SoftRssQueue {
+    ring: Arc<MbufRing>,
+    port_id: PortId,
+    queue_id: QueueId,
+}
+
+impl SoftRssQueue {
+    /// Port the packets were received on
+    #[inline]
+    pub fn port_id(&self) -> PortId {
+        self.port_id
+    }
+
+    /// Logical queue ID this ring feeds
+    #[inline]
+    pub fn queue_id(&self) -> QueueId {
+        self.queue_id
+    }
+
+    /// Number of packets waiting in the ring
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Check if no packets are waiting
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    /// Dequeue a burst of packets.
+    #[inline]
+    pub fn rx<const N: usize>(&self, mbufs: &mut ArrayVec<Mbuf, N>) -> usize {
+        self.ring.dequeue(mbufs)
+    }
+
+    /// Dequeue at most `max` packets into the remaining capacity of `mbufs`.
+    #[inline]
+    pub fn rx_up_to<const N: usize>(&self, mbufs: &mut ArrayVec<Mbuf, N>, max: usize) -> usize {
+        self.ring.dequeue_up_to(mbufs, max)
+    }
+}
+
+/// Where a [`DpdkDevice`](super::DpdkDevice) receives from.
+pub(crate) enum RxSource {
+    /// Hardware RX queue
+    Queue(RxQueue),
+    /// Software RSS ring
+    SoftRss(SoftRssQueue),
+    /// One VLAN's share of a hardware RX queue
+    Vlan(VlanQueue),
+}
+
+impl RxSource {
+    /// Receive at most `max` packets into the remaining capacity of `mbufs`.
+    #[inline]
+    pub(crate) fn rx<const N: usize>(&self, mbufs: &mut ArrayVec<Mbuf, N>, max: usize) -> usize {
+        match self {
+            RxSource::Queue(rxq) => rxq.rx_up_to(mbufs, max),
+            RxSource::SoftRss(queue) => queue.rx_up_to(mbufs, max),
+            RxSource::Vlan(queue) => queue.rx(mbufs, max),
+        }
+    }
+
+    /// Packets waiting to be received, if the source can tell.
+    pub(crate) fn depth(&self) -> Option<usize> {
+        match self {
+            RxSource::Queue(rxq) => rxq.count().ok(),
+            RxSource::SoftRss(queue) => Some(queue.len()),
+            RxSource::Vlan(_) => None,
+        }
+    }
+
+    pub(crate) fn port_id(&self) -> PortId {
+        match self {
+            RxSource::Queue(rxq) => rxq.port_id(),
+            RxSource::SoftRss(queue) => queue.port_id(),
+            RxSource::Vlan(queue) => queue.port_id(),
+        }
+    }
+
+    pub(crate) fn queue_id(&self) -> QueueId {
+        match self {
+            RxSource::Queue(rxq) => rxq.queue_id(),
+            RxSource::SoftRss(queue) => queue.queue_id(),
+            RxSource::Vlan(queue) => queue.queue_id(),
+        }
+    }
+}
+
+/// Software RSS dispatcher.
+///
+/// Reads one hardware RX queue and spreads packets over `nb_queues` rings.
+/// Run [`poll`](Self::poll) (or [`run`](Self::run)) on a single, DPDK
+/// registered thread.
+pub struct SoftRss {
+    rxq: RxQueue,
+    queues: Vec<SoftRssQueue>,
+    counters: Vec<QueueCounters>,
+    key: [u8; 40],
+    reta: [QueueId; SOFT_RSS_RETA_SIZE],
+    rx_batch: ArrayVec<Mbuf, MAX_BURST_SIZE>,
+    out: Vec<ArrayVec<Mbuf, MAX_BURST_SIZE>>,
+}
+
+impl SoftRss {
+    /// Create a dispatcher reading `rxq` and feeding `nb_queues` rings of
+    /// `ring_size` mbufs each (a power of two).
+    ///
+    /// Rings are named `soft_rss_p{port}_q{queue}`, so one dispatcher per
+    /// port can exist at a time.
+    pub fn new(rxq: RxQueue, nb_queues: u16, ring_size: u32) -> crate::api::Result<Self> {
+        if nb_queues == 0 {
+            return Err(crate::api::Errno::EINVAL);
+        }
+        let port_id = rxq.port_id();
+        let mut queues = Vec::with_capacity(nb_queues as usize);
+        for queue_id in 0..nb_queues {
+            let ring = MbufRing::create(
+                format!("soft_rss_p{port_id}_q{queue_id}"),
+                ring_size,
+                -1,
+                ring_flags::SP_ENQ | ring_flags::SC_DEQ,
+            )?;
+            queues.push(SoftRssQueue {
+                ring: Arc::new(ring),
+                port_id,
+                queue_id,
+            });
+        }
+
+        let queue_ids: Vec<QueueId> = (0..nb_queues).collect();
+        let mut soft_rss = Self {
+            rxq,
+            queues,
+            counters: (0..nb_queues).map(|_| QueueCounters::default()).collect(),
+            key: RSS_KEY_40,
+            reta: [0; SOFT_RSS_RETA_SIZE],
+            rx_batch: ArrayVec::new(),
+            out: (0..nb_queues).map(|_| ArrayVec::new()).collect(),
+        };
+        soft_rss.set_reta(&queue_ids)?;
+        Ok(soft_rss)
+    }
+
+    /// Use a different 40-byte Toeplitz key.
+    pub fn with_key(mut self, key: [u8; 40]) -> Self {
+        self.key = key;
+        self
+    }
+
+    /// Spread traffic round-robin over `queues`, like
+    /// [`EthDev::configure_rss_reta_queues`](crate::api::rte::eth::EthDev::configure_rss_reta_queues).
+    pub fn set_reta(&mut self, queues: &[QueueId]) -> crate::api::Result<()> {
+        if queues.is_empty() || queues.iter().any(|&q| q as usize >= self.queues.len()) {
+            return Err(crate::api::Errno::EINVAL);
+        }
+        for (i, entry) in self.reta.iter_mut().enumerate() {
+            *entry = queues[i % queues.len()];
+        }
+        Ok(())
+    }
+
+    /// Toeplitz key in use
+    pub fn key(&self) -> &[u8; 40] {
+        &self.key
+    }
+
+    /// Redirection table, indexed by hash modulo [`SOFT_RSS_RETA_SIZE`]
+    pub fn reta(&self) -> &[QueueId] {
+        &self.reta
+    }
+
+    /// Receive handle for `queue_id`.
+    pub fn queue(&self, queue_id: QueueId) -> Option<SoftRssQueue> {
+        self.queues.get(queue_id as usize).cloned()
+    }
+
+    /// Number of queues
+    pub fn nb_queues(&self) -> u16 {
+        self.queues.len() as u16
+    }
+
+    /// Queue a frame is dispatched to.
+    #[inline]
+    pub fn queue_for(&self, frame: &[u8]) -> QueueId {
+        dispatch_queue(&self.key, &self.reta, frame)
+    }
+
+    /// Dispatch one burst from the RX queue.
+    ///
+    /// Returns the number of packets received (dispatched or dropped).
+    pub fn poll(&mut self) -> usize {
+        let received = self.rxq.rx(&mut self.rx_batch);
+        if received == 0 {
+            return 0;
+        }
+
+        for mbuf in self.rx_batch.drain(..) {
+            // Headers are in the first segment
+            let queue = dispatch_queue(&self.key, &self.reta, mbuf.data());
+            // out[queue] has room for a full burst
+            self.out[queue as usize].push(mbuf);
+        }
+
+        for (queue, out) in self.out.iter_mut().enumerate() {
+            if out.is_empty() {
+                continue;
+            }
+            let counters = &self.counters[queue];
+            let enqueued = self.queues[queue].ring.enqueue(out);
+            counters
+                .dispatched
+                .fetch_add(enqueued as u64, Ordering::Relaxed);
+            if !out.is_empty() {
+                // Ring full: the queue is not keeping up, drop the rest
+                counters
+                    .dropped
+                    .fetch_add(out.len() as u64, Ordering::Relaxed);
+                out.clear();
+            }
+        }
+        received
+    }
+
+    /// Dispatch until `cancel` is set.
+    ///
+    /// The calling thread must be registered with DPDK.
+    pub fn run(&mut self, cancel: &AtomicBool) {
+        while !cancel.load(Ordering::Relaxed) {
+            if self.poll() == 0 {
+                std::hint::spin_loop();
+            }
+        }
+    }
+
+    /// Counters for each queue, indexed by queue ID.
+    pub fn stats(&self) -> Vec<SoftRssQueueStats> {
+        self.counters
+            .iter()
+            .map(|c| SoftRssQueueStats {
+                dispatched: c.dispatched.load(Ordering::Relaxed),
+                dropped: c.dropped.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frame with the given L3/L4 headers after a 14-byte Ethernet header
+    fn frame(ethertype: [u8; 2], l3: &[u8], ports: (u16, u16)) -> Vec<u8> {
+        let mut frame = vec![0u8; 14];
+        frame[12..14].copy_from_slice(&ethertype);
+        frame.extend_from_slice(l3);
+        frame.extend_from_slice(&ports.0.to_be_bytes());
+        frame.extend_from_slice(&ports.1.to_be_bytes());
+        frame.extend_from_slice(&[0; 16]);
+        frame
+    }
+
+    fn ipv4(proto: u8, src: [u8; 4], dst: [u8; 4]) -> Vec<u8> {
+        let mut ip = vec![0u8; 20];
+        ip[0] = 0x45;
+        ip[9] = proto;
+        ip[12..16].copy_from_slice(&src);
+        ip[16..20].copy_from_slice(&dst);
+        ip
+    }
+
+    #[test]
+    fn test_toeplitz_verification_suite() {
+        // Microsoft RSS verification suite, IPv4 with TCP ports
+        let cases = [
+            (
+                [66, 9, 149, 187],
+                2794,
+                [161, 142, 100, 80],
+                1766,
+                0x51cc_c178,
+            ),
+            (
+                [199, 92, 111, 2],
+                14230,
+                [65, 69, 140, 83],
+                4739,
+                0xc626_b0ea,
+            ),
+            (
+                [24, 19, 198, 95],
+                12898,
+                [12, 22, 207, 184],
+                38024,
+                0x5c2b_394a,
+            ),
+        ];
+        for (src, sport, dst, dport, expected) in cases {
+            let f = frame([0x08, 0x00], &ipv4(6, src, dst), (sport, dport));
+            let (input, len) = rss_input(&f).unwrap();
+            assert_eq!(len, 12);
+            assert_eq!(toeplitz_hash(&RSS_KEY_40, &input[..len]), expected);
+        }
+
+        // IPv6 with TCP ports
+        let src: std::net::Ipv6Addr = "3ffe:2501:200:1fff::7".parse().unwrap();
+        let dst: std::net::Ipv6Addr = "3ffe:2501:200:3::1".parse().unwrap();
+        let mut ip = vec![0u8; 40];
+        ip[0] = 0x60;
+        ip[6] = 6;
+        ip[8..24].copy_from_slice(&src.octets());
+        ip[24..40].copy_from_slice(&dst.octets());
+        let f = frame([0x86, 0xdd], &ip, (2794, 1766));
+        let (input, len) = rss_input(&f).unwrap();
+        assert_eq!(len, 36);
+        assert_eq!(toeplitz_hash(&RSS_KEY_40, &input[..len]), 0x4020_7d3d);
+    }
+
+    #[test]
+    #[should_panic(expected = "too short")]
+    fn test_toeplitz_short_key() {
+        toeplitz_hash(&RSS_KEY_40[..15], &[0; 12]);
+    }
+
+    #[test]
+    fn test_rss_input_non_tcp_udp() {
+        // ICMP
+        let f = frame([0x08, 0x00], &ipv4(1, [10, 0, 0, 1], [10, 0, 0, 2]), (0, 0));
+        assert!(rss_input(&f).is_none());
+
+        // Fragment
+        let mut ip = ipv4(17, [10, 0, 0, 1], [10, 0, 0, 2]);
+        ip[6] = 0x20;
+        assert!(rss_input(&frame([0x08, 0x00], &ip, (1, 2))).is_none());
+
+        // ARP
+        assert!(rss_input(&frame([0x08, 0x06], &[0; 28], (0, 0))).is_none());
+    }
+}
@@ -0,0 +1,634 @@
+//! TCP segmentation offload for [`DpdkDevice`](super::DpdkDevice).
+//!
+//! With TSO enabled the device advertises a larger MTU to smoltcp, so TCP
+//! segments of up to [`TsoConfig::max_frame_size`] bytes come out of the
+//! stack in one piece. The NIC splits them into wire-sized segments, or
+//! rte_gso does it in software when the NIC lacks the capability.
+//!
+//! smoltcp never sends more than the MSS the peer advertised, and advertises
+//! an MSS derived from the device MTU. The device therefore rewrites the MSS
+//! option of SYN segments: received SYNs announce the large segment size to
+//! smoltcp (the peer's real MSS is remembered per connection and used as the
+//! TSO segment size), and sent SYNs announce the real MTU to the peer.
+//!
+//! Only TCP/IPv4 frames are segmented. Other frames larger than the real MTU
+//! (e.g. UDP datagrams) are dropped and counted in `tx_dropped`. IPv6 SYNs
+//! keep the peer's MSS on receive, so TCP/IPv6 segments always fit the MTU,
+//! but sent ones are clamped like IPv4 SYNs.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use arrayvec::ArrayVec;
+
+use crate::api::Errno;
+use crate::api::rte::eth::ChecksumOffload;
+use crate::api::rte::gso::{GsoContext, MAX_GSO_SEGMENTS};
+use crate::api::rte::mbuf::{Mbuf, ol_flags};
+use crate::api::rte::pktmbuf::MemPool;
+
+use super::checksum::ETH_HDR_LEN;
+use super::counters::DeviceCounters;
+
+/// IPv4 + TCP header length without options
+const TCPIP_HDR_LEN: usize = 40;
+
+/// IPv6 + TCP header length without options or extension headers
+const TCPIP6_HDR_LEN: usize = 60;
+
+const TCP_FLAG_SYN: u8 = 0x02;
+const TCP_FLAG_RST: u8 = 0x04;
+const TCP_OPT_END: u8 = 0;
+const TCP_OPT_NOP: u8 = 1;
+const TCP_OPT_MSS: u8 = 2;
+
+/// Largest frame handed to the device by default (maximum IPv4 packet)
+pub const DEFAULT_TSO_MAX_FRAME_SIZE: usize = ETH_HDR_LEN + u16::MAX as usize;
+
+/// Number of connections whose peer MSS is tracked before the table is reset
+const MAX_TRACKED_FLOWS: usize = 65536;
+
+/// Where large TCP segments get split.
+#[derive(Clone)]
+pub enum TsoMode {
+    /// The NIC segments (`RTE_ETH_TX_OFFLOAD_TCP_TSO` is enabled on the port)
+    Hardware,
+    /// rte_gso segments in software before transmit
+    Software(GsoContext),
+}
+
+/// TSO configuration for [`DpdkDevice::with_tso`](super::DpdkDevice::with_tso).
+///
+/// Use [`TsoConfig::hardware`] if [`EthDev::tso_enabled`](crate::api::rte::eth::EthDev::tso_enabled)
+/// reports TSO on the port, otherwise [`TsoConfig::software`].
+#[derive(Clone)]
+pub struct TsoConfig {
+    mode: TsoMode,
+    max_frame_size: usize,
+}
+
+impl TsoConfig {
+    /// Segment in the NIC.
+    pub fn hardware() -> Self {
+        Self {
+            mode: TsoMode::Hardware,
+            max_frame_size: DEFAULT_TSO_MAX_FRAME_SIZE,
+        }
+    }
+
+    /// Segment in software with rte_gso.
+    pub fn software(gso: GsoContext) -> Self {
+        Self {
+            mode: TsoMode::Software(gso),
+            max_frame_size: DEFAULT_TSO_MAX_FRAME_SIZE,
+        }
+    }
+
+    /// Set the largest frame smoltcp may emit (default: [`DEFAULT_TSO_MAX_FRAME_SIZE`]).
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.max_frame_size = size.min(DEFAULT_TSO_MAX_FRAME_SIZE);
+        self
+    }
+
+    /// Get the segmentation mode.
+    pub fn mode(&self) -> &TsoMode {
+        &self.mode
+    }
+}
+
+/// Connection key from our side: (remote address, remote port, local port)
+type FlowKey = (IpAddr, u16, u16);
+
+/// Why a frame larger than the real MTU was dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LargeDrop {
+    NotTcpIpv4,
+    NoMbuf,
+    BatchFull,
+    Segmentation,
+}
+
+impl fmt::Display for LargeDrop {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LargeDrop::NotTcpIpv4 => "not TCP/IPv4",
+            LargeDrop::NoMbuf => "mbuf allocation failed",
+            LargeDrop::BatchFull => "TX batch full",
+            LargeDrop::Segmentation => "segmentation failed",
+        })
+    }
+}
+
+/// Per-device TSO state.
+pub(crate) struct TsoState {
+    mode: TsoMode,
+    max_frame_size: usize,
+    /// MSS for the real MTU over IPv4
+    mss: u16,
+    /// MSS for the real MTU over IPv6
+    mss6: u16,
+    /// Segment size per connection: min(peer MSS, our MSS)
+    segment_sizes: HashMap<FlowKey, u16>,
+    /// Why the last large frame was dropped, until one is queued again
+    last_drop: Option<LargeDrop>,
+}
+
+impl TsoState {
+    pub(crate) fn new(config: TsoConfig, mtu: usize) -> Self {
+        Self {
+            mode: config.mode,
+            max_frame_size: config.max_frame_size.max(mtu),
+            mss: (mtu - ETH_HDR_LEN - TCPIP_HDR_LEN) as u16,
+            mss6: (mtu - ETH_HDR_LEN - TCPIP6_HDR_LEN) as u16,
+            segment_sizes: HashMap::new(),
+            last_drop: None,
+        }
+    }
+
+    /// MTU advertised to smoltcp.
+    pub(crate) fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// TX batch slots one frame may need.
+    pub(crate) fn tx_slots(&self) -> usize {
+        match self.mode {
+            TsoMode::Hardware => 1,
+            TsoMode::Software(_) => MAX_GSO_SEGMENTS,
+        }
+    }
+
+    /// Raise the MSS announced by a received IPv4 SYN and remember the real one.
+    pub(crate) fn on_rx(&mut self, frame: &mut [u8]) {
+        let Some(hdrs) = TcpHeaders::parse(frame) else {
+            return;
+        };
+        // Only TCP/IPv4 is segmented: IPv6 peers keep their MSS
+        if hdrs.ipv6 {
+            return;
+        }
+        let flags = hdrs.flags(frame);
+        let key = (
+            hdrs.src_addr(frame),
+            hdrs.src_port(frame),
+            hdrs.dst_port(frame),
+        );
+        if flags & TCP_FLAG_RST != 0 {
+            self.segment_sizes.remove(&key);
+            return;
+        }
+        if flags & TCP_FLAG_SYN == 0 {
+            return;
+        }
+        let Some(offset) = hdrs.mss_offset(frame) else {
+            return;
+        };
+        let peer_mss = read_u16(frame, offset);
+        let segment_size = peer_mss.min(self.mss);
+        if self.segment_sizes.len() >= MAX_TRACKED_FLOWS {
+            self.segment_sizes.clear();
+        }
+        self.segment_sizes.insert(key, segment_size);
+
+        // Keep each large segment within what one segmentation call produces
+        let large_mss = (self.max_frame_size - ETH_HDR_LEN - TCPIP_HDR_LEN)
+            .min(segment_size as usize * MAX_GSO_SEGMENTS)
+            .min(u16::MAX as usize) as u16;
+        if large_mss > peer_mss {
+            hdrs.rewrite_mss(frame, offset, large_mss, true);
+        }
+    }
+
+    /// Clamp the MSS announced by an outgoing SYN to the real MTU.
+    ///
+    /// `update_checksum` is false when the TCP checksum is left to the NIC.
+    pub(crate) fn on_tx(&mut self, frame: &mut [u8], update_checksum: bool) {
+        let Some(hdrs) = TcpHeaders::parse(frame) else {
+            return;
+        };
+        let flags = hdrs.flags(frame);
+        if flags & TCP_FLAG_RST != 0 {
+            let key = (
+                hdrs.dst_addr(frame),
+                hdrs.dst_port(frame),
+                hdrs.src_port(frame),
+            );
+            self.segment_sizes.remove(&key);
+            return;
+        }
+        if flags & TCP_FLAG_SYN == 0 {
+            return;
+        }
+        let mss = if hdrs.ipv6 { self.mss6 } else { self.mss };
+        if let Some(offset) = hdrs.mss_offset(frame)
+            && read_u16(frame, offset) > mss
+        {
+            hdrs.rewrite_mss(frame, offset, mss, update_checksum);
+        }
+    }
+
+    /// Queue a frame larger than the real MTU for segmentation.
+    ///
+    /// Dropped frames are counted; the log only says when the reason changes.
+    pub(crate) fn queue_large<const N: usize>(
+        &mut self,
+        frame: &[u8],
+        mempool: &MemPool,
+        tx_batch: &mut ArrayVec<Mbuf, N>,
+        checksum: &ChecksumOffload,
+        counters: &DeviceCounters,
+    ) {
+        match self.segment_large(frame, mempool, tx_batch, checksum) {
+            Ok(()) => self.last_drop = None,
+            Err(reason) => {
+                if reason == LargeDrop::NoMbuf {
+                    counters.tx_alloc_failure();
+                }
+                counters.tx_dropped();
+                if self.last_drop.replace(reason) != Some(reason) {
+                    tracing::warn!(len = frame.len(), "Dropping large TX frames: {}", reason);
+                }
+            }
+        }
+    }
+
+    fn segment_large<const N: usize>(
+        &self,
+        frame: &[u8],
+        mempool: &MemPool,
+        tx_batch: &mut ArrayVec<Mbuf, N>,
+        checksum: &ChecksumOffload,
+    ) -> Result<(), LargeDrop> {
+        let hdrs = TcpHeaders::parse(frame)
+            .filter(|hdrs| !hdrs.ipv6)
+            .ok_or(LargeDrop::NotTcpIpv4)?;
+        let key = (
+            hdrs.dst_addr(frame),
+            hdrs.dst_port(frame),
+            hdrs.src_port(frame),
+        );
+        let segment_size = self.segment_sizes.get(&key).copied().unwrap_or(self.mss);
+
+        let mut pkt = Mbuf::alloc_from_slice(mempool, frame).ok_or(LargeDrop::NoMbuf)?;
+        pkt.set_tx_offload(
+            ol_flags::TX_IPV4 | ol_flags::TX_IP_CKSUM | ol_flags::TX_TCP_SEG,
+            ETH_HDR_LEN as u16,
+            hdrs.l3_len as u16,
+            hdrs.l4_len as u16,
+        );
+        pkt.set_tso_segsz(segment_size);
+
+        match &self.mode {
+            TsoMode::Hardware => {
+                pkt.prepare_tx_cksum();
+                tx_batch.try_push(pkt).map_err(|_| LargeDrop::BatchFull)?;
+            }
+            TsoMode::Software(gso) => {
+                let gso_size = (ETH_HDR_LEN + hdrs.l3_len + hdrs.l4_len) as u16 + segment_size;
+                let start = tx_batch.len();
+                gso.segment(pkt, gso_size, tx_batch)
+                    .map_err(|errno| match errno {
+                        Errno::ENOBUFS => LargeDrop::BatchFull,
+                        Errno::ENOMEM => LargeDrop::NoMbuf,
+                        _ => LargeDrop::Segmentation,
+                    })?;
+                for seg in &mut tx_batch[start..] {
+                    finish_segment(seg, &hdrs, checksum);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fill in checksums of a packet produced by software segmentation.
+fn finish_segment(seg: &mut Mbuf, hdrs: &TcpHeaders, checksum: &ChecksumOffload) {
+    seg.clear_ol_flags(ol_flags::TX_TCP_SEG);
+    let (l3_len, l4_len) = (hdrs.l3_len as u16, hdrs.l4_len as u16);
+    if checksum.tx_ipv4 && checksum.tx_tcp {
+        seg.set_tx_offload(
+            ol_flags::TX_IPV4 | ol_flags::TX_IP_CKSUM | ol_flags::TX_TCP_CKSUM,
+            ETH_HDR_LEN as u16,
+            l3_len,
+            l4_len,
+        );
+        seg.prepare_tx_cksum();
+    } else {
+        seg.set_tx_offload(0, ETH_HDR_LEN as u16, l3_len, l4_len);
+        seg.compute_tx_cksum();
+    }
+}
+
+fn read_u16(frame: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([frame[offset], frame[offset + 1]])
+}
+
+fn read_ipv4(frame: &[u8], offset: usize) -> IpAddr {
+    let octets: [u8; 4] = frame[offset..offset + 4].try_into().unwrap();
+    IpAddr::V4(Ipv4Addr::from(octets))
+}
+
+fn read_ipv6(frame: &[u8], offset: usize) -> IpAddr {
+    let octets: [u8; 16] = frame[offset..offset + 16].try_into().unwrap();
+    IpAddr::V6(Ipv6Addr::from(octets))
+}
+
+/// Incrementally update an Internet checksum for a changed 16-bit word (RFC 1624).
+fn update_checksum(checksum: u16, old: u16, new: u16) -> u16 {
+    let mut sum = (!checksum as u32) + (!old as u32) + new as u32;
+    sum = (sum & 0xffff) + (sum >> 16);
+    sum = (sum & 0xffff) + (sum >> 16);
+    !(sum as u16)
+}
+
+/// Header lengths of an Ethernet/IPv4/TCP or Ethernet/IPv6/TCP frame.
+///
+/// IPv6 packets with extension headers are not parsed.
+struct TcpHeaders {
+    ipv6: bool,
+    l3_len: usize,
+    l4_len: usize,
+}
+
+impl TcpHeaders {
+    fn parse(frame: &[u8]) -> Option<Self> {
+        if frame.len() < ETH_HDR_LEN + TCPIP_HDR_LEN {
+            return None;
+        }
+        let ip = &frame[ETH_HDR_LEN..];
+        let (ipv6, l3_len, protocol) = match frame[12..14] {
+            [0x08, 0x00] => (false, ((ip[0] & 0x0f) as usize) * 4, ip[9]),
+            [0x86, 0xdd] => (true, TCPIP6_HDR_LEN - 20, ip[6]),
+            _ => return None,
+        };
+        if protocol != 6 || l3_len < 20 || ip.len() < l3_len + 20 {
+            return None;
+        }
+        let l4_len = ((ip[l3_len + 12] >> 4) as usize) * 4;
+        if l4_len < 20 || ip.len() < l3_len + l4_len {
+            return None;
+        }
+        Some(Self {
+            ipv6,
+            l3_len,
+            l4_len,
+        })
+    }
+
+    fn tcp_offset(&self) -> usize {
+        ETH_HDR_LEN + self.l3_len
+    }
+
+    fn flags(&self, frame: &[u8]) -> u8 {
+        frame[self.tcp_offset() + 13]
+    }
+
+    fn src_addr(&self, frame: &[u8]) -> IpAddr {
+        if self.ipv6 {
+            read_ipv6(frame, ETH_HDR_LEN + 8)
+        } else {
+            read_ipv4(frame, ETH_HDR_LEN + 12)
+        }
+    }
+
+    fn dst_addr(&self, frame: &[u8]) -> IpAddr {
+        if self.ipv6 {
+            read_ipv6(frame, ETH_HDR_LEN + 24)
+        } else {
+            read_ipv4(frame, ETH_HDR_LEN + 16)
+        }
+    }
+
+    fn src_port(&self, frame: &[u8]) -> u16 {
+        read_u16(frame, self.tcp_offset())
+    }
+
+    fn dst_port(&self, frame: &[u8]) -> u16 {
+        read_u16(frame, self.tcp_offset() + 2)
+    }
+
+    /// Offset of the MSS option value, if present.
+    fn mss_offset(&self, frame: &[u8]) -> Option<usize> {
+        let end = self.tcp_offset() + self.l4_len;
+        let mut i = self.tcp_offset() + 20;
+        while i < end {
+            match frame[i] {
+                TCP_OPT_END => break,
+                TCP_OPT_NOP => i += 1,
+                kind => {
+                    let len = *frame.get(i + 1)? as usize;
+                    if len < 2 || i + len > end {
+                        return None;
+                    }
+                    if kind == TCP_OPT_MSS && len == 4 {
+                        return Some(i + 2);
+                    }
+                    i += len;
+                }
+            }
+        }
+        None
+    }
+
+    fn rewrite_mss(&self, frame: &mut [u8], offset: usize, mss: u16, update_checksum: bool) {
+        let old = read_u16(frame, offset);
+        frame[offset..offset + 2].copy_from_slice(&mss.to_be_bytes());
+        if update_checksum {
+            let csum_offset = self.tcp_offset() + 16;
+            let csum = self::update_checksum(read_u16(frame, csum_offset), old, mss);
+            frame[csum_offset..csum_offset + 2].copy_from_slice(&csum.to_be_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smoltcp::phy::ChecksumCapabilities;
+    use smoltcp::wire::{
+        EthernetAddress, EthernetFrame, EthernetProtocol, EthernetRepr, IpAddress, IpProtocol,
+        Ipv4Address, Ipv4Packet, Ipv4Repr, Ipv6Address, Ipv6Packet, Ipv6Repr, TcpControl,
+        TcpPacket, TcpRepr, TcpSeqNumber,
+    };
+
+    const LOCAL: Ipv4Address = Ipv4Address::new(10, 0, 0, 1);
+    const REMOTE: Ipv4Address = Ipv4Address::new(10, 0, 0, 2);
+    const LOCAL6: Ipv6Address = Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 1);
+    const REMOTE6: Ipv6Address = Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 2);
+
+    fn syn_repr(from_remote: bool, mss: u16) -> TcpRepr<'static> {
+        let (src_port, dst_port) = if from_remote {
+            (40000, 80)
+        } else {
+            (80, 40000)
+        };
+        TcpRepr {
+            src_port,
+            dst_port,
+            control: TcpControl::Syn,
+            seq_number: TcpSeqNumber(1),
+            ack_number: None,
+            window_len: 1024,
+            window_scale: None,
+            max_seg_size: Some(mss),
+            sack_permitted: false,
+            sack_ranges: [None; 3],
+            timestamp: None,
+            payload: &[],
+        }
+    }
+
+    /// Build an Ethernet/IPv4/TCP SYN from `src` with the given MSS option.
+    fn syn_frame(src: Ipv4Address, dst: Ipv4Address, mss: u16) -> Vec<u8> {
+        let tcp = syn_repr(src == REMOTE, mss);
+        let ip = Ipv4Repr {
+            src_addr: src,
+            dst_addr: dst,
+            next_header: IpProtocol::Tcp,
+            payload_len: tcp.header_len(),
+            hop_limit: 64,
+        };
+        let eth = EthernetRepr {
+            src_addr: EthernetAddress([0x02, 0, 0, 0, 0, 1]),
+            dst_addr: EthernetAddress([0x02, 0, 0, 0, 0, 2]),
+            ethertype: EthernetProtocol::Ipv4,
+        };
+        let caps = ChecksumCapabilities::default();
+        let mut buf = vec![0u8; ETH_HDR_LEN + 20 + tcp.header_len()];
+        let mut frame = EthernetFrame::new_unchecked(&mut buf);
+        eth.emit(&mut frame);
+        let mut ip_packet = Ipv4Packet::new_unchecked(frame.payload_mut());
+        ip.emit(&mut ip_packet, &caps);
+        let mut tcp_packet = TcpPacket::new_unchecked(ip_packet.payload_mut());
+        tcp.emit(
+            &mut tcp_packet,
+            &IpAddress::Ipv4(src),
+            &IpAddress::Ipv4(dst),
+            &caps,
+        );
+        buf
+    }
+
+    /// Build an Ethernet/IPv6/TCP SYN from `src` with the given MSS option.
+    fn syn6_frame(src: Ipv6Address, dst: Ipv6Address, mss: u16) -> Vec<u8> {
+        let tcp = syn_repr(src == REMOTE6, mss);
+        let ip = Ipv6Repr {
+            src_addr: src,
+            dst_addr: dst,
+            next_header: IpProtocol::Tcp,
+            payload_len: tcp.header_len(),
+            hop_limit: 64,
+        };
+        let eth = EthernetRepr {
+            src_addr: EthernetAddress([0x02, 0, 0, 0, 0, 1]),
+            dst_addr: EthernetAddress([0x02, 0, 0, 0, 0, 2]),
+            ethertype: EthernetProtocol::Ipv6,
+        };
+        let caps = ChecksumCapabilities::default();
+        let mut buf = vec![0u8; ETH_HDR_LEN + 40 + tcp.header_len()];
+        let mut frame = EthernetFrame::new_unchecked(&mut buf);
+        eth.emit(&mut frame);
+        let mut ip_packet = Ipv6Packet::new_unchecked(frame.payload_mut());
+        ip.emit(&mut ip_packet);
+        let mut tcp_packet = TcpPacket::new_unchecked(ip_packet.payload_mut());
+        tcp.emit(
+            &mut tcp_packet,
+            &IpAddress::Ipv6(src),
+            &IpAddress::Ipv6(dst),
+            &caps,
+        );
+        buf
+    }
+
+    /// Parse the MSS option and verify the TCP checksum.
+    fn mss_and_checksum_ok(frame: &[u8]) -> (Option<u16>, bool) {
+        let eth = EthernetFrame::new_checked(frame).unwrap();
+        let (src, dst, payload): (IpAddress, IpAddress, &[u8]) = match eth.ethertype() {
+            EthernetProtocol::Ipv6 => {
+                let ip = Ipv6Packet::new_checked(eth.payload()).unwrap();
+                (ip.src_addr().into(), ip.dst_addr().into(), ip.payload())
+            }
+            _ => {
+                let ip = Ipv4Packet::new_checked(eth.payload()).unwrap();
+                (ip.src_addr().into(), ip.dst_addr().into(), ip.payload())
+            }
+        };
+        let tcp = TcpPacket::new_checked(payload).unwrap();
+        let repr = TcpRepr::parse(&tcp, &src, &dst, &ChecksumCapabilities::ignored()).unwrap();
+        (repr.max_seg_size, tcp.verify_checksum(&src, &dst))
+    }
+
+    fn state() -> TsoState {
+        TsoState::new(TsoConfig::hardware(), 1500)
+    }
+
+    #[test]
+    fn test_update_checksum() {
+        // Recomputing after an incremental update matches a full recompute
+        let mut frame = syn_frame(REMOTE, LOCAL, 1460);
+        let hdrs = TcpHeaders::parse(&frame).unwrap();
+        let offset = hdrs.mss_offset(&frame).unwrap();
+        hdrs.rewrite_mss(&mut frame, offset, 9000, true);
+        assert_eq!(mss_and_checksum_ok(&frame), (Some(9000), true));
+        assert_eq!(update_checksum(0x1234, 0xabcd, 0xabcd), 0x1234);
+    }
+
+    #[test]
+    fn test_rx_syn_raises_mss() {
+        let mut tso = state();
+        let mut frame = syn_frame(REMOTE, LOCAL, 1460);
+        tso.on_rx(&mut frame);
+
+        let (mss, checksum_ok) = mss_and_checksum_ok(&frame);
+        assert!(checksum_ok);
+        // Large MSS is bounded by the segments one GSO call produces
+        let our_mss = tso.mss as usize;
+        let expected = (DEFAULT_TSO_MAX_FRAME_SIZE - ETH_HDR_LEN - TCPIP_HDR_LEN)
+            .min(our_mss * MAX_GSO_SEGMENTS);
+        assert_eq!(mss, Some(expected as u16));
+        // Segment size is min(peer MSS, our MSS)
+        assert_eq!(tso.segment_sizes[&(REMOTE.into(), 40000, 80)], tso.mss);
+    }
+
+    #[test]
+    fn test_tx_syn_clamps_mss() {
+        let mut tso = state();
+        let mut frame = syn_frame(LOCAL, REMOTE, 65000);
+        tso.on_tx(&mut frame, true);
+        assert_eq!(mss_and_checksum_ok(&frame), (Some(tso.mss), true));
+
+        // Already within the real MTU: untouched
+        let mut frame = syn_frame(LOCAL, REMOTE, 536);
+        let original = frame.clone();
+        tso.on_tx(&mut frame, true);
+        assert_eq!(frame, original);
+    }
+
+    #[test]
+    fn test_ipv6_syn() {
+        let mut tso = state();
+        // Sent: clamped to the IPv6 MSS, 20 bytes less than over IPv4
+        let mut frame = syn6_frame(LOCAL6, REMOTE6, 65000);
+        tso.on_tx(&mut frame, true);
+        assert_eq!(mss_and_checksum_ok(&frame), (Some(tso.mss - 20), true));
+
+        // Received: not raised, IPv6 is not segmented
+        let mut frame = syn6_frame(REMOTE6, LOCAL6, 1440);
+        let original = frame.clone();
+        tso.on_rx(&mut frame);
+        assert_eq!(frame, original);
+        assert!(tso.segment_sizes.is_empty());
+    }
+
+    #[test]
+    fn test_non_tcp_ignored() {
+        let mut tso = state();
+        let mut frame = syn_frame(REMOTE, LOCAL, 1460);
+        frame[12..14].copy_from_slice(&[0x08, 0x06]);
+        let original = frame.clone();
+        tso.on_rx(&mut frame);
+        assert_eq!(frame, original);
+        assert!(tso.segment_sizes.is_empty());
+    }
+}
@@ -0,0 +1,871 @@
+//! Zero-copy UDP receive and transmit.
+//!
+//! smoltcp copies every UDP payload into the socket's packet buffer, and
+//! [`UdpSocket::recv_from`](crate::socket::UdpSocket::recv_from) copies it
+//! again into the caller's slice. For small datagrams at high rates those
+//! copies dominate. With [`DpdkDevice::with_udp_zero_copy`](super::DpdkDevice::with_udp_zero_copy),
+//! the device takes datagrams for ports bound with
+//! [`UdpSocket::bind_zero_copy`](crate::socket::UdpSocket::bind_zero_copy)
+//! out of the RX burst before smoltcp sees them, and queues the mbufs
+//! themselves. The socket hands them out as [`UdpBuf`]s, which point into
+//! the mbuf and free it when dropped.
+//!
+//! Only unfragmented datagrams in a single-segment mbuf to one of the
+//! interface's unicast addresses (or IPv4 broadcast) take this path;
+//! everything else goes through smoltcp as before, and the socket returns
+//! it as a copied [`UdpBuf`].
+//!
+//! Sending works the other way round: the caller writes the payload into
+//! an mbuf from the device's mempool, and the socket prepends the
+//! Ethernet/IP/UDP headers in its headroom and queues it for the device,
+//! which fills in the checksums (or has the NIC do it) and transmits it.
+//! smoltcp's neighbor cache is private, so the device learns neighbor MACs
+//! itself from the ARP replies and Neighbor Advertisements it receives.
+//! Datagrams to a neighbor not (or no longer) known are copied through
+//! smoltcp instead, which resolves it.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::net::IpAddr;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use std::task::Waker;
+use std::time::{Duration, Instant};
+
+use arrayvec::ArrayVec;
+use smoltcp::iface::Interface;
+use smoltcp::phy::ChecksumCapabilities;
+use smoltcp::socket::udp::{SendError, UdpMetadata};
+use smoltcp::wire::{
+    EthernetAddress, EthernetFrame, EthernetProtocol, EthernetRepr, HardwareAddress, IpAddress,
+    IpCidr, IpEndpoint, IpProtocol, Ipv4Packet, Ipv4Repr, Ipv6Packet, Ipv6Repr, UdpPacket, UdpRepr,
+};
+
+use crate::api::rte::eth::ChecksumOffload;
+use crate::api::rte::mbuf::Mbuf;
+use crate::api::rte::pktmbuf::MemPool;
+
+use super::arp_cache::parse_arp_reply;
+use super::capture::CaptureTap;
+use super::checksum::{prepare_tx_offload, rx_checksum_ok};
+use super::ndp_cache::parse_neighbor_advert;
+use super::vlan::{VLAN_TAG_LEN, VlanState};
+
+/// Default number of datagrams queued per zero-copy port.
+pub const DEFAULT_UDP_ZERO_COPY_QUEUE_LEN: usize = 1024;
+
+/// Headroom a zero-copy TX mbuf needs: Ethernet, IPv6 and UDP headers plus
+/// an 802.1Q tag. Mbufs keep 128 bytes (`RTE_PKTMBUF_HEADROOM`) by default.
+pub const UDP_ZERO_COPY_TX_HEADROOM: usize = 14 + 40 + 8 + VLAN_TAG_LEN;
+
+/// How long a learned neighbor MAC is used before sends go through smoltcp
+/// again (as long as smoltcp keeps its own neighbor cache entries).
+const NEIGHBOR_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Most neighbors remembered at once; expired ones make room for new ones.
+const MAX_NEIGHBORS: usize = 1024;
+
+/// Hop limit of sent datagrams unless the socket sets one (smoltcp's default)
+pub(crate) const DEFAULT_HOP_LIMIT: u8 = 64;
+
+/// A received UDP payload.
+///
+/// Usually points into the mbuf the datagram arrived in, which is returned
+/// to its mempool when the buffer is dropped. Datagrams that went through
+/// smoltcp are held in a `Vec` instead. Holding many buffers holds as many
+/// mbufs, so drop them (or convert them into [`bytes::Bytes`] and drop
+/// those) promptly.
+pub struct UdpBuf {
+    inner: UdpBufInner,
+}
+
+enum UdpBufInner {
+    Mbuf {
+        mbuf: Mbuf,
+        offset: usize,
+        len: usize,
+    },
+    Vec(Vec<u8>),
+}
+
+impl UdpBuf {
+    /// Buffer for a payload smoltcp received (copied)
+    pub(crate) fn from_vec(payload: Vec<u8>) -> Self {
+        Self {
+            inner: UdpBufInner::Vec(payload),
+        }
+    }
+
+    /// Whether the payload is still in the mbuf it arrived in
+    pub fn is_zero_copy(&self) -> bool {
+        matches!(self.inner, UdpBufInner::Mbuf { .. })
+    }
+}
+
+impl Deref for UdpBuf {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        match &self.inner {
+            UdpBufInner::Mbuf { mbuf, offset, len } => &mbuf.data()[*offset..*offset + *len],
+            UdpBufInner::Vec(payload) => payload,
+        }
+    }
+}
+
+impl AsRef<[u8]> for UdpBuf {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl fmt::Debug for UdpBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UdpBuf")
+            .field("len", &self.len())
+            .field("zero_copy", &self.is_zero_copy())
+            .finish()
+    }
+}
+
+impl From<UdpBuf> for bytes::Bytes {
+    /// Wraps the buffer without copying; the mbuf is freed when the last
+    /// `Bytes` referring to it is dropped.
+    fn from(buf: UdpBuf) -> Self {
+        match buf.inner {
+            UdpBufInner::Vec(payload) => bytes::Bytes::from(payload),
+            UdpBufInner::Mbuf { .. } => bytes::Bytes::from_owner(buf),
+        }
+    }
+}
+
+/// A UDP datagram in an Ethernet frame.
+struct Datagram {
+    dst_addr: IpAddress,
+    dst_port: u16,
+    meta: UdpMetadata,
+    /// Payload position in the frame
+    offset: usize,
+    len: usize,
+}
+
+impl Datagram {
+    /// Parse an untagged frame, verifying the checksums `verify_*` asks for.
+    ///
+    /// Returns `None` for anything but a valid, unfragmented UDP datagram.
+    fn parse(frame: &[u8], verify_ip: bool, verify_udp: bool) -> Option<Self> {
+        let eth = EthernetFrame::new_checked(frame).ok()?;
+        let (src_addr, dst_addr, ip_payload, ip_offset) = match eth.ethertype() {
+            EthernetProtocol::Ipv4 => {
+                let ip = Ipv4Packet::new_checked(eth.payload()).ok()?;
+                if ip.next_header() != IpProtocol::Udp
+                    || ip.more_frags()
+                    || ip.frag_offset() != 0
+                    || (verify_ip && !ip.verify_checksum())
+                {
+                    return None;
+                }
+                let header_len = ip.header_len() as usize;
+                let payload = &eth.payload()[header_len..ip.total_len() as usize];
+                let (src, dst) = (ip.src_addr().into(), ip.dst_addr().into());
+                (
+                    src,
+                    dst,
+                    payload,
+                    EthernetFrame::<&[u8]>::header_len() + header_len,
+                )
+            }
+            EthernetProtocol::Ipv6 => {
+                let ip = Ipv6Packet::new_checked(eth.payload()).ok()?;
+                if ip.next_header() != IpProtocol::Udp {
+                    return None;
+                }
+                let (src, dst) = (ip.src_addr().into(), ip.dst_addr().into());
+                let offset = EthernetFrame::<&[u8]>::header_len() + ip.header_len();
+                (src, dst, ip.payload(), offset)
+            }
+            _ => return None,
+        };
+        let udp = UdpPacket::new_checked(ip_payload).ok()?;
+        if verify_udp && !udp.verify_checksum(&src_addr, &dst_addr) {
+            return None;
+        }
+        // IPv6 forbids a zero UDP checksum
+        if matches!(src_addr, IpAddress::Ipv6(_)) && udp.checksum() == 0 {
+            return None;
+        }
+        let mut meta = UdpMetadata::from(IpEndpoint::new(src_addr, udp.src_port()));
+        meta.local_address = Some(dst_addr);
+        Some(Self {
+            dst_addr,
+            dst_port: udp.dst_port(),
+            meta,
+            offset: ip_offset + 8,
+            len: udp.payload().len(),
+        })
+    }
+}
+
+/// Datagrams waiting for one zero-copy socket.
+struct PortQueue {
+    packets: VecDeque<(UdpBuf, UdpMetadata)>,
+    waker: Option<Waker>,
+    dropped: u64,
+    /// Datagrams the socket queued for the device directly
+    sent: u64,
+}
+
+struct Ports {
+    ports: HashMap<u16, PortQueue>,
+    /// Interface addresses datagrams may be sent to
+    local_addrs: Vec<IpAddr>,
+    queue_len: usize,
+}
+
+impl Ports {
+    fn accepts(&self, dst_addr: IpAddress) -> bool {
+        let dst: IpAddr = dst_addr.into();
+        self.local_addrs.contains(&dst) || matches!(dst, IpAddr::V4(addr) if addr.is_broadcast())
+    }
+}
+
+/// Link-layer and IP addresses of an outgoing datagram.
+struct TxHeaders {
+    src_mac: EthernetAddress,
+    dst_mac: EthernetAddress,
+    src: IpEndpoint,
+    dst: IpEndpoint,
+    hop_limit: u8,
+}
+
+impl TxHeaders {
+    fn ip_header_len(&self) -> usize {
+        match self.dst.addr {
+            IpAddress::Ipv4(_) => 20,
+            IpAddress::Ipv6(_) => 40,
+        }
+    }
+
+    /// Bytes in front of the payload
+    fn len(&self) -> usize {
+        EthernetFrame::<&[u8]>::header_len() + self.ip_header_len() + 8
+    }
+
+    /// Write the headers in front of the payload that ends `frame`.
+    ///
+    /// Checksums are left zero for [`fill_checksums`].
+    fn emit(&self, frame: &mut [u8]) {
+        let payload_len = frame.len() - self.len();
+        let udp_len = 8 + payload_len;
+        let ethertype = match self.dst.addr {
+            IpAddress::Ipv4(_) => EthernetProtocol::Ipv4,
+            IpAddress::Ipv6(_) => EthernetProtocol::Ipv6,
+        };
+        let mut eth = EthernetFrame::new_unchecked(frame);
+        EthernetRepr {
+            src_addr: self.src_mac,
+            dst_addr: self.dst_mac,
+            ethertype,
+        }
+        .emit(&mut eth);
+
+        let caps = ChecksumCapabilities::ignored();
+        let ip = eth.payload_mut();
+        match (self.src.addr, self.dst.addr) {
+            (IpAddress::Ipv4(src_addr), IpAddress::Ipv4(dst_addr)) => Ipv4Repr {
+                src_addr,
+                dst_addr,
+                next_header: IpProtocol::Udp,
+                payload_len: udp_len,
+                hop_limit: self.hop_limit,
+            }
+            .emit(&mut Ipv4Packet::new_unchecked(&mut *ip), &caps),
+            (IpAddress::Ipv6(src_addr), IpAddress::Ipv6(dst_addr)) => Ipv6Repr {
+                src_addr,
+                dst_addr,
+                next_header: IpProtocol::Udp,
+                payload_len: udp_len,
+                hop_limit: self.hop_limit,
+            }
+            .emit(&mut Ipv6Packet::new_unchecked(&mut *ip)),
+            _ => unreachable!("source address picked for the destination's family"),
+        }
+        UdpRepr {
+            src_port: self.src.port,
+            dst_port: self.dst.port,
+        }
+        .emit(
+            &mut UdpPacket::new_unchecked(&mut ip[self.ip_header_len()..]),
+            &self.src.addr,
+            &self.dst.addr,
+            payload_len,
+            |_| {},
+            &caps,
+        );
+    }
+}
+
+/// Fill in the checksums of a frame from [`TxHeaders::emit`] that the NIC
+/// will not. IPv6 has no header checksum, and its UDP checksum is left to
+/// the `tx_udp` offload like IPv4's.
+fn fill_checksums(frame: &mut [u8], checksum: &ChecksumOffload) {
+    let mut eth = EthernetFrame::new_unchecked(frame);
+    match eth.ethertype() {
+        EthernetProtocol::Ipv4 => {
+            let mut ip = Ipv4Packet::new_unchecked(eth.payload_mut());
+            if !checksum.tx_ipv4 {
+                ip.fill_checksum();
+            }
+            if !checksum.tx_udp {
+                let (src, dst) = (ip.src_addr().into(), ip.dst_addr().into());
+                UdpPacket::new_unchecked(ip.payload_mut()).fill_checksum(&src, &dst);
+            }
+        }
+        EthernetProtocol::Ipv6 => {
+            if !checksum.tx_udp {
+                let mut ip = Ipv6Packet::new_unchecked(eth.payload_mut());
+                let (src, dst) = (ip.src_addr().into(), ip.dst_addr().into());
+                UdpPacket::new_unchecked(ip.payload_mut()).fill_checksum(&src, &dst);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// MAC a broadcast or multicast IP address maps to
+fn group_mac(addr: IpAddress) -> Option<EthernetAddress> {
+    match addr {
+        IpAddress::Ipv4(addr) if addr.is_broadcast() => Some(EthernetAddress::BROADCAST),
+        IpAddress::Ipv4(addr) if addr.is_multicast() => {
+            let [_, b, c, d] = addr.octets();
+            Some(EthernetAddress([0x01, 0x00, 0x5e, b & 0x7f, c, d]))
+        }
+        IpAddress::Ipv6(addr) if addr.is_multicast() => {
+            let octets = addr.octets();
+            let [a, b, c, d] = [octets[12], octets[13], octets[14], octets[15]];
+            Some(EthernetAddress([0x33, 0x33, a, b, c, d]))
+        }
+        _ => None,
+    }
+}
+
+/// Where a datagram to `dst` goes first: `dst` itself if on-link, else the
+/// gateway of the most specific route.
+fn next_hop(iface: &mut Interface, dst: IpAddress) -> Option<IpAddress> {
+    if iface.ip_addrs().iter().any(|cidr| cidr.contains_addr(&dst)) {
+        return Some(dst);
+    }
+    let mut hop = None;
+    let now = smoltcp::time::Instant::now();
+    iface.routes_mut().update(|routes| {
+        hop = routes
+            .iter()
+            .filter(|route| route.expires_at.is_none_or(|expires| now <= expires))
+            .filter(|route| route.cidr.contains_addr(&dst))
+            .max_by_key(|route| route.cidr.prefix_len())
+            .map(|route| route.via_router);
+    });
+    hop
+}
+
+/// MACs from received ARP replies and Neighbor Advertisements.
+///
+/// Entries are used for [`NEIGHBOR_LIFETIME`]. Once [`MAX_NEIGHBORS`] are
+/// known, expired ones are pruned before a new one is added; if none has
+/// expired, the new one is not remembered and sends to it go through
+/// smoltcp.
+#[derive(Default)]
+struct Neighbors {
+    entries: HashMap<IpAddress, (EthernetAddress, Instant)>,
+}
+
+impl Neighbors {
+    fn get(&self, ip: &IpAddress, now: Instant) -> Option<EthernetAddress> {
+        let &(mac, learned) = self.entries.get(ip)?;
+        (now.saturating_duration_since(learned) < NEIGHBOR_LIFETIME).then_some(mac)
+    }
+
+    fn insert(&mut self, ip: IpAddress, mac: EthernetAddress, now: Instant) {
+        if self.entries.len() >= MAX_NEIGHBORS && !self.entries.contains_key(&ip) {
+            self.entries.retain(|_, &mut (_, learned)| {
+                now.saturating_duration_since(learned) < NEIGHBOR_LIFETIME
+            });
+            if self.entries.len() >= MAX_NEIGHBORS {
+                return;
+            }
+        }
+        self.entries.insert(ip, (mac, now));
+    }
+}
+
+/// Datagrams sockets built for the device to send, and the neighbors to
+/// send them to.
+struct TxState {
+    mempool: Arc<MemPool>,
+    /// Largest frame the device sends
+    mtu: usize,
+    packets: VecDeque<Mbuf>,
+    queue_len: usize,
+    /// Sockets waiting for room in `packets`
+    wakers: Vec<Waker>,
+    neighbors: Neighbors,
+}
+
+/// Ports whose datagrams bypass smoltcp, shared by a
+/// [`DpdkDevice`](super::DpdkDevice) and its zero-copy sockets.
+///
+/// Opaque: the device hands it to the [`Reactor`](crate::runtime::Reactor)
+/// through [`ReactorDevice::udp_zero_copy`](crate::runtime::ReactorDevice::udp_zero_copy).
+#[derive(Clone)]
+pub struct UdpZeroCopy {
+    inner: Arc<Mutex<Ports>>,
+    tx: Arc<Mutex<TxState>>,
+}
+
+impl UdpZeroCopy {
+    pub(crate) fn new(queue_len: usize, mempool: Arc<MemPool>, mtu: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Ports {
+                ports: HashMap::new(),
+                local_addrs: Vec::new(),
+                queue_len,
+            })),
+            tx: Arc::new(Mutex::new(TxState {
+                mempool,
+                mtu,
+                packets: VecDeque::new(),
+                queue_len,
+                wakers: Vec::new(),
+                neighbors: Neighbors::default(),
+            })),
+        }
+    }
+
+    /// Start taking datagrams for `port`; `false` if it is already taken.
+    pub(crate) fn register(&self, port: u16) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.ports.contains_key(&port) {
+            return false;
+        }
+        inner.ports.insert(
+            port,
+            PortQueue {
+                packets: VecDeque::new(),
+                waker: None,
+                dropped: 0,
+                sent: 0,
+            },
+        );
+        true
+    }
+
+    /// Stop taking datagrams for `port`, freeing any still queued.
+    pub(crate) fn unregister(&self, port: u16) {
+        self.inner.lock().unwrap().ports.remove(&port);
+    }
+
+    /// Accept datagrams sent to these addresses (the interface's).
+    pub(crate) fn set_local_addrs(&self, addrs: impl IntoIterator<Item = IpAddr>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.local_addrs.clear();
+        inner.local_addrs.extend(addrs);
+    }
+
+    /// Next datagram for `port`, or register `waker` for the next one.
+    pub(crate) fn pop(&self, port: u16, waker: &Waker) -> Option<(UdpBuf, UdpMetadata)> {
+        let mut inner = self.inner.lock().unwrap();
+        let queue = inner.ports.get_mut(&port)?;
+        let packet = queue.packets.pop_front();
+        if packet.is_none() {
+            queue.waker = Some(waker.clone());
+        }
+        packet
+    }
+
+    /// Datagrams for `port` dropped because its queue was full
+    pub(crate) fn dropped(&self, port: u16) -> u64 {
+        let inner = self.inner.lock().unwrap();
+        inner.ports.get(&port).map_or(0, |queue| queue.dropped)
+    }
+
+    /// Datagrams from `port` queued for the device without going through
+    /// smoltcp
+    pub(crate) fn sent(&self, port: u16) -> u64 {
+        let inner = self.inner.lock().unwrap();
+        inner.ports.get(&port).map_or(0, |queue| queue.sent)
+    }
+
+    /// Take the datagrams for registered ports out of an RX burst.
+    ///
+    /// Packets the checksum offloads flagged bad are left for the device
+    /// to drop; checksums that are not offloaded are verified here, since
+    /// smoltcp never sees these packets.
+    pub(crate) fn intercept<const N: usize>(
+        &self,
+        batch: &mut ArrayVec<Mbuf, N>,
+        checksum: &ChecksumOffload,
+        capture: Option<&CaptureTap>,
+    ) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.ports.is_empty() {
+            return;
+        }
+        let mut index = 0;
+        while index < batch.len() {
+            let mbuf = &batch[index];
+            let frame = mbuf.data();
+            let datagram = (mbuf.is_contiguous()
+                && (!checksum.any_rx() || rx_checksum_ok(mbuf.ol_flags(), frame, checksum)))
+            .then(|| Datagram::parse(frame, !checksum.rx_ipv4, !checksum.rx_udp))
+            .flatten()
+            .filter(|datagram| {
+                inner.ports.contains_key(&datagram.dst_port) && inner.accepts(datagram.dst_addr)
+            });
+            let Some(datagram) = datagram else {
+                index += 1;
+                continue;
+            };
+
+            let mbuf = batch.remove(index);
+            if let Some(capture) = capture {
+                capture.rx(mbuf.data());
+            }
+            let queue_len = inner.queue_len;
+            let Some(queue) = inner.ports.get_mut(&datagram.dst_port) else {
+                continue;
+            };
+            if queue.packets.len() >= queue_len {
+                queue.dropped += 1;
+                continue;
+            }
+            let buf = UdpBuf {
+                inner: UdpBufInner::Mbuf {
+                    mbuf,
+                    offset: datagram.offset,
+                    len: datagram.len,
+                },
+            };
+            queue.packets.push_back((buf, datagram.meta));
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Empty mbuf from the device's mempool for the caller to write a
+    /// payload into.
+    pub(crate) fn alloc_tx(&self) -> Option<Mbuf> {
+        self.tx.lock().unwrap().mempool.try_alloc()
+    }
+
+    /// Prepend the headers for sending the payload in `mbuf` to `dst`.
+    ///
+    /// Returns `Ok(false)`, leaving the mbuf alone, if the next hop's MAC is
+    /// not known; the datagram then has to go through smoltcp. Fails with
+    /// `Unaddressable` if there is no route or source address, or the
+    /// datagram does not fit into one unfragmented frame.
+    pub(crate) fn prepare_tx(
+        &self,
+        mbuf: &mut Mbuf,
+        iface: &mut Interface,
+        src_port: u16,
+        dst: IpEndpoint,
+        hop_limit: u8,
+    ) -> Result<bool, SendError> {
+        if dst.addr.is_unspecified() || dst.port == 0 {
+            return Err(SendError::Unaddressable);
+        }
+        let src_addr = iface
+            .get_source_address(&dst.addr)
+            .ok_or(SendError::Unaddressable)?;
+        let subnet_broadcast = match dst.addr {
+            IpAddress::Ipv4(addr) => iface
+                .ip_addrs()
+                .iter()
+                .any(|cidr| matches!(cidr, IpCidr::Ipv4(cidr) if cidr.broadcast() == Some(addr))),
+            IpAddress::Ipv6(_) => false,
+        };
+        let dst_mac = if subnet_broadcast {
+            EthernetAddress::BROADCAST
+        } else if let Some(mac) = group_mac(dst.addr) {
+            mac
+        } else {
+            let hop = next_hop(iface, dst.addr).ok_or(SendError::Unaddressable)?;
+            let tx = self.tx.lock().unwrap();
+            match tx.neighbors.get(&hop, Instant::now()) {
+                Some(mac) => mac,
+                None => return Ok(false),
+            }
+        };
+
+        let HardwareAddress::Ethernet(src_mac) = iface.hardware_addr();
+        let headers = TxHeaders {
+            src_mac,
+            dst_mac,
+            src: IpEndpoint::new(src_addr, src_port),
+            dst,
+            hop_limit,
+        };
+        let mtu = self.tx.lock().unwrap().mtu;
+        if !mbuf.is_contiguous() || headers.len() + mbuf.data_len() > mtu {
+            return Err(SendError::Unaddressable);
+        }
+        if mbuf.prepend(headers.len()).is_none() {
+            return Err(SendError::Unaddressable);
+        }
+        headers.emit(mbuf.data_mut());
+        Ok(true)
+    }
+
+    /// Queue a datagram from `src_port`, prepared by
+    /// [`prepare_tx`](Self::prepare_tx), for the device.
+    ///
+    /// Hands the mbuf back, and wakes `waker` once there is room, if the
+    /// queue is full.
+    pub(crate) fn push_tx(&self, src_port: u16, mbuf: Mbuf, waker: &Waker) -> Result<(), Mbuf> {
+        {
+            let mut tx = self.tx.lock().unwrap();
+            if tx.packets.len() >= tx.queue_len {
+                tx.wakers.push(waker.clone());
+                return Err(mbuf);
+            }
+            tx.packets.push_back(mbuf);
+        }
+        if let Some(queue) = self.inner.lock().unwrap().ports.get_mut(&src_port) {
+            queue.sent += 1;
+        }
+        Ok(())
+    }
+
+    /// Move queued datagrams into the device's TX batch, filling it up to
+    /// `limit` packets.
+    ///
+    /// Applies what the device does to smoltcp's frames: checksums (or
+    /// their offload), capture and VLAN tagging.
+    pub(crate) fn drain_tx<const N: usize>(
+        &self,
+        batch: &mut ArrayVec<Mbuf, N>,
+        limit: usize,
+        checksum: &ChecksumOffload,
+        capture: Option<&CaptureTap>,
+        vlan: Option<&VlanState>,
+    ) {
+        let mut tx = self.tx.lock().unwrap();
+        if tx.packets.is_empty() {
+            return;
+        }
+        while batch.len() < limit.min(N) {
+            let Some(mut mbuf) = tx.packets.pop_front() else {
+                break;
+            };
+            fill_checksums(mbuf.data_mut(), checksum);
+            if let Some(capture) = capture {
+                capture.tx(mbuf.data());
+            }
+            if checksum.any_tx() {
+                prepare_tx_offload(&mut mbuf, checksum);
+            }
+            if let Some(vlan) = vlan
+                && !vlan.tag_tx(&mut mbuf)
+            {
+                tracing::warn!("Dropping zero-copy UDP datagram: no headroom for VLAN tag");
+                continue;
+            }
+            batch.push(mbuf);
+        }
+        for waker in tx.wakers.drain(..) {
+            waker.wake();
+        }
+    }
+
+    /// Remember the sender of an ARP reply or Neighbor Advertisement.
+    #[inline]
+    pub(crate) fn learn(&self, frame: &[u8]) {
+        let neighbor = parse_arp_reply(frame)
+            .map(|(ip, mac)| (IpAddress::Ipv4(ip), mac))
+            .or_else(|| parse_neighbor_advert(frame).map(|(ip, mac)| (IpAddress::Ipv6(ip), mac)));
+        if let Some((ip, mac)) = neighbor {
+            let mut tx = self.tx.lock().unwrap();
+            tx.neighbors
+                .insert(ip, EthernetAddress(mac), Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use smoltcp::phy::ChecksumCapabilities;
+    use smoltcp::wire::{EthernetAddress, EthernetRepr, Ipv4Address, Ipv4Repr, UdpRepr};
+
+    const SRC: Ipv4Address = Ipv4Address::new(10, 0, 0, 1);
+    const DST: Ipv4Address = Ipv4Address::new(10, 0, 0, 2);
+
+    fn udp_frame(payload: &[u8]) -> Vec<u8> {
+        let udp = UdpRepr {
+            src_port: 5000,
+            dst_port: 9000,
+        };
+        let ip = Ipv4Repr {
+            src_addr: SRC,
+            dst_addr: DST,
+            next_header: IpProtocol::Udp,
+            payload_len: 8 + payload.len(),
+            hop_limit: 64,
+        };
+        let eth = EthernetRepr {
+            src_addr: EthernetAddress([2, 0, 0, 0, 0, 1]),
+            dst_addr: EthernetAddress([2, 0, 0, 0, 0, 2]),
+            ethertype: EthernetProtocol::Ipv4,
+        };
+        let mut frame = vec![0u8; 14 + 20 + 8 + payload.len()];
+        let caps = ChecksumCapabilities::default();
+        eth.emit(&mut EthernetFrame::new_unchecked(&mut frame[..]));
+        ip.emit(&mut Ipv4Packet::new_unchecked(&mut frame[14..]), &caps);
+        udp.emit(
+            &mut UdpPacket::new_unchecked(&mut frame[34..]),
+            &SRC.into(),
+            &DST.into(),
+            payload.len(),
+            |buf| buf.copy_from_slice(payload),
+            &caps,
+        );
+        frame
+    }
+
+    #[test]
+    fn test_parse_datagram() {
+        let frame = udp_frame(b"telemetry");
+        let datagram = Datagram::parse(&frame, true, true).unwrap();
+        assert_eq!(datagram.dst_addr, IpAddress::Ipv4(DST));
+        assert_eq!(datagram.dst_port, 9000);
+        assert_eq!(datagram.meta.endpoint, IpEndpoint::new(SRC.into(), 5000));
+        assert_eq!(datagram.meta.local_address, Some(DST.into()));
+        assert_eq!(
+            &frame[datagram.offset..datagram.offset + datagram.len],
+            b"telemetry"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects() {
+        // Corrupted payload: only a problem if we verify
+        let mut frame = udp_frame(b"telemetry");
+        frame[45] ^= 0xff;
+        assert!(Datagram::parse(&frame, true, true).is_none());
+        assert!(Datagram::parse(&frame, true, false).is_some());
+
+        // Fragment
+        let mut frame = udp_frame(b"telemetry");
+        frame[20] |= 0x20;
+        assert!(Datagram::parse(&frame, false, false).is_none());
+
+        // TCP
+        let mut frame = udp_frame(b"telemetry");
+        frame[23] = 6;
+        assert!(Datagram::parse(&frame, false, false).is_none());
+    }
+
+    fn tx_headers(src: IpAddress, dst: IpAddress) -> TxHeaders {
+        TxHeaders {
+            src_mac: EthernetAddress([2, 0, 0, 0, 0, 1]),
+            dst_mac: EthernetAddress([2, 0, 0, 0, 0, 2]),
+            src: IpEndpoint::new(src, 5000),
+            dst: IpEndpoint::new(dst, 9000),
+            hop_limit: DEFAULT_HOP_LIMIT,
+        }
+    }
+
+    #[test]
+    fn test_tx_headers() {
+        let v6 = |last| IpAddress::v6(0xfd00, 0, 0, 0, 0, 0, 0, last);
+        for (src, dst) in [(SRC.into(), DST.into()), (v6(1), v6(2))] {
+            let headers = tx_headers(src, dst);
+            let mut frame = vec![0u8; headers.len()];
+            frame.extend_from_slice(b"media");
+            headers.emit(&mut frame);
+            fill_checksums(&mut frame, &ChecksumOffload::default());
+
+            let datagram = Datagram::parse(&frame, true, true).unwrap();
+            assert_eq!(datagram.meta.endpoint, headers.src);
+            assert_eq!(datagram.meta.local_address, Some(dst));
+            assert_eq!(datagram.dst_port, 9000);
+            assert_eq!(datagram.offset, headers.len());
+            assert_eq!(&frame[datagram.offset..], b"media");
+        }
+    }
+
+    #[test]
+    fn test_tx_checksum_offload() {
+        let headers = tx_headers(SRC.into(), DST.into());
+        let mut frame = vec![0u8; headers.len() + 5];
+        headers.emit(&mut frame);
+        let offload = ChecksumOffload {
+            tx_ipv4: true,
+            tx_udp: true,
+            ..Default::default()
+        };
+        fill_checksums(&mut frame, &offload);
+        // Left for the NIC
+        assert_eq!(frame[24..26], [0, 0]);
+        assert_eq!(frame[40..42], [0, 0]);
+
+        let v6 = |last| IpAddress::v6(0xfd00, 0, 0, 0, 0, 0, 0, last);
+        let headers = tx_headers(v6(1), v6(2));
+        let mut frame = vec![0u8; headers.len() + 5];
+        headers.emit(&mut frame);
+        fill_checksums(&mut frame, &offload);
+        assert_eq!(frame[60..62], [0, 0]);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let mac = EthernetAddress([2, 0, 0, 0, 0, 2]);
+        let start = Instant::now();
+        let mut neighbors = Neighbors::default();
+        neighbors.insert(DST.into(), mac, start);
+        assert_eq!(neighbors.get(&DST.into(), start), Some(mac));
+        assert_eq!(neighbors.get(&DST.into(), start + NEIGHBOR_LIFETIME), None);
+
+        // Full of live entries: new neighbors are not remembered
+        for i in 1..MAX_NEIGHBORS as u32 {
+            let ip = Ipv4Address::from(0x0b00_0000 + i);
+            neighbors.insert(ip.into(), mac, start);
+        }
+        neighbors.insert(SRC.into(), mac, start);
+        assert_eq!(neighbors.get(&SRC.into(), start), None);
+        assert_eq!(neighbors.entries.len(), MAX_NEIGHBORS);
+
+        // Expired ones make room
+        let later = start + NEIGHBOR_LIFETIME;
+        neighbors.insert(SRC.into(), mac, later);
+        assert_eq!(neighbors.get(&SRC.into(), later), Some(mac));
+        assert_eq!(neighbors.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_group_mac() {
+        assert_eq!(
+            group_mac(IpAddress::v4(255, 255, 255, 255)),
+            Some(EthernetAddress::BROADCAST)
+        );
+        assert_eq!(
+            group_mac(IpAddress::v4(239, 129, 2, 3)),
+            Some(EthernetAddress([0x01, 0x00, 0x5e, 0x01, 0x02, 0x03]))
+        );
+        assert_eq!(
+            group_mac(IpAddress::v6(0xff02, 0, 0, 0, 0, 0, 0x1234, 0x5678)),
+            Some(EthernetAddress([0x33, 0x33, 0x12, 0x34, 0x56, 0x78]))
+        );
+        assert_eq!(group_mac(DST.into()), None);
+    }
+
+    #[test]
+    fn test_vec_buf() {
+        let buf = UdpBuf::from_vec(b"copied".to_vec());
+        assert!(!buf.is_zero_copy());
+        assert_eq!(&*buf, b"copied");
+        assert_eq!(bytes::Bytes::from(buf), &b"copied"[..]);
+    }
+}
@@ -0,0 +1,302 @@
+//! 802.1Q VLAN tagging and per-VLAN sub-interfaces.
+//!
+//! A [`DpdkDevice`](super::DpdkDevice) configured with
+//! [`with_vlan`](super::DpdkDevice::with_vlan) behaves like a VLAN
+//! sub-interface: smoltcp only ever sees untagged frames, received frames of
+//! other VLANs are dropped, and transmitted frames are tagged. Tags are
+//! stripped and inserted by the NIC when the port negotiated
+//! [`VlanOffload`], and pushed/popped in the mbuf headroom otherwise.
+//!
+//! Several sub-interfaces can share one RX queue through a [`VlanDemux`],
+//! which sorts the queue's packets by VLAN ID so each sub-interface (each
+//! with its own address and smoltcp `Interface`) gets its own traffic.
+//! VLAN ID 0 stands for untagged (native) traffic throughout.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use arrayvec::ArrayVec;
+
+use crate::api::rte::eth::{PortId, QueueId, VlanOffload};
+use crate::api::rte::mbuf::{Mbuf, ol_flags};
+use crate::api::rte::queue::{MAX_BURST_SIZE, RxQueue};
+
+/// 802.1Q tag protocol identifier
+pub const ETHERTYPE_VLAN: u16 = 0x8100;
+
+/// Length of an 802.1Q tag
+pub const VLAN_TAG_LEN: usize = 4;
+
+/// Default number of packets a [`VlanDemux`] buffers per VLAN.
+pub const DEFAULT_VLAN_QUEUE_LIMIT: usize = 1024;
+
+/// The 12-bit VLAN ID of a tag control information field
+const VID_MASK: u16 = 0x0fff;
+
+/// TX offload flags whose `l2_len` covers an inserted tag
+const TX_OFFLOAD_MASK: u64 = ol_flags::TX_IPV4
+    | ol_flags::TX_IP_CKSUM
+    | ol_flags::TX_TCP_CKSUM
+    | ol_flags::TX_UDP_CKSUM
+    | ol_flags::TX_TCP_SEG;
+
+/// Ethertype of the payload and length of the Ethernet header, skipping a
+/// single 802.1Q tag if present.
+///
+/// Returns `None` for frames too short to hold the header.
+pub fn ethertype(frame: &[u8]) -> Option<(u16, usize)> {
+    let outer = u16::from_be_bytes([*frame.get(12)?, *frame.get(13)?]);
+    if outer != ETHERTYPE_VLAN {
+        return Some((outer, 14));
+    }
+    let inner = u16::from_be_bytes([*frame.get(16)?, *frame.get(17)?]);
+    Some((inner, 14 + VLAN_TAG_LEN))
+}
+
+/// VLAN ID of a tagged frame, or `None` if the frame carries no tag.
+pub fn vlan_id(frame: &[u8]) -> Option<u16> {
+    tci(frame).map(|tci| tci & VID_MASK)
+}
+
+/// Tag control information of a tagged frame
+fn tci(frame: &[u8]) -> Option<u16> {
+    if frame.get(12..14)? != ETHERTYPE_VLAN.to_be_bytes() {
+        return None;
+    }
+    Some(u16::from_be_bytes([*frame.get(14)?, *frame.get(15)?]))
+}
+
+/// Remove the VLAN tag from a received packet's data.
+///
+/// Leaves the packet as the NIC's strip offload would: untagged data, the
+/// tag in `vlan_tci`, and `RX_VLAN | RX_VLAN_STRIPPED` set. Returns the VLAN
+/// ID, or 0 for untagged packets.
+pub(crate) fn strip(mbuf: &mut Mbuf) -> u16 {
+    if mbuf.ol_flags() & ol_flags::RX_VLAN_STRIPPED != 0 {
+        return mbuf.vlan_tci() & VID_MASK;
+    }
+    let Some(tci) = tci(mbuf.data()) else {
+        return 0;
+    };
+    // Slide the MAC addresses over the tag, then drop the first 4 bytes
+    mbuf.data_mut().copy_within(0..12, VLAN_TAG_LEN);
+    mbuf.adj(VLAN_TAG_LEN);
+    mbuf.set_vlan_tci(tci);
+    mbuf.set_ol_flags(ol_flags::RX_VLAN | ol_flags::RX_VLAN_STRIPPED);
+    tci & VID_MASK
+}
+
+/// Per-device VLAN state: which VLAN the device belongs to and how to tag.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VlanState {
+    vid: u16,
+    offload: VlanOffload,
+}
+
+impl VlanState {
+    pub(crate) fn new(vid: u16, offload: VlanOffload) -> Self {
+        Self {
+            vid: vid & VID_MASK,
+            offload,
+        }
+    }
+
+    /// Strip a received packet's tag; `false` if it belongs to another VLAN.
+    #[inline]
+    pub(crate) fn accept_rx(&self, mbuf: &mut Mbuf) -> bool {
+        strip(mbuf) == self.vid
+    }
+
+    /// Tag an outgoing packet (after its TX offload flags are set).
+    ///
+    /// Returns `false` if there is no headroom for a software tag.
+    #[inline]
+    pub(crate) fn tag_tx(&self, mbuf: &mut Mbuf) -> bool {
+        if self.vid == 0 {
+            return true;
+        }
+        if self.offload.tx_insert {
+            mbuf.set_vlan_tci(self.vid);
+            mbuf.set_ol_flags(ol_flags::TX_VLAN);
+            return true;
+        }
+        if mbuf.prepend(VLAN_TAG_LEN).is_none() {
+            return false;
+        }
+        let data = mbuf.data_mut();
+        data.copy_within(VLAN_TAG_LEN..VLAN_TAG_LEN + 12, 0);
+        data[12..14].copy_from_slice(&ETHERTYPE_VLAN.to_be_bytes());
+        data[14..16].copy_from_slice(&self.vid.to_be_bytes());
+        // Offloads locate the L3 header through l2_len
+        if mbuf.ol_flags() & TX_OFFLOAD_MASK != 0 {
+            mbuf.set_l2_len(mbuf.l2_len() + VLAN_TAG_LEN as u16);
+        }
+        true
+    }
+}
+
+/// Packet counters of a [`VlanDemux`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VlanDemuxStats {
+    /// Packets for VLANs without a sub-interface
+    pub unknown: u64,
+    /// Packets dropped because their VLAN's queue was full
+    pub dropped: u64,
+}
+
+struct VlanDemuxInner {
+    rxq: RxQueue,
+    queues: HashMap<u16, VecDeque<Mbuf>>,
+    limit: usize,
+    stats: VlanDemuxStats,
+}
+
+impl VlanDemuxInner {
+    /// Burst the RX queue and sort the packets by VLAN
+    fn fill(&mut self) {
+        let mut batch = ArrayVec::<Mbuf, MAX_BURST_SIZE>::new();
+        self.rxq.rx(&mut batch);
+        for mut mbuf in batch {
+            let vid = strip(&mut mbuf);
+            match self.queues.get_mut(&vid) {
+                Some(queue) if queue.len() < self.limit => queue.push_back(mbuf),
+                Some(_) => self.stats.dropped += 1,
+                None => self.stats.unknown += 1,
+            }
+        }
+    }
+}
+
+/// Splits one RX queue between several VLAN sub-interfaces.
+///
+/// Each [`VlanQueue`] handed out by [`queue`](Self::queue) feeds one
+/// [`DpdkDevice`](super::DpdkDevice) through
+/// [`with_vlan_queue`](super::DpdkDevice::with_vlan_queue). Whichever device
+/// polls first bursts the hardware queue and files the packets under their
+/// VLAN ID; packets of other VLANs are dropped. The devices usually share a
+/// thread, but may run on different ones.
+#[derive(Clone)]
+pub struct VlanDemux {
+    inner: Arc<Mutex<VlanDemuxInner>>,
+    port_id: PortId,
+    queue_id: QueueId,
+}
+
+impl VlanDemux {
+    /// Demultiplex `rxq` between the VLANs in `vids` (0 = untagged).
+    pub fn new(rxq: RxQueue, vids: impl IntoIterator<Item = u16>) -> Self {
+        let port_id = rxq.port_id();
+        let queue_id = rxq.queue_id();
+        let queues = vids
+            .into_iter()
+            .map(|vid| (vid & VID_MASK, VecDeque::new()))
+            .collect();
+        Self {
+            inner: Arc::new(Mutex::new(VlanDemuxInner {
+                rxq,
+                queues,
+                limit: DEFAULT_VLAN_QUEUE_LIMIT,
+                stats: VlanDemuxStats::default(),
+            })),
+            port_id,
+            queue_id,
+        }
+    }
+
+    /// Buffer at most `limit` packets per VLAN instead of
+    /// [`DEFAULT_VLAN_QUEUE_LIMIT`].
+    pub fn with_queue_limit(self, limit: usize) -> Self {
+        self.inner.lock().unwrap().limit = limit;
+        self
+    }
+
+    /// Receive side of the sub-interface for `vid`, or `None` if `vid` was
+    /// not passed to [`new`](Self::new).
+    pub fn queue(&self, vid: u16) -> Option<VlanQueue> {
+        let vid = vid & VID_MASK;
+        self.inner
+            .lock()
+            .unwrap()
+            .queues
+            .contains_key(&vid)
+            .then(|| VlanQueue {
+                demux: self.clone(),
+                vid,
+            })
+    }
+
+    /// Snapshot of the counters
+    pub fn stats(&self) -> VlanDemuxStats {
+        self.inner.lock().unwrap().stats
+    }
+}
+
+/// One VLAN's share of a [`VlanDemux`].
+pub struct VlanQueue {
+    demux: VlanDemux,
+    vid: u16,
+}
+
+impl VlanQueue {
+    /// VLAN ID this queue receives (0 = untagged)
+    pub fn vid(&self) -> u16 {
+        self.vid
+    }
+
+    /// Port the underlying RX queue belongs to
+    pub fn port_id(&self) -> PortId {
+        self.demux.port_id
+    }
+
+    /// Underlying hardware RX queue
+    pub fn queue_id(&self) -> QueueId {
+        self.demux.queue_id
+    }
+
+    /// Move at most `max` of this VLAN's pending packets (already stripped)
+    /// into `mbufs`.
+    pub(crate) fn rx<const N: usize>(&self, mbufs: &mut ArrayVec<Mbuf, N>, max: usize) -> usize {
+        let mut inner = self.demux.inner.lock().unwrap();
+        if inner.queues.get(&self.vid).is_some_and(VecDeque::is_empty) {
+            inner.fill();
+        }
+        let Some(queue) = inner.queues.get_mut(&self.vid) else {
+            return 0;
+        };
+        let count = queue.len().min(mbufs.remaining_capacity()).min(max);
+        mbufs.extend(queue.drain(..count));
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(tag: Option<u16>, ethertype: u16) -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        if let Some(tci) = tag {
+            frame.extend_from_slice(&ETHERTYPE_VLAN.to_be_bytes());
+            frame.extend_from_slice(&tci.to_be_bytes());
+        }
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        frame.extend_from_slice(&[0u8; 28]);
+        frame
+    }
+
+    #[test]
+    fn test_ethertype() {
+        assert_eq!(ethertype(&frame(None, 0x0800)), Some((0x0800, 14)));
+        assert_eq!(ethertype(&frame(Some(100), 0x0806)), Some((0x0806, 18)));
+        assert_eq!(ethertype(&[0u8; 13]), None);
+        assert_eq!(ethertype(&frame(Some(100), 0x0806)[..17]), None);
+    }
+
+    #[test]
+    fn test_vlan_id() {
+        assert_eq!(vlan_id(&frame(None, 0x0800)), None);
+        assert_eq!(vlan_id(&frame(Some(100), 0x0800)), Some(100));
+        // Priority bits are not part of the ID
+        assert_eq!(vlan_id(&frame(Some(0xe000 | 4094), 0x0800)), Some(4094));
+    }
+}
@@ -12,6 +12,10 @@
 //! polling - there are no interrupts to notify us when packets arrive.
 //! The `Reactor::run()` method polls DPDK in a loop.
 //!
+//! The reactor is generic over the smoltcp device, so the same sockets also
+//! run over a smoltcp `Loopback` or TUN/TAP device, e.g. in tests without
+//! DPDK or hugepages.
+//!
 //! ## How Wakers Work
 //!
 //! 1. **Reactor polls DPDK + smoltcp** continuously in a background task
@@ -56,4 +60,4 @@ mod traits;
 pub use reactor::{Reactor, ReactorHandle, ReactorInner};
 #[cfg(feature = "tokio")]
 pub use tokio_compat::{TokioRuntime, TokioTcpStream};
-pub use traits::Runtime;
+pub use traits::{ReactorDevice, Runtime};
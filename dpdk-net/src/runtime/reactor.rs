@@ -1,17 +1,22 @@
 //! Async reactor for DPDK + smoltcp networking.
 //!
 //! The reactor drives the network stack by continuously polling DPDK for packets
-//! and processing them through smoltcp.
+//! and processing them through smoltcp. Any smoltcp [`Device`] implementing
+//! [`ReactorDevice`] works: the socket types only see the device-independent
+//! [`ReactorInner`], so the same code runs over a smoltcp `Loopback` or a
+//! TUN/TAP device.
 
-use super::Runtime;
 #[cfg(feature = "tokio")]
 use super::TokioRuntime;
-use crate::device::DpdkDevice;
+use super::{ReactorDevice, Runtime};
+use crate::device::UdpZeroCopy;
+use crate::socket::RssPortAllocator;
 
 use smoltcp::iface::{Interface, PollIngressSingleResult, SocketHandle, SocketSet};
 use smoltcp::phy::Device;
 use smoltcp::time::Instant;
 use std::cell::RefCell;
+use std::net::IpAddr;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -23,41 +28,39 @@ const DEFAULT_INGRESS_BATCH_SIZE: usize = 32;
 /// Shared state for the async reactor
 ///
 /// This holds all the smoltcp state and provides interior mutability
-/// so that futures can access it.
+/// so that futures can access it. The device stays in the [`Reactor`], so
+/// sockets work the same whatever it is.
 ///
 /// Wakers are managed by smoltcp's socket API directly via
 /// `register_recv_waker()` and `register_send_waker()`.
-pub struct ReactorInner<D: Device> {
-    pub device: D,
+pub struct ReactorInner {
     pub iface: Interface,
     pub sockets: SocketSet<'static>,
     /// Orphaned sockets that are in graceful close but no longer owned by a TcpStream.
     /// These will be cleaned up once they reach Closed or TimeWait state.
     pub(crate) orphaned_closing: Vec<SocketHandle>,
+    /// Local ports for `TcpStream::connect_ephemeral`
+    pub(crate) port_allocator: RssPortAllocator,
+    /// Ports bypassing smoltcp, when the device supports it
+    pub(crate) udp_zero_copy: Option<UdpZeroCopy>,
 }
 
-impl<D: Device> ReactorInner<D> {
+impl ReactorInner {
     /// Process one incoming packet (bounded work).
     ///
     /// Returns whether a packet was processed and whether socket state changed.
-    fn poll_ingress_single(&mut self, timestamp: Instant) -> PollIngressSingleResult {
-        let ReactorInner {
-            device,
-            iface,
-            sockets,
-            ..
-        } = self;
+    fn poll_ingress_single<D: Device>(
+        &mut self,
+        timestamp: Instant,
+        device: &mut D,
+    ) -> PollIngressSingleResult {
+        let ReactorInner { iface, sockets, .. } = self;
         iface.poll_ingress_single(timestamp, device, sockets)
     }
 
     /// Transmit queued packets (bounded work).
-    fn poll_egress(&mut self, timestamp: Instant) {
-        let ReactorInner {
-            device,
-            iface,
-            sockets,
-            ..
-        } = self;
+    fn poll_egress<D: Device>(&mut self, timestamp: Instant, device: &mut D) {
+        let ReactorInner { iface, sockets, .. } = self;
         iface.poll_egress(timestamp, device, sockets);
     }
 
@@ -85,23 +88,74 @@ impl<D: Device> ReactorInner<D> {
 ///
 /// This must be polled repeatedly to make progress on network I/O.
 /// Use with tokio's single-threaded runtime (`current_thread`).
-pub struct Reactor<D: Device> {
-    inner: Rc<RefCell<ReactorInner<D>>>,
+///
+/// `D` is usually a [`DpdkDevice`](crate::device::DpdkDevice), but any
+/// smoltcp [`Device`] with a [`ReactorDevice`] impl will do (the hooks
+/// default to no-ops), e.g. `smoltcp::phy::Loopback` to run socket code
+/// without DPDK. Wrappers such as [`FaultyDevice`](crate::device::FaultyDevice)
+/// forward the hooks, so a wrapped `DpdkDevice` keeps address sync,
+/// backpressure and its zero-copy UDP ports (whose datagrams bypass the
+/// injected faults).
+pub struct Reactor<D: ReactorDevice> {
+    device: D,
+    inner: Rc<RefCell<ReactorInner>>,
+    /// Interface addresses last passed to the zero-copy UDP ports
+    zero_copy_addrs: Vec<IpAddr>,
 }
 
-impl Reactor<DpdkDevice> {
-    /// Create a new reactor with the given DPDK device and interface
-    pub fn new(device: DpdkDevice, iface: Interface) -> Self {
+impl<D: ReactorDevice> Reactor<D> {
+    /// Create a new reactor with the given device and interface
+    pub fn new(device: D, iface: Interface) -> Self {
+        let udp_zero_copy = device.udp_zero_copy().cloned();
         Self {
+            device,
+            zero_copy_addrs: Vec::new(),
             inner: Rc::new(RefCell::new(ReactorInner {
-                device,
                 iface,
                 sockets: SocketSet::new(vec![]),
                 orphaned_closing: Vec::new(),
+                port_allocator: RssPortAllocator::any_queue(),
+                udp_zero_copy,
             })),
         }
     }
 
+    /// Pick local ports for [`TcpStream::connect_ephemeral`] with `allocator`,
+    /// so replies come back to this reactor's queue.
+    ///
+    /// [`TcpStream::connect_ephemeral`]: crate::socket::TcpStream::connect_ephemeral
+    pub fn with_port_allocator(self, allocator: RssPortAllocator) -> Self {
+        self.inner.borrow_mut().port_allocator = allocator;
+        self
+    }
+
+    /// Let the device follow interface address changes (gratuitous ARP).
+    #[inline]
+    fn sync_ipv4_addr(&mut self) {
+        if let Some(ip) = self.inner.borrow().iface.ipv4_addr() {
+            self.device.set_ipv4_addr(ip);
+        }
+    }
+
+    /// Tell the zero-copy UDP ports which addresses are ours.
+    #[inline]
+    fn sync_zero_copy_addrs(&mut self) {
+        let inner = self.inner.borrow();
+        let Some(udp) = &inner.udp_zero_copy else {
+            return;
+        };
+        let addrs = inner.iface.ip_addrs();
+        let unchanged = addrs.len() == self.zero_copy_addrs.len()
+            && addrs
+                .iter()
+                .zip(&self.zero_copy_addrs)
+                .all(|(cidr, &addr)| IpAddr::from(cidr.address()) == addr);
+        if !unchanged {
+            self.zero_copy_addrs = addrs.iter().map(|cidr| cidr.address().into()).collect();
+            udp.set_local_addrs(self.zero_copy_addrs.iter().copied());
+        }
+    }
+
     /// Get a handle to the reactor's inner state (for creating sockets)
     pub fn handle(&self) -> ReactorHandle {
         ReactorHandle {
@@ -195,7 +249,7 @@ impl Reactor<DpdkDevice> {
     /// reactor.run_with::<TokioRuntime>(64, cancel).await;
     /// # }
     /// ```
-    pub async fn run_with<R: Runtime>(self, batch_size: usize, cancel: Arc<AtomicBool>) {
+    pub async fn run_with<R: Runtime>(mut self, batch_size: usize, cancel: Arc<AtomicBool>) {
         while !cancel.load(Ordering::Relaxed) {
             let timestamp = Instant::now();
             let mut packets_processed = 0;
@@ -204,7 +258,7 @@ impl Reactor<DpdkDevice> {
             loop {
                 let result = {
                     let mut inner = self.inner.borrow_mut();
-                    inner.poll_ingress_single(timestamp)
+                    inner.poll_ingress_single(timestamp, &mut self.device)
                 };
 
                 match result {
@@ -220,11 +274,18 @@ impl Reactor<DpdkDevice> {
                 }
             }
 
-            // Process egress (bounded work - just transmits queued packets)
-            {
-                let mut inner = self.inner.borrow_mut();
-                inner.poll_egress(timestamp);
+            // Process egress (bounded work - just transmits queued packets).
+            // A device out of buffers or TX ring space (e.g. a DpdkDevice)
+            // refuses TX tokens and smoltcp keeps the data in the sockets;
+            // hold egress back until ReactorDevice::tx_ready reports room
+            // again instead of waiting for retransmits.
+            if self.device.tx_ready() {
+                self.inner
+                    .borrow_mut()
+                    .poll_egress(timestamp, &mut self.device);
             }
+            self.sync_ipv4_addr();
+            self.sync_zero_copy_addrs();
 
             // Clean up orphaned closing sockets that have completed their handshake
             {
@@ -242,5 +303,85 @@ impl Reactor<DpdkDevice> {
 /// Handle to the reactor for creating sockets
 #[derive(Clone)]
 pub struct ReactorHandle {
-    pub(crate) inner: Rc<RefCell<ReactorInner<DpdkDevice>>>,
+    pub(crate) inner: Rc<RefCell<ReactorInner>>,
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use super::*;
+    use crate::socket::{TcpListener, TcpStream, UdpSocket};
+    use smoltcp::iface::Config;
+    use smoltcp::phy::{Loopback, Medium};
+    use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, IpEndpoint};
+    use std::future::Future;
+
+    /// Reactor over a smoltcp loopback device, no DPDK involved
+    fn loopback_reactor() -> Reactor<Loopback> {
+        let mut device = Loopback::new(Medium::Ethernet);
+        let config = Config::new(EthernetAddress([0x02, 0, 0, 0, 0, 1]).into());
+        let mut iface = Interface::new(config, &mut device, Instant::now());
+        iface.update_ip_addrs(|addrs| {
+            addrs
+                .push(IpCidr::new(IpAddress::v4(127, 0, 0, 1), 8))
+                .unwrap();
+        });
+        Reactor::new(device, iface)
+    }
+
+    /// Run `test` next to the reactor on a single-threaded tokio runtime.
+    fn run<F: Future<Output = ()>>(test: impl FnOnce(ReactorHandle) -> F) {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let local = tokio::task::LocalSet::new();
+        local.block_on(&rt, async {
+            let reactor = loopback_reactor();
+            let handle = reactor.handle();
+            let cancel = Arc::new(AtomicBool::new(false));
+            let task = tokio::task::spawn_local(reactor.run(cancel.clone()));
+            test(handle).await;
+            cancel.store(true, Ordering::Relaxed);
+            task.await.unwrap();
+        });
+    }
+
+    #[test]
+    fn test_tcp_over_loopback() {
+        run(|handle| async move {
+            let mut listener = TcpListener::bind(&handle, 7, 4096, 4096).unwrap();
+            let client =
+                TcpStream::connect(&handle, IpAddress::v4(127, 0, 0, 1), 7, 49152, 4096, 4096)
+                    .unwrap();
+            client.wait_connected().await.unwrap();
+            let server = listener.accept().await.unwrap();
+
+            client.send(b"ping").await.unwrap();
+            let mut buf = [0u8; 16];
+            let len = server.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], b"ping");
+            server.send(b"pong").await.unwrap();
+            let len = client.recv(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], b"pong");
+
+            // Both ends close, so neither waits in FIN-WAIT-2
+            let client_closed = client.close();
+            server.close().await;
+            client_closed.await;
+        });
+    }
+
+    #[test]
+    fn test_udp_over_loopback() {
+        run(|handle| async move {
+            let a = UdpSocket::bind(&handle, 5000, 4, 4, 1500).unwrap();
+            let b = UdpSocket::bind(&handle, 5001, 4, 4, 1500).unwrap();
+            let to_b = IpEndpoint::new(IpAddress::v4(127, 0, 0, 1), 5001);
+            a.send_to(b"hello", to_b).await.unwrap();
+
+            let mut buf = [0u8; 16];
+            let (len, meta) = b.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], b"hello");
+            assert_eq!(meta.endpoint.port, 5000);
+        });
+    }
 }
@@ -1,9 +1,15 @@
 //! Runtime abstraction for async executors.
 //!
 //! This module provides the [`Runtime`] trait for abstracting runtime-specific
-//! operations, allowing the reactor to work with different async runtimes.
+//! operations, allowing the reactor to work with different async runtimes, and
+//! the [`ReactorDevice`] trait for the device hooks the reactor calls.
 
 use std::future::Future;
+use std::net::Ipv4Addr;
+
+use smoltcp::phy::{Device, Loopback};
+
+use crate::device::UdpZeroCopy;
 
 /// Trait for abstracting async runtime operations.
 ///
@@ -30,3 +36,55 @@ pub trait Runtime {
     /// to avoid monopolizing the executor.
     fn yield_now() -> impl Future<Output = ()>;
 }
+
+/// Device hooks the [`Reactor`](super::Reactor) uses beyond smoltcp's
+/// [`Device`].
+///
+/// Every method has a no-op default, so any smoltcp device can be driven
+/// with an empty impl:
+///
+/// ```rust
+/// use dpdk_net::runtime::ReactorDevice;
+/// # use smoltcp::phy::{Device, DeviceCapabilities, Loopback};
+/// # use smoltcp::time::Instant;
+/// # struct MyDevice(Loopback);
+/// # impl Device for MyDevice {
+/// #     type RxToken<'a> = <Loopback as Device>::RxToken<'a>;
+/// #     type TxToken<'a> = <Loopback as Device>::TxToken<'a>;
+/// #     fn receive(&mut self, t: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+/// #         self.0.receive(t)
+/// #     }
+/// #     fn transmit(&mut self, t: Instant) -> Option<Self::TxToken<'_>> {
+/// #         self.0.transmit(t)
+/// #     }
+/// #     fn capabilities(&self) -> DeviceCapabilities {
+/// #         self.0.capabilities()
+/// #     }
+/// # }
+///
+/// impl ReactorDevice for MyDevice {}
+/// ```
+///
+/// Wrappers such as [`FaultyDevice`](crate::device::FaultyDevice) forward
+/// the hooks to the device they wrap.
+pub trait ReactorDevice: Device {
+    /// The interface's IPv4 address changed (e.g. to repeat gratuitous ARP).
+    fn set_ipv4_addr(&mut self, _ip: Ipv4Addr) {}
+
+    /// Ports whose UDP datagrams bypass smoltcp, if the device has them.
+    ///
+    /// Asked once, when the reactor is created.
+    fn udp_zero_copy(&self) -> Option<&UdpZeroCopy> {
+        None
+    }
+
+    /// Whether a frame could be sent now.
+    ///
+    /// The reactor skips egress while this is `false`, leaving the data in
+    /// the sockets, e.g. while the device is out of buffers or ring space.
+    fn tx_ready(&mut self) -> bool {
+        true
+    }
+}
+
+impl ReactorDevice for Loopback {}
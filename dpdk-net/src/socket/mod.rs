@@ -1,24 +1,33 @@
 //! Async socket implementations for TCP and UDP.
 //!
 //! This module provides async TCP and UDP sockets backed by DPDK and smoltcp.
+//! They are tied to a [`ReactorHandle`](crate::runtime::ReactorHandle), not to
+//! a device type, so they work over any device a
+//! [`Reactor`](crate::runtime::Reactor) drives.
 //!
 //! # TCP Sockets
 //!
 //! - [`TcpStream`]: A connected TCP stream for bidirectional data transfer
 //! - [`TcpListener`]: A TCP listener for accepting incoming connections
+//! - [`RssPortAllocator`]: Local ports whose replies RSS steers back to the calling queue
 //!
 //! # UDP Sockets
 //!
 //! - [`UdpSocket`]: A UDP socket for connectionless datagram transfer
+//! - [`UdpBuf`]: A received payload, zero-copy with [`UdpSocket::bind_zero_copy`]
 
+mod port_alloc;
 mod tcp;
 mod udp;
 
+pub use port_alloc::{DEFAULT_EPHEMERAL_PORTS, RssPortAllocator};
 pub use tcp::{
     AcceptFuture, CloseFuture, TcpListener, TcpRecvFuture, TcpSendFuture, TcpStream,
     WaitConnectedFuture,
 };
-pub use udp::{UdpRecvFuture, UdpSendFuture, UdpSocket};
+pub use udp::{UdpMbufSendFuture, UdpRecvFuture, UdpSendFuture, UdpSocket, UdpZeroCopyRecvFuture};
+
+pub use crate::device::UdpBuf;
 
 // Re-export smoltcp error types for convenience
 pub use smoltcp::socket::tcp::{ConnectError, ListenError};
@@ -0,0 +1,236 @@
+//! RSS-aware ephemeral port selection for outbound TCP connections.
+//!
+//! With one smoltcp instance per queue, a connection only works if the
+//! peer's replies are hashed back to the queue that opened it. The NIC picks
+//! the queue from the Toeplitz hash of the reply's 4-tuple and the RETA, and
+//! the only part of that tuple we choose is the local port. An
+//! [`RssPortAllocator`] evaluates the same hash in software and hands out
+//! local ports whose return path lands on its queue.
+
+use std::ops::RangeInclusive;
+
+use smoltcp::wire::IpAddress;
+
+use crate::api::rte::eth::{EthDev, QueueId};
+use crate::device::{RSS_INPUT_MAX_LEN, toeplitz_hash};
+
+/// Default ephemeral port range (IANA dynamic ports).
+pub const DEFAULT_EPHEMERAL_PORTS: RangeInclusive<u16> = 49152..=65535;
+
+/// Picks local ports whose return traffic RSS steers to one queue.
+///
+/// Pass one to [`Reactor::with_port_allocator`](crate::runtime::Reactor::with_port_allocator)
+/// and open connections with [`TcpStream::connect_ephemeral`](super::TcpStream::connect_ephemeral).
+/// Without a RETA ([`any_queue`](Self::any_queue)), every port qualifies.
+#[derive(Debug, Clone)]
+pub struct RssPortAllocator {
+    key: Vec<u8>,
+    reta: Vec<QueueId>,
+    queue_id: QueueId,
+    ports: RangeInclusive<u16>,
+    /// Where the next search starts, so consecutive connections spread out
+    next: u16,
+}
+
+impl Default for RssPortAllocator {
+    fn default() -> Self {
+        Self::any_queue()
+    }
+}
+
+impl RssPortAllocator {
+    /// Allocator for `queue_id` given the port's RSS key and RETA.
+    ///
+    /// Fails with `EINVAL` if the key is shorter than 40 bytes, too short to
+    /// hash IPv6 replies, or the RETA is empty.
+    pub fn new(
+        key: impl Into<Vec<u8>>,
+        reta: Vec<QueueId>,
+        queue_id: QueueId,
+    ) -> crate::api::Result<Self> {
+        let key = key.into();
+        if key.len() < RSS_INPUT_MAX_LEN + 4 || reta.is_empty() {
+            return Err(crate::api::Errno::EINVAL);
+        }
+        Ok(Self {
+            key,
+            reta,
+            queue_id,
+            ..Self::any_queue()
+        })
+    }
+
+    /// Allocator that ignores RSS, for single-queue setups.
+    pub fn any_queue() -> Self {
+        Self {
+            key: Vec::new(),
+            reta: Vec::new(),
+            queue_id: 0,
+            ports: DEFAULT_EPHEMERAL_PORTS,
+            next: *DEFAULT_EPHEMERAL_PORTS.start(),
+        }
+    }
+
+    /// Allocator for `queue_id` using the key and RETA configured on `eth_dev`.
+    ///
+    /// Fails with `ENOTSUP` if the port has no RETA or does not hash TCP
+    /// ports, since the local port then has no influence on the queue.
+    pub fn from_eth_dev(eth_dev: &EthDev, queue_id: QueueId) -> crate::api::Result<Self> {
+        let (_, key) = eth_dev.rss_hash_conf()?;
+        let reta = eth_dev.query_rss_reta()?;
+        if reta.is_empty() || !eth_dev.has_tcp_rss()? {
+            return Err(crate::api::Errno::ENOTSUP);
+        }
+        Self::new(key, reta, queue_id)
+    }
+
+    /// Hand out ports from `ports` instead of [`DEFAULT_EPHEMERAL_PORTS`].
+    pub fn with_port_range(mut self, ports: RangeInclusive<u16>) -> Self {
+        self.next = *ports.start();
+        self.ports = ports;
+        self
+    }
+
+    /// Queue this allocator picks ports for
+    pub fn queue_id(&self) -> QueueId {
+        self.queue_id
+    }
+
+    /// Queue the peer's replies to a connection are received on.
+    ///
+    /// Returns `None` without a RETA, or for mixed address families.
+    pub fn return_queue(
+        &self,
+        local_addr: IpAddress,
+        local_port: u16,
+        remote_addr: IpAddress,
+        remote_port: u16,
+    ) -> Option<QueueId> {
+        if self.reta.is_empty() {
+            return None;
+        }
+        // Replies travel remote -> local
+        let mut input = [0u8; RSS_INPUT_MAX_LEN];
+        let len = match (remote_addr, local_addr) {
+            (IpAddress::Ipv4(src), IpAddress::Ipv4(dst)) => {
+                input[..4].copy_from_slice(&src.octets());
+                input[4..8].copy_from_slice(&dst.octets());
+                8
+            }
+            (IpAddress::Ipv6(src), IpAddress::Ipv6(dst)) => {
+                input[..16].copy_from_slice(&src.octets());
+                input[16..32].copy_from_slice(&dst.octets());
+                32
+            }
+            _ => return None,
+        };
+        input[len..len + 2].copy_from_slice(&remote_port.to_be_bytes());
+        input[len + 2..len + 4].copy_from_slice(&local_port.to_be_bytes());
+        let hash = toeplitz_hash(&self.key, &input[..len + 4]);
+        Some(self.reta[hash as usize % self.reta.len()])
+    }
+
+    /// Pick a local port for a connection to `remote_addr:remote_port`.
+    ///
+    /// `in_use` rejects ports already taken for this peer. Returns `None` if
+    /// no free port in the range maps to this queue.
+    pub fn allocate(
+        &mut self,
+        local_addr: IpAddress,
+        remote_addr: IpAddress,
+        remote_port: u16,
+        in_use: impl Fn(u16) -> bool,
+    ) -> Option<u16> {
+        let (start, end) = (*self.ports.start(), *self.ports.end());
+        let count = end as u32 - start as u32 + 1;
+        let first = self.next.clamp(start, end);
+        for i in 0..count {
+            let port = start + ((first - start) as u32 + i).rem_euclid(count) as u16;
+            let ours = self
+                .return_queue(local_addr, port, remote_addr, remote_port)
+                .is_none_or(|queue| queue == self.queue_id);
+            if ours && !in_use(port) {
+                self.next = if port == end { start } else { port + 1 };
+                return Some(port);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rte::eth::RSS_KEY_40;
+    use smoltcp::wire::Ipv4Address;
+
+    const LOCAL: IpAddress = IpAddress::Ipv4(Ipv4Address::new(10, 0, 0, 2));
+    const REMOTE: IpAddress = IpAddress::Ipv4(Ipv4Address::new(10, 0, 0, 1));
+
+    #[test]
+    fn test_return_queue_matches_verification_suite() {
+        // Microsoft RSS verification suite: 66.9.149.187:2794 -> 161.142.100.80:1766
+        let reta: Vec<QueueId> = (0..128).collect();
+        let alloc = RssPortAllocator::new(RSS_KEY_40, reta, 0).unwrap();
+        let queue = alloc.return_queue(
+            IpAddress::v4(161, 142, 100, 80),
+            1766,
+            IpAddress::v4(66, 9, 149, 187),
+            2794,
+        );
+        assert_eq!(queue, Some((0x51cc_c178u32 % 128) as QueueId));
+    }
+
+    #[test]
+    fn test_new_rejects_bad_key() {
+        let reta: Vec<QueueId> = (0..128).collect();
+        assert_eq!(
+            RssPortAllocator::new(&RSS_KEY_40[..16], reta.clone(), 0).unwrap_err(),
+            crate::api::Errno::EINVAL
+        );
+        assert_eq!(
+            RssPortAllocator::new(RSS_KEY_40, Vec::new(), 0).unwrap_err(),
+            crate::api::Errno::EINVAL
+        );
+        assert!(RssPortAllocator::new(RSS_KEY_40, reta, 0).is_ok());
+    }
+
+    #[test]
+    fn test_allocate_for_queue() {
+        let reta: Vec<QueueId> = (0..128).map(|i| i % 4).collect();
+        for queue_id in 0..4 {
+            let mut alloc = RssPortAllocator::new(RSS_KEY_40, reta.clone(), queue_id).unwrap();
+            let mut ports = Vec::new();
+            for _ in 0..16 {
+                let port = alloc
+                    .allocate(LOCAL, REMOTE, 80, |p| ports.contains(&p))
+                    .unwrap();
+                assert_eq!(alloc.return_queue(LOCAL, port, REMOTE, 80), Some(queue_id));
+                assert!(DEFAULT_EPHEMERAL_PORTS.contains(&port));
+                ports.push(port);
+            }
+        }
+    }
+
+    #[test]
+    fn test_allocate_exhausted() {
+        let reta = vec![0, 1];
+        let mut alloc = RssPortAllocator::new(RSS_KEY_40, reta, 1)
+            .unwrap()
+            .with_port_range(50000..=50007);
+        let mut ports = Vec::new();
+        while let Some(port) = alloc.allocate(LOCAL, REMOTE, 80, |p| ports.contains(&p)) {
+            ports.push(port);
+        }
+        let expected = (50000..=50007)
+            .filter(|&p| alloc.return_queue(LOCAL, p, REMOTE, 80) == Some(1))
+            .count();
+        assert_eq!(ports.len(), expected);
+
+        // Without RSS every port is usable
+        let mut any = RssPortAllocator::any_queue().with_port_range(50000..=50001);
+        assert_eq!(any.allocate(LOCAL, REMOTE, 80, |_| false), Some(50000));
+        assert_eq!(any.allocate(LOCAL, REMOTE, 80, |_| false), Some(50001));
+        assert_eq!(any.allocate(LOCAL, REMOTE, 80, |p| p == 50000), Some(50001));
+    }
+}
@@ -1,10 +1,10 @@
 //! Async TCP socket implementation
 
-use crate::device::DpdkDevice;
 use crate::runtime::{ReactorHandle, ReactorInner};
 use smoltcp::iface::SocketHandle;
+use smoltcp::socket::Socket;
 use smoltcp::socket::tcp::{self, ConnectError, ListenError, RecvError, SendError, State};
-use smoltcp::wire::IpAddress;
+use smoltcp::wire::{IpAddress, IpEndpoint};
 use std::cell::RefCell;
 use std::future::Future;
 use std::pin::Pin;
@@ -20,7 +20,7 @@ use std::task::{Context, Poll};
 /// [`TcpStream::connect`], or by accepting a connection from a [`TcpListener`].
 pub struct TcpStream {
     pub(crate) handle: SocketHandle,
-    pub(crate) reactor: Rc<RefCell<ReactorInner<DpdkDevice>>>,
+    pub(crate) reactor: Rc<RefCell<ReactorInner>>,
 }
 
 impl TcpStream {
@@ -57,13 +57,71 @@ impl TcpStream {
         })
     }
 
+    /// Opens a TCP connection from a local port picked by the reactor's
+    /// [`RssPortAllocator`](super::RssPortAllocator).
+    ///
+    /// With an allocator for this queue's RSS configuration (see
+    /// [`Reactor::with_port_allocator`](crate::runtime::Reactor::with_port_allocator)),
+    /// the peer's replies are hashed back to this queue. Ports already used
+    /// for the same peer are skipped. Returns `Unaddressable` if the
+    /// interface has no address of the peer's family or no port is free.
+    pub fn connect_ephemeral(
+        handle: &ReactorHandle,
+        remote_addr: IpAddress,
+        remote_port: u16,
+        rx_buffer_size: usize,
+        tx_buffer_size: usize,
+    ) -> Result<Self, ConnectError> {
+        let local_port = {
+            let mut inner = handle.inner.borrow_mut();
+            let local_addr = inner
+                .iface
+                .ip_addrs()
+                .iter()
+                .map(|cidr| cidr.address())
+                .find(|addr| addr.version() == remote_addr.version())
+                .ok_or(ConnectError::Unaddressable)?;
+
+            // Ports taken towards this peer, and listening ports
+            let in_use: Vec<u16> = inner
+                .sockets
+                .iter()
+                .filter_map(|(_, socket)| match socket {
+                    Socket::Tcp(socket) if socket.state() == State::Listen => {
+                        Some(socket.listen_endpoint().port)
+                    }
+                    Socket::Tcp(socket)
+                        if socket.remote_endpoint()
+                            == Some(IpEndpoint::new(remote_addr, remote_port)) =>
+                    {
+                        socket.local_endpoint().map(|ep| ep.port)
+                    }
+                    _ => None,
+                })
+                .collect();
+
+            inner
+                .port_allocator
+                .allocate(local_addr, remote_addr, remote_port, |port| {
+                    in_use.contains(&port)
+                })
+                .ok_or(ConnectError::Unaddressable)?
+        };
+
+        Self::connect(
+            handle,
+            remote_addr,
+            remote_port,
+            local_port,
+            rx_buffer_size,
+            tx_buffer_size,
+        )
+    }
+
     /// Create a TcpStream from an already-connected socket handle.
     ///
     /// This is used internally by TcpListener::accept().
-    pub(crate) fn from_handle(
-        handle: SocketHandle,
-        reactor: Rc<RefCell<ReactorInner<DpdkDevice>>>,
-    ) -> Self {
+    pub(crate) fn from_handle(handle: SocketHandle, reactor: Rc<RefCell<ReactorInner>>) -> Self {
         TcpStream { handle, reactor }
     }
 
@@ -182,7 +240,7 @@ impl Drop for TcpStream {
 pub struct TcpListener {
     /// Pool of sockets for handling concurrent connections
     handles: Vec<SocketHandle>,
-    reactor: Rc<RefCell<ReactorInner<DpdkDevice>>>,
+    reactor: Rc<RefCell<ReactorInner>>,
     port: u16,
     rx_buffer_size: usize,
     tx_buffer_size: usize,
@@ -234,7 +292,7 @@ impl TcpListener {
 
     /// Create a new listening socket and add it to the reactor
     fn create_listening_socket(
-        inner: &mut ReactorInner<DpdkDevice>,
+        inner: &mut ReactorInner,
         port: u16,
         rx_buffer_size: usize,
         tx_buffer_size: usize,
@@ -1,6 +1,7 @@
 //! Async UDP socket implementation
 
-use crate::device::DpdkDevice;
+use crate::api::rte::mbuf::Mbuf;
+use crate::device::{DEFAULT_HOP_LIMIT, UdpBuf, UdpZeroCopy};
 use crate::runtime::{ReactorHandle, ReactorInner};
 use smoltcp::iface::SocketHandle;
 use smoltcp::socket::udp::{self, BindError, RecvError, SendError, UdpMetadata};
@@ -20,7 +21,10 @@ use std::task::{Context, Poll};
 /// any endpoint without establishing a connection first.
 pub struct UdpSocket {
     handle: SocketHandle,
-    reactor: Rc<RefCell<ReactorInner<DpdkDevice>>>,
+    reactor: Rc<RefCell<ReactorInner>>,
+    port: u16,
+    /// Datagrams the device queues for us in their mbufs (None = smoltcp only)
+    zero_copy: Option<UdpZeroCopy>,
 }
 
 impl UdpSocket {
@@ -58,9 +62,47 @@ impl UdpSocket {
         Ok(UdpSocket {
             handle: socket_handle,
             reactor: handle.inner.clone(),
+            port,
+            zero_copy: None,
         })
     }
 
+    /// Creates a UDP socket whose datagrams skip smoltcp's buffers.
+    ///
+    /// The reactor's device must have been created with
+    /// [`DpdkDevice::with_udp_zero_copy`](crate::device::DpdkDevice::with_udp_zero_copy);
+    /// otherwise this fails with `Unaddressable`. Datagrams for `port` are
+    /// then queued in the mbufs they arrived in and returned by
+    /// [`recv_zero_copy`](Self::recv_zero_copy) without copying. The smoltcp
+    /// buffers (same arguments as [`bind`](Self::bind)) still carry sent
+    /// datagrams and the received ones the device cannot pass on directly.
+    pub fn bind_zero_copy(
+        handle: &ReactorHandle,
+        port: u16,
+        rx_buffer_packets: usize,
+        tx_buffer_packets: usize,
+        max_packet_size: usize,
+    ) -> Result<Self, BindError> {
+        let zero_copy = handle
+            .inner
+            .borrow()
+            .udp_zero_copy
+            .clone()
+            .ok_or(BindError::Unaddressable)?;
+        let mut socket = Self::bind(
+            handle,
+            port,
+            rx_buffer_packets,
+            tx_buffer_packets,
+            max_packet_size,
+        )?;
+        if !zero_copy.register(port) {
+            return Err(BindError::InvalidState);
+        }
+        socket.zero_copy = Some(zero_copy);
+        Ok(socket)
+    }
+
     /// Get the underlying socket handle
     pub fn socket_handle(&self) -> SocketHandle {
         self.handle
@@ -98,6 +140,64 @@ impl UdpSocket {
         UdpRecvFuture { socket: self, buf }
     }
 
+    /// Receive a datagram without copying its payload.
+    ///
+    /// On a socket from [`bind_zero_copy`](Self::bind_zero_copy), the
+    /// returned [`UdpBuf`] usually still lives in its mbuf; keep it only as
+    /// long as needed, since it holds the mbuf. Datagrams that came through
+    /// smoltcp (and all datagrams of other sockets) are copied once.
+    pub fn recv_zero_copy(&self) -> UdpZeroCopyRecvFuture<'_> {
+        UdpZeroCopyRecvFuture { socket: self }
+    }
+
+    /// Allocate an mbuf to write a payload into for
+    /// [`send_mbuf_to`](Self::send_mbuf_to).
+    ///
+    /// The mbuf comes from the device's mempool, empty, with headroom for
+    /// the headers (see [`UDP_ZERO_COPY_TX_HEADROOM`](crate::device::UDP_ZERO_COPY_TX_HEADROOM)).
+    /// Returns `None` if the socket is not from
+    /// [`bind_zero_copy`](Self::bind_zero_copy) or the mempool is exhausted.
+    pub fn alloc_tx_mbuf(&self) -> Option<Mbuf> {
+        self.zero_copy.as_ref()?.alloc_tx()
+    }
+
+    /// Send the payload in `mbuf` to `endpoint` without copying it.
+    ///
+    /// On a socket from [`bind_zero_copy`](Self::bind_zero_copy), the
+    /// Ethernet/IP/UDP headers are prepended in the mbuf's headroom and
+    /// the mbuf itself is handed to the device, once the next hop's MAC is
+    /// known. Until then (and on other sockets) the payload is copied
+    /// through smoltcp like [`send_to`](Self::send_to) does, so the first
+    /// datagrams to a new neighbor may be overtaken by later ones. The
+    /// payload must be in a single segment and fit into one unfragmented
+    /// frame; otherwise this fails with `Unaddressable`.
+    ///
+    /// Returns the payload length when the datagram is queued.
+    pub fn send_mbuf_to(&self, mbuf: Mbuf, endpoint: IpEndpoint) -> UdpMbufSendFuture<'_> {
+        UdpMbufSendFuture {
+            socket: self,
+            len: mbuf.data_len(),
+            mbuf: Some(mbuf),
+            endpoint,
+            prepared: false,
+        }
+    }
+
+    /// Datagrams dropped because the zero-copy queue was full
+    pub fn zero_copy_dropped(&self) -> u64 {
+        self.zero_copy
+            .as_ref()
+            .map_or(0, |zero_copy| zero_copy.dropped(self.port))
+    }
+
+    /// Datagrams [`send_mbuf_to`](Self::send_mbuf_to) handed to the device
+    /// without copying them through smoltcp
+    pub fn zero_copy_sent(&self) -> u64 {
+        self.zero_copy
+            .as_ref()
+            .map_or(0, |zero_copy| zero_copy.sent(self.port))
+    }
+
     /// Close the socket.
     pub fn close(&self) {
         let mut inner = self.reactor.borrow_mut();
@@ -108,6 +208,9 @@ impl UdpSocket {
 
 impl Drop for UdpSocket {
     fn drop(&mut self) {
+        if let Some(zero_copy) = &self.zero_copy {
+            zero_copy.unregister(self.port);
+        }
         let mut inner = self.reactor.borrow_mut();
         let socket = inner.sockets.get_mut::<udp::Socket>(self.handle);
         socket.close();
@@ -151,6 +254,17 @@ impl Future for UdpRecvFuture<'_> {
     type Output = Result<(usize, UdpMetadata), RecvError>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Datagrams the device queued for us come first
+        if let Some(zero_copy) = &self.socket.zero_copy
+            && let Some((payload, metadata)) = zero_copy.pop(self.socket.port, cx.waker())
+        {
+            let Some(buf) = self.buf.get_mut(..payload.len()) else {
+                return Poll::Ready(Err(RecvError::Truncated));
+            };
+            buf.copy_from_slice(&payload);
+            return Poll::Ready(Ok((payload.len(), metadata)));
+        }
+
         let mut inner = self.socket.reactor.borrow_mut();
         let socket = inner.sockets.get_mut::<udp::Socket>(self.socket.handle);
 
@@ -165,3 +279,105 @@ impl Future for UdpRecvFuture<'_> {
         }
     }
 }
+
+/// Future for receiving a UDP datagram without copying it
+pub struct UdpZeroCopyRecvFuture<'a> {
+    socket: &'a UdpSocket,
+}
+
+impl Future for UdpZeroCopyRecvFuture<'_> {
+    type Output = Result<(UdpBuf, UdpMetadata), RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(zero_copy) = &self.socket.zero_copy
+            && let Some(datagram) = zero_copy.pop(self.socket.port, cx.waker())
+        {
+            return Poll::Ready(Ok(datagram));
+        }
+
+        let mut inner = self.socket.reactor.borrow_mut();
+        let socket = inner.sockets.get_mut::<udp::Socket>(self.socket.handle);
+
+        match socket.recv() {
+            Ok((payload, metadata)) => {
+                Poll::Ready(Ok((UdpBuf::from_vec(payload.to_vec()), metadata)))
+            }
+            Err(RecvError::Exhausted) => {
+                socket.register_recv_waker(cx.waker());
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+}
+
+/// Future for sending a UDP datagram from a caller-filled mbuf
+pub struct UdpMbufSendFuture<'a> {
+    socket: &'a UdpSocket,
+    mbuf: Option<Mbuf>,
+    endpoint: IpEndpoint,
+    len: usize,
+    /// Headers are in place, waiting for room in the device's queue
+    prepared: bool,
+}
+
+impl Future for UdpMbufSendFuture<'_> {
+    type Output = Result<usize, SendError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let owner = this.socket;
+        let mut mbuf = this.mbuf.take().expect("polled after completion");
+        if !mbuf.is_contiguous() {
+            return Poll::Ready(Err(SendError::Unaddressable));
+        }
+
+        if !this.prepared {
+            let mut inner = owner.reactor.borrow_mut();
+            let inner = &mut *inner;
+            let direct = match &owner.zero_copy {
+                Some(zero_copy) => {
+                    let hop_limit = inner
+                        .sockets
+                        .get::<udp::Socket>(owner.handle)
+                        .hop_limit()
+                        .unwrap_or(DEFAULT_HOP_LIMIT);
+                    match zero_copy.prepare_tx(
+                        &mut mbuf,
+                        &mut inner.iface,
+                        owner.port,
+                        this.endpoint,
+                        hop_limit,
+                    ) {
+                        Ok(direct) => direct,
+                        Err(e) => return Poll::Ready(Err(e)),
+                    }
+                }
+                None => false,
+            };
+            if !direct {
+                // smoltcp resolves the neighbor (and copies the payload)
+                let socket = inner.sockets.get_mut::<udp::Socket>(owner.handle);
+                return match socket.send_slice(mbuf.data(), this.endpoint) {
+                    Ok(()) => Poll::Ready(Ok(this.len)),
+                    Err(SendError::BufferFull) => {
+                        socket.register_send_waker(cx.waker());
+                        this.mbuf = Some(mbuf);
+                        Poll::Pending
+                    }
+                    Err(e) => Poll::Ready(Err(e)),
+                };
+            }
+            this.prepared = true;
+        }
+
+        // Only zero-copy sockets prepare mbufs
+        if let Some(zero_copy) = &owner.zero_copy
+            && let Err(mbuf) = zero_copy.push_tx(owner.port, mbuf, cx.waker())
+        {
+            this.mbuf = Some(mbuf);
+            return Poll::Pending;
+        }
+        Poll::Ready(Ok(this.len))
+    }
+}